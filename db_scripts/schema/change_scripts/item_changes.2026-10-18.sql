@@ -0,0 +1,21 @@
+/*** Item Changes Script - Journal of the record changes of the wallets, written when the change feed is enabled. ***/
+
+USE `wallet`;
+
+ALTER TABLE `wallets`
+    ADD COLUMN `change_seq` BIGINT(20) UNSIGNED NOT NULL DEFAULT 0 AFTER `metadata`;
+
+CREATE TABLE IF NOT EXISTS `item_changes` (
+    `wallet_id` BIGINT(20) NOT NULL,
+    `seq` BIGINT(20) UNSIGNED NOT NULL,
+    `type` VARCHAR(128) NOT NULL,
+    `name` VARCHAR(1024) NOT NULL,
+    `operation` VARCHAR(16) NOT NULL,
+    `version` BIGINT(20) UNSIGNED NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`wallet_id`, `seq`),
+    CONSTRAINT `fk_item_changes_wallet_id` FOREIGN KEY (`wallet_id`)
+        REFERENCES `wallets` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii;
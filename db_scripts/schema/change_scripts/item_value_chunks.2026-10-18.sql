@@ -0,0 +1,33 @@
+/*** Item Value Chunks Script - Stores the values above the chunk size of the chunked value mode in chunks, also in the snapshots. ***/
+
+USE `wallet`;
+
+ALTER TABLE `items`
+    ADD COLUMN `value_chunks` INT(10) UNSIGNED NOT NULL DEFAULT 0 AFTER `value`;
+
+CREATE TABLE IF NOT EXISTS `item_value_chunks` (
+    `item_id` BIGINT(20) NOT NULL,
+    `seq` INT(10) UNSIGNED NOT NULL,
+    `data` LONGBLOB NOT NULL,
+    PRIMARY KEY (`item_id`, `seq`),
+    CONSTRAINT `fk_item_value_chunks_item_id` FOREIGN KEY (`item_id`)
+        REFERENCES `items` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii;
+
+ALTER TABLE `snapshot_items`
+    ADD COLUMN `value_chunks` INT(10) UNSIGNED NOT NULL DEFAULT 0 AFTER `value`;
+
+CREATE TABLE IF NOT EXISTS `snapshot_value_chunks` (
+    `snapshot_id` BIGINT(20) NOT NULL,
+    `type` VARCHAR(128) NOT NULL,
+    `name` VARCHAR(1024) NOT NULL,
+    `seq` INT(10) UNSIGNED NOT NULL,
+    `data` LONGBLOB NOT NULL,
+    PRIMARY KEY (`snapshot_id`, `type`, `name`, `seq`),
+    CONSTRAINT `fk_snapshot_value_chunks_snapshot_id` FOREIGN KEY (`snapshot_id`)
+        REFERENCES `snapshots` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii;
@@ -0,0 +1,9 @@
+/*** Item Value Encoding Script - Marks the stored values that are compressed, 0 for the values stored as given. ***/
+
+USE `wallet`;
+
+ALTER TABLE `items`
+    ADD COLUMN `value_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0 AFTER `value_chunks`;
+
+ALTER TABLE `snapshot_items`
+    ADD COLUMN `value_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0 AFTER `value`;
@@ -0,0 +1,6 @@
+/*** Items Version Script - Adds a version to every item, incremented on each mutation. ***/
+
+USE `wallet`;
+
+ALTER TABLE `items`
+    ADD COLUMN `version` BIGINT(20) UNSIGNED NOT NULL DEFAULT 1 AFTER `tags`;
@@ -0,0 +1,22 @@
+/*** Wallet Data Keys Script - Data keys of the wallets with encrypted values and metadata, wrapped by a key-encryption key. ***/
+
+USE `wallet`;
+
+ALTER TABLE `wallets`
+    ADD COLUMN `metadata_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0 AFTER `metadata`;
+
+ALTER TABLE `snapshots`
+    ADD COLUMN `metadata_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0 AFTER `metadata`;
+
+CREATE TABLE IF NOT EXISTS `wallet_data_keys` (
+    `wallet_id` BIGINT(20) NOT NULL,
+    `version` INT(10) UNSIGNED NOT NULL,
+    `kek_id` VARCHAR(255) NOT NULL,
+    `wrapped_key` VARBINARY(255) NOT NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`wallet_id`, `version`),
+    CONSTRAINT `fk_wallet_data_keys_wallet_id` FOREIGN KEY (`wallet_id`)
+        REFERENCES `wallets` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii;
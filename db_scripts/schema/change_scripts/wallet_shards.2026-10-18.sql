@@ -0,0 +1,9 @@
+/*** Wallet Shards Script - Directory of the shards the wallets are placed on, used by the `directory` shard placement. ***/
+
+USE `wallet`;
+
+CREATE TABLE IF NOT EXISTS `wallet_shards` (
+    `name` VARCHAR(1024) NOT NULL,
+    `shard` VARCHAR(255) NOT NULL,
+    PRIMARY KEY (`name`)
+) ENGINE=InnoDB DEFAULT CHARSET=ascii;
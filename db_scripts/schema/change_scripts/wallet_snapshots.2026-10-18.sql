@@ -0,0 +1,31 @@
+/*** Wallet Snapshots Script - Point in time copies of the records and metadata of the wallets. ***/
+
+USE `wallet`;
+
+CREATE TABLE IF NOT EXISTS `snapshots` (
+    `id` BIGINT(20) NOT NULL AUTO_INCREMENT,
+    `wallet_id` BIGINT(20) NOT NULL,
+    `label` VARCHAR(255) NOT NULL,
+    `metadata` VARCHAR(10240) NOT NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `ux_snapshots_wallet_id_label` (`wallet_id`, `label`),
+    CONSTRAINT `fk_snapshots_wallet_id` FOREIGN KEY (`wallet_id`)
+        REFERENCES `wallets` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii;
+
+CREATE TABLE IF NOT EXISTS `snapshot_items` (
+    `snapshot_id` BIGINT(20) NOT NULL,
+    `type` VARCHAR(128) NOT NULL,
+    `name` VARCHAR(1024) NOT NULL,
+    `value` LONGBLOB NOT NULL,
+    `tags` JSON NOT NULL,
+    `version` BIGINT(20) UNSIGNED NOT NULL,
+    PRIMARY KEY (`snapshot_id`, `type`, `name`),
+    CONSTRAINT `fk_snapshot_items_snapshot_id` FOREIGN KEY (`snapshot_id`)
+        REFERENCES `snapshots` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii;
@@ -0,0 +1,8 @@
+/*** Item Changes Revert Script **/
+
+USE `wallet`;
+
+DROP TABLE IF EXISTS `item_changes`;
+
+ALTER TABLE `wallets`
+    DROP COLUMN `change_seq`;
@@ -0,0 +1,13 @@
+/*** Item Value Chunks Revert Script **/
+
+USE `wallet`;
+
+DROP TABLE IF EXISTS `snapshot_value_chunks`;
+
+DROP TABLE IF EXISTS `item_value_chunks`;
+
+ALTER TABLE `snapshot_items`
+    DROP COLUMN `value_chunks`;
+
+ALTER TABLE `items`
+    DROP COLUMN `value_chunks`;
@@ -0,0 +1,9 @@
+/*** Item Value Encoding Revert Script **/
+
+USE `wallet`;
+
+ALTER TABLE `snapshot_items`
+    DROP COLUMN `value_encoding`;
+
+ALTER TABLE `items`
+    DROP COLUMN `value_encoding`;
@@ -0,0 +1,6 @@
+/*** Items Version Revert Script **/
+
+USE `wallet`;
+
+ALTER TABLE `items`
+    DROP COLUMN `version`;
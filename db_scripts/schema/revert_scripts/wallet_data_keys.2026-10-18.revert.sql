@@ -0,0 +1,11 @@
+/*** Wallet Data Keys Revert Script **/
+
+USE `wallet`;
+
+DROP TABLE IF EXISTS `wallet_data_keys`;
+
+ALTER TABLE `snapshots`
+    DROP COLUMN `metadata_encoding`;
+
+ALTER TABLE `wallets`
+    DROP COLUMN `metadata_encoding`;
@@ -0,0 +1,5 @@
+/*** Wallet Shards Revert Script **/
+
+USE `wallet`;
+
+DROP TABLE IF EXISTS `wallet_shards`;
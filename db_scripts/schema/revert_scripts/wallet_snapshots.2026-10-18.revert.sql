@@ -0,0 +1,6 @@
+/*** Wallet Snapshots Revert Script **/
+
+USE `wallet`;
+
+DROP TABLE IF EXISTS `snapshot_items`;
+DROP TABLE IF EXISTS `snapshots`;
@@ -0,0 +1,9 @@
+/*** Tenant Tables Revert Script - Drops the wallet tables with the `table_prefix` of a tenant. ***/
+
+USE `wallet`;
+
+SET @drop_tables = CONCAT('DROP TABLE IF EXISTS `', @table_prefix, 'wallet_data_keys`, `', @table_prefix, 'snapshot_value_chunks`, `', @table_prefix, 'item_value_chunks`, `', @table_prefix, 'item_changes`, `', @table_prefix, 'snapshot_items`, `', @table_prefix, 'snapshots`, `', @table_prefix, 'wallet_shards`, `', @table_prefix, 'items`, `', @table_prefix, 'wallets`');
+
+PREPARE drop_tables FROM @drop_tables;
+EXECUTE drop_tables;
+DEALLOCATE PREPARE drop_tables;
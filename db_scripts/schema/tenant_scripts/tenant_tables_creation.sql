@@ -0,0 +1,167 @@
+/*** Tenant Tables Creation Script - Creates the wallet tables with the `table_prefix` of a tenant. ***/
+/*** Set the prefix before running the script, e.g. mysql -e "SET @table_prefix = 'acme_'; SOURCE tenant_tables_creation.sql" ***/
+/*** The tables match the schema after all of the change scripts are applied. ***/
+
+USE `wallet`;
+
+SET @create_wallets = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'wallets` (
+    `id` BIGINT(20) NOT NULL AUTO_INCREMENT,
+    `name` VARCHAR(1024) NOT NULL,
+    `metadata` VARCHAR(10240) NOT NULL,
+    `metadata_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0,
+    `change_seq` BIGINT(20) UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `wallet_name` (`name`)
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_wallets FROM @create_wallets;
+EXECUTE create_wallets;
+DEALLOCATE PREPARE create_wallets;
+
+SET @create_items = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'items` (
+    `id` BIGINT(20) NOT NULL AUTO_INCREMENT,
+    `wallet_id` BIGINT(20) NOT NULL,
+    `type` VARCHAR(128) NOT NULL,
+    `name` VARCHAR(1024) NOT NULL,
+    `value` LONGBLOB NOT NULL,
+    `value_chunks` INT(10) UNSIGNED NOT NULL DEFAULT 0,
+    `value_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0,
+    `tags` JSON NOT NULL,
+    `version` BIGINT(20) UNSIGNED NOT NULL DEFAULT 1,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `ux_items_wallet_id_type_name` (`wallet_id`, `type`, `name`),
+    CONSTRAINT `fk_', @table_prefix, 'items_wallet_id` FOREIGN KEY (`wallet_id`)
+        REFERENCES `', @table_prefix, 'wallets` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_items FROM @create_items;
+EXECUTE create_items;
+DEALLOCATE PREPARE create_items;
+
+SET @create_wallet_shards = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'wallet_shards` (
+    `name` VARCHAR(1024) NOT NULL,
+    `shard` VARCHAR(255) NOT NULL,
+    PRIMARY KEY (`name`)
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_wallet_shards FROM @create_wallet_shards;
+EXECUTE create_wallet_shards;
+DEALLOCATE PREPARE create_wallet_shards;
+
+SET @create_snapshots = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'snapshots` (
+    `id` BIGINT(20) NOT NULL AUTO_INCREMENT,
+    `wallet_id` BIGINT(20) NOT NULL,
+    `label` VARCHAR(255) NOT NULL,
+    `metadata` VARCHAR(10240) NOT NULL,
+    `metadata_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `ux_snapshots_wallet_id_label` (`wallet_id`, `label`),
+    CONSTRAINT `fk_', @table_prefix, 'snapshots_wallet_id` FOREIGN KEY (`wallet_id`)
+        REFERENCES `', @table_prefix, 'wallets` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_snapshots FROM @create_snapshots;
+EXECUTE create_snapshots;
+DEALLOCATE PREPARE create_snapshots;
+
+SET @create_snapshot_items = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'snapshot_items` (
+    `snapshot_id` BIGINT(20) NOT NULL,
+    `type` VARCHAR(128) NOT NULL,
+    `name` VARCHAR(1024) NOT NULL,
+    `value` LONGBLOB NOT NULL,
+    `value_chunks` INT(10) UNSIGNED NOT NULL DEFAULT 0,
+    `value_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0,
+    `tags` JSON NOT NULL,
+    `version` BIGINT(20) UNSIGNED NOT NULL,
+    PRIMARY KEY (`snapshot_id`, `type`, `name`),
+    CONSTRAINT `fk_', @table_prefix, 'snapshot_items_snapshot_id` FOREIGN KEY (`snapshot_id`)
+        REFERENCES `', @table_prefix, 'snapshots` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_snapshot_items FROM @create_snapshot_items;
+EXECUTE create_snapshot_items;
+DEALLOCATE PREPARE create_snapshot_items;
+
+SET @create_item_changes = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'item_changes` (
+    `wallet_id` BIGINT(20) NOT NULL,
+    `seq` BIGINT(20) UNSIGNED NOT NULL,
+    `type` VARCHAR(128) NOT NULL,
+    `name` VARCHAR(1024) NOT NULL,
+    `operation` VARCHAR(16) NOT NULL,
+    `version` BIGINT(20) UNSIGNED NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`wallet_id`, `seq`),
+    CONSTRAINT `fk_', @table_prefix, 'item_changes_wallet_id` FOREIGN KEY (`wallet_id`)
+        REFERENCES `', @table_prefix, 'wallets` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_item_changes FROM @create_item_changes;
+EXECUTE create_item_changes;
+DEALLOCATE PREPARE create_item_changes;
+
+SET @create_item_value_chunks = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'item_value_chunks` (
+    `item_id` BIGINT(20) NOT NULL,
+    `seq` INT(10) UNSIGNED NOT NULL,
+    `data` LONGBLOB NOT NULL,
+    PRIMARY KEY (`item_id`, `seq`),
+    CONSTRAINT `fk_', @table_prefix, 'item_value_chunks_item_id` FOREIGN KEY (`item_id`)
+        REFERENCES `', @table_prefix, 'items` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_item_value_chunks FROM @create_item_value_chunks;
+EXECUTE create_item_value_chunks;
+DEALLOCATE PREPARE create_item_value_chunks;
+
+SET @create_snapshot_value_chunks = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'snapshot_value_chunks` (
+    `snapshot_id` BIGINT(20) NOT NULL,
+    `type` VARCHAR(128) NOT NULL,
+    `name` VARCHAR(1024) NOT NULL,
+    `seq` INT(10) UNSIGNED NOT NULL,
+    `data` LONGBLOB NOT NULL,
+    PRIMARY KEY (`snapshot_id`, `type`, `name`, `seq`),
+    CONSTRAINT `fk_', @table_prefix, 'snapshot_value_chunks_snapshot_id` FOREIGN KEY (`snapshot_id`)
+        REFERENCES `', @table_prefix, 'snapshots` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_snapshot_value_chunks FROM @create_snapshot_value_chunks;
+EXECUTE create_snapshot_value_chunks;
+DEALLOCATE PREPARE create_snapshot_value_chunks;
+
+SET @create_wallet_data_keys = CONCAT('
+CREATE TABLE IF NOT EXISTS `', @table_prefix, 'wallet_data_keys` (
+    `wallet_id` BIGINT(20) NOT NULL,
+    `version` INT(10) UNSIGNED NOT NULL,
+    `kek_id` VARCHAR(255) NOT NULL,
+    `wrapped_key` VARBINARY(255) NOT NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`wallet_id`, `version`),
+    CONSTRAINT `fk_', @table_prefix, 'wallet_data_keys_wallet_id` FOREIGN KEY (`wallet_id`)
+        REFERENCES `', @table_prefix, 'wallets` (`id`)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=ascii');
+
+PREPARE create_wallet_data_keys FROM @create_wallet_data_keys;
+EXECUTE create_wallet_data_keys;
+DEALLOCATE PREPARE create_wallet_data_keys;
@@ -1,11 +1,13 @@
 use utils::handle_store::HandleStore;
+use utils::worker_runtime::WorkerRuntime;
 use errors::error_code::ErrorCode;
-use mysql_storage::{MySQLStorage};
+use mysql_storage::{MySQLStorage, RecordKey};
 use libc::c_char;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::slice;
 use serde_json;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 macro_rules! c_char_to_str {
     ($x: expr) => {
@@ -18,62 +20,816 @@ macro_rules! c_char_to_str {
 
 lazy_static! {
     static ref STORAGES: HandleStore<MySQLStorage<'static>> = HandleStore::new();
+    static ref JSON_RESULTS: HandleStore<CString> = HandleStore::new();
+}
+
+fn return_json(json: String, json_p: *mut *const c_char, json_handle_p: *mut i32) -> ErrorCode {
+    let json = check_result!(CString::new(json), ErrorCode::InvalidState);
+    let handle = JSON_RESULTS.insert(json);
+    let json = check_option!(JSON_RESULTS.get(handle), ErrorCode::InvalidState);
+
+    unsafe {
+        *json_p = json.as_ptr();
+        *json_handle_p = handle;
+    }
+
+    ErrorCode::Success
+}
+
+///
+/// Executes a call on the worker runtime, or on the calling thread if there is no runtime.
+///
+fn execute<T, F>(runtime: Option<Arc<WorkerRuntime>>, call: F) -> Result<T, ErrorCode> where T: Send + 'static, F: FnOnce() -> T + Send + 'static {
+    execute_or_discard(runtime, call, |_| ())
+}
+
+///
+/// Executes a call like `execute`, the result of a call that timed out on the runtime is passed to `discard`.
+///
+fn execute_or_discard<T, F, D>(runtime: Option<Arc<WorkerRuntime>>, call: F, discard: D) -> Result<T, ErrorCode>
+    where T: Send + 'static, F: FnOnce() -> T + Send + 'static, D: FnOnce(T) + Send + 'static {
+    match runtime {
+        None => Ok(call()),
+        Some(runtime) => runtime.run_or_discard(call, discard),
+    }
+}
+
+fn execute_call<F>(runtime: Option<Arc<WorkerRuntime>>, call: F) -> ErrorCode where F: FnOnce() -> ErrorCode + Send + 'static {
+    match execute(runtime, call) {
+        Err(err) => err,
+        Ok(err) => err,
+    }
+}
+
+///
+/// Executes a call returning a handle, which is written to `handle_p` on the calling thread once the call succeeded.
+/// The handle of a call that succeeded after it timed out is passed to `release`.
+///
+fn execute_handle_call<F, R>(runtime: Option<Arc<WorkerRuntime>>, handle_p: *mut i32, call: F, release: R) -> ErrorCode
+    where F: FnOnce(*mut i32) -> ErrorCode + Send + 'static, R: FnOnce(i32) + Send + 'static {
+    let call = move || {
+        let mut handle: i32 = -1;
+        let err = call(&mut handle);
+        (err, handle)
+    };
+
+    let result = execute_or_discard(runtime, call, move |(err, handle)| {
+        if err == ErrorCode::Success {
+            release(handle);
+        }
+    });
+
+    match result {
+        Err(err) => err,
+        Ok((ErrorCode::Success, handle)) => {
+            unsafe { *handle_p = handle; }
+            ErrorCode::Success
+        },
+        Ok((err, _)) => err,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn create_storage(name: *const c_char, config: *const c_char, credentials: *const c_char, metadata: *const c_char) -> ErrorCode {
+
+    trace!("Create Storage Called");
+
+    let name = c_char_to_str!(name);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    let metadata = c_char_to_str!(metadata);
+
+    let runtime = match MySQLStorage::runtime_for(config) {
+        Err(err) => return err,
+        Ok(runtime) => runtime,
+    };
+
+    let (name, config, credentials, metadata) = (name.to_owned(), config.to_owned(), credentials.to_owned(), metadata.to_owned());
+
+    execute_call(runtime, move || MySQLStorage::create_storage(&name, &config, &credentials, &metadata))
+}
+
+#[no_mangle]
+pub extern "C" fn delete_storage(name: *const c_char, config: *const c_char, credentials: *const c_char) -> ErrorCode {
+
+    trace!("Delete Storage Called");
+
+    let name = c_char_to_str!(name);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    let runtime = match MySQLStorage::runtime_for(config) {
+        Err(err) => return err,
+        Ok(runtime) => runtime,
+    };
+
+    let (name, config, credentials) = (name.to_owned(), config.to_owned(), credentials.to_owned());
+
+    execute_call(runtime, move || MySQLStorage::delete_storage(&name, &config, &credentials))
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_rename_storage(name: *const c_char, new_name: *const c_char, config: *const c_char, credentials: *const c_char) -> ErrorCode {
+
+    trace!("Rename Storage Called");
+
+    let name = c_char_to_str!(name);
+    let new_name = c_char_to_str!(new_name);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    MySQLStorage::rename_storage(&name, &new_name, &config, &credentials)
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_copy_storage(name: *const c_char, new_name: *const c_char, config: *const c_char, credentials: *const c_char) -> ErrorCode {
+
+    trace!("Copy Storage Called");
+
+    let name = c_char_to_str!(name);
+    let new_name = c_char_to_str!(new_name);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    MySQLStorage::copy_storage(&name, &new_name, &config, &credentials)
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_move_storage(name: *const c_char,
+                                             source_config: *const c_char, source_credentials: *const c_char,
+                                             target_config: *const c_char, target_credentials: *const c_char) -> ErrorCode {
+
+    trace!("Move Storage Called");
+
+    let name = c_char_to_str!(name);
+
+    if source_config.is_null() || target_config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let source_config = c_char_to_str!(source_config);
+    let target_config = c_char_to_str!(target_config);
+
+    if source_credentials.is_null() || target_credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let source_credentials = c_char_to_str!(source_credentials);
+    let target_credentials = c_char_to_str!(target_credentials);
+
+    MySQLStorage::move_storage(&name, &source_config, &source_credentials, &target_config, &target_credentials)
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_rebalance_storage(name: *const c_char, shard_name: *const c_char, config: *const c_char, credentials: *const c_char) -> ErrorCode {
+
+    trace!("Rebalance Storage Called");
+
+    let name = c_char_to_str!(name);
+    let shard_name = if shard_name.is_null() { None } else { Some(c_char_to_str!(shard_name)) };
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    MySQLStorage::rebalance_storage(&name, shard_name, &config, &credentials)
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_list_storages(config: *const c_char, credentials: *const c_char, options_json_p: *const c_char, list_json_p: *mut *const c_char, list_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("List Storages Called");
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    let options_json = c_char_to_str!(options_json_p);
+
+    match MySQLStorage::list_storages(&config, &credentials, &options_json) {
+        Err(err) => err,
+        Ok(list_json) => return_json(list_json, list_json_p, list_handle_p)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_get_storage_stats(name: *const c_char, config: *const c_char, credentials: *const c_char, stats_json_p: *mut *const c_char, stats_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Get Storage Stats Called");
+
+    let name = c_char_to_str!(name);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    match MySQLStorage::get_storage_stats(&name, &config, &credentials) {
+        Err(err) => err,
+        Ok(stats_json) => return_json(stats_json, stats_json_p, stats_handle_p)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_verify_storage(name: *const c_char, config: *const c_char, credentials: *const c_char, report_json_p: *mut *const c_char, report_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Verify Storage Called");
+
+    let name = c_char_to_str!(name);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    match MySQLStorage::verify_storage(&name, &config, &credentials) {
+        Err(err) => err,
+        Ok(report_json) => return_json(report_json, report_json_p, report_handle_p)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_snapshot_storage(name: *const c_char, label: *const c_char, config: *const c_char, credentials: *const c_char) -> ErrorCode {
+
+    trace!("Snapshot Storage Called");
+
+    let name = c_char_to_str!(name);
+    let label = c_char_to_str!(label);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    MySQLStorage::snapshot_storage(&name, &label, &config, &credentials)
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_list_snapshots(name: *const c_char, config: *const c_char, credentials: *const c_char, list_json_p: *mut *const c_char, list_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("List Snapshots Called");
+
+    let name = c_char_to_str!(name);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    match MySQLStorage::list_snapshots(&name, &config, &credentials) {
+        Err(err) => err,
+        Ok(list_json) => return_json(list_json, list_json_p, list_handle_p)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_restore_snapshot(name: *const c_char, label: *const c_char, config: *const c_char, credentials: *const c_char) -> ErrorCode {
+
+    trace!("Restore Snapshot Called");
+
+    let name = c_char_to_str!(name);
+    let label = c_char_to_str!(label);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    MySQLStorage::restore_snapshot(&name, &label, &config, &credentials)
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_drop_snapshot(name: *const c_char, label: *const c_char, config: *const c_char, credentials: *const c_char) -> ErrorCode {
+
+    trace!("Drop Snapshot Called");
+
+    let name = c_char_to_str!(name);
+    let label = c_char_to_str!(label);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    MySQLStorage::drop_snapshot(&name, &label, &config, &credentials)
+}
+
+#[no_mangle]
+pub extern "C" fn open_storage(name: *const c_char, config: *const c_char, credentials: *const c_char, handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Open Storage Called");
+
+    let name = c_char_to_str!(name);
+
+    if config.is_null() {
+        warn!("Provided config JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let config = c_char_to_str!(config);
+
+    if credentials.is_null() {
+        warn!("Provided credentials JSON is NULL");
+        return ErrorCode::InvalidStructure;
+    }
+
+    let credentials = c_char_to_str!(credentials);
+
+    let runtime = match MySQLStorage::runtime_for(config) {
+        Err(err) => return err,
+        Ok(runtime) => runtime,
+    };
+
+    let (name, config, credentials) = (name.to_owned(), config.to_owned(), credentials.to_owned());
+
+    execute_handle_call(runtime, handle_p, move |handle_p| {
+        match MySQLStorage::open_storage(&name, &config, &credentials) {
+            Ok(storage) => {
+                unsafe { *handle_p = STORAGES.insert(storage); }
+                ErrorCode::Success
+            },
+            Err(err) => err
+        }
+    }, |handle| { STORAGES.remove(handle); })
+}
+
+#[no_mangle]
+pub extern "C" fn close_storage(storage_handle: i32) -> ErrorCode {
+
+    trace!("Close Storage Called");
+
+    if STORAGES.remove(storage_handle) {
+        ErrorCode::Success
+    }
+    else {
+        ErrorCode::InvalidState
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn add_record(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, value_p: *const u8, value_len: usize, tags_json_p: *const c_char) -> ErrorCode {
+
+    trace!("Add Record Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tags = c_char_to_str!(tags_json_p).to_owned();
+
+    let mut value: Vec<u8> = Vec::new();
+    unsafe { value.extend_from_slice(slice::from_raw_parts(value_p, value_len)); }
+
+    execute_call(storage.runtime(), move || storage.add_record(&type_, &id, &value, &tags))
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_upsert_record(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, value_p: *const u8, value_len: usize, tags_json_p: *const c_char, options_json_p: *const c_char, inserted_p: *mut bool) -> ErrorCode {
+
+    trace!("Upsert Record Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tags = c_char_to_str!(tags_json_p).to_owned();
+    let options = c_char_to_str!(options_json_p).to_owned();
+
+    let mut value: Vec<u8> = Vec::new();
+    unsafe { value.extend_from_slice(slice::from_raw_parts(value_p, value_len)); }
+
+    match execute(storage.runtime(), move || storage.upsert_record(&type_, &id, &value, &tags, &options)) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(inserted)) => {
+            unsafe { *inserted_p = inserted; }
+            ErrorCode::Success
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn get_record(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, options_json_p: *const c_char, record_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Get Record Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let options = c_char_to_str!(options_json_p).to_owned();
+
+    let cleanup = storage.clone();
+
+    execute_handle_call(storage.runtime(), record_handle_p, move |record_handle_p| storage.fetch_record(&type_, &id, &options, record_handle_p), move |record_handle| { cleanup.free_record(record_handle); })
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_fetch_records(storage_handle: i32, type_p: *const c_char, ids_json_p: *const c_char, options_json_p: *const c_char, search_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Fetch Records Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let ids: Vec<String> = check_result!(serde_json::from_str(c_char_to_str!(ids_json_p)), ErrorCode::InvalidStructure);
+    let options = c_char_to_str!(options_json_p).to_owned();
+
+    let cleanup = storage.clone();
+
+    execute_handle_call(storage.runtime(), search_handle_p, move |search_handle_p| storage.fetch_records(&type_, &ids, &options, search_handle_p), move |search_handle| { cleanup.free_search(search_handle); })
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_fetch_records_by_keys(storage_handle: i32, keys_json_p: *const c_char, options_json_p: *const c_char, search_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Fetch Records By Keys Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let keys: Vec<RecordKey> = check_result!(serde_json::from_str(c_char_to_str!(keys_json_p)), ErrorCode::InvalidStructure);
+    let options = c_char_to_str!(options_json_p).to_owned();
+
+    let cleanup = storage.clone();
+
+    execute_handle_call(storage.runtime(), search_handle_p, move |search_handle_p| storage.fetch_records_by_keys(&keys, &options, search_handle_p), move |search_handle| { cleanup.free_search(search_handle); })
+}
+
+#[no_mangle]
+pub extern "C" fn delete_record(storage_handle: i32, type_p: *const c_char, id_p: *const c_char) -> ErrorCode {
+
+    trace!("Delete Record Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+
+    execute_call(storage.runtime(), move || storage.delete_record(&type_, &id, None))
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_delete_record_with_version(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, expected_version: u64) -> ErrorCode {
+
+    trace!("Delete Record With Version Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+
+    execute_call(storage.runtime(), move || storage.delete_record(&type_, &id, Some(expected_version)))
+}
+
+#[no_mangle]
+pub extern "C" fn update_record_value(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, value_p: *const u8, value_len: usize) -> ErrorCode {
+
+    trace!("Update Record Value Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+
+    let mut value: Vec<u8> = Vec::new();
+    unsafe { value.extend_from_slice(slice::from_raw_parts(value_p, value_len)); }
+
+    execute_call(storage.runtime(), move || storage.update_record_value(&type_, &id, &value, None))
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_update_record_value_with_version(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, value_p: *const u8, value_len: usize, expected_version: u64) -> ErrorCode {
+
+    trace!("Update Record Value With Version Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+
+    let mut value: Vec<u8> = Vec::new();
+    unsafe { value.extend_from_slice(slice::from_raw_parts(value_p, value_len)); }
+
+    execute_call(storage.runtime(), move || storage.update_record_value(&type_, &id, &value, Some(expected_version)))
 }
 
 #[no_mangle]
-pub extern "C" fn create_storage(name: *const c_char, config: *const c_char, credentials: *const c_char, metadata: *const c_char) -> ErrorCode {
+pub extern "C" fn add_record_tags(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tags_json_p: *const c_char) -> ErrorCode {
 
-    trace!("Create Storage Called");
+    trace!("Add Record Tags Called");
 
-    let name = c_char_to_str!(name);
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    if config.is_null() {
-        warn!("Provided config JSON is NULL");
-        return ErrorCode::InvalidStructure;
-    }
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tags: HashMap<String, serde_json::Value> = check_result!(serde_json::from_str(c_char_to_str!(tags_json_p)), ErrorCode::InvalidStructure);
 
-    let config = c_char_to_str!(config);
+    execute_call(storage.runtime(), move || storage.add_record_tags(&type_, &id, &tags, None))
+}
 
-    if credentials.is_null() {
-        warn!("Provided credentials JSON is NULL");
-        return ErrorCode::InvalidStructure;
+#[no_mangle]
+pub extern "C" fn mysql_storage_add_record_tags_with_version(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tags_json_p: *const c_char, expected_version: u64) -> ErrorCode {
+
+    trace!("Add Record Tags With Version Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tags: HashMap<String, serde_json::Value> = check_result!(serde_json::from_str(c_char_to_str!(tags_json_p)), ErrorCode::InvalidStructure);
+
+    execute_call(storage.runtime(), move || storage.add_record_tags(&type_, &id, &tags, Some(expected_version)))
+}
+
+#[no_mangle]
+pub extern "C" fn update_record_tags(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tags_json_p: *const c_char) -> ErrorCode {
+
+    trace!("Update Record Tags Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tags = c_char_to_str!(tags_json_p).to_owned();
+
+    execute_call(storage.runtime(), move || storage.update_record_tags(&type_, &id, &tags, None))
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_update_record_tags_with_version(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tags_json_p: *const c_char, expected_version: u64) -> ErrorCode {
+
+    trace!("Update Record Tags With Version Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tags = c_char_to_str!(tags_json_p).to_owned();
+
+    execute_call(storage.runtime(), move || storage.update_record_tags(&type_, &id, &tags, Some(expected_version)))
+}
+
+#[no_mangle]
+pub extern "C" fn delete_record_tags(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tag_names_json_p: *const c_char) -> ErrorCode {
+
+    trace!("Delete Record Tags Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tag_names: Vec<String> = check_result!(serde_json::from_str(c_char_to_str!(tag_names_json_p)), ErrorCode::InvalidStructure);
+
+    execute_call(storage.runtime(), move || storage.delete_record_tags(&type_, &id, &tag_names, None))
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_delete_record_tags_with_version(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tag_names_json_p: *const c_char, expected_version: u64) -> ErrorCode {
+
+    trace!("Delete Record Tags With Version Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tag_names: Vec<String> = check_result!(serde_json::from_str(c_char_to_str!(tag_names_json_p)), ErrorCode::InvalidStructure);
+
+    execute_call(storage.runtime(), move || storage.delete_record_tags(&type_, &id, &tag_names, Some(expected_version)))
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_update_record(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, value_p: *const u8, value_len: usize, tags_json_p: *const c_char, tag_names_json_p: *const c_char, condition_json_p: *const c_char) -> ErrorCode {
+
+    trace!("Update Record Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+    let tags: HashMap<String, String> = check_result!(serde_json::from_str(c_char_to_str!(tags_json_p)), ErrorCode::InvalidStructure);
+    let tag_names: Vec<String> = check_result!(serde_json::from_str(c_char_to_str!(tag_names_json_p)), ErrorCode::InvalidStructure);
+
+    // value and condition are optional, a null pointer leaves the value unchanged and updates unconditionally
+    let value = if value_p.is_null() { None } else { Some(unsafe { slice::from_raw_parts(value_p, value_len) }.to_vec()) };
+
+    let condition_json = if condition_json_p.is_null() { None } else { Some(c_char_to_str!(condition_json_p).to_owned()) };
+
+    execute_call(storage.runtime(), move || storage.update_record(&type_, &id, value.as_ref(), &tags, &tag_names, condition_json.as_ref().map(String::as_str)))
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_delete_records_by_query(storage_handle: i32, type_p: *const c_char, query_json_p: *const c_char, affected_rows_p: *mut u64) -> ErrorCode {
+
+    trace!("Delete Records By Query Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let query_json = c_char_to_str!(query_json_p).to_owned();
+
+    match execute(storage.runtime(), move || storage.delete_records_by_query(&type_, &query_json)) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(affected_rows)) => {
+            unsafe { *affected_rows_p = affected_rows; }
+            ErrorCode::Success
+        }
     }
+}
 
-    let credentials = c_char_to_str!(credentials);
+#[no_mangle]
+pub extern "C" fn mysql_storage_update_tags_by_query(storage_handle: i32, type_p: *const c_char, query_json_p: *const c_char, tags_json_p: *const c_char, tag_names_json_p: *const c_char, affected_rows_p: *mut u64) -> ErrorCode {
 
-    let metadata = c_char_to_str!(metadata);
+    trace!("Update Tags By Query Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    MySQLStorage::create_storage(&name, &config, &credentials, &metadata)
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let query_json = c_char_to_str!(query_json_p).to_owned();
+    let tags: HashMap<String, String> = check_result!(serde_json::from_str(c_char_to_str!(tags_json_p)), ErrorCode::InvalidStructure);
+    let tag_names: Vec<String> = check_result!(serde_json::from_str(c_char_to_str!(tag_names_json_p)), ErrorCode::InvalidStructure);
+
+    match execute(storage.runtime(), move || storage.update_tags_by_query(&type_, &query_json, &tags, &tag_names)) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(affected_rows)) => {
+            unsafe { *affected_rows_p = affected_rows; }
+            ErrorCode::Success
+        }
+    }
 }
 
 #[no_mangle]
-pub extern "C" fn delete_storage(name: *const c_char, config: *const c_char, credentials: *const c_char) -> ErrorCode {
+pub extern "C" fn mysql_storage_read_changes(storage_handle: i32, since_seq: u64, limit: u64, changes_json_p: *mut *const c_char, changes_handle_p: *mut i32) -> ErrorCode {
 
-    trace!("Delete Storage Called");
+    trace!("Read Changes Called");
 
-    let name = c_char_to_str!(name);
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    if config.is_null() {
-        warn!("Provided config JSON is NULL");
-        return ErrorCode::InvalidStructure;
+    match execute(storage.runtime(), move || storage.read_changes(since_seq, limit)) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(changes_json)) => return_json(changes_json, changes_json_p, changes_handle_p)
     }
+}
 
-    let config = c_char_to_str!(config);
+#[no_mangle]
+pub extern "C" fn mysql_storage_trim_changes(storage_handle: i32, up_to_seq: u64, trimmed_p: *mut u64) -> ErrorCode {
 
-    if credentials.is_null() {
-        warn!("Provided credentials JSON is NULL");
-        return ErrorCode::InvalidStructure;
+    trace!("Trim Changes Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    match execute(storage.runtime(), move || storage.trim_changes(up_to_seq)) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(trimmed)) => {
+            unsafe { *trimmed_p = trimmed; }
+            ErrorCode::Success
+        }
     }
+}
 
-    let credentials = c_char_to_str!(credentials);
+#[no_mangle]
+pub extern "C" fn mysql_storage_rotate_data_key(storage_handle: i32, version_p: *mut u32) -> ErrorCode {
+
+    trace!("Rotate Data Key Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    MySQLStorage::delete_storage(&name, &config, &credentials)
+    match execute(storage.runtime(), move || storage.rotate_data_key()) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(version)) => {
+            unsafe { *version_p = version; }
+            ErrorCode::Success
+        }
+    }
 }
 
 #[no_mangle]
-pub extern "C" fn open_storage(name: *const c_char, config: *const c_char, credentials: *const c_char, handle_p: *mut i32) -> ErrorCode {
+pub extern "C" fn mysql_storage_rewrap_data_keys(name: *const c_char, config: *const c_char, credentials: *const c_char, rewrapped_p: *mut u64) -> ErrorCode {
 
-    trace!("Open Storage Called");
+    trace!("Rewrap Data Keys Called");
 
     let name = c_char_to_str!(name);
 
@@ -91,129 +847,157 @@ pub extern "C" fn open_storage(name: *const c_char, config: *const c_char, crede
 
     let credentials = c_char_to_str!(credentials);
 
-    let handle = match MySQLStorage::open_storage(&name, &config, &credentials) {
-        Ok(storage) => STORAGES.insert(storage),
-        Err(err) => return err
-    };
+    match MySQLStorage::rewrap_data_keys(&name, &config, &credentials) {
+        Err(err) => err,
+        Ok(rewrapped) => {
+            unsafe { *rewrapped_p = rewrapped; }
+            ErrorCode::Success
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_start_reencryption(storage_handle: i32) -> ErrorCode {
 
-    unsafe { *handle_p = handle; }
+    trace!("Start Re-encryption Called");
 
-    ErrorCode::Success
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    execute_call(storage.runtime(), move || storage.start_reencryption())
 }
 
 #[no_mangle]
-pub extern "C" fn close_storage(storage_handle: i32) -> ErrorCode {
+pub extern "C" fn mysql_storage_get_reencryption_status(storage_handle: i32, status_json_p: *mut *const c_char, status_handle_p: *mut i32) -> ErrorCode {
 
-    trace!("Close Storage Called");
+    trace!("Get Re-encryption Status Called");
 
-    if STORAGES.remove(storage_handle) {
-        ErrorCode::Success
-    }
-    else {
-        ErrorCode::InvalidState
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    match execute(storage.runtime(), move || storage.get_reencryption_status()) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(status_json)) => return_json(status_json, status_json_p, status_handle_p)
     }
 }
 
 #[no_mangle]
-pub extern "C" fn add_record(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, value_p: *const u8, value_len: usize, tags_json_p: *const c_char) -> ErrorCode {
+pub extern "C" fn mysql_storage_get_cache_stats(storage_handle: i32, stats_json_p: *mut *const c_char, stats_handle_p: *mut i32) -> ErrorCode {
 
-    trace!("Add Record Called");
+    trace!("Get Cache Stats Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    let type_ = c_char_to_str!(type_p);
-    let id = c_char_to_str!(id_p);
-    let tags = c_char_to_str!(tags_json_p);
+    return_json(storage.get_cache_stats(), stats_json_p, stats_handle_p)
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_open_value_reader(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, reader_handle_p: *mut i32) -> ErrorCode {
 
-    let mut value: Vec<u8> = Vec::new();
-    unsafe { value.extend_from_slice(slice::from_raw_parts(value_p, value_len)); }
+    trace!("Open Value Reader Called");
 
-    storage.add_record(&type_, &id, &value, &tags)
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
+
+    let cleanup = storage.clone();
+    let release = move |reader: Result<i32, ErrorCode>| if let Ok(reader_handle) = reader { cleanup.close_value_reader(reader_handle); };
+
+    match execute_or_discard(storage.runtime(), move || storage.open_value_reader(&type_, &id), release) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(reader_handle)) => {
+            unsafe { *reader_handle_p = reader_handle; }
+            ErrorCode::Success
+        }
+    }
 }
 
 #[no_mangle]
-pub extern "C" fn get_record(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, options_json_p: *const c_char, record_handle_p: *mut i32) -> ErrorCode {
+pub extern "C" fn mysql_storage_read_value(storage_handle: i32, reader_handle: i32, buffer_p: *mut u8, buffer_len: usize, read_len_p: *mut usize) -> ErrorCode {
 
-    trace!("Get Record Called");
+    trace!("Read Value Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    let type_ = c_char_to_str!(type_p);
-    let id = c_char_to_str!(id_p);
-    let options = c_char_to_str!(options_json_p);
+    // the value is read into an owned buffer on the runtime, then copied to the caller's buffer
+    let result = execute(storage.runtime(), move || {
+        let mut buffer = vec![0; buffer_len];
+        storage.read_value(reader_handle, &mut buffer).map(|read_len| { buffer.truncate(read_len); buffer })
+    });
 
-    storage.fetch_record(type_, id, options, record_handle_p)
+    match result {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(data)) => {
+            unsafe {
+                slice::from_raw_parts_mut(buffer_p, buffer_len)[..data.len()].copy_from_slice(&data);
+                *read_len_p = data.len();
+            }
+            ErrorCode::Success
+        }
+    }
 }
 
 #[no_mangle]
-pub extern "C" fn delete_record(storage_handle: i32, type_p: *const c_char, id_p: *const c_char) -> ErrorCode {
+pub extern "C" fn mysql_storage_close_value_reader(storage_handle: i32, reader_handle: i32) -> ErrorCode {
 
-    trace!("Delete Record Called");
+    trace!("Close Value Reader Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    let type_ = c_char_to_str!(type_p);
-    let id = c_char_to_str!(id_p);
-
-    storage.delete_record(&type_, &id)
+    execute_call(storage.runtime(), move || storage.close_value_reader(reader_handle))
 }
 
 #[no_mangle]
-pub extern "C" fn update_record_value(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, value_p: *const u8, value_len: usize) -> ErrorCode {
+pub extern "C" fn mysql_storage_open_value_writer(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, writer_handle_p: *mut i32) -> ErrorCode {
 
-    trace!("Update Record Value Called");
+    trace!("Open Value Writer Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    let type_ = c_char_to_str!(type_p);
-    let id = c_char_to_str!(id_p);
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let id = c_char_to_str!(id_p).to_owned();
 
-    let mut value: Vec<u8> = Vec::new();
-    unsafe { value.extend_from_slice(slice::from_raw_parts(value_p, value_len)); }
+    let cleanup = storage.clone();
+    let release = move |writer: Result<i32, ErrorCode>| if let Ok(writer_handle) = writer { cleanup.abort_value_writer(writer_handle); };
 
-    storage.update_record_value(&type_, &id, &value)
+    match execute_or_discard(storage.runtime(), move || storage.open_value_writer(&type_, &id), release) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(writer_handle)) => {
+            unsafe { *writer_handle_p = writer_handle; }
+            ErrorCode::Success
+        }
+    }
 }
 
 #[no_mangle]
-pub extern "C" fn add_record_tags(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tags_json_p: *const c_char) -> ErrorCode {
+pub extern "C" fn mysql_storage_write_value(storage_handle: i32, writer_handle: i32, data_p: *const u8, data_len: usize) -> ErrorCode {
 
-    trace!("Add Record Tags Called");
+    trace!("Write Value Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    let type_ = c_char_to_str!(type_p);
-    let id = c_char_to_str!(id_p);
-    let tags: HashMap<String, serde_json::Value> = check_result!(serde_json::from_str(c_char_to_str!(tags_json_p)), ErrorCode::InvalidStructure);
+    let data = unsafe { slice::from_raw_parts(data_p, data_len) }.to_vec();
 
-    storage.add_record_tags(&type_, &id, &tags)
+    execute_call(storage.runtime(), move || storage.write_value(writer_handle, &data))
 }
 
 #[no_mangle]
-pub extern "C" fn update_record_tags(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tags_json_p: *const c_char) -> ErrorCode {
+pub extern "C" fn mysql_storage_finish_value_writer(storage_handle: i32, writer_handle: i32) -> ErrorCode {
 
-    trace!("Update Record Tags Called");
+    trace!("Finish Value Writer Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    let type_ = c_char_to_str!(type_p);
-    let id = c_char_to_str!(id_p);
-    let tags = c_char_to_str!(tags_json_p);
-
-    storage.update_record_tags(&type_, &id, &tags)
+    execute_call(storage.runtime(), move || storage.finish_value_writer(writer_handle))
 }
 
 #[no_mangle]
-pub extern "C" fn delete_record_tags(storage_handle: i32, type_p: *const c_char, id_p: *const c_char, tag_names_json_p: *const c_char) -> ErrorCode {
+pub extern "C" fn mysql_storage_abort_value_writer(storage_handle: i32, writer_handle: i32) -> ErrorCode {
 
-    trace!("Delete Record Tags Called");
+    trace!("Abort Value Writer Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    let type_ = c_char_to_str!(type_p);
-    let id = c_char_to_str!(id_p);
-    let tag_names: Vec<String> = check_result!(serde_json::from_str(c_char_to_str!(tag_names_json_p)), ErrorCode::InvalidStructure);
-
-    storage.delete_record_tags(&type_, &id, &tag_names)
+    execute_call(storage.runtime(), move || storage.abort_value_writer(writer_handle))
 }
 
 #[no_mangle]
@@ -245,6 +1029,23 @@ pub extern "C" fn get_record_id(storage_handle: i32, record_handle: i32, id_p: *
     ErrorCode::Success
 }
 
+#[no_mangle]
+pub extern "C" fn mysql_storage_get_record_version(storage_handle: i32, record_handle: i32, version_p: *mut u64) -> ErrorCode {
+
+    trace!("Get Record Version Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+    let record = check_option!(storage.get_record(record_handle), ErrorCode::InvalidState);
+
+    match record.version {
+        None => ErrorCode::InvalidState,
+        Some(version) => {
+            unsafe { *version_p = version; }
+            ErrorCode::Success
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn get_record_value(storage_handle: i32, record_handle: i32, value_p: *mut *const u8, value_len_p: *mut usize) -> ErrorCode {
 
@@ -297,7 +1098,13 @@ pub extern "C" fn get_metadata(storage_handle: i32, metadata_ptr: *mut *const c_
     trace!("Get Metadata Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
-    let metadata = storage.get_metadata();
+    let cleanup = storage.clone();
+    let release = move |metadata: Result<(Arc<CString>, i32), ErrorCode>| if let Ok((_, metadata_handle)) = metadata { cleanup.free_metadata(metadata_handle); };
+
+    let metadata = match execute_or_discard(storage.runtime(), move || storage.get_metadata(), release) {
+        Err(err) => return err,
+        Ok(metadata) => metadata,
+    };
 
     match metadata {
         Err(e) => e,
@@ -317,8 +1124,8 @@ pub extern "C" fn set_metadata(storage_handle: i32, metadata_ptr: *const c_char)
     trace!("Set Metadata Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
-    let metadata = c_char_to_str!(metadata_ptr);
-    storage.set_metadata(metadata)
+    let metadata = c_char_to_str!(metadata_ptr).to_owned();
+    execute_call(storage.runtime(), move || storage.set_metadata(&metadata))
 }
 
 #[no_mangle]
@@ -337,11 +1144,29 @@ pub extern "C" fn search_records(storage_handle: i32, type_p: *const c_char, que
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    let query_json = c_char_to_str!(query_json_p);
-    let options_json = c_char_to_str!(options_json_p);
-    let type_ = c_char_to_str!(type_p);
+    let query_json = c_char_to_str!(query_json_p).to_owned();
+    let options_json = c_char_to_str!(options_json_p).to_owned();
+    let type_ = c_char_to_str!(type_p).to_owned();
+
+    let cleanup = storage.clone();
+
+    execute_handle_call(storage.runtime(), search_handle_p, move |search_handle_p| storage.search_records(&type_, &query_json, &options_json, search_handle_p), move |search_handle| { cleanup.free_search(search_handle); })
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_search_records_by_types(storage_handle: i32, types_json_p: *const c_char, query_json_p: *const c_char, options_json_p: *const c_char, search_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Search Records By Types Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let types_json = c_char_to_str!(types_json_p).to_owned();
+    let query_json = c_char_to_str!(query_json_p).to_owned();
+    let options_json = c_char_to_str!(options_json_p).to_owned();
 
-    storage.search_records(type_, query_json, options_json, search_handle_p)
+    let cleanup = storage.clone();
+
+    execute_handle_call(storage.runtime(), search_handle_p, move |search_handle_p| storage.search_records_by_types(&types_json, &query_json, &options_json, search_handle_p), move |search_handle| { cleanup.free_search(search_handle); })
 }
 
 #[no_mangle]
@@ -351,7 +1176,24 @@ pub extern "C" fn search_all_records(storage_handle: i32, search_handle_p: *mut
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
 
-    storage.search_all_records(search_handle_p)
+    let cleanup = storage.clone();
+
+    execute_handle_call(storage.runtime(), search_handle_p, move |search_handle_p| storage.search_all_records(search_handle_p), move |search_handle| { cleanup.free_search(search_handle); })
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_search_all_records_with_options(storage_handle: i32, query_json_p: *const c_char, options_json_p: *const c_char, search_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Search All Records With Options Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let query_json = c_char_to_str!(query_json_p).to_owned();
+    let options_json = c_char_to_str!(options_json_p).to_owned();
+
+    let cleanup = storage.clone();
+
+    execute_handle_call(storage.runtime(), search_handle_p, move |search_handle_p| storage.search_all_records_with_options(&query_json, &options_json, search_handle_p), move |search_handle| { cleanup.free_search(search_handle); })
 }
 
 #[no_mangle]
@@ -363,13 +1205,24 @@ pub extern "C" fn get_search_total_count(storage_handle: i32, search_handle: i32
     storage.get_search_total_count(search_handle, total_count_p)
 }
 
+#[no_mangle]
+pub extern "C" fn mysql_storage_get_search_missing_keys(storage_handle: i32, search_handle: i32, missing_keys_json_p: *mut *const c_char) -> ErrorCode {
+
+    trace!("Get Search Missing Keys Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+    storage.get_search_missing_keys(search_handle, missing_keys_json_p)
+}
+
 #[no_mangle]
 pub extern "C" fn fetch_search_next_record(storage_handle: i32, search_handle: i32, record_handle_p: *mut i32) -> ErrorCode {
 
     trace!("Fetch Next Record Called");
 
     let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
-    storage.fetch_search_next_record(search_handle, record_handle_p)
+    let cleanup = storage.clone();
+
+    execute_handle_call(storage.runtime(), record_handle_p, move |record_handle_p| storage.fetch_search_next_record(search_handle, record_handle_p), move |record_handle| { cleanup.free_record(record_handle); })
 }
 
 #[no_mangle]
@@ -382,3 +1235,49 @@ pub extern "C" fn free_search(storage_handle: i32, search_handle: i32) -> ErrorC
         Some(storage) => storage.free_search(search_handle)
     }
 }
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_explain_search(storage_handle: i32, type_p: *const c_char, query_json_p: *const c_char, options_json_p: *const c_char, explain_json_p: *mut *const c_char, explain_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Explain Search Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let type_ = c_char_to_str!(type_p).to_owned();
+    let query_json = c_char_to_str!(query_json_p).to_owned();
+    let options_json = c_char_to_str!(options_json_p).to_owned();
+
+    match execute(storage.runtime(), move || storage.explain_search(&type_, &query_json, &options_json)) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(explain_json)) => return_json(explain_json, explain_json_p, explain_handle_p)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_aggregate_records(storage_handle: i32, query_json_p: *const c_char, options_json_p: *const c_char, aggregate_json_p: *mut *const c_char, aggregate_handle_p: *mut i32) -> ErrorCode {
+
+    trace!("Aggregate Records Called");
+
+    let storage = check_option!(STORAGES.get(storage_handle), ErrorCode::InvalidState);
+
+    let query_json = c_char_to_str!(query_json_p).to_owned();
+    let options_json = c_char_to_str!(options_json_p).to_owned();
+
+    match execute(storage.runtime(), move || storage.aggregate_records(&query_json, &options_json)) {
+        Err(err) | Ok(Err(err)) => err,
+        Ok(Ok(aggregate_json)) => return_json(aggregate_json, aggregate_json_p, aggregate_handle_p)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn mysql_storage_free_json(json_handle: i32) -> ErrorCode {
+
+    trace!("Free JSON Called");
+
+    if JSON_RESULTS.remove(json_handle) {
+        ErrorCode::Success
+    }
+    else {
+        ErrorCode::InvalidState
+    }
+}
@@ -37,6 +37,67 @@ pub enum ErrorCode {
     /// Entity already exists in the wallet.
     /// </summary>
     ItemAlreadyExists = 213,
+
+    /// <summary>
+    /// WQL query exceeds one of the configured query limits (depth, operator count, `$in` size, SQL length)
+    /// </summary>
+    QueryLimitExceeded = 1000,
+
+    /// <summary>
+    /// Version of the record does not match the version expected by the caller
+    /// </summary>
+    VersionConflict = 1001,
+
+    /// <summary>
+    /// Record does not match the condition of a conditional update
+    /// </summary>
+    ConditionNotMet = 1002,
+
+    /// <summary>
+    /// Record count or checksum of a copied wallet does not match the source wallet
+    /// </summary>
+    VerificationFailed = 1003,
+
+    /// <summary>
+    /// Wallet has no snapshot with the requested label
+    /// </summary>
+    SnapshotNotFound = 1004,
+
+    /// <summary>
+    /// Wallet already has a snapshot with the given label
+    /// </summary>
+    SnapshotAlreadyExists = 1005,
+
+    /// <summary>
+    /// Record value exceeds the configured maximum value size
+    /// </summary>
+    ValueTooLarge = 1006,
+
+    /// <summary>
+    /// Key-encryption key needed to unwrap a data key of the wallet is not in the configured keys
+    /// </summary>
+    EncryptionKeyNotFound = 1007,
+
+    /// <summary>
+    /// Stored value, metadata or data key could not be decrypted, it was changed or encrypted with another key
+    /// </summary>
+    DecryptionFailed = 1008,
+
+    /// <summary>
+    /// Call or statement did not complete within its timeout. A timed out statement is rolled back,
+    /// while a call timed out by the worker runtime may still complete later
+    /// </summary>
+    Timeout = 1009,
+
+    /// <summary>
+    /// Queue of the calls waiting for a worker is full
+    /// </summary>
+    QueueFull = 1010,
+
+    /// <summary>
+    /// Wallet was moved, but could not be deleted from the source DB, the moved wallet is kept
+    /// </summary>
+    SourceNotDeleted = 1011,
 }
 
 macro_rules! check_result {
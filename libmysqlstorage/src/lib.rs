@@ -1,5 +1,6 @@
 extern crate libc;
 
+#[macro_use]
 extern crate serde_json;
 
 #[macro_use]
@@ -14,11 +15,16 @@ extern crate lazy_static;
 #[macro_use]
 extern crate log;
 
-pub mod utils;
+extern crate flate2;
+extern crate zstd;
+extern crate ring;
+extern crate base64;
 
 #[macro_use]
 pub mod errors;
 
+pub mod utils;
+
 pub mod api;
 
 mod mysql_storage;
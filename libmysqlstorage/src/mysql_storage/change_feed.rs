@@ -0,0 +1,217 @@
+use mysql::{Pool, PooledConn, Transaction, QueryResult, Params, Error};
+
+use mysql_storage::Tables;
+use mysql_storage::query_translator::{self, TypeFilter, Operator};
+use utils::multi_pool::QueryLimits;
+use errors::error_code::ErrorCode;
+
+///
+/// Kind of a journaled record change, stored in the `operation` column.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeOperation {
+    Add,
+    Update,
+    Delete,
+}
+
+impl ChangeOperation {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ChangeOperation::Add => "add",
+            ChangeOperation::Update => "update",
+            ChangeOperation::Delete => "delete",
+        }
+    }
+}
+
+enum Connection {
+    Pooled(PooledConn),
+    Transaction(Transaction<'static>),
+}
+
+///
+/// State of the journal of a wallet for the duration of a transaction.
+///
+struct Journal {
+    wallets_table: String,
+    items_table: String,
+    changes_table: String,
+    wallet_id: u64,
+    /// `change_seq` of the wallet when the transaction started
+    first_seq: u64,
+    /// sequence number of the last change journaled by the transaction
+    last_seq: u64,
+}
+
+///
+/// Connection the record mutations of a wallet are executed on.
+///
+/// With the change feed enabled the mutation runs in a transaction that first locks the `change_seq` of the wallet,
+/// so the changes of a wallet are journaled in the order their transactions commit. Without it the statements
+/// are executed directly and the `log_*` calls do nothing.
+///
+pub struct Writer {
+    connection: Connection,
+    journal: Option<Journal>,
+}
+
+impl Writer {
+    pub fn start(pool: &Pool, tables: &Tables, wallet_id: u64, journal_changes: bool) -> Result<Writer, ErrorCode> {
+        if !journal_changes {
+            let connection = check_query!(pool.get_conn(), Err(ErrorCode::IOError));
+            return Ok(Writer { connection: Connection::Pooled(connection), journal: None });
+        }
+
+        Writer::start_transaction(pool, tables, wallet_id, true)
+    }
+
+    ///
+    /// Writer that always runs in a transaction, for the mutations made of several statements.
+    ///
+    pub fn start_transaction(pool: &Pool, tables: &Tables, wallet_id: u64, journal_changes: bool) -> Result<Writer, ErrorCode> {
+        let transaction = check_query!(pool.start_transaction(false, None, None), Err(ErrorCode::IOError));
+
+        Writer::from_transaction(transaction, tables, wallet_id, journal_changes)
+    }
+
+    ///
+    /// Writer over a transaction that is already started, e.g. one that has looked up the wallet by name.
+    ///
+    pub fn from_transaction(transaction: Transaction<'static>, tables: &Tables, wallet_id: u64, journal_changes: bool) -> Result<Writer, ErrorCode> {
+        let mut writer = Writer { connection: Connection::Transaction(transaction), journal: None };
+
+        if !journal_changes {
+            return Ok(writer);
+        }
+
+        let change_seq: u64 = {
+            let mut result = check_query!(
+                writer.prep_exec(
+                    format!("SELECT change_seq FROM {} WHERE id = :wallet_id FOR UPDATE", tables.wallets),
+                    params!{
+                        wallet_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::WalletNotFound)), Err(ErrorCode::IOError));
+
+            check_option!(row.get(0), Err(ErrorCode::InvalidState))
+        };
+
+        writer.journal = Some(Journal {
+            wallets_table: tables.wallets.clone(),
+            items_table: tables.items.clone(),
+            changes_table: tables.changes.clone(),
+            wallet_id,
+            first_seq: change_seq,
+            last_seq: change_seq,
+        });
+
+        Ok(writer)
+    }
+
+    pub fn prep_exec<A: AsRef<str>, T: Into<Params>>(&mut self, query: A, params: T) -> Result<QueryResult<'_>, Error> {
+        match self.connection {
+            Connection::Pooled(ref mut connection) => connection.prep_exec(query, params),
+            Connection::Transaction(ref mut transaction) => transaction.prep_exec(query, params),
+        }
+    }
+
+    ///
+    /// Journals a change of a single record. The version of the record is read after the mutation,
+    /// so it is NULL for a deleted record.
+    ///
+    pub fn log_record(&mut self, operation: ChangeOperation, type_: &str, id: &str) -> Result<(), ErrorCode> {
+        let (query, wallet_id, seq) = match self.journal {
+            None => return Ok(()),
+            Some(ref journal) => (
+                format!("INSERT INTO {} (wallet_id, seq, type, name, operation, version) \
+                         VALUES (:wallet_id, :seq, :type, :name, :operation, \
+                            (SELECT version FROM {} WHERE wallet_id = :wallet_id AND type = :type AND name = :name))",
+                        journal.changes_table, journal.items_table),
+                journal.wallet_id,
+                journal.last_seq + 1
+            ),
+        };
+
+        check_query!(
+            self.prep_exec(
+                query,
+                params!{
+                    "wallet_id" => wallet_id,
+                    "seq" => seq,
+                    "type" => type_,
+                    "name" => id,
+                    "operation" => operation.as_str()
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        if let Some(ref mut journal) = self.journal {
+            journal.last_seq = seq;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Journals a change of every record matching the query, called before the mutation so the records
+    /// are matched in their current state. See `query_translator::wql_to_sql_log_changes` for the `version_increment`.
+    ///
+    pub fn log_query(&mut self, operation: ChangeOperation, type_filter: &TypeFilter, wql: &Operator, version_increment: Option<u64>, limits: &QueryLimits) -> Result<(), ErrorCode> {
+        let (query, arguments) = match self.journal {
+            None => return Ok(()),
+            Some(ref journal) => query_translator::wql_to_sql_log_changes(
+                journal.wallet_id, &journal.items_table, &journal.changes_table, type_filter, wql,
+                operation.as_str(), version_increment, journal.last_seq, limits
+            )?,
+        };
+
+        let logged = check_query!(self.prep_exec(query, arguments), Err(ErrorCode::IOError)).affected_rows();
+
+        if let Some(ref mut journal) = self.journal {
+            journal.last_seq += logged;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Stores the `change_seq` of the wallet and commits the transaction, if there is one.
+    ///
+    pub fn commit(mut self) -> Result<(), ErrorCode> {
+        let update = match self.journal {
+            Some(ref journal) if journal.last_seq != journal.first_seq => Some((
+                format!("UPDATE {} SET change_seq = :change_seq WHERE id = :wallet_id", journal.wallets_table),
+                journal.last_seq,
+                journal.wallet_id
+            )),
+            _ => None,
+        };
+
+        if let Some((query, change_seq, wallet_id)) = update {
+            check_query!(
+                self.prep_exec(
+                    query,
+                    params!{
+                        change_seq,
+                        wallet_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+        }
+
+        match self.connection {
+            Connection::Pooled(_) => Ok(()),
+            Connection::Transaction(transaction) => {
+                check_query!(transaction.commit(), Err(ErrorCode::IOError));
+                Ok(())
+            },
+        }
+    }
+}
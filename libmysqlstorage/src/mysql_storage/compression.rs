@@ -0,0 +1,120 @@
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use zstd;
+
+use utils::multi_pool::{CompressionConfig, CompressionAlgorithm};
+use errors::error_code::ErrorCode;
+
+///
+/// Encodings of a stored value, kept in the `value_encoding` column of its record.
+///
+/// The encoding can not be a prefix of the value itself, since the values written by libindy are ciphertexts
+/// that may start with any byte, so the values stored before compression was enabled could not be told apart.
+///
+pub const RAW: u8 = 0;
+pub const DEFLATE: u8 = 1;
+pub const ZSTD: u8 = 2;
+
+///
+/// Compresses a value of a record type that is configured for compression.
+///
+/// # Returns
+///
+///  * `(Cow<[u8]>, u8)` - the value to store and its encoding, the value is stored as given
+///     if it is too short or does not get any shorter
+///
+pub fn compress<'v>(type_: &str, value: &'v [u8], config: &CompressionConfig) -> (Cow<'v, [u8]>, u8) {
+    let algorithm = match config.types.get(type_) {
+        Some(algorithm) if value.len() >= config.min_size => *algorithm,
+        _ => return (Cow::Borrowed(value), RAW),
+    };
+
+    let (compressed, encoding) = match algorithm {
+        CompressionAlgorithm::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            (encoder.write_all(value).and_then(|_| encoder.finish()), DEFLATE)
+        },
+        CompressionAlgorithm::Zstd => (zstd::encode_all(value, 0), ZSTD),
+    };
+
+    match compressed {
+        Ok(compressed) => if compressed.len() < value.len() { (Cow::Owned(compressed), encoding) } else { (Cow::Borrowed(value), RAW) },
+        Err(err) => {
+            warn!("Error while compressing a value of the type: {}, the value is stored uncompressed. Err: {:?}", type_, err);
+            (Cow::Borrowed(value), RAW)
+        }
+    }
+}
+
+///
+/// Restores a value read from the DB as it was given to the storage.
+///
+pub fn decompress(value: Vec<u8>, encoding: u8) -> Result<Vec<u8>, ErrorCode> {
+    let decompressed = match encoding {
+        RAW => return Ok(value),
+        DEFLATE => {
+            let mut decompressed = Vec::new();
+            DeflateDecoder::new(&value[..]).read_to_end(&mut decompressed).map(|_| decompressed)
+        },
+        ZSTD => zstd::decode_all(&value[..]),
+        _ => {
+            warn!("Unknown value encoding: {}", encoding);
+            return Err(ErrorCode::InvalidState);
+        }
+    };
+
+    Ok(check_result!(decompressed, Err(ErrorCode::InvalidState)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(algorithm: CompressionAlgorithm) -> CompressionConfig {
+        let mut types = HashMap::new();
+        types.insert("compressed".to_string(), algorithm);
+
+        CompressionConfig { types, min_size: 16 }
+    }
+
+    #[test]
+    fn compress_round_trips() {
+        let value = vec![7u8; 1000];
+
+        for &(algorithm, expected_encoding) in &[(CompressionAlgorithm::Deflate, DEFLATE), (CompressionAlgorithm::Zstd, ZSTD)] {
+            let (stored, encoding) = compress("compressed", &value, &config(algorithm));
+
+            assert_eq!(encoding, expected_encoding);
+            assert!(stored.len() < value.len());
+            assert_eq!(decompress(stored.into_owned(), encoding).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn compress_skips_other_types_and_short_values() {
+        let config = config(CompressionAlgorithm::Zstd);
+
+        assert_eq!(compress("other", &[7u8; 1000], &config).1, RAW);
+        assert_eq!(compress("compressed", &[7u8; 15], &config).1, RAW);
+    }
+
+    #[test]
+    fn compress_keeps_values_that_do_not_shrink() {
+        let value: Vec<u8> = (0..32).collect();
+
+        let (stored, encoding) = compress("compressed", &value, &config(CompressionAlgorithm::Deflate));
+
+        assert_eq!(encoding, RAW);
+        assert_eq!(&stored[..], &value[..]);
+    }
+
+    #[test]
+    fn decompress_rejects_unknown_encoding() {
+        assert_eq!(decompress(vec![1, 2, 3], 9).unwrap_err(), ErrorCode::InvalidState);
+        assert_eq!(decompress(vec![1, 2, 3], RAW).unwrap(), vec![1, 2, 3]);
+    }
+}
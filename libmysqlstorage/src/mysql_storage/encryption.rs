@@ -0,0 +1,543 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{RwLock, Arc};
+use mysql::Pool;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use base64;
+use serde_json;
+
+use mysql_storage::Tables;
+use mysql_storage::change_feed::Writer;
+use utils::multi_pool::EncryptionConfig;
+use errors::error_code::ErrorCode;
+
+///
+/// Flag of the `value_encoding` and `metadata_encoding` columns, set when the stored data is encrypted.
+/// The other bits of `value_encoding` hold the compression, which is applied before the encryption.
+///
+pub const ENCRYPTED: u8 = 0x80;
+
+/// additional authenticated data of the encrypted record values
+pub const VALUE: &'static [u8] = b"value";
+/// additional authenticated data of the encrypted metadata
+pub const METADATA: &'static [u8] = b"metadata";
+/// additional authenticated data of the wrapped data keys
+const DATA_KEY: &'static [u8] = b"data key";
+
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+const VERSION_LEN: usize = 4;
+
+///
+/// Key-encryption keys (KEKs) by id, from the config and the key file.
+///
+pub struct KeyEncryptionKeys {
+    current: String,
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl KeyEncryptionKeys {
+    ///
+    /// Loads the KEKs, None if the config does not enable encryption.
+    ///
+    pub fn from_config(config: &EncryptionConfig) -> Result<Option<KeyEncryptionKeys>, ErrorCode> {
+        let current = match config.current_key {
+            None => return Ok(None),
+            Some(ref current) => current.clone(),
+        };
+
+        let mut encoded_keys = config.keys.clone();
+
+        if let Some(ref key_file) = config.key_file {
+            let content = check_result!(fs::read_to_string(key_file), Err(ErrorCode::InvalidStructure));
+            let file_keys: HashMap<String, String> = check_result!(serde_json::from_str(&content), Err(ErrorCode::InvalidStructure));
+
+            encoded_keys.extend(file_keys);
+        }
+
+        let mut keys = HashMap::new();
+
+        // the decoding errors are not logged, they may hold parts of the keys
+        for (id, encoded_key) in encoded_keys {
+            match base64::decode(&encoded_key) {
+                Ok(ref key) if key.len() == KEY_LEN => { keys.insert(id, key.clone()); },
+                _ => {
+                    warn!("Key-encryption key {} is not a base64 encoded 256-bit key", id);
+                    return Err(ErrorCode::InvalidStructure);
+                }
+            }
+        }
+
+        if !keys.contains_key(&current) {
+            warn!("Current key-encryption key {} is not one of the configured keys", current);
+            return Err(ErrorCode::EncryptionKeyNotFound);
+        }
+
+        Ok(Some(KeyEncryptionKeys { current, keys }))
+    }
+
+    ///
+    /// Wraps a data key with the current KEK.
+    ///
+    /// # Returns
+    ///
+    ///  * `(String, Vec<u8>)` - id of the KEK and the wrapped key
+    ///
+    fn wrap(&self, data_key: &[u8], version: u32) -> Result<(String, Vec<u8>), ErrorCode> {
+        let kek = check_option!(self.keys.get(&self.current), Err(ErrorCode::EncryptionKeyNotFound));
+
+        Ok((self.current.clone(), seal(kek, &aad(DATA_KEY, version), data_key)?))
+    }
+
+    fn unwrap(&self, kek_id: &str, wrapped_key: &[u8], version: u32) -> Result<Vec<u8>, ErrorCode> {
+        let kek = match self.keys.get(kek_id) {
+            None => {
+                warn!("Key-encryption key {} of the data key version {} is not one of the configured keys", kek_id, version);
+                return Err(ErrorCode::EncryptionKeyNotFound);
+            },
+            Some(kek) => kek,
+        };
+
+        open(kek, &aad(DATA_KEY, version), wrapped_key)
+    }
+}
+
+fn aad(label: &[u8], version: u32) -> Vec<u8> {
+    let mut aad = label.to_vec();
+    aad.extend_from_slice(&version_bytes(version));
+    aad
+}
+
+fn version_bytes(version: u32) -> [u8; VERSION_LEN] {
+    [(version >> 24) as u8, (version >> 16) as u8, (version >> 8) as u8, version as u8]
+}
+
+///
+/// Version of the data key that encrypted an envelope, None if it is too short to be one.
+///
+pub fn envelope_version(envelope: &[u8]) -> Option<u32> {
+    if envelope.len() < VERSION_LEN {
+        return None;
+    }
+
+    Some(envelope[..VERSION_LEN].iter().fold(0u32, |version, &byte| (version << 8) | byte as u32))
+}
+
+///
+/// AES-256-GCM encryption with a random nonce.
+///
+/// # Returns
+///
+///  * `Vec<u8>` - nonce followed by the ciphertext and the tag
+///
+fn seal(key: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ErrorCode> {
+    let key = LessSafeKey::new(check_result!(UnboundKey::new(&AES_256_GCM, key), Err(ErrorCode::InvalidState)));
+
+    let mut nonce = [0u8; NONCE_LEN];
+    check_result!(SystemRandom::new().fill(&mut nonce), Err(ErrorCode::InvalidState));
+
+    let mut in_out = plaintext.to_vec();
+    check_result!(key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad), &mut in_out), Err(ErrorCode::InvalidState));
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(in_out);
+
+    Ok(sealed)
+}
+
+fn open(key: &[u8], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, ErrorCode> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        warn!("Encrypted data of {} bytes is too short", sealed.len());
+        return Err(ErrorCode::DecryptionFailed);
+    }
+
+    let key = LessSafeKey::new(check_result!(UnboundKey::new(&AES_256_GCM, key), Err(ErrorCode::InvalidState)));
+    let nonce = check_result!(Nonce::try_assume_unique_for_key(&sealed[..NONCE_LEN]), Err(ErrorCode::DecryptionFailed));
+
+    let mut in_out = sealed[NONCE_LEN..].to_vec();
+    let plaintext_len = check_result!(key.open_in_place(nonce, Aad::from(aad), &mut in_out), Err(ErrorCode::DecryptionFailed)).len();
+    in_out.truncate(plaintext_len);
+
+    Ok(in_out)
+}
+
+///
+/// Unwrapped data keys of a wallet by version, new data is encrypted with the `current` one.
+///
+struct DataKeys {
+    current: u32,
+    keys: HashMap<u32, Vec<u8>>,
+}
+
+///
+/// Envelope encryption of the data of a wallet.
+///
+/// Data is encrypted with the latest data key of the wallet, into an envelope starting with the version of the key,
+/// so the data encrypted with the older keys stays readable. The data keys are stored in the `wallet_data_keys`
+/// table wrapped by a KEK and are never deleted, since snapshots may still hold data encrypted with them.
+///
+pub struct Encryption {
+    keks: KeyEncryptionKeys,
+    pool: Arc<Pool>,
+    tables: Tables,
+    wallet_id: u64,
+    data_keys: RwLock<DataKeys>,
+}
+
+impl Encryption {
+    ///
+    /// Loads the data keys of a wallet, the first data key is created for a wallet that has none yet.
+    ///
+    pub fn open(keks: KeyEncryptionKeys, pool: Arc<Pool>, tables: &Tables, wallet_id: u64) -> Result<Encryption, ErrorCode> {
+        let mut data_keys = load_data_keys(&keks, &pool, tables, wallet_id)?;
+
+        if data_keys.current == 0 {
+            create_data_key(&keks, &pool, tables, wallet_id, true)?;
+            data_keys = load_data_keys(&keks, &pool, tables, wallet_id)?;
+        }
+
+        if !data_keys.keys.contains_key(&data_keys.current) {
+            warn!("Current data key version {} of the wallet {} can not be unwrapped", data_keys.current, wallet_id);
+            return Err(ErrorCode::EncryptionKeyNotFound);
+        }
+
+        Ok(Encryption { keks, pool, tables: tables.clone(), wallet_id, data_keys: RwLock::new(data_keys) })
+    }
+
+    ///
+    /// Loads the data keys of a wallet to read its data, a wallet that has none yet gets none.
+    ///
+    pub fn load(keks: KeyEncryptionKeys, pool: Arc<Pool>, tables: &Tables, wallet_id: u64) -> Result<Encryption, ErrorCode> {
+        let data_keys = load_data_keys(&keks, &pool, tables, wallet_id)?;
+
+        Ok(Encryption { keks, pool, tables: tables.clone(), wallet_id, data_keys: RwLock::new(data_keys) })
+    }
+
+    pub fn current_version(&self) -> Result<u32, ErrorCode> {
+        Ok(check_result!(self.data_keys.read(), Err(ErrorCode::InvalidState)).current)
+    }
+
+    ///
+    /// Encrypts data with the current data key into an envelope of the key version, the nonce, the ciphertext and the tag.
+    ///
+    pub fn encrypt(&self, plaintext: &[u8], label: &[u8]) -> Result<Vec<u8>, ErrorCode> {
+        let data_keys = check_result!(self.data_keys.read(), Err(ErrorCode::InvalidState));
+        let key = check_option!(data_keys.keys.get(&data_keys.current), Err(ErrorCode::EncryptionKeyNotFound));
+
+        let mut envelope = version_bytes(data_keys.current).to_vec();
+        envelope.extend(seal(key, &aad(label, data_keys.current), plaintext)?);
+
+        Ok(envelope)
+    }
+
+    ///
+    /// Decrypts an envelope, the data keys are loaded again if it was encrypted with a key created by another storage.
+    ///
+    pub fn decrypt(&self, envelope: &[u8], label: &[u8]) -> Result<Vec<u8>, ErrorCode> {
+        let version = check_option!(envelope_version(envelope), Err(ErrorCode::DecryptionFailed));
+
+        let key = {
+            let data_keys = check_result!(self.data_keys.read(), Err(ErrorCode::InvalidState));
+            data_keys.keys.get(&version).cloned()
+        };
+
+        let key = match key {
+            Some(key) => key,
+            None => {
+                self.reload()?;
+
+                let data_keys = check_result!(self.data_keys.read(), Err(ErrorCode::InvalidState));
+                check_option!(data_keys.keys.get(&version).cloned(), Err(ErrorCode::EncryptionKeyNotFound))
+            }
+        };
+
+        open(&key, &aad(label, version), &envelope[VERSION_LEN..])
+    }
+
+    ///
+    /// Encrypts the metadata, base64 encoded since the `metadata` column holds text.
+    ///
+    pub fn encrypt_metadata(&self, metadata: &str) -> Result<String, ErrorCode> {
+        Ok(base64::encode(&self.encrypt(metadata.as_bytes(), METADATA)?))
+    }
+
+    ///
+    /// Creates a new data key, which encrypts the data written from now on.
+    ///
+    /// # Returns
+    ///
+    ///  * `u32` - version of the new data key
+    ///
+    pub fn rotate(&self) -> Result<u32, ErrorCode> {
+        let version = create_data_key(&self.keks, &self.pool, &self.tables, self.wallet_id, false)?;
+
+        self.reload()?;
+
+        Ok(version)
+    }
+
+    fn reload(&self) -> Result<(), ErrorCode> {
+        let data_keys = load_data_keys(&self.keks, &self.pool, &self.tables, self.wallet_id)?;
+
+        *check_result!(self.data_keys.write(), Err(ErrorCode::InvalidState)) = data_keys;
+
+        Ok(())
+    }
+}
+
+///
+/// Restores metadata read from the DB as it was given to the storage.
+///
+pub fn decode_metadata(encryption: Option<&Encryption>, metadata: String, metadata_encoding: u8) -> Result<String, ErrorCode> {
+    if metadata_encoding & ENCRYPTED == 0 {
+        return Ok(metadata);
+    }
+
+    let encryption = match encryption {
+        None => {
+            warn!("Metadata is encrypted, but no encryption keys are configured");
+            return Err(ErrorCode::EncryptionKeyNotFound);
+        },
+        Some(encryption) => encryption,
+    };
+
+    let envelope = check_result!(base64::decode(&metadata), Err(ErrorCode::DecryptionFailed));
+    let metadata = encryption.decrypt(&envelope, METADATA)?;
+
+    Ok(check_result!(String::from_utf8(metadata), Err(ErrorCode::InvalidState)))
+}
+
+///
+/// Data key and metadata of a wallet that is about to be created.
+///
+/// # Returns
+///
+///  * `(u32, String, Vec<u8>, String)` - version of the data key, id of its KEK, the wrapped key and the encrypted metadata
+///
+pub fn new_wallet_key(keks: &KeyEncryptionKeys, metadata: &str) -> Result<(u32, String, Vec<u8>, String), ErrorCode> {
+    let version = 1;
+    let data_key = generate_key()?;
+    let (kek_id, wrapped_key) = keks.wrap(&data_key, version)?;
+
+    let mut envelope = version_bytes(version).to_vec();
+    envelope.extend(seal(&data_key, &aad(METADATA, version), metadata.as_bytes())?);
+
+    Ok((version, kek_id, wrapped_key, base64::encode(&envelope)))
+}
+
+fn generate_key() -> Result<Vec<u8>, ErrorCode> {
+    let mut key = vec![0u8; KEY_LEN];
+    check_result!(SystemRandom::new().fill(&mut key), Err(ErrorCode::InvalidState));
+
+    Ok(key)
+}
+
+fn load_data_keys(keks: &KeyEncryptionKeys, pool: &Pool, tables: &Tables, wallet_id: u64) -> Result<DataKeys, ErrorCode> {
+    let result = check_query!(
+        pool.prep_exec(
+            format!("SELECT version, kek_id, wrapped_key FROM {} WHERE wallet_id = :wallet_id", tables.data_keys),
+            params!{
+                wallet_id
+            }
+        ),
+        Err(ErrorCode::IOError)
+    );
+
+    let mut data_keys = DataKeys { current: 0, keys: HashMap::new() };
+
+    for row in result {
+        let row = check_query!(row, Err(ErrorCode::IOError));
+
+        let version: u32 = check_option!(row.get(0), Err(ErrorCode::IOError));
+        let kek_id: String = check_option!(row.get(1), Err(ErrorCode::IOError));
+        let wrapped_key: Vec<u8> = check_option!(row.get(2), Err(ErrorCode::IOError));
+
+        data_keys.current = data_keys.current.max(version);
+
+        // a key whose KEK is not configured only fails the reads of the data it encrypted
+        match keks.unwrap(&kek_id, &wrapped_key, version) {
+            Err(ErrorCode::EncryptionKeyNotFound) => continue,
+            Err(err) => return Err(err),
+            Ok(key) => { data_keys.keys.insert(version, key); },
+        }
+    }
+
+    Ok(data_keys)
+}
+
+///
+/// Stores the next data key version of a wallet, the wallet row is locked so concurrent rotations get distinct versions.
+/// With `only_if_none` no key is created if the wallet already has one.
+///
+fn create_data_key(keks: &KeyEncryptionKeys, pool: &Pool, tables: &Tables, wallet_id: u64, only_if_none: bool) -> Result<u32, ErrorCode> {
+    let mut writer = Writer::start_transaction(pool, tables, wallet_id, false)?;
+
+    check_query!(
+        writer.prep_exec(
+            format!("SELECT id FROM {} WHERE id = :wallet_id FOR UPDATE", tables.wallets),
+            params!{
+                wallet_id
+            }
+        ),
+        Err(ErrorCode::IOError)
+    );
+
+    let last_version: u32 = {
+        let mut result = check_query!(
+            writer.prep_exec(
+                format!("SELECT COALESCE(MAX(version), 0) FROM {} WHERE wallet_id = :wallet_id", tables.data_keys),
+                params!{
+                    wallet_id
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        let row = check_query!(check_option!(result.next(), Err(ErrorCode::InvalidState)), Err(ErrorCode::IOError));
+
+        check_option!(row.get(0), Err(ErrorCode::IOError))
+    };
+
+    if only_if_none && last_version != 0 {
+        return Ok(last_version);
+    }
+
+    let version = last_version + 1;
+
+    store_data_key(&mut writer, tables, wallet_id, version, &keks.wrap(&generate_key()?, version)?)?;
+    writer.commit()?;
+
+    Ok(version)
+}
+
+pub fn store_data_key(writer: &mut Writer, tables: &Tables, wallet_id: u64, version: u32, wrapped: &(String, Vec<u8>)) -> Result<(), ErrorCode> {
+    let (ref kek_id, ref wrapped_key) = *wrapped;
+
+    check_query!(
+        writer.prep_exec(
+            format!("INSERT INTO {} (wallet_id, version, kek_id, wrapped_key) VALUES (:wallet_id, :version, :kek_id, :wrapped_key)", tables.data_keys),
+            params!{
+                "wallet_id" => wallet_id,
+                "version" => version,
+                "kek_id" => kek_id.as_str(),
+                "wrapped_key" => &wrapped_key[..]
+            }
+        ),
+        Err(ErrorCode::IOError)
+    );
+
+    Ok(())
+}
+
+///
+/// Wraps the data keys of a wallet that are not wrapped by the current KEK with it.
+///
+/// # Returns
+///
+///  * `u64` - number of re-wrapped data keys
+///
+pub fn rewrap_data_keys(keks: &KeyEncryptionKeys, pool: &Pool, tables: &Tables, wallet_id: u64) -> Result<u64, ErrorCode> {
+    let mut writer = Writer::start_transaction(pool, tables, wallet_id, false)?;
+
+    let mut wrapped_keys: Vec<(u32, String, Vec<u8>)> = Vec::new();
+
+    {
+        let result = check_query!(
+            writer.prep_exec(
+                format!("SELECT version, kek_id, wrapped_key FROM {} WHERE wallet_id = :wallet_id AND kek_id <> :current FOR UPDATE", tables.data_keys),
+                params!{
+                    "wallet_id" => wallet_id,
+                    "current" => keks.current.as_str()
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        for row in result {
+            let row = check_query!(row, Err(ErrorCode::IOError));
+
+            wrapped_keys.push((
+                check_option!(row.get(0), Err(ErrorCode::IOError)),
+                check_option!(row.get(1), Err(ErrorCode::IOError)),
+                check_option!(row.get(2), Err(ErrorCode::IOError)),
+            ));
+        }
+    }
+
+    for &(version, ref kek_id, ref wrapped_key) in wrapped_keys.iter() {
+        let (kek_id, wrapped_key) = keks.wrap(&keks.unwrap(kek_id, wrapped_key, version)?, version)?;
+
+        check_query!(
+            writer.prep_exec(
+                format!("UPDATE {} SET kek_id = :kek_id, wrapped_key = :wrapped_key WHERE wallet_id = :wallet_id AND version = :version", tables.data_keys),
+                params!{
+                    kek_id,
+                    wrapped_key,
+                    wallet_id,
+                    version
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+    }
+
+    writer.commit()?;
+
+    Ok(wrapped_keys.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keks(current: &str) -> KeyEncryptionKeys {
+        let mut keys = HashMap::new();
+        keys.insert("kek-1".to_string(), base64::encode(&[1u8; KEY_LEN]));
+        keys.insert("kek-2".to_string(), base64::encode(&[2u8; KEY_LEN]));
+
+        KeyEncryptionKeys::from_config(&EncryptionConfig { current_key: Some(current.to_string()), keys, key_file: None }).unwrap().unwrap()
+    }
+
+    #[test]
+    fn seal_round_trips() {
+        let key = [3u8; KEY_LEN];
+
+        let sealed = seal(&key, b"aad", b"plaintext").unwrap();
+
+        assert_eq!(open(&key, b"aad", &sealed).unwrap(), b"plaintext".to_vec());
+        assert_eq!(open(&key, b"other", &sealed).unwrap_err(), ErrorCode::DecryptionFailed);
+        assert_eq!(open(&[4u8; KEY_LEN], b"aad", &sealed).unwrap_err(), ErrorCode::DecryptionFailed);
+        assert_eq!(open(&key, b"aad", &sealed[..10]).unwrap_err(), ErrorCode::DecryptionFailed);
+    }
+
+    #[test]
+    fn data_keys_are_wrapped_by_version() {
+        let keks = keks("kek-1");
+        let data_key = generate_key().unwrap();
+
+        let (kek_id, wrapped_key) = keks.wrap(&data_key, 2).unwrap();
+
+        assert_eq!(kek_id, "kek-1");
+        assert_eq!(keks.unwrap(&kek_id, &wrapped_key, 2).unwrap(), data_key);
+        assert_eq!(keks.unwrap(&kek_id, &wrapped_key, 3).unwrap_err(), ErrorCode::DecryptionFailed);
+        assert_eq!(keks.unwrap("kek-3", &wrapped_key, 2).unwrap_err(), ErrorCode::EncryptionKeyNotFound);
+    }
+
+    #[test]
+    fn key_config_is_validated() {
+        assert!(KeyEncryptionKeys::from_config(&EncryptionConfig::default()).unwrap().is_none());
+
+        let mut config = EncryptionConfig { current_key: Some("kek-1".to_string()), keys: HashMap::new(), key_file: None };
+        assert_eq!(KeyEncryptionKeys::from_config(&config).err(), Some(ErrorCode::EncryptionKeyNotFound));
+
+        config.keys.insert("kek-1".to_string(), base64::encode(&[1u8; 16]));
+        assert_eq!(KeyEncryptionKeys::from_config(&config).err(), Some(ErrorCode::InvalidStructure));
+    }
+
+    #[test]
+    fn envelope_version_is_read_from_the_header() {
+        assert_eq!(envelope_version(&[0, 0, 1, 2, 9]), Some(258));
+        assert_eq!(envelope_version(&[0, 0, 1]), None);
+    }
+}
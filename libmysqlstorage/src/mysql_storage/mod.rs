@@ -1,12 +1,53 @@
+///
+/// `check_result!` for the results of the DB calls, which returns `Timeout` instead of the given error code
+/// for a statement that exceeded the statement timeout or waited for a lock longer than the lock wait timeout.
+///
+macro_rules! check_query {
+    ($r: expr, Err($e: expr)) => {
+        match $r {
+            Err(err) => {
+                warn!("Error Occurred: {:?}", err);
+                return Err(::mysql_storage::query_error(&err, $e))
+            },
+            Ok(x) => x
+        }
+    };
+    ($r: expr, $e: expr) => {
+        match $r {
+            Err(err) => {
+                warn!("Error Occurred: {:?}", err);
+                return ::mysql_storage::query_error(&err, $e)
+            },
+            Ok(x) => x
+        }
+    }
+}
+
 mod query_translator;
+mod sharding;
+mod change_feed;
+mod value_chunks;
+mod compression;
+mod encryption;
+mod reencryption;
+use self::change_feed::{Writer, ChangeOperation};
+use self::value_chunks::{ValueReader, ValueWriter};
+use self::encryption::{Encryption, KeyEncryptionKeys};
+use self::reencryption::{ReencryptionJob, ReencryptionStatus};
 use utils::handle_store::HandleStore;
-use utils::multi_pool::{MultiPool, StorageCredentials, StorageConfig};
-
-use std::sync::{RwLock, Arc};
-use mysql::{Pool, QueryResult, Error};
+use utils::multi_pool::{MultiPool, StorageCredentials, StorageConfig, QueryLimits, SnapshotRetention, ChangeFeedConfig, CacheConfig, ValueConfig, CompressionConfig};
+use utils::cache::{Cache, CacheRegistry, CacheStats};
+use utils::worker_runtime::{WorkerRuntimes, WorkerRuntime};
+
+use std::sync::{RwLock, Mutex, Arc};
+use mysql::{Pool, QueryResult, Error, Value, Row, Transaction};
+use mysql::prelude::GenericConnection;
+use ring::digest;
 use errors::error_code::ErrorCode;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::ffi::CString;
+use std::time::Duration;
+use libc::c_char;
 use serde_json;
 
 fn default_true() -> bool {
@@ -27,6 +68,17 @@ pub struct FetchOptions {
 
     #[serde(default="default_true", rename="retrieveTags")]
     retrieve_tags: bool,
+
+    /// timeout (in milliseconds) of the statement reading the record, overrides `statement_timeout` from the config
+    #[serde(default, rename="statementTimeout")]
+    statement_timeout: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct UpsertOptions {
+    /// merge the given tags into the tags of the existing record instead of replacing them
+    #[serde(default="default_false", rename="mergeTags")]
+    merge_tags: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,19 +97,121 @@ pub struct SearchOptions {
 
     #[serde(default="default_false", rename="retrieveTags")]
     pub retrieve_tags: bool,
+
+    /// `statementTimeout` is accepted as well, as in the fetch options
+    #[serde(default, rename="maxExecutionTime", alias="statementTimeout")]
+    pub max_execution_time: Option<u64>,
+}
+
+///
+/// Type and id of a record, `{"type": "...", "id": "..."}`.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordKey {
+    #[serde(rename="type")]
+    pub type_: String,
+
+    pub id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ChunkOptions {
+    #[serde(default, rename="chunkSize")]
+    pub chunk_size: Option<u64>,
+
+    /// key of the last record of the previous chunk, records are chunked in `(type, id)` order
+    #[serde(default)]
+    pub after: Option<RecordKey>,
+}
+
+impl ChunkOptions {
+    pub fn is_chunked(&self) -> bool {
+        self.chunk_size.is_some() || self.after.is_some()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchAllOptions {
+    #[serde(flatten)]
+    pub search_options: SearchOptions,
+
+    #[serde(flatten)]
+    pub chunk_options: ChunkOptions,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AggregateOptions {
+    #[serde(rename="groupBy")]
+    pub group_by: String,
+
+    #[serde(default)]
+    pub types: Option<serde_json::Value>,
+
+    #[serde(default="default_false")]
+    pub distinct: bool,
+
+    #[serde(default)]
+    pub limit: Option<u64>,
+
+    /// `statementTimeout` is accepted as well, as in the fetch options
+    #[serde(default, rename="maxExecutionTime", alias="statementTimeout")]
+    pub max_execution_time: Option<u64>,
+}
+
+fn default_list_limit() -> u64 {
+    100
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListStoragesOptions {
+    /// only wallets whose name starts with the prefix are listed
+    #[serde(default)]
+    pub prefix: Option<String>,
+
+    #[serde(default="default_list_limit")]
+    pub limit: u64,
+
+    /// name of the last wallet of the previous page, wallets are listed in name order
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExplainOptions {
+    #[serde(flatten)]
+    pub search_options: SearchOptions,
+
+    #[serde(default="default_false", rename="runExplain")]
+    pub run_explain: bool,
 }
 
 #[derive(Debug)]
 pub struct Search<'a> {
     pub search_result: Option<RwLock<QueryResult<'a>>>,
     pub total_count: Option<usize>,
+    pub fetched_records: Option<RwLock<VecDeque<Record>>>,
+    pub missing_keys: Option<CString>,
 }
 
 impl<'a> Search<'a> {
     fn new(search_result: Option<QueryResult<'a>>, total_count: Option<usize>) -> Self {
         Self {
             search_result: search_result.map(|result|{RwLock::new(result)}),
-            total_count: total_count
+            total_count: total_count,
+            fetched_records: None,
+            missing_keys: None,
+        }
+    }
+
+    ///
+    /// Search over records that were already fetched from the DB, used for fetching multiple records by id.
+    ///
+    fn from_records(records: VecDeque<Record>, missing_keys: CString) -> Self {
+        Self {
+            search_result: None,
+            total_count: Some(records.len()),
+            fetched_records: Some(RwLock::new(records)),
+            missing_keys: Some(missing_keys),
         }
     }
 }
@@ -68,16 +222,189 @@ pub struct Record {
     pub value: Option<Vec<u8>>,
     pub tags: Option<CString>,
     pub type_: Option<CString>,
+    pub version: Option<u64>,
 }
 
 impl Record {
-    fn new(id: CString, value: Option<Vec<u8>>, tags: Option<CString>, type_: Option<CString>) -> Self {
-        Self{id, value, tags, type_}
+    fn new(id: CString, value: Option<Vec<u8>>, tags: Option<CString>, type_: Option<CString>, version: Option<u64>) -> Self {
+        Self{id, value, tags, type_, version}
+    }
+
+    ///
+    /// Builds a record from a `type, name, value, tags, version` row, where the attributes that were not fetched are NULL.
+    /// Rows that retrieve the value end with the `query_translator::STORED_VALUE_COLUMNS`.
+    ///
+    /// # Returns
+    ///
+    ///  * `(Record, Option<u64>, u8)` - the record as stored, the id of the item whose chunks hold the value if it is chunked,
+    ///     and the encoding of the value
+    ///
+    fn from_row(row: Row) -> Result<(Self, Option<u64>, u8), ErrorCode> {
+        let record_type: Option<String> = check_option!(row.get(0), Err(ErrorCode::IOError));
+        let record_id: String = check_option!(row.get(1), Err(ErrorCode::IOError));
+        let record_value: Option<Vec<u8>> = check_option!(row.get(2), Err(ErrorCode::IOError));
+        let record_tags: Option<String> = check_option!(row.get(3), Err(ErrorCode::IOError));
+        let record_version: u64 = check_option!(row.get(4), Err(ErrorCode::IOError));
+        let chunked_item_id: Option<u64> = if row.len() > 5 { check_option!(row.get(5), Err(ErrorCode::IOError)) } else { None };
+        let value_encoding: u8 = if row.len() > 6 { check_option!(row.get(6), Err(ErrorCode::IOError)) } else { compression::RAW };
+
+        let record = Record::new(
+            check_result!(CString::new(record_id), Err(ErrorCode::InvalidState)),
+            record_value,
+            if let Some(record_tags) = record_tags { Some(check_result!(CString::new(record_tags), Err(ErrorCode::InvalidState))) } else { None },
+            if let Some(record_type) = record_type { Some(check_result!(CString::new(record_type), Err(ErrorCode::InvalidState))) } else { None },
+            Some(record_version),
+        );
+
+        Ok((record, chunked_item_id, value_encoding))
     }
 }
 
+///
+/// Key of an entry of the storage cache.
+///
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+enum CacheKey {
+    Metadata,
+    Record(String, String),
+}
+
+///
+/// Entry of the storage cache, records are cached with their value and tags whatever the fetch options were.
+///
+enum CachedItem {
+    Metadata(String),
+    Record(Vec<u8>, String, u64),
+}
+
+/// estimated size of the bookkeeping of a cache entry, added to the size of the cached data
+const CACHE_ENTRY_OVERHEAD: usize = 64;
+
+/// number of times a record is read again when its chunked value is changed while it is read
+const MAX_CHUNKED_READ_ATTEMPTS: usize = 3;
+
 lazy_static! {
     static ref CONNECTIONS: MultiPool = MultiPool::new();
+    static ref RUNTIMES: WorkerRuntimes = WorkerRuntimes::new();
+    static ref CACHES: CacheRegistry<CacheKey, CachedItem> = CacheRegistry::new();
+}
+
+const MAX_TABLE_PREFIX_LENGTH: usize = 32;
+
+/// MySQL error of a SELECT statement interrupted by `max_execution_time`
+const ER_QUERY_TIMEOUT: u16 = 3024;
+
+/// MySQL error of a statement that waited for a row lock longer than `innodb_lock_wait_timeout`
+const ER_LOCK_WAIT_TIMEOUT: u16 = 1205;
+
+/// MySQL error of a statement that does not parse, e.g. because of a tag name that is not a valid JSON path
+const ER_PARSE_ERROR: u16 = 1064;
+
+///
+/// Error code of a failed DB call, `Timeout` if the statement exceeded one of the timeouts, the given code otherwise.
+///
+pub fn query_error(err: &Error, default: ErrorCode) -> ErrorCode {
+    match *err {
+        Error::MySqlError(ref err) if err.code == ER_QUERY_TIMEOUT || err.code == ER_LOCK_WAIT_TIMEOUT => ErrorCode::Timeout,
+        _ => default,
+    }
+}
+
+/// number of records copied by one statement when a wallet is moved
+const MOVE_BATCH_SIZE: usize = 100;
+
+/// maximum number of rounds catching up on the records changed during a move, before the writes are locked
+const MOVE_CATCH_UP_ROUNDS: usize = 3;
+
+/// number of record hashes combined into one batch hash of a verification report
+const VERIFY_BATCH_SIZE: usize = 1000;
+
+/// maximum number of records with invalid tags listed in a verification report
+const MAX_REPORTED_INVALID_TAGS: usize = 100;
+
+/// maximum length of a snapshot label, the length of the `label` column
+const MAX_SNAPSHOT_LABEL_LENGTH: usize = 255;
+
+/// maximum number of changes returned by a single `read_changes` call
+const MAX_READ_CHANGES_LIMIT: u64 = 1000;
+
+///
+/// Describes why the tags of a record are not a flat JSON object of strings, None if they are.
+///
+fn invalid_tags_error(tags: &str) -> Option<String> {
+    let tags: serde_json::Value = match serde_json::from_str(tags) {
+        Err(err) => return Some(format!("tags are not valid JSON: {}", err)),
+        Ok(tags) => tags,
+    };
+
+    let tags = match tags.as_object() {
+        None => return Some("tags are not a JSON object".to_string()),
+        Some(tags) => tags,
+    };
+
+    tags.iter()
+        .find(|&(_, value)| !value.is_string())
+        .map(|(name, _)| format!("value of the tag {} is not a string", name))
+}
+
+///
+/// Lowercase hex encoded SHA-256 of the data, as returned by the `SHA2(data, 256)` function of MySQL.
+///
+fn sha256_hex(data: &[u8]) -> String {
+    digest::digest(&digest::SHA256, data).as_ref().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+///
+/// First and last key, number of records and concatenated leaf hashes of a batch of a verification report.
+///
+fn leaves_to_batch(leaves: &Vec<(RecordKey, String)>) -> (RecordKey, RecordKey, usize, String) {
+    (
+        leaves[0].0.clone(),
+        leaves[leaves.len() - 1].0.clone(),
+        leaves.len(),
+        leaves.iter().map(|&(_, ref leaf)| leaf.as_str()).collect::<Vec<&str>>().concat()
+    )
+}
+
+///
+/// Names of the tables used by a storage, `table_prefix` from the config followed by the default table name.
+///
+#[derive(Debug, Clone)]
+pub struct Tables {
+    pub wallets: String,
+    pub items: String,
+    pub shards: String,
+    pub snapshots: String,
+    pub snapshot_items: String,
+    pub snapshot_value_chunks: String,
+    pub changes: String,
+    pub value_chunks: String,
+    pub data_keys: String,
+}
+
+impl Tables {
+    ///
+    /// Returns None if the prefix is too long or contains anything but ASCII letters, digits and underscores,
+    /// since the table names are interpolated into the SQL statements.
+    ///
+    pub fn new(table_prefix: &str) -> Option<Self> {
+        if table_prefix.len() > MAX_TABLE_PREFIX_LENGTH || !table_prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            warn!("Invalid table prefix: {}", table_prefix);
+            return None;
+        }
+
+        Some(Tables {
+            wallets: format!("{}wallets", table_prefix),
+            items: format!("{}items", table_prefix),
+            shards: format!("{}wallet_shards", table_prefix),
+            snapshots: format!("{}snapshots", table_prefix),
+            snapshot_items: format!("{}snapshot_items", table_prefix),
+            snapshot_value_chunks: format!("{}snapshot_value_chunks", table_prefix),
+            changes: format!("{}item_changes", table_prefix),
+            value_chunks: format!("{}item_value_chunks", table_prefix),
+            data_keys: format!("{}wallet_data_keys", table_prefix),
+        })
+    }
 }
 
 pub struct MySQLStorage<'a> {
@@ -87,11 +414,49 @@ pub struct MySQLStorage<'a> {
     metadata: HandleStore<CString>,
     read_pool: Arc<Pool>, // cached reference to the pool
     write_pool: Arc<Pool>,
+    query_limits: QueryLimits,
+    tables: Tables,
+    change_feed: ChangeFeedConfig,
+    cache: Option<Arc<Cache<CacheKey, CachedItem>>>,
+    values: ValueConfig,
+    value_readers: HandleStore<Mutex<ValueReader>>,
+    value_writers: HandleStore<Mutex<ValueWriter>>,
+    compression: CompressionConfig,
+    encryption: Option<Arc<Encryption>>,
+    /// status of the last re-encryption job started by this storage
+    reencryption: Mutex<Option<Arc<Mutex<ReencryptionStatus>>>>,
+    runtime: Option<Arc<WorkerRuntime>>,
 }
 
 impl<'a> MySQLStorage<'a> {
-    pub fn new(wallet_id: u64, read_pool: Arc<Pool>, write_pool: Arc<Pool>) -> Self {
-        Self{wallet_id, records: HandleStore::new(), searches: HandleStore::new(), metadata: HandleStore::new(), read_pool, write_pool}
+    pub fn new(wallet_id: u64, read_pool: Arc<Pool>, write_pool: Arc<Pool>, query_limits: QueryLimits, tables: Tables, change_feed: ChangeFeedConfig, cache_config: CacheConfig, values: ValueConfig, compression: CompressionConfig, encryption: Option<Arc<Encryption>>, runtime: Option<Arc<WorkerRuntime>>) -> Self {
+        let cache = if cache_config.enabled { Some(Arc::new(Cache::new(cache_config.max_bytes, Duration::from_millis(cache_config.ttl)))) } else { None };
+
+        Self{
+            wallet_id, records: HandleStore::new(), searches: HandleStore::new(), metadata: HandleStore::new(), read_pool, write_pool, query_limits, tables, change_feed, cache,
+            values, value_readers: HandleStore::new(), value_writers: HandleStore::new(), compression, encryption, reencryption: Mutex::new(None), runtime,
+        }
+    }
+
+    ///
+    /// Worker runtime the storage calls of libindy are executed on, None if they are executed on the calling thread.
+    ///
+    pub fn runtime(&self) -> Option<Arc<WorkerRuntime>> {
+        self.runtime.clone()
+    }
+
+    ///
+    /// Worker runtime of the storages with the given config, started on first use.
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidStructure` - Invalid structure of the config JSON, or of its runtime settings
+    ///  * `InvalidState` - Worker threads could not be started
+    ///
+    pub fn runtime_for(config: &str) -> Result<Option<Arc<WorkerRuntime>>, ErrorCode> {
+        let config: StorageConfig = check_result!(serde_json::from_str(config), Err(ErrorCode::InvalidStructure));
+
+        RUNTIMES.get(&config.runtime)
     }
 
     ///
@@ -120,12 +485,24 @@ impl<'a> MySQLStorage<'a> {
         trace!("Creating Storage -> name: {}, metadata: {}", name, metadata);
 
         let config: StorageConfig = check_result!(serde_json::from_str(config), ErrorCode::InvalidStructure);
+        let tables = check_option!(Tables::new(config.table_prefix), ErrorCode::InvalidStructure);
         let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), ErrorCode::InvalidStructure);
 
-        let write_pool = check_option!(CONNECTIONS.get(false, &config, &credentials), ErrorCode::IOError);
+        let shard = match sharding::route_new(name, &config, &credentials, &tables) {
+            Err(err) => return err,
+            Ok(shard) => shard,
+        };
+
+        let write_pool = check_option!(CONNECTIONS.get(false, &shard.config, &credentials), ErrorCode::IOError);
+
+        match KeyEncryptionKeys::from_config(&config.encryption) {
+            Err(err) => return err,
+            Ok(Some(keks)) => return MySQLStorage::_create_encrypted_storage(name, metadata, &keks, &write_pool, &tables),
+            Ok(None) => (),
+        }
 
         let result = write_pool.prep_exec(
-                        "INSERT INTO wallets(name, metadata) VALUES (:name, :metadata)",
+                        format!("INSERT INTO {}(name, metadata) VALUES (:name, :metadata)", tables.wallets),
                          params!{
                             name,
                             metadata
@@ -138,6 +515,7 @@ impl<'a> MySQLStorage<'a> {
                     warn!("MySQL Error while executing query. Err Code: {}, Err State: {}", err.code, err.state);
                     match err.code {
                         1062 => return ErrorCode::WalletAlreadyExistsError,
+                        ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
                         _ => return ErrorCode::IOError,
                     };
                 },
@@ -153,6 +531,56 @@ impl<'a> MySQLStorage<'a> {
         ErrorCode::Success
     }
 
+    ///
+    /// Creates a wallet with encrypted metadata, together with its first data key.
+    ///
+    fn _create_encrypted_storage(name: &str, metadata: &str, keks: &KeyEncryptionKeys, write_pool: &Pool, tables: &Tables) -> ErrorCode {
+        let (version, kek_id, wrapped_key, metadata) = match encryption::new_wallet_key(keks, metadata) {
+            Err(err) => return err,
+            Ok(wallet_key) => wallet_key,
+        };
+
+        let mut writer = match Writer::start_transaction(write_pool, tables, 0, false) {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        let wallet_id = match writer.prep_exec(
+            format!("INSERT INTO {}(name, metadata, metadata_encoding) VALUES (:name, :metadata, :metadata_encoding)", tables.wallets),
+            params!{
+                "name" => name,
+                "metadata" => metadata,
+                "metadata_encoding" => encryption::ENCRYPTED
+            }
+        ) {
+            Err(Error::MySqlError(err)) => {
+                warn!("MySQL Error while executing query. Err Code: {}, Err State: {}", err.code, err.state);
+                match err.code {
+                    1062 => return ErrorCode::WalletAlreadyExistsError,
+                    ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                    _ => return ErrorCode::IOError,
+                };
+            },
+            Err(err) => {
+                warn!("Unexpected Error while executing query. Err: {:?}", err);
+                return ErrorCode::IOError
+            },
+            Ok(result) => result.last_insert_id(),
+        };
+
+        if let Err(err) = encryption::store_data_key(&mut writer, tables, wallet_id, version, &(kek_id, wrapped_key)) {
+            return err;
+        }
+
+        if let Err(err) = writer.commit() {
+            return err;
+        }
+
+        trace!("Success Creating Storage with the name: {}", name);
+
+        ErrorCode::Success
+    }
+
     ///
     /// Opens a wallet with the given name in the DB specified in the config.
     ///
@@ -171,6 +599,7 @@ impl<'a> MySQLStorage<'a> {
     ///  * `Success` - Execution successful
     ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials
     ///  * `InvalidState` - No wallet with the given name found in the DB
+    ///  * `EncryptionKeyNotFound` - Current key-encryption key, or the one of the current data key, is not in the config
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
     ///
     pub fn open_storage(name: &str, config: &str, credentials: &str) -> Result<Self, ErrorCode> {
@@ -178,14 +607,27 @@ impl<'a> MySQLStorage<'a> {
         trace!("Opening Storage -> name: {}", name);
 
         let config: StorageConfig = check_result!(serde_json::from_str(config), Err(ErrorCode::InvalidStructure));
+        let tables = check_option!(Tables::new(config.table_prefix), Err(ErrorCode::InvalidStructure));
         let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), Err(ErrorCode::InvalidStructure));
 
-        let read_pool = check_option!(CONNECTIONS.get(true, &config, &credentials), Err(ErrorCode::IOError));
-        let write_pool = check_option!(CONNECTIONS.get(false, &config, &credentials), Err(ErrorCode::IOError));
+        if config.values.chunk_size == 0 {
+            warn!("Invalid value chunk size: 0");
+            return Err(ErrorCode::InvalidStructure);
+        }
+
+        if config.statement_timeout == Some(0) {
+            warn!("Invalid statement timeout: 0");
+            return Err(ErrorCode::InvalidStructure);
+        }
+
+        let shard = sharding::route_existing(name, &config, &credentials, &tables)?;
 
-        let mut result = check_result!(
+        let read_pool = check_option!(CONNECTIONS.get(true, &shard.config, &credentials), Err(ErrorCode::IOError));
+        let write_pool = check_option!(CONNECTIONS.get(false, &shard.config, &credentials), Err(ErrorCode::IOError));
+
+        let mut result = check_query!(
                             read_pool.prep_exec(
-                                "SELECT id FROM wallets WHERE name = :name",
+                                format!("SELECT id FROM {} WHERE name = :name", tables.wallets),
                                 params!{
                                     name
                                  }
@@ -193,14 +635,36 @@ impl<'a> MySQLStorage<'a> {
         );
 
         let wallet_id: u64 = check_option!(
-            check_result!(
+            check_query!(
                 check_option!(result.next(), Err(ErrorCode::WalletNotFound)),
                 Err(ErrorCode::IOError)).get(0),
             Err(ErrorCode::InvalidState));
 
+        let encryption = match KeyEncryptionKeys::from_config(&config.encryption)? {
+            None => None,
+            Some(keks) => Some(Arc::new(Encryption::open(keks, write_pool.clone(), &tables, wallet_id)?)),
+        };
+
         trace!("Success Opening Storage with the name: {}", name);
 
-        Ok(MySQLStorage::new(wallet_id, read_pool, write_pool))
+        let runtime = RUNTIMES.get(&config.runtime)?;
+
+        let cache_key = MySQLStorage::_cache_key(name, &shard.config, &tables);
+
+        let storage = MySQLStorage::new(wallet_id, read_pool, write_pool, config.query_limits, tables, config.change_feed, config.cache, config.values, config.compression.clone(), encryption, runtime);
+
+        if let Some(ref cache) = storage.cache {
+            CACHES.register(cache_key, cache);
+        }
+
+        Ok(storage)
+    }
+
+    ///
+    /// Key of the caches of the storages opened on a wallet, identifies the wallet across the DBs and table prefixes.
+    ///
+    fn _cache_key(name: &str, config: &StorageConfig, tables: &Tables) -> String {
+        format!("{}:{}/{}/{}/{}", config.write_host, config.port, config.db_name, tables.wallets, name)
     }
 
     ///
@@ -228,13 +692,23 @@ impl<'a> MySQLStorage<'a> {
         trace!("Deleting Storage -> name: {}", name);
 
         let config: StorageConfig = check_result!(serde_json::from_str(config), ErrorCode::InvalidStructure);
+        let tables = check_option!(Tables::new(config.table_prefix), ErrorCode::InvalidStructure);
         let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), ErrorCode::InvalidStructure);
 
-        let write_pool = check_option!(CONNECTIONS.get(false, &config, &credentials), ErrorCode::IOError);
+        let shard = match sharding::route_existing(name, &config, &credentials, &tables) {
+            Err(ErrorCode::WalletNotFound) => {
+                warn!("Trying to delete a non existent storage, name: {}", name);
+                return ErrorCode::InvalidState;
+            },
+            Err(err) => return err,
+            Ok(shard) => shard,
+        };
 
-        let result = check_result!(
+        let write_pool = check_option!(CONNECTIONS.get(false, &shard.config, &credentials), ErrorCode::IOError);
+
+        let result = check_query!(
                         write_pool.prep_exec(
-                            "DELETE FROM wallets WHERE name = :name",
+                            format!("DELETE FROM {} WHERE name = :name", tables.wallets),
                              params!{
                                 name
                              }
@@ -246,17 +720,27 @@ impl<'a> MySQLStorage<'a> {
             return ErrorCode::InvalidState;
         }
 
+        CACHES.clear(&MySQLStorage::_cache_key(name, &shard.config, &tables));
+
+        let err = sharding::unregister(name, &config, &credentials, &tables);
+        if err != ErrorCode::Success {
+            return err;
+        }
+
         trace!("Success Deleting Storage with the name: {}", name);
 
         ErrorCode::Success
     }
 
     ///
-    /// Removes a record handle, thus removing the referenced object from memory.
+    /// Renames a wallet in the DB specified in the config, records and metadata stay with the wallet.
     ///
     /// # Arguments
     ///
-    ///  * `record_handle` - unique identifier of a record fetch request
+    ///  * `name` - current name of the wallet
+    ///  * `new_name` - new name of the wallet
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
     ///
     /// # Returns
     ///
@@ -265,96 +749,72 @@ impl<'a> MySQLStorage<'a> {
     /// # ErrorCodes
     ///
     ///  * `Success` - Execution successful
-    ///  * `InvalidState` - Provided record handle does not exist
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `WalletAlreadyExistsError` - Wallet with the new name already exists in the DB
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
     ///
-    pub fn free_record(&self, record_handle: i32) -> ErrorCode {
+    pub fn rename_storage(name: &str, new_name: &str, config: &str, credentials: &str) -> ErrorCode {
 
-        trace!("Freeing Record -> record_handle: {}", record_handle);
+        trace!("Renaming Storage -> name: {}, new_name: {}", name, new_name);
 
-        if self.records.remove(record_handle) {
-            trace!("Success Freeing Record with the record_handle: {}", record_handle);
-            ErrorCode::Success
+        let config: StorageConfig = check_result!(serde_json::from_str(config), ErrorCode::InvalidStructure);
+        let tables = check_option!(Tables::new(config.table_prefix), ErrorCode::InvalidStructure);
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), ErrorCode::InvalidStructure);
+
+        let shard = match sharding::route_existing(name, &config, &credentials, &tables) {
+            Err(err) => return err,
+            Ok(shard) => shard,
+        };
+
+        // the renamed wallet stays on its shard, so the new name is registered before the rename
+        // and has to be free on all of the other shards as well
+        let err = sharding::check_name_free(new_name, &config, &credentials, &tables);
+        if err != ErrorCode::Success {
+            return err;
         }
-        else {
-            warn!("Trying to free a non existent Record, record_handle: {}", record_handle);
-            ErrorCode::InvalidState
+
+        let err = sharding::register(new_name, &shard, &config, &credentials, &tables);
+        if err != ErrorCode::Success {
+            return err;
         }
-    }
 
-    ///
-    /// Removes a record handle, thus removing the referenced object from memory.
-    ///
-    /// # Arguments
-    ///
-    ///  * `record_handle` - unique identifier of a search result
-    ///
-    /// # Returns
-    ///
-    ///  * `ErrorCode`
-    ///
-    /// # ErrorCodes
-    ///
-    ///  * `Success` - Execution successful
-    ///  * `InvalidState` - Provided search handle does not exist
-    ///
-    pub fn free_search(&self, search_handle: i32) -> ErrorCode {
+        let err = MySQLStorage::_rename_storage(name, new_name, &shard.config, &credentials, &tables);
+        if err != ErrorCode::Success {
+            sharding::unregister(new_name, &config, &credentials, &tables);
+            return err;
+        }
 
-        trace!("Freeing Search Result -> search_handle: {}", search_handle);
+        CACHES.rename(&MySQLStorage::_cache_key(name, &shard.config, &tables), MySQLStorage::_cache_key(new_name, &shard.config, &tables));
 
-        if self.searches.remove(search_handle) {
-            trace!("Success Freeing Search Result with the search_handle: {}", search_handle);
-            ErrorCode::Success
-        }
-        else {
-            warn!("Trying to free a non existent Search Result, search_handle: {}", search_handle);
-            ErrorCode::InvalidState
+        let err = sharding::unregister(name, &config, &credentials, &tables);
+        if err != ErrorCode::Success {
+            return err;
         }
+
+        trace!("Success Renaming Storage with the name: {} to: {}", name, new_name);
+
+        ErrorCode::Success
     }
 
-    ///
-    /// Adds a new record identified by type and id.
-    ///
-    /// # Arguments
-    ///
-    ///  * `type_` - record type
-    ///  * `id` - record id (name)
-    ///  * `value` - record value
-    ///  * `tags` - a map of (tag_name: tag_value) pairs
-    ///
-    /// # Returns
-    ///
-    ///  * `ErrorCode`
-    ///
-    /// # ErrorCodes
-    ///
-    ///  * `Success` - Execution successful
-    ///  * `ItemAlreadyExists` - Record with the provided type and id already exist in the DB
-    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tags
-    ///  * `IOError` - Unexpected error occurred while communicating with the DB
-    ///
-    pub fn add_record(&self, type_: &str, id: &str, value: &Vec<u8>, tags: &str) -> ErrorCode {
+    fn _rename_storage(name: &str, new_name: &str, config: &StorageConfig, credentials: &StorageCredentials, tables: &Tables) -> ErrorCode {
 
-        trace!("Adding Record -> type: {}, id: {}, value: {:?}, tags: {}", type_, id, value, tags);
+        let write_pool = check_option!(CONNECTIONS.get(false, config, credentials), ErrorCode::IOError);
 
-        let result = {
-            self.write_pool.prep_exec(
-                        "INSERT INTO items (type, name, value, tags, wallet_id) VALUE (:type, :name, :value, :tags, :wallet_id)",
-                        params!{
-                            "type" => type_,
-                            "name" => id,
-                            "value" => value,
-                            "tags" => tags,
-                            "wallet_id" => self.wallet_id
-                        }
-                )
-        };
+        let result = write_pool.prep_exec(
+                        format!("UPDATE {} SET name = :new_name WHERE name = :name", tables.wallets),
+                         params!{
+                            name,
+                            new_name
+                         }
+        );
 
-        match result {
+        let result = match result {
                 Err(Error::MySqlError(err)) => {
-                    warn!("MySQL Error while executing query. Err Code: {}", err.code);
+                    warn!("MySQL Error while executing query. Err Code: {}, Err State: {}", err.code, err.state);
                     match err.code {
-                        1062 => return ErrorCode::ItemAlreadyExists,
-                        3140 => return ErrorCode::InvalidStructure, // Invalid JSON
+                        1062 => return ErrorCode::WalletAlreadyExistsError,
+                        ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
                         _ => return ErrorCode::IOError,
                     };
                 },
@@ -365,20 +825,25 @@ impl<'a> MySQLStorage<'a> {
                 Ok(result) => result,
         };
 
-        trace!("Success Adding Record with the type: {}, id: {}", type_, id);
+        if result.affected_rows() != 1 {
+            warn!("Trying to rename a non existent storage, name: {}", name);
+            return ErrorCode::WalletNotFound;
+        }
 
         ErrorCode::Success
     }
 
     ///
-    /// Fetches a record identified by type and id.
+    /// Copies a wallet with all of its records and metadata to a new wallet in the DB specified in the config.
+    ///
+    /// Records are copied on the DB server with `INSERT ... SELECT`, within a single transaction.
     ///
     /// # Arguments
     ///
-    ///  * `type_` - record type
-    ///  * `id` - record id (name)
-    ///  * `options` - options in the form of {"fetch_value": true, "fetch_tags": true} determining whether to fetch the value and/or tags
-    ///  * `record_handle_p` - output param - handle that will be used for accessing the fetched record
+    ///  * `name` - name of the wallet to be copied
+    ///  * `new_name` - name of the new wallet
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
     ///
     /// # Returns
     ///
@@ -387,89 +852,3454 @@ impl<'a> MySQLStorage<'a> {
     /// # ErrorCodes
     ///
     ///  * `Success` - Execution successful
-    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> options
-    ///  * `ItemNotFound` - Record with the provided type and id does not exist in the DB
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `WalletAlreadyExistsError` - Wallet with the new name already exists in the DB
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
-    ///  * `InvalidState` - Invalid encoding of a provided/fetched string
     ///
-    pub fn fetch_record(&self, type_: &str, id: &str, options: &str, record_handle_p: *mut i32) -> ErrorCode {
+    pub fn copy_storage(name: &str, new_name: &str, config: &str, credentials: &str) -> ErrorCode {
 
-        trace!("Fetching Record -> type: {}, id: {}, options: {}", type_, id, options);
+        trace!("Copying Storage -> name: {}, new_name: {}", name, new_name);
 
-        let options: FetchOptions = check_result!(serde_json::from_str(options), ErrorCode::InvalidStructure);
+        let config: StorageConfig = check_result!(serde_json::from_str(config), ErrorCode::InvalidStructure);
+        let tables = check_option!(Tables::new(config.table_prefix), ErrorCode::InvalidStructure);
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), ErrorCode::InvalidStructure);
 
-        let record: Record;
+        let shard = match sharding::route_existing(name, &config, &credentials, &tables) {
+            Err(err) => return err,
+            Ok(shard) => shard,
+        };
+
+        // the copy is created on the shard of the source wallet
+        let err = sharding::check_name_free(new_name, &config, &credentials, &tables);
+        if err != ErrorCode::Success {
+            return err;
+        }
+
+        let err = sharding::register(new_name, &shard, &config, &credentials, &tables);
+        if err != ErrorCode::Success {
+            return err;
+        }
+
+        let err = MySQLStorage::_copy_storage(name, new_name, &shard.config, &credentials, &tables);
+        if err != ErrorCode::Success {
+            sharding::unregister(new_name, &config, &credentials, &tables);
+            return err;
+        }
+
+        // storages still open on a deleted wallet of the same name must not serve the records of the copy from their caches
+        CACHES.clear(&MySQLStorage::_cache_key(new_name, &shard.config, &tables));
+
+        ErrorCode::Success
+    }
+
+    fn _copy_storage(name: &str, new_name: &str, config: &StorageConfig, credentials: &StorageCredentials, tables: &Tables) -> ErrorCode {
+
+        let write_pool = check_option!(CONNECTIONS.get(false, config, credentials), ErrorCode::IOError);
+
+        // the transaction is rolled back when dropped without a commit
+        let mut transaction = check_query!(write_pool.start_transaction(false, None, None), ErrorCode::IOError);
+
+        let wallet_id: u64 = {
+            let mut result = check_query!(
+                                transaction.prep_exec(
+                                    format!("SELECT id FROM {} WHERE name = :name LOCK IN SHARE MODE", tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), ErrorCode::IOError
+            );
+
+            check_option!(
+                check_query!(
+                    check_option!(result.next(), ErrorCode::WalletNotFound),
+                    ErrorCode::IOError).get(0),
+                ErrorCode::InvalidState)
+        };
+
+        let new_wallet_id = {
+            let result = transaction.prep_exec(
+                            format!("INSERT INTO {0}(name, metadata, metadata_encoding) SELECT :new_name, metadata, metadata_encoding FROM {0} WHERE id = :wallet_id", tables.wallets),
+                             params!{
+                                new_name,
+                                wallet_id
+                             }
+            );
+
+            match result {
+                    Err(Error::MySqlError(err)) => {
+                        warn!("MySQL Error while executing query. Err Code: {}, Err State: {}", err.code, err.state);
+                        match err.code {
+                            1062 => return ErrorCode::WalletAlreadyExistsError,
+                            ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                            _ => return ErrorCode::IOError,
+                        };
+                    },
+                    Err(err) => {
+                        warn!("Unexpected Error while executing query. Err: {:?}", err);
+                        return ErrorCode::IOError
+                    },
+                    Ok(result) => result.last_insert_id(),
+            }
+        };
+
+        let copied_records = {
+            let result = check_query!(
+                            transaction.prep_exec(
+                                format!("INSERT INTO {0}(wallet_id, type, name, value, value_chunks, value_encoding, tags, version) \
+                                         SELECT :new_wallet_id, type, name, value, value_chunks, value_encoding, tags, version FROM {0} WHERE wallet_id = :wallet_id", tables.items),
+                                 params!{
+                                    new_wallet_id,
+                                    wallet_id
+                                 }
+                            ), ErrorCode::IOError
+            );
+
+            result.affected_rows()
+        };
+
+        check_query!(
+            transaction.prep_exec(
+                format!("INSERT INTO {0}(item_id, seq, data) \
+                         SELECT n.id, c.seq, c.data FROM {1} o \
+                            JOIN {0} c ON c.item_id = o.id \
+                            JOIN {1} n ON n.wallet_id = :new_wallet_id AND n.type = o.type AND n.name = o.name \
+                         WHERE o.wallet_id = :wallet_id AND o.value_chunks > 0", tables.value_chunks, tables.items),
+                 params!{
+                    new_wallet_id,
+                    wallet_id
+                 }
+            ), ErrorCode::IOError
+        );
+
+        // the copied values and metadata stay encrypted with the data keys of the source wallet
+        check_query!(
+            transaction.prep_exec(
+                format!("INSERT INTO {0}(wallet_id, version, kek_id, wrapped_key) \
+                         SELECT :new_wallet_id, version, kek_id, wrapped_key FROM {0} WHERE wallet_id = :wallet_id", tables.data_keys),
+                 params!{
+                    new_wallet_id,
+                    wallet_id
+                 }
+            ), ErrorCode::IOError
+        );
+
+        check_query!(transaction.commit(), ErrorCode::IOError);
+
+        trace!("Success Copying Storage with the name: {} to: {}. Copied Records: {}", name, new_name, copied_records);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Lists names of the wallets in the DB specified in the config, one page at a time.
+    ///
+    /// # Arguments
+    ///
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
+    ///  * `options_json` - listing options
+    ///         {
+    ///             prefix: (optional) only wallets whose name starts with the prefix are listed
+    ///             limit: (optional, 100 by default) maximum number of wallets in a page
+    ///             after: (optional) name of the last wallet of the previous page
+    ///         }
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<String, ErrorCode>` - json in the form of `{"wallets": ["..."], "next": "..." | null}`,
+    ///     where `next` is passed as `after` to get the next page and is null on the last page
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials | options
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn list_storages(config: &str, credentials: &str, options_json: &str) -> Result<String, ErrorCode> {
+
+        trace!("Listing Storages -> options: {}", options_json);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), Err(ErrorCode::InvalidStructure));
+        let tables = check_option!(Tables::new(config.table_prefix), Err(ErrorCode::InvalidStructure));
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), Err(ErrorCode::InvalidStructure));
+        let options: ListStoragesOptions = check_result!(serde_json::from_str(options_json), Err(ErrorCode::InvalidStructure));
+
+        if options.limit == 0 {
+            warn!("List Storages Error: `limit` must be greater than 0");
+            return Err(ErrorCode::InvalidStructure);
+        }
+
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut arguments: Vec<Value> = Vec::new();
+
+        if let Some(ref prefix) = options.prefix {
+            let escaped_prefix = prefix.replace("\\", "\\\\").replace("%", "\\%").replace("_", "\\_");
+            conditions.push("name LIKE ?");
+            arguments.push(format!("{}%", escaped_prefix).into());
+        }
+
+        if let Some(ref after) = options.after {
+            conditions.push("name > ?");
+            arguments.push(after.into());
+        }
 
+        // one more wallet than requested is fetched to know whether there is a next page
         let query = format!(
-            "SELECT {}, {} \
-             FROM items i \
-             WHERE \
-                wallet_id = :wallet_id \
-                AND type = :type \
-                AND name = :name",
-            if options.retrieve_value { "value" } else {"''"},
-            if options.retrieve_tags { "tags" } else {"''"}
+            "SELECT name FROM {}{} ORDER BY name LIMIT {}",
+            tables.wallets,
+            if conditions.is_empty() { String::new() } else { format!(" WHERE {}", conditions.join(" AND ")) },
+            options.limit + 1
         );
 
-        let mut result: QueryResult = check_result!(
-            self.read_pool.prep_exec(
-                &query,
+        let mut names: Vec<String> = Vec::new();
+
+        for shard in sharding::all_shards(&config) {
+            let read_pool = check_option!(CONNECTIONS.get(true, &shard.config, &credentials), Err(ErrorCode::IOError));
+
+            let result: QueryResult = check_query!(read_pool.prep_exec(&query, arguments.clone()), Err(ErrorCode::IOError));
+
+            for row in result {
+                let row = check_query!(row, Err(ErrorCode::IOError));
+                names.push(check_option!(row.get(0), Err(ErrorCode::IOError)));
+            }
+        }
+
+        // pages of the shards are merged in the order of the case insensitive collation of the `name` column
+        if config.shards.len() > 1 {
+            names.sort_by_key(|name| name.to_ascii_lowercase());
+        }
+
+        let next = if names.len() as u64 > options.limit {
+            names.truncate(options.limit as usize);
+            names.last().cloned()
+        } else {
+            None
+        };
+
+        trace!("Success Listing Storages -> options: {}. Listed: {}", options_json, names.len());
+
+        Ok(json!({"wallets": names, "next": next}).to_string())
+    }
+
+    ///
+    /// Gets the size statistics of a wallet in the DB specified in the config.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<String, ErrorCode>` - json in the form of
+    ///         {
+    ///             "name": "...",
+    ///             "metadataBytes": 100,
+    ///             "itemCount": 3,
+    ///             "valueBytes": 300,
+    ///             "tagsBytes": 150,
+    ///             "types": [{"type": "...", "itemCount": 3, "valueBytes": 300, "tagsBytes": 150}]
+    ///         }
+    ///     where the byte counts are the stored sizes of values, including the chunks of the chunked values,
+    ///     and of the tags json documents
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn get_storage_stats(name: &str, config: &str, credentials: &str) -> Result<String, ErrorCode> {
+
+        trace!("Getting Storage Stats -> name: {}", name);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), Err(ErrorCode::InvalidStructure));
+        let tables = check_option!(Tables::new(config.table_prefix), Err(ErrorCode::InvalidStructure));
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), Err(ErrorCode::InvalidStructure));
+
+        let shard = sharding::route_existing(name, &config, &credentials, &tables)?;
+
+        let read_pool = check_option!(CONNECTIONS.get(true, &shard.config, &credentials), Err(ErrorCode::IOError));
+
+        let (wallet_id, metadata_bytes): (u64, u64) = {
+            let mut result = check_query!(
+                                read_pool.prep_exec(
+                                    format!("SELECT id, LENGTH(metadata) FROM {} WHERE name = :name", tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::WalletNotFound)), Err(ErrorCode::IOError));
+
+            (check_option!(row.get(0), Err(ErrorCode::InvalidState)), check_option!(row.get(1), Err(ErrorCode::InvalidState)))
+        };
+
+        let result: QueryResult = check_query!(
+            read_pool.prep_exec(
+                format!("SELECT i.type, count(*), \
+                            CAST(SUM(LENGTH(i.value) + (SELECT COALESCE(SUM(LENGTH(c.data)), 0) FROM {} c WHERE c.item_id = i.id)) AS UNSIGNED), \
+                            CAST(SUM(LENGTH(i.tags)) AS UNSIGNED) \
+                         FROM {} i WHERE i.wallet_id = :wallet_id GROUP BY i.type ORDER BY i.type", tables.value_chunks, tables.items),
                 params!{
-                    "wallet_id" => self.wallet_id,
-                    "type" => type_,
-                    "name" => id
+                    wallet_id
                 }
             ),
-            ErrorCode::IOError
+            Err(ErrorCode::IOError)
         );
 
-        let row = check_result!(check_option!(result.next(), ErrorCode::ItemNotFound), ErrorCode::IOError);
+        let mut types: Vec<serde_json::Value> = Vec::new();
+        let (mut item_count, mut value_bytes, mut tags_bytes) = (0u64, 0u64, 0u64);
 
-        // These 2 values cannot be NULL.
-        let db_value: Vec<u8> = check_option!(row.get(0), ErrorCode::IOError);
-        let tags: String = check_option!(row.get(1), ErrorCode::IOError);
+        for row in result {
+            let row = check_query!(row, Err(ErrorCode::IOError));
 
-        record = Record::new(
-            check_result!(CString::new(id), ErrorCode::InvalidState),
-            if options.retrieve_value {Some(db_value)} else {None},
-            if options.retrieve_tags {Some(check_result!(CString::new(tags), ErrorCode::InvalidState))} else {None},
-            if options.retrieve_type {Some(check_result!(CString::new(type_), ErrorCode::InvalidState))} else {None}
+            let type_: String = check_option!(row.get(0), Err(ErrorCode::IOError));
+            let type_item_count: u64 = check_option!(row.get(1), Err(ErrorCode::IOError));
+            let type_value_bytes: u64 = check_option!(row.get(2), Err(ErrorCode::IOError));
+            let type_tags_bytes: u64 = check_option!(row.get(3), Err(ErrorCode::IOError));
+
+            item_count += type_item_count;
+            value_bytes += type_value_bytes;
+            tags_bytes += type_tags_bytes;
+
+            types.push(json!({"type": type_, "itemCount": type_item_count, "valueBytes": type_value_bytes, "tagsBytes": type_tags_bytes}));
+        }
+
+        trace!("Success Getting Storage Stats with the name: {}", name);
+
+        Ok(json!({
+            "name": name,
+            "metadataBytes": metadata_bytes,
+            "itemCount": item_count,
+            "valueBytes": value_bytes,
+            "tagsBytes": tags_bytes,
+            "types": types
+        }).to_string())
+    }
+
+    ///
+    /// Computes an integrity report of a wallet, to confirm that a wallet matches its source after
+    /// a restore or a migration.
+    ///
+    /// The records are streamed ordered by type and name within a consistent snapshot. Every record
+    /// has a leaf hash over the hashes of its type, name, value and tags, consecutive leaves are hashed
+    /// into batches and the batch hashes into the root, all with SHA-256. Values and metadata are hashed
+    /// as they were written, decompressed and decrypted with the keys of the config, so that wallets stored
+    /// with different compression or encryption settings can be compared. A chunked value is hashed whole.
+    /// Equal roots mean equal records, and the batches narrow down where two wallets differ. Wallet names
+    /// are not part of the hashes.
+    /// Every `tags` value is also checked to be a flat JSON object of strings.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<String, ErrorCode>` - json in the form of
+    ///         {
+    ///             "name": "...",
+    ///             "algorithm": "sha256",
+    ///             "itemCount": 1500,
+    ///             "metadataHash": "...",
+    ///             "root": "...",
+    ///             "batchSize": 1000,
+    ///             "batches": [{"first": {"type": "...", "id": "..."}, "last": {...}, "itemCount": 1000, "hash": "..."}],
+    ///             "invalidTagsCount": 1,
+    ///             "invalidTags": [{"type": "...", "id": "...", "error": "..."}]
+    ///         }
+    ///     where at most the first 100 records with invalid tags are listed
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `EncryptionKeyNotFound` - Wallet holds encrypted data, but the config lacks the KEK of its data key
+    ///  * `DecryptionFailed` - Stored value or metadata could not be decrypted
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn verify_storage(name: &str, config: &str, credentials: &str) -> Result<String, ErrorCode> {
+
+        trace!("Verifying Storage -> name: {}", name);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), Err(ErrorCode::InvalidStructure));
+        let tables = check_option!(Tables::new(config.table_prefix), Err(ErrorCode::InvalidStructure));
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), Err(ErrorCode::InvalidStructure));
+
+        let shard = sharding::route_existing(name, &config, &credentials, &tables)?;
+
+        let read_pool = check_option!(CONNECTIONS.get(true, &shard.config, &credentials), Err(ErrorCode::IOError));
+
+        // all of the records are read from the same snapshot, even if the wallet is written to meanwhile
+        let mut transaction = check_query!(read_pool.start_transaction(true, None, Some(true)), Err(ErrorCode::IOError));
+
+        let (wallet_id, metadata, metadata_encoding): (u64, String, u8) = {
+            let mut result = check_query!(
+                                transaction.prep_exec(
+                                    format!("SELECT id, metadata, metadata_encoding FROM {} WHERE name = :name", tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::WalletNotFound)), Err(ErrorCode::IOError));
+
+            (check_option!(row.get(0), Err(ErrorCode::InvalidState)), check_option!(row.get(1), Err(ErrorCode::InvalidState)), check_option!(row.get(2), Err(ErrorCode::InvalidState)))
+        };
+
+        // the data keys are never deleted, the ones loaded after the snapshot was taken cover all of the data in it
+        let encryption = match KeyEncryptionKeys::from_config(&config.encryption)? {
+            None => None,
+            Some(keks) => Some(Encryption::load(keks, read_pool.clone(), &tables, wallet_id)?),
+        };
+
+        let metadata_hash = sha256_hex(encryption::decode_metadata(encryption.as_ref(), metadata, metadata_encoding)?.as_bytes());
+
+        let mut item_count: u64 = 0;
+        let mut batches: Vec<(RecordKey, RecordKey, usize, String)> = Vec::new();
+        let mut invalid_tags: Vec<serde_json::Value> = Vec::new();
+        let mut invalid_tags_count: u64 = 0;
+
+        // key, id and value encoding of the item and the hashes of the value and the tags, the hashes of the chunked values are added below
+        let mut records: Vec<(RecordKey, u64, u8, Option<String>, String)> = Vec::new();
+
+        {
+            let result = check_query!(
+                            transaction.prep_exec(
+                                format!("SELECT id, type, name, value, value_chunks, value_encoding, tags FROM {} WHERE wallet_id = :wallet_id ORDER BY type, name", tables.items),
+                                params!{
+                                    wallet_id
+                                 }
+                            ), Err(ErrorCode::IOError)
+            );
+
+            for row in result {
+                let row = check_query!(row, Err(ErrorCode::IOError));
+
+                let item_id: u64 = check_option!(row.get(0), Err(ErrorCode::InvalidState));
+                let key = RecordKey {
+                    type_: check_option!(row.get(1), Err(ErrorCode::InvalidState)),
+                    id: check_option!(row.get(2), Err(ErrorCode::InvalidState)),
+                };
+                let value: Vec<u8> = check_option!(row.get(3), Err(ErrorCode::InvalidState));
+                let value_chunks: u32 = check_option!(row.get(4), Err(ErrorCode::InvalidState));
+                let value_encoding: u8 = check_option!(row.get(5), Err(ErrorCode::InvalidState));
+                let tags: String = check_option!(row.get(6), Err(ErrorCode::InvalidState));
+
+                item_count += 1;
+
+                if let Some(error) = invalid_tags_error(&tags) {
+                    invalid_tags_count += 1;
+                    if invalid_tags.len() < MAX_REPORTED_INVALID_TAGS {
+                        invalid_tags.push(json!({"type": key.type_, "id": key.id, "error": error}));
+                    }
+                }
+
+                let value_hash = if value_chunks > 0 {
+                    None
+                } else {
+                    Some(sha256_hex(&value_chunks::decode(vec![value], value_encoding, encryption.as_ref())?))
+                };
+
+                records.push((key, item_id, value_encoding, value_hash, sha256_hex(tags.as_bytes())));
+            }
+        }
+
+        let mut leaves: Vec<(RecordKey, String)> = Vec::new();
+
+        for (key, item_id, value_encoding, value_hash, tags_hash) in records {
+            let value_hash = match value_hash {
+                Some(value_hash) => value_hash,
+                None => {
+                    let chunks = MySQLStorage::_read_value_chunks(&mut transaction, &tables, item_id)?;
+                    sha256_hex(&value_chunks::decode(chunks, value_encoding, encryption.as_ref())?)
+                },
+            };
+
+            let leaf = sha256_hex(format!("{}{}{}{}", sha256_hex(key.type_.as_bytes()), sha256_hex(key.id.as_bytes()), value_hash, tags_hash).as_bytes());
+
+            leaves.push((key, leaf));
+
+            if leaves.len() == VERIFY_BATCH_SIZE {
+                batches.push(leaves_to_batch(&leaves));
+                leaves.clear();
+            }
+        }
+
+        if !leaves.is_empty() {
+            batches.push(leaves_to_batch(&leaves));
+        }
+
+        let mut batches_json: Vec<serde_json::Value> = Vec::new();
+        let mut batch_hashes = String::new();
+
+        for (first, last, count, leaves) in batches {
+            let hash = sha256_hex(leaves.as_bytes());
+            batch_hashes.push_str(&hash);
+            batches_json.push(json!({"first": first, "last": last, "itemCount": count, "hash": hash}));
+        }
+
+        let root = sha256_hex(batch_hashes.as_bytes());
+
+        trace!("Success Verifying Storage with the name: {}. Items: {}, Root: {}", name, item_count, root);
+
+        Ok(json!({
+            "name": name,
+            "algorithm": "sha256",
+            "itemCount": item_count,
+            "metadataHash": metadata_hash,
+            "root": root,
+            "batchSize": VERIFY_BATCH_SIZE,
+            "batches": batches_json,
+            "invalidTagsCount": invalid_tags_count,
+            "invalidTags": invalid_tags
+        }).to_string())
+    }
+
+    ///
+    /// Stored chunks of a value, in order.
+    ///
+    fn _read_value_chunks(transaction: &mut Transaction, tables: &Tables, item_id: u64) -> Result<Vec<Vec<u8>>, ErrorCode> {
+
+        let result = check_query!(
+                        transaction.prep_exec(
+                            format!("SELECT data FROM {} WHERE item_id = :item_id ORDER BY seq", tables.value_chunks),
+                            params!{
+                                item_id
+                             }
+                        ), Err(ErrorCode::IOError)
         );
 
-        let record_handle = self.records.insert(record);
+        let mut chunks: Vec<Vec<u8>> = Vec::new();
 
-        unsafe { *record_handle_p = record_handle; }
+        for row in result {
+            let row = check_query!(row, Err(ErrorCode::IOError));
 
-        trace!("Success Fetching Record with the type: {}, id: {}. Record Handle: {}", type_, id, record_handle);
+            chunks.push(check_option!(row.get(0), Err(ErrorCode::InvalidState)));
+        }
 
-        ErrorCode::Success
+        Ok(chunks)
     }
 
     ///
-    /// Returns a record with the specified handle.
+    /// Takes a snapshot of a wallet, copying its records and metadata into the snapshot tables on the DB server,
+    /// e.g. before a key rotation. Snapshots exceeding the `snapshot_retention` of the config are dropped afterwards.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet
+    ///  * `label` - label of the snapshot, unique within the wallet, up to 255 characters
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials,
+    ///     the label is empty or too long, or `max_snapshots` of the retention is 0
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `SnapshotAlreadyExists` - Wallet already has a snapshot with the label
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn snapshot_storage(name: &str, label: &str, config: &str, credentials: &str) -> ErrorCode {
+
+        trace!("Taking Storage Snapshot -> name: {}, label: {}", name, label);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), ErrorCode::InvalidStructure);
+        let tables = check_option!(Tables::new(config.table_prefix), ErrorCode::InvalidStructure);
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), ErrorCode::InvalidStructure);
+
+        if label.is_empty() || label.len() > MAX_SNAPSHOT_LABEL_LENGTH {
+            warn!("Snapshot Storage Error: label must have between 1 and {} characters", MAX_SNAPSHOT_LABEL_LENGTH);
+            return ErrorCode::InvalidStructure;
+        }
+
+        if config.snapshot_retention.max_snapshots == Some(0) {
+            warn!("Snapshot Storage Error: `max_snapshots` must be greater than 0");
+            return ErrorCode::InvalidStructure;
+        }
+
+        let shard = match sharding::route_existing(name, &config, &credentials, &tables) {
+            Err(err) => return err,
+            Ok(shard) => shard,
+        };
+
+        let write_pool = check_option!(CONNECTIONS.get(false, &shard.config, &credentials), ErrorCode::IOError);
+
+        // the transaction is rolled back when dropped without a commit
+        let mut transaction = check_query!(write_pool.start_transaction(false, None, None), ErrorCode::IOError);
+
+        let (wallet_id, metadata, metadata_encoding): (u64, String, u8) = {
+            let mut result = check_query!(
+                                transaction.prep_exec(
+                                    format!("SELECT id, metadata, metadata_encoding FROM {} WHERE name = :name LOCK IN SHARE MODE", tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), ErrorCode::IOError
+            );
+
+            let row = check_query!(check_option!(result.next(), ErrorCode::WalletNotFound), ErrorCode::IOError);
+
+            (check_option!(row.get(0), ErrorCode::InvalidState), check_option!(row.get(1), ErrorCode::InvalidState), check_option!(row.get(2), ErrorCode::InvalidState))
+        };
+
+        let snapshot_id = {
+            let result = transaction.prep_exec(
+                            format!("INSERT INTO {}(wallet_id, label, metadata, metadata_encoding) VALUES (:wallet_id, :label, :metadata, :metadata_encoding)", tables.snapshots),
+                             params!{
+                                wallet_id,
+                                label,
+                                metadata,
+                                metadata_encoding
+                             }
+            );
+
+            match result {
+                    Err(Error::MySqlError(err)) => {
+                        warn!("MySQL Error while executing query. Err Code: {}, Err State: {}", err.code, err.state);
+                        match err.code {
+                            1062 => return ErrorCode::SnapshotAlreadyExists,
+                            ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                            _ => return ErrorCode::IOError,
+                        };
+                    },
+                    Err(err) => {
+                        warn!("Unexpected Error while executing query. Err: {:?}", err);
+                        return ErrorCode::IOError
+                    },
+                    Ok(result) => result.last_insert_id(),
+            }
+        };
+
+        // INSERT ... SELECT reads the records with shared locks, so the snapshot is consistent
+        let snapshot_records = {
+            let result = check_query!(
+                            transaction.prep_exec(
+                                format!("INSERT INTO {}(snapshot_id, type, name, value, value_chunks, value_encoding, tags, version) \
+                                         SELECT :snapshot_id, type, name, value, value_chunks, value_encoding, tags, version FROM {} WHERE wallet_id = :wallet_id", tables.snapshot_items, tables.items),
+                                 params!{
+                                    snapshot_id,
+                                    wallet_id
+                                 }
+                            ), ErrorCode::IOError
+            );
+
+            result.affected_rows()
+        };
+
+        check_query!(
+            transaction.prep_exec(
+                format!("INSERT INTO {}(snapshot_id, type, name, seq, data) \
+                         SELECT :snapshot_id, i.type, i.name, c.seq, c.data FROM {} i \
+                            JOIN {} c ON c.item_id = i.id \
+                         WHERE i.wallet_id = :wallet_id AND i.value_chunks > 0", tables.snapshot_value_chunks, tables.items, tables.value_chunks),
+                 params!{
+                    snapshot_id,
+                    wallet_id
+                 }
+            ), ErrorCode::IOError
+        );
+
+        let err = MySQLStorage::_apply_snapshot_retention(&mut transaction, &tables, wallet_id, &config.snapshot_retention);
+        if err != ErrorCode::Success {
+            return err;
+        }
+
+        check_query!(transaction.commit(), ErrorCode::IOError);
+
+        trace!("Success Taking Storage Snapshot with the name: {}, label: {}. Records: {}", name, label, snapshot_records);
+
+        ErrorCode::Success
+    }
+
+    fn _apply_snapshot_retention(transaction: &mut Transaction, tables: &Tables, wallet_id: u64, retention: &SnapshotRetention) -> ErrorCode {
+
+        if let Some(max_age) = retention.max_age {
+            let result = check_query!(
+                            transaction.prep_exec(
+                                format!("DELETE FROM {} WHERE wallet_id = :wallet_id AND created_at < NOW() - INTERVAL :max_age SECOND", tables.snapshots),
+                                params!{
+                                    wallet_id,
+                                    max_age
+                                 }
+                            ), ErrorCode::IOError
+            );
+
+            trace!("Dropped Snapshots older than {} seconds: {}", max_age, result.affected_rows());
+        }
+
+        if let Some(max_snapshots) = retention.max_snapshots {
+            let mut expired: Vec<Value> = Vec::new();
+
+            {
+                let result = check_query!(
+                                transaction.prep_exec(
+                                    format!("SELECT id FROM {} WHERE wallet_id = :wallet_id ORDER BY id DESC", tables.snapshots),
+                                    params!{
+                                        wallet_id
+                                     }
+                                ), ErrorCode::IOError
+                );
+
+                for row in result.skip(max_snapshots) {
+                    let row = check_query!(row, ErrorCode::IOError);
+                    expired.push(check_option!(row.get::<u64, usize>(0), ErrorCode::InvalidState).into());
+                }
+            }
+
+            if !expired.is_empty() {
+                trace!("Dropping Snapshots over the limit of {}: {}", max_snapshots, expired.len());
+
+                check_query!(
+                    transaction.prep_exec(
+                        format!("DELETE FROM {} WHERE id IN ({})", tables.snapshots, vec!["?"; expired.len()].join(", ")),
+                        expired
+                    ), ErrorCode::IOError
+                );
+            }
+        }
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Lists the snapshots of a wallet, oldest first.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<String, ErrorCode>` - json in the form of
+    ///         {"snapshots": [{"label": "...", "createdAt": 1792281600, "itemCount": 3}]}
+    ///     where `createdAt` is a unix timestamp in seconds
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn list_snapshots(name: &str, config: &str, credentials: &str) -> Result<String, ErrorCode> {
+
+        trace!("Listing Storage Snapshots -> name: {}", name);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), Err(ErrorCode::InvalidStructure));
+        let tables = check_option!(Tables::new(config.table_prefix), Err(ErrorCode::InvalidStructure));
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), Err(ErrorCode::InvalidStructure));
+
+        let shard = sharding::route_existing(name, &config, &credentials, &tables)?;
+
+        let read_pool = check_option!(CONNECTIONS.get(true, &shard.config, &credentials), Err(ErrorCode::IOError));
+
+        let wallet_id: u64 = {
+            let mut result = check_query!(
+                                read_pool.prep_exec(
+                                    format!("SELECT id FROM {} WHERE name = :name", tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::WalletNotFound)), Err(ErrorCode::IOError));
+
+            check_option!(row.get(0), Err(ErrorCode::InvalidState))
+        };
+
+        let result: QueryResult = check_query!(
+            read_pool.prep_exec(
+                format!("SELECT s.label, UNIX_TIMESTAMP(s.created_at), COUNT(i.snapshot_id) FROM {} s \
+                         LEFT JOIN {} i ON i.snapshot_id = s.id \
+                         WHERE s.wallet_id = :wallet_id GROUP BY s.id, s.label, s.created_at ORDER BY s.id", tables.snapshots, tables.snapshot_items),
+                params!{
+                    wallet_id
+                 }
+            ), Err(ErrorCode::IOError)
+        );
+
+        let mut snapshots: Vec<serde_json::Value> = Vec::new();
+
+        for row in result {
+            let row = check_query!(row, Err(ErrorCode::IOError));
+
+            let label: String = check_option!(row.get(0), Err(ErrorCode::InvalidState));
+            let created_at: u64 = check_option!(row.get(1), Err(ErrorCode::InvalidState));
+            let item_count: u64 = check_option!(row.get(2), Err(ErrorCode::InvalidState));
+
+            snapshots.push(json!({"label": label, "createdAt": created_at, "itemCount": item_count}));
+        }
+
+        trace!("Success Listing Storage Snapshots with the name: {}. Listed: {}", name, snapshots.len());
+
+        Ok(json!({"snapshots": snapshots}).to_string())
+    }
+
+    ///
+    /// Restores the records and metadata of a wallet from a snapshot, replacing all of the current records
+    /// in a single transaction. The snapshot is kept.
+    ///
+    /// Restored records get versions above the versions of all of the current records, so that updates
+    /// based on records read before the restore fail with `VersionConflict`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet
+    ///  * `label` - label of the snapshot
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `SnapshotNotFound` - Wallet has no snapshot with the label
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn restore_snapshot(name: &str, label: &str, config: &str, credentials: &str) -> ErrorCode {
+
+        trace!("Restoring Storage Snapshot -> name: {}, label: {}", name, label);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), ErrorCode::InvalidStructure);
+        let tables = check_option!(Tables::new(config.table_prefix), ErrorCode::InvalidStructure);
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), ErrorCode::InvalidStructure);
+
+        let shard = match sharding::route_existing(name, &config, &credentials, &tables) {
+            Err(err) => return err,
+            Ok(shard) => shard,
+        };
+
+        let write_pool = check_option!(CONNECTIONS.get(false, &shard.config, &credentials), ErrorCode::IOError);
+
+        // the transaction is rolled back when dropped without a commit
+        let mut transaction = check_query!(write_pool.start_transaction(false, None, None), ErrorCode::IOError);
+
+        // locking the wallet row blocks adding records to the wallet during the restore
+        let wallet_id: u64 = {
+            let mut result = check_query!(
+                                transaction.prep_exec(
+                                    format!("SELECT id FROM {} WHERE name = :name FOR UPDATE", tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), ErrorCode::IOError
+            );
+
+            let row = check_query!(check_option!(result.next(), ErrorCode::WalletNotFound), ErrorCode::IOError);
+
+            check_option!(row.get(0), ErrorCode::InvalidState)
+        };
+
+        let mut transaction = match Writer::from_transaction(transaction, &tables, wallet_id, config.change_feed.enabled) {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        let (snapshot_id, metadata, metadata_encoding): (u64, String, u8) = {
+            let mut result = check_query!(
+                                transaction.prep_exec(
+                                    format!("SELECT id, metadata, metadata_encoding FROM {} WHERE wallet_id = :wallet_id AND label = :label", tables.snapshots),
+                                    params!{
+                                        wallet_id,
+                                        label
+                                     }
+                                ), ErrorCode::IOError
+            );
+
+            let row = check_query!(check_option!(result.next(), ErrorCode::SnapshotNotFound), ErrorCode::IOError);
+
+            (check_option!(row.get(0), ErrorCode::InvalidState), check_option!(row.get(1), ErrorCode::InvalidState), check_option!(row.get(2), ErrorCode::InvalidState))
+        };
+
+        let version_offset: u64 = {
+            let mut result = check_query!(
+                                transaction.prep_exec(
+                                    format!("SELECT CAST(COALESCE(MAX(version), 0) AS UNSIGNED) FROM {} WHERE wallet_id = :wallet_id FOR UPDATE", tables.items),
+                                    params!{
+                                        wallet_id
+                                     }
+                                ), ErrorCode::IOError
+            );
+
+            let row = check_query!(check_option!(result.next(), ErrorCode::InvalidState), ErrorCode::IOError);
+
+            check_option!(row.get(0), ErrorCode::InvalidState)
+        };
+
+        // the restore is journaled as the deletion of the current records followed by the addition of the restored ones
+        if let Err(err) = transaction.log_query(ChangeOperation::Delete, &query_translator::TypeFilter::Any, &query_translator::Operator::And(vec![]), None, &config.query_limits) {
+            return err;
+        }
+
+        check_query!(
+            transaction.prep_exec(
+                format!("DELETE FROM {} WHERE wallet_id = :wallet_id", tables.items),
+                params!{
+                    wallet_id
+                 }
+            ), ErrorCode::IOError
+        );
+
+        let restored_records = {
+            let result = check_query!(
+                            transaction.prep_exec(
+                                format!("INSERT INTO {}(wallet_id, type, name, value, value_chunks, value_encoding, tags, version) \
+                                         SELECT :wallet_id, type, name, value, value_chunks, value_encoding, tags, version + :version_offset FROM {} WHERE snapshot_id = :snapshot_id", tables.items, tables.snapshot_items),
+                                 params!{
+                                    wallet_id,
+                                    version_offset,
+                                    snapshot_id
+                                 }
+                            ), ErrorCode::IOError
+            );
+
+            result.affected_rows()
+        };
+
+        // the chunks of the deleted records were deleted with them
+        check_query!(
+            transaction.prep_exec(
+                format!("INSERT INTO {}(item_id, seq, data) \
+                         SELECT i.id, c.seq, c.data FROM {} c \
+                            JOIN {} i ON i.wallet_id = :wallet_id AND i.type = c.type AND i.name = c.name \
+                         WHERE c.snapshot_id = :snapshot_id", tables.value_chunks, tables.snapshot_value_chunks, tables.items),
+                 params!{
+                    wallet_id,
+                    snapshot_id
+                 }
+            ), ErrorCode::IOError
+        );
+
+        if let Err(err) = transaction.log_query(ChangeOperation::Add, &query_translator::TypeFilter::Any, &query_translator::Operator::And(vec![]), Some(0), &config.query_limits) {
+            return err;
+        }
+
+        check_query!(
+            transaction.prep_exec(
+                format!("UPDATE {} SET metadata = :metadata, metadata_encoding = :metadata_encoding WHERE id = :wallet_id", tables.wallets),
+                params!{
+                    metadata,
+                    metadata_encoding,
+                    wallet_id
+                 }
+            ), ErrorCode::IOError
+        );
+
+        if let Err(err) = transaction.commit() {
+            return err;
+        }
+
+        CACHES.clear(&MySQLStorage::_cache_key(name, &shard.config, &tables));
+
+        trace!("Success Restoring Storage Snapshot with the name: {}, label: {}. Records: {}", name, label, restored_records);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Drops a snapshot of a wallet.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet
+    ///  * `label` - label of the snapshot
+    ///  * `config` - json containing information like db_host, db_port, db_name
+    ///  * `credentials` - json containing information about user and password for db access
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `SnapshotNotFound` - Wallet has no snapshot with the label
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn drop_snapshot(name: &str, label: &str, config: &str, credentials: &str) -> ErrorCode {
+
+        trace!("Dropping Storage Snapshot -> name: {}, label: {}", name, label);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), ErrorCode::InvalidStructure);
+        let tables = check_option!(Tables::new(config.table_prefix), ErrorCode::InvalidStructure);
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), ErrorCode::InvalidStructure);
+
+        let shard = match sharding::route_existing(name, &config, &credentials, &tables) {
+            Err(err) => return err,
+            Ok(shard) => shard,
+        };
+
+        let write_pool = check_option!(CONNECTIONS.get(false, &shard.config, &credentials), ErrorCode::IOError);
+
+        let wallet_id: u64 = {
+            let mut result = check_query!(
+                                write_pool.prep_exec(
+                                    format!("SELECT id FROM {} WHERE name = :name", tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), ErrorCode::IOError
+            );
+
+            let row = check_query!(check_option!(result.next(), ErrorCode::WalletNotFound), ErrorCode::IOError);
+
+            check_option!(row.get(0), ErrorCode::InvalidState)
+        };
+
+        let result = check_query!(
+                        write_pool.prep_exec(
+                            format!("DELETE FROM {} WHERE wallet_id = :wallet_id AND label = :label", tables.snapshots),
+                            params!{
+                                wallet_id,
+                                label
+                             }
+                        ), ErrorCode::IOError
+        );
+
+        if result.affected_rows() != 1 {
+            warn!("Trying to drop a non existent snapshot, name: {}, label: {}", name, label);
+            return ErrorCode::SnapshotNotFound;
+        }
+
+        trace!("Success Dropping Storage Snapshot with the name: {}, label: {}", name, label);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Moves a wallet with all of its records and metadata to another shard of a sharded storage.
+    ///
+    /// The wallet is moved online as by `move_storage`. With the directory placement the entry of the wallet
+    /// points to the target shard before the source wallet is deleted.
+    /// Storages opened before the move keep using the source shard and have to be opened again.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet to be moved
+    ///  * `shard_name` - (optional) name of the target shard, the shard the wallet is placed on
+    ///     by consistent hashing if not set, e.g. to rebalance the wallets after adding a shard
+    ///  * `config` - json containing information like db_host, db_port, db_name and the shards
+    ///  * `credentials` - json containing information about user and password for db access
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful, also when the wallet already is on the target shard
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials,
+    ///     the config lists no shards or no shard with the given name
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist on any of the shards
+    ///  * `WalletAlreadyExistsError` - Wallet with the same name already exists on the target shard
+    ///  * `InvalidState` - The directory places the wallet on a shard which is not in the config
+    ///  * `VerificationFailed` - Record count or checksum of the copy differs from the source wallet
+    ///  * `SourceNotDeleted` - Wallet is moved to the target shard, but could not be deleted from the source shard
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn rebalance_storage(name: &str, shard_name: Option<&str>, config: &str, credentials: &str) -> ErrorCode {
+
+        trace!("Rebalancing Storage -> name: {}, shard: {:?}", name, shard_name);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), ErrorCode::InvalidStructure);
+        let tables = check_option!(Tables::new(config.table_prefix), ErrorCode::InvalidStructure);
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), ErrorCode::InvalidStructure);
+
+        if config.shards.is_empty() {
+            warn!("Rebalance Storage Error: the config lists no shards");
+            return ErrorCode::InvalidStructure;
+        }
+
+        let target_index = match shard_name {
+            None => sharding::hashed_shard_index(&config, name),
+            Some(shard_name) => match sharding::shard_index(&config, shard_name) {
+                None => {
+                    warn!("Rebalance Storage Error: no shard with the name {} in the config", shard_name);
+                    return ErrorCode::InvalidStructure;
+                },
+                Some(index) => index,
+            },
+        };
+        let target = sharding::shard(&config, target_index);
+
+        let source = match sharding::route_existing(name, &config, &credentials, &tables) {
+            Err(err) => return err,
+            Ok(source) => source,
+        };
+
+        if source.name == target.name {
+            trace!("Storage with the name: {} already is on the shard: {:?}", name, target.name);
+            return ErrorCode::Success;
+        }
+
+        let err = MySQLStorage::_move_wallet(
+            name,
+            &source.config, &credentials, &tables,
+            &target.config, &credentials, &tables,
+            &|| sharding::register(name, &target, &config, &credentials, &tables)
+        );
+
+        if err == ErrorCode::Success {
+            trace!("Success Rebalancing Storage with the name: {} from: {:?} to: {:?}", name, source.name, target.name);
+        }
+
+        err
+    }
+
+    ///
+    /// Moves a wallet with all of its records and metadata from one DB to another, e.g. from a legacy DB
+    /// to a new cluster, with the wallet staying online during most of the move.
+    ///
+    /// The records are copied in batches ordered by their ids, then the records changed in the meantime
+    /// are found by comparing the version and the digests of the value and tags of every record, and copied
+    /// again. The writes to the source wallet are locked only for the last of these rounds, after which the
+    /// record count and checksum of both wallets are compared, and the source wallet is deleted.
+    /// Both configs can be sharded, the wallet is then routed as by `open_storage` and `create_storage`.
+    /// Storages opened before the move keep using the source DB and have to be opened again. The snapshots
+    /// of the wallet are not moved and are dropped with the source wallet.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet to be moved
+    ///  * `source_config` - json containing information like db_host, db_port, db_name of the source DB
+    ///  * `source_credentials` - json containing information about user and password for the source DB
+    ///  * `target_config` - json containing information like db_host, db_port, db_name of the target DB
+    ///  * `target_credentials` - json containing information about user and password for the target DB
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> configs | credentials
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the source DB
+    ///  * `WalletAlreadyExistsError` - Wallet with the same name already exists in the target DB
+    ///  * `VerificationFailed` - Record count or checksum of the copy differs from the source wallet,
+    ///     the copy is removed and the source wallet is kept
+    ///  * `SourceNotDeleted` - Wallet is moved, but could not be deleted from the source DB,
+    ///     the moved wallet stays registered in the target DB
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn move_storage(name: &str, source_config: &str, source_credentials: &str, target_config: &str, target_credentials: &str) -> ErrorCode {
+
+        trace!("Moving Storage -> name: {}", name);
+
+        let source_config: StorageConfig = check_result!(serde_json::from_str(source_config), ErrorCode::InvalidStructure);
+        let source_tables = check_option!(Tables::new(source_config.table_prefix), ErrorCode::InvalidStructure);
+        let source_credentials: StorageCredentials = check_result!(serde_json::from_str(source_credentials), ErrorCode::InvalidStructure);
+
+        let target_config: StorageConfig = check_result!(serde_json::from_str(target_config), ErrorCode::InvalidStructure);
+        let target_tables = check_option!(Tables::new(target_config.table_prefix), ErrorCode::InvalidStructure);
+        let target_credentials: StorageCredentials = check_result!(serde_json::from_str(target_credentials), ErrorCode::InvalidStructure);
+
+        let source = match sharding::route_existing(name, &source_config, &source_credentials, &source_tables) {
+            Err(err) => return err,
+            Ok(source) => source,
+        };
+
+        let target = match sharding::route_new(name, &target_config, &target_credentials, &target_tables) {
+            Err(err) => return err,
+            Ok(target) => target,
+        };
+
+        let err = MySQLStorage::_move_wallet(
+            name,
+            &source.config, &source_credentials, &source_tables,
+            &target.config, &target_credentials, &target_tables,
+            &|| sharding::register(name, &target, &target_config, &target_credentials, &target_tables)
+        );
+
+        if err != ErrorCode::Success {
+            // an existing directory entry belongs to the existing wallet, and a moved wallet keeps its entry
+            if err != ErrorCode::WalletAlreadyExistsError && err != ErrorCode::SourceNotDeleted {
+                sharding::unregister(name, &target_config, &target_credentials, &target_tables);
+            }
+            return err;
+        }
+
+        if !sharding::same_directory(&source_config, &source_tables, &target_config, &target_tables) {
+            let err = sharding::unregister(name, &source_config, &source_credentials, &source_tables);
+            if err != ErrorCode::Success {
+                return err;
+            }
+        }
+
+        trace!("Success Moving Storage with the name: {}", name);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Moves a wallet between two DBs, `before_source_delete` is called once the copy is committed,
+    /// while the writes to the source wallet are still locked. From then on the copy is kept, even if
+    /// the source wallet can not be deleted.
+    ///
+    fn _move_wallet(name: &str,
+                    source_config: &StorageConfig, source_credentials: &StorageCredentials, source_tables: &Tables,
+                    target_config: &StorageConfig, target_credentials: &StorageCredentials, target_tables: &Tables,
+                    before_source_delete: &dyn Fn() -> ErrorCode) -> ErrorCode {
+
+        // the source is read from the write host, the read host may be lagging behind
+        let source_pool = check_option!(CONNECTIONS.get(false, source_config, source_credentials), ErrorCode::IOError);
+        let target_pool = check_option!(CONNECTIONS.get(false, target_config, target_credentials), ErrorCode::IOError);
+
+        let (wallet_id, metadata, metadata_encoding): (u64, String, u8) = {
+            let mut result = check_query!(
+                                source_pool.prep_exec(
+                                    format!("SELECT id, metadata, metadata_encoding FROM {} WHERE name = :name", source_tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), ErrorCode::IOError
+            );
+
+            let row = check_query!(check_option!(result.next(), ErrorCode::WalletNotFound), ErrorCode::IOError);
+
+            (check_option!(row.get(0), ErrorCode::InvalidState), check_option!(row.get(1), ErrorCode::InvalidState), check_option!(row.get(2), ErrorCode::InvalidState))
+        };
+
+        let new_wallet_id = {
+            let result = target_pool.prep_exec(
+                            format!("INSERT INTO {}(name, metadata, metadata_encoding) VALUES (:name, :metadata, :metadata_encoding)", target_tables.wallets),
+                             params!{
+                                name,
+                                metadata,
+                                metadata_encoding
+                             }
+            );
+
+            match result {
+                    Err(Error::MySqlError(err)) => {
+                        warn!("MySQL Error while executing query. Err Code: {}, Err State: {}", err.code, err.state);
+                        match err.code {
+                            1062 => return ErrorCode::WalletAlreadyExistsError,
+                            ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                            _ => return ErrorCode::IOError,
+                        };
+                    },
+                    Err(err) => {
+                        warn!("Unexpected Error while executing query. Err: {:?}", err);
+                        return ErrorCode::IOError
+                    },
+                    Ok(result) => result.last_insert_id(),
+            }
+        };
+
+        let copied = MySQLStorage::_copy_wallet_records(&source_pool, source_tables, wallet_id, &target_pool, target_tables, new_wallet_id)
+            .and_then(|source_transaction| match before_source_delete() {
+                ErrorCode::Success => Ok(source_transaction),
+                err => Err(err),
+            });
+
+        let mut source_transaction = match copied {
+            Err(err) => {
+                // the source wallet stays in place, the copy is removed so that the move can be retried
+                if let Err(delete_err) = target_pool.prep_exec(format!("DELETE FROM {} WHERE id = :new_wallet_id", target_tables.wallets), params!{new_wallet_id}) {
+                    error!("Could not remove the copy of the wallet {}. Err: {:?}", name, delete_err);
+                }
+                return err;
+            },
+            Ok(source_transaction) => source_transaction,
+        };
+
+        let deleted = source_transaction.prep_exec(
+                        format!("DELETE FROM {} WHERE id = :wallet_id", source_tables.wallets),
+                        params!{
+                            wallet_id
+                         }
+        ).map(|_| ());
+
+        // the copy may already be in use through its directory entry, so it is not removed
+        if let Err(err) = deleted.and_then(|_| source_transaction.commit()) {
+            error!("Storage with the name: {} is moved, but could not be deleted from the source DB. Err: {:?}", name, err);
+            return ErrorCode::SourceNotDeleted;
+        }
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Copies the records of a wallet and returns the source transaction holding the locks on the source wallet
+    /// and its records once the copy is committed and verified.
+    ///
+    fn _copy_wallet_records(source_pool: &Pool, source_tables: &Tables, wallet_id: u64,
+                            target_pool: &Pool, target_tables: &Tables, new_wallet_id: u64) -> Result<Transaction<'static>, ErrorCode> {
+
+        let mut last_id: u64 = 0;
+        let mut copied_records = 0;
+
+        let mut source_connection = check_query!(source_pool.get_conn(), Err(ErrorCode::IOError));
+
+        loop {
+            let mut arguments: Vec<Value> = Vec::new();
+            let mut chunked: Vec<(String, String)> = Vec::new();
+
+            {
+                let result = check_query!(
+                                source_connection.prep_exec(
+                                    format!("SELECT id, type, name, value, value_chunks, value_encoding, tags, version FROM {} \
+                                             WHERE wallet_id = :wallet_id AND id > :last_id ORDER BY id LIMIT {}", source_tables.items, MOVE_BATCH_SIZE),
+                                    params!{
+                                        wallet_id,
+                                        last_id
+                                     }
+                                ), Err(ErrorCode::IOError)
+                );
+
+                for row in result {
+                    let row = check_query!(row, Err(ErrorCode::IOError));
+
+                    last_id = check_option!(row.get(0), Err(ErrorCode::InvalidState));
+                    let value_chunks: u32 = check_option!(row.get(4), Err(ErrorCode::InvalidState));
+                    if value_chunks > 0 {
+                        chunked.push((check_option!(row.get(1), Err(ErrorCode::InvalidState)), check_option!(row.get(2), Err(ErrorCode::InvalidState))));
+                    }
+
+                    arguments.push(new_wallet_id.into());
+                    arguments.extend(row.unwrap().into_iter().skip(1));
+                }
+            }
+
+            if arguments.is_empty() {
+                break;
+            }
+
+            copied_records += arguments.len() / 8;
+
+            let mut target_transaction = check_query!(target_pool.start_transaction(false, None, None), Err(ErrorCode::IOError));
+            MySQLStorage::_upsert_moved_records(&mut target_transaction, target_tables, arguments)?;
+            MySQLStorage::_copy_moved_chunks(&mut source_connection, source_tables, wallet_id, &mut target_transaction, target_tables, new_wallet_id, &[], &chunked)?;
+            check_query!(target_transaction.commit(), Err(ErrorCode::IOError));
+        }
+
+        trace!("Copied Records: {}", copied_records);
+
+        for _ in 0..MOVE_CATCH_UP_ROUNDS {
+            let mut source_transaction = check_query!(source_pool.start_transaction(false, None, None), Err(ErrorCode::IOError));
+            let mut target_transaction = check_query!(target_pool.start_transaction(false, None, None), Err(ErrorCode::IOError));
+
+            let changed_records = MySQLStorage::_sync_records(&mut source_transaction, false, source_tables, wallet_id, &mut target_transaction, target_tables, new_wallet_id)?;
+            check_query!(target_transaction.commit(), Err(ErrorCode::IOError));
+
+            trace!("Caught up on Changed Records: {}", changed_records);
+
+            if changed_records <= MOVE_BATCH_SIZE {
+                break;
+            }
+        }
+
+        // the transactions are rolled back when dropped without a commit, releasing the locks
+        let mut source_transaction = check_query!(source_pool.start_transaction(false, None, None), Err(ErrorCode::IOError));
+        let mut target_transaction = check_query!(target_pool.start_transaction(false, None, None), Err(ErrorCode::IOError));
+
+        // locking the wallet row blocks adding records to the wallet, as well as changing its metadata and data keys
+        let (metadata, metadata_encoding): (String, u8) = {
+            let mut result = check_query!(
+                                source_transaction.prep_exec(
+                                    format!("SELECT metadata, metadata_encoding FROM {} WHERE id = :wallet_id FOR UPDATE", source_tables.wallets),
+                                    params!{
+                                        wallet_id
+                                     }
+                                ), Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::WalletNotFound)), Err(ErrorCode::IOError));
+
+            (check_option!(row.get(0), Err(ErrorCode::InvalidState)), check_option!(row.get(1), Err(ErrorCode::InvalidState)))
+        };
+
+        check_query!(
+            target_transaction.prep_exec(
+                format!("UPDATE {} SET metadata = :metadata, metadata_encoding = :metadata_encoding WHERE id = :new_wallet_id", target_tables.wallets),
+                params!{
+                    metadata,
+                    metadata_encoding,
+                    new_wallet_id
+                 }
+            ), Err(ErrorCode::IOError)
+        );
+
+        MySQLStorage::_copy_data_keys(&mut source_transaction, source_tables, wallet_id, &mut target_transaction, target_tables, new_wallet_id)?;
+
+        let changed_records = MySQLStorage::_sync_records(&mut source_transaction, true, source_tables, wallet_id, &mut target_transaction, target_tables, new_wallet_id)?;
+
+        trace!("Caught up on Changed Records while locked: {}", changed_records);
+
+        let source_summary = MySQLStorage::_records_summary(&mut source_transaction, source_tables, wallet_id)?;
+        let target_summary = MySQLStorage::_records_summary(&mut target_transaction, target_tables, new_wallet_id)?;
+
+        if source_summary != target_summary {
+            warn!("Moved Records do not match the source. (count, checksum) source: {:?}, copy: {:?}", source_summary, target_summary);
+            return Err(ErrorCode::VerificationFailed);
+        }
+
+        check_query!(target_transaction.commit(), Err(ErrorCode::IOError));
+
+        Ok(source_transaction)
+    }
+
+    ///
+    /// Copies the data keys of a moved wallet, the moved values and metadata stay encrypted with them.
+    ///
+    fn _copy_data_keys(source_transaction: &mut Transaction, source_tables: &Tables, wallet_id: u64,
+                       target_transaction: &mut Transaction, target_tables: &Tables, new_wallet_id: u64) -> Result<(), ErrorCode> {
+
+        let result = check_query!(
+                        source_transaction.prep_exec(
+                            format!("SELECT version, kek_id, wrapped_key FROM {} WHERE wallet_id = :wallet_id", source_tables.data_keys),
+                            params!{
+                                wallet_id
+                             }
+                        ), Err(ErrorCode::IOError)
+        );
+
+        let mut data_keys: Vec<(u32, String, Vec<u8>)> = Vec::new();
+
+        for row in result {
+            let row = check_query!(row, Err(ErrorCode::IOError));
+
+            data_keys.push((
+                check_option!(row.get(0), Err(ErrorCode::InvalidState)),
+                check_option!(row.get(1), Err(ErrorCode::InvalidState)),
+                check_option!(row.get(2), Err(ErrorCode::InvalidState)),
+            ));
+        }
+
+        for (version, kek_id, wrapped_key) in data_keys {
+            check_query!(
+                target_transaction.prep_exec(
+                    format!("INSERT INTO {}(wallet_id, version, kek_id, wrapped_key) VALUES (:new_wallet_id, :version, :kek_id, :wrapped_key)", target_tables.data_keys),
+                    params!{
+                        new_wallet_id,
+                        version,
+                        kek_id,
+                        wrapped_key
+                     }
+                ), Err(ErrorCode::IOError)
+            );
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Makes the records of the copy match the ones of the source wallet, returns the number of records
+    /// that were changed, added or deleted. With `lock` the source records are locked against changes.
+    ///
+    fn _sync_records(source_transaction: &mut Transaction, lock: bool, source_tables: &Tables, wallet_id: u64,
+                     target_transaction: &mut Transaction, target_tables: &Tables, new_wallet_id: u64) -> Result<usize, ErrorCode> {
+
+        let source_digests = MySQLStorage::_record_digests(source_transaction, lock, source_tables, wallet_id)?;
+        let target_digests = MySQLStorage::_record_digests(target_transaction, false, target_tables, new_wallet_id)?;
+
+        let changed: Vec<&(String, String)> = source_digests.iter()
+            .filter(|&(key, digest)| target_digests.get(key) != Some(digest))
+            .map(|(key, _)| key)
+            .collect();
+
+        let deleted: Vec<&(String, String)> = target_digests.keys()
+            .filter(|key| !source_digests.contains_key(key))
+            .collect();
+
+        // deleted first, a record re-added with a differently cased name matches the deleted one in the unique key
+        for keys in deleted.chunks(MOVE_BATCH_SIZE) {
+            let mut arguments: Vec<Value> = vec![new_wallet_id.into()];
+
+            for &&(ref type_, ref name) in keys {
+                arguments.push(type_.into());
+                arguments.push(name.into());
+            }
+
+            check_query!(
+                target_transaction.prep_exec(
+                    format!("DELETE FROM {} WHERE wallet_id = ? AND (type, name) IN ({})", target_tables.items, vec!["(?, ?)"; keys.len()].join(", ")),
+                    arguments
+                ), Err(ErrorCode::IOError)
+            );
+        }
+
+        for keys in changed.chunks(MOVE_BATCH_SIZE) {
+            let mut arguments: Vec<Value> = vec![wallet_id.into()];
+
+            for &&(ref type_, ref name) in keys {
+                arguments.push(type_.into());
+                arguments.push(name.into());
+            }
+
+            let mut records: Vec<Value> = Vec::new();
+            let mut chunked: Vec<(String, String)> = Vec::new();
+
+            {
+                let result = check_query!(
+                                source_transaction.prep_exec(
+                                    format!("SELECT type, name, value, value_chunks, value_encoding, tags, version FROM {} WHERE wallet_id = ? AND (type, name) IN ({})",
+                                            source_tables.items, vec!["(?, ?)"; keys.len()].join(", ")),
+                                    arguments
+                                ), Err(ErrorCode::IOError)
+                );
+
+                for row in result {
+                    let row = check_query!(row, Err(ErrorCode::IOError));
+
+                    let value_chunks: u32 = check_option!(row.get(3), Err(ErrorCode::InvalidState));
+                    if value_chunks > 0 {
+                        chunked.push((check_option!(row.get(0), Err(ErrorCode::InvalidState)), check_option!(row.get(1), Err(ErrorCode::InvalidState))));
+                    }
+
+                    records.push(new_wallet_id.into());
+                    records.extend(row.unwrap());
+                }
+            }
+
+            if !records.is_empty() {
+                MySQLStorage::_upsert_moved_records(target_transaction, target_tables, records)?;
+            }
+
+            MySQLStorage::_copy_moved_chunks(source_transaction, source_tables, wallet_id, target_transaction, target_tables, new_wallet_id, keys, &chunked)?;
+        }
+
+        Ok(changed.len() + deleted.len())
+    }
+
+    ///
+    /// Version and digest of the value, value encoding and tags of every record of a wallet, by type and name.
+    /// The chunks of a value are always written together with a new version of the record.
+    ///
+    fn _record_digests(transaction: &mut Transaction, lock: bool, tables: &Tables, wallet_id: u64) -> Result<HashMap<(String, String), (u64, String)>, ErrorCode> {
+
+        let result = check_query!(
+                        transaction.prep_exec(
+                            format!("SELECT type, name, version, CONCAT(MD5(value), value_chunks, value_encoding, MD5(tags)) FROM {} WHERE wallet_id = :wallet_id{}",
+                                    tables.items, if lock { " LOCK IN SHARE MODE" } else { "" }),
+                            params!{
+                                wallet_id
+                             }
+                        ), Err(ErrorCode::IOError)
+        );
+
+        let mut digests = HashMap::new();
+
+        for row in result {
+            let row = check_query!(row, Err(ErrorCode::IOError));
+
+            digests.insert(
+                (check_option!(row.get(0), Err(ErrorCode::InvalidState)), check_option!(row.get(1), Err(ErrorCode::InvalidState))),
+                (check_option!(row.get(2), Err(ErrorCode::InvalidState)), check_option!(row.get(3), Err(ErrorCode::InvalidState)))
+            );
+        }
+
+        Ok(digests)
+    }
+
+    ///
+    /// Record count and an order independent checksum over the type, name, version, value, value chunks, value encoding
+    /// and tags of the records.
+    ///
+    fn _records_summary(transaction: &mut Transaction, tables: &Tables, wallet_id: u64) -> Result<(u64, u64), ErrorCode> {
+
+        let mut result = check_query!(
+                            transaction.prep_exec(
+                                format!("SELECT COUNT(*), CAST(COALESCE(SUM(CRC32(CONCAT(MD5(i.type), MD5(i.name), MD5(i.value), i.value_encoding, MD5(i.tags), i.version, \
+                                            (SELECT COALESCE(SUM(CRC32(CONCAT(c.seq, MD5(c.data)))), 0) FROM {} c WHERE c.item_id = i.id)))), 0) AS UNSIGNED) \
+                                         FROM {} i WHERE i.wallet_id = :wallet_id", tables.value_chunks, tables.items),
+                                params!{
+                                    wallet_id
+                                 }
+                            ), Err(ErrorCode::IOError)
+        );
+
+        let row = check_query!(check_option!(result.next(), Err(ErrorCode::InvalidState)), Err(ErrorCode::IOError));
+
+        Ok((check_option!(row.get(0), Err(ErrorCode::InvalidState)), check_option!(row.get(1), Err(ErrorCode::InvalidState))))
+    }
+
+    fn _upsert_moved_records(transaction: &mut Transaction, tables: &Tables, arguments: Vec<Value>) -> Result<(), ErrorCode> {
+
+        let records = vec!["(?, ?, ?, ?, ?, ?, ?, ?)"; arguments.len() / 8];
+
+        check_query!(
+            transaction.prep_exec(
+                format!("INSERT INTO {}(wallet_id, type, name, value, value_chunks, value_encoding, tags, version) VALUES {} \
+                         ON DUPLICATE KEY UPDATE value = VALUES(value), value_chunks = VALUES(value_chunks), value_encoding = VALUES(value_encoding), \
+                            tags = VALUES(tags), version = VALUES(version)",
+                        tables.items, records.join(", ")),
+                arguments
+            ), Err(ErrorCode::IOError)
+        );
+
+        Ok(())
+    }
+
+    ///
+    /// Copies the chunks of the moved records, after the records are upserted into the copy. The chunks of the `replaced`
+    /// records of the copy are deleted first, the `chunked` ones then get the chunks of the source record.
+    ///
+    fn _copy_moved_chunks<C: GenericConnection>(source: &mut C, source_tables: &Tables, wallet_id: u64,
+                                                target_transaction: &mut Transaction, target_tables: &Tables, new_wallet_id: u64,
+                                                replaced: &[&(String, String)], chunked: &[(String, String)]) -> Result<(), ErrorCode> {
+
+        if !replaced.is_empty() {
+            let mut arguments: Vec<Value> = vec![new_wallet_id.into()];
+
+            for &&(ref type_, ref name) in replaced {
+                arguments.push(type_.into());
+                arguments.push(name.into());
+            }
+
+            check_query!(
+                target_transaction.prep_exec(
+                    format!("DELETE c FROM {} c JOIN {} i ON i.id = c.item_id WHERE i.wallet_id = ? AND (i.type, i.name) IN ({})",
+                            target_tables.value_chunks, target_tables.items, vec!["(?, ?)"; replaced.len()].join(", ")),
+                    arguments
+                ), Err(ErrorCode::IOError)
+            );
+        }
+
+        // a chunked value may be as large as a whole batch of records, so the chunks are copied one record at a time
+        for &(ref type_, ref name) in chunked {
+            let mut chunks: Vec<(u32, Vec<u8>)> = Vec::new();
+
+            {
+                let result = check_query!(
+                                source.prep_exec(
+                                    format!("SELECT c.seq, c.data FROM {} c JOIN {} i ON i.id = c.item_id \
+                                             WHERE i.wallet_id = :wallet_id AND i.type = :type AND i.name = :name ORDER BY c.seq",
+                                            source_tables.value_chunks, source_tables.items),
+                                    params!{
+                                        "wallet_id" => wallet_id,
+                                        "type" => type_,
+                                        "name" => name
+                                    }
+                                ), Err(ErrorCode::IOError)
+                );
+
+                for row in result {
+                    let row = check_query!(row, Err(ErrorCode::IOError));
+
+                    chunks.push((check_option!(row.get(0), Err(ErrorCode::InvalidState)), check_option!(row.get(1), Err(ErrorCode::InvalidState))));
+                }
+            }
+
+            for (seq, data) in chunks {
+                check_query!(
+                    target_transaction.prep_exec(
+                        format!("INSERT INTO {}(item_id, seq, data) \
+                                 SELECT id, :seq, :data FROM {} WHERE wallet_id = :new_wallet_id AND type = :type AND name = :name",
+                                target_tables.value_chunks, target_tables.items),
+                        params!{
+                            "seq" => seq,
+                            "data" => data,
+                            "new_wallet_id" => new_wallet_id,
+                            "type" => type_,
+                            "name" => name
+                        }
+                    ), Err(ErrorCode::IOError)
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Removes a record handle, thus removing the referenced object from memory.
+    ///
+    /// # Arguments
+    ///
+    ///  * `record_handle` - unique identifier of a record fetch request
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidState` - Provided record handle does not exist
+    ///
+    pub fn free_record(&self, record_handle: i32) -> ErrorCode {
+
+        trace!("Freeing Record -> record_handle: {}", record_handle);
+
+        if self.records.remove(record_handle) {
+            trace!("Success Freeing Record with the record_handle: {}", record_handle);
+            ErrorCode::Success
+        }
+        else {
+            warn!("Trying to free a non existent Record, record_handle: {}", record_handle);
+            ErrorCode::InvalidState
+        }
+    }
+
+    ///
+    /// Removes a record handle, thus removing the referenced object from memory.
+    ///
+    /// # Arguments
+    ///
+    ///  * `record_handle` - unique identifier of a search result
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidState` - Provided search handle does not exist
+    ///
+    pub fn free_search(&self, search_handle: i32) -> ErrorCode {
+
+        trace!("Freeing Search Result -> search_handle: {}", search_handle);
+
+        if self.searches.remove(search_handle) {
+            trace!("Success Freeing Search Result with the search_handle: {}", search_handle);
+            ErrorCode::Success
+        }
+        else {
+            warn!("Trying to free a non existent Search Result, search_handle: {}", search_handle);
+            ErrorCode::InvalidState
+        }
+    }
+
+    ///
+    /// Adds a new record identified by type and id.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `value` - record value
+    ///  * `tags` - a map of (tag_name: tag_value) pairs
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `ItemAlreadyExists` - Record with the provided type and id already exist in the DB
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tags
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `ValueTooLarge` - Value exceeds the configured maximum value size
+    ///
+    pub fn add_record(&self, type_: &str, id: &str, value: &Vec<u8>, tags: &str) -> ErrorCode {
+
+        trace!("Adding Record -> type: {}, id: {}, value: {:?}, tags: {}", type_, id, value, tags);
+
+        let stored = match self._encode_value(type_, value) {
+            Err(err) => return err,
+            Ok(stored) => stored,
+        };
+
+        let mut writer = match self._value_writer(!stored.chunks.is_empty()) {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        {
+            let result = writer.prep_exec(
+                        format!("INSERT INTO {} (type, name, value, value_chunks, value_encoding, tags, wallet_id) \
+                                 VALUE (:type, :name, :value, :value_chunks, :value_encoding, :tags, :wallet_id)", self.tables.items),
+                        params!{
+                            "type" => type_,
+                            "name" => id,
+                            "value" => &stored.value[..],
+                            "value_chunks" => stored.chunks.len() as u32,
+                            "value_encoding" => stored.encoding,
+                            "tags" => tags,
+                            "wallet_id" => self.wallet_id
+                        }
+                );
+
+            match result {
+                    Err(Error::MySqlError(err)) => {
+                        warn!("MySQL Error while executing query. Err Code: {}", err.code);
+                        match err.code {
+                            1062 => return ErrorCode::ItemAlreadyExists,
+                            3140 => return ErrorCode::InvalidStructure, // Invalid JSON
+                            ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                            _ => return ErrorCode::IOError,
+                        };
+                    },
+                    Err(err) => {
+                        warn!("Unexpected Error while executing query. Err: {:?}", err);
+                        return ErrorCode::IOError
+                    },
+                    Ok(_) => (),
+            }
+        }
+
+        if let Err(err) = value_chunks::store_chunks(&mut writer, &self.tables, self.wallet_id, type_, id, &stored.chunks, false)
+            .and_then(|_| writer.log_record(ChangeOperation::Add, type_, id))
+            .and_then(|_| writer.commit()) {
+            return err;
+        }
+
+        self._invalidate_record(type_, id);
+
+        trace!("Success Adding Record with the type: {}, id: {}", type_, id);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Adds a new record identified by type and id, or updates the value and tags of the existing one.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `value` - record value
+    ///  * `tags` - a map of (tag_name: tag_value) pairs
+    ///  * `options` - upsert options
+    ///         {
+    ///             mergeTags: (optional, false by default) If true, the tags are merged into the tags
+    ///                 of the existing record, otherwise they replace them
+    ///         }
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<bool, ErrorCode>` - true if a new record was inserted, false if an existing one was updated
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tags | options
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `ValueTooLarge` - Value exceeds the configured maximum value size
+    ///
+    pub fn upsert_record(&self, type_: &str, id: &str, value: &Vec<u8>, tags: &str, options: &str) -> Result<bool, ErrorCode> {
+
+        trace!("Upserting Record -> type: {}, id: {}, value: {:?}, tags: {}, options: {}", type_, id, value, tags, options);
+
+        let options: UpsertOptions = check_result!(serde_json::from_str(options), Err(ErrorCode::InvalidStructure));
+
+        let stored = self._encode_value(type_, value)?;
+
+        let query = format!(
+            "INSERT INTO {} (type, name, value, value_chunks, value_encoding, tags, wallet_id) \
+             VALUE (:type, :name, :value, :value_chunks, :value_encoding, :tags, :wallet_id) \
+             ON DUPLICATE KEY UPDATE value = VALUES(value), value_chunks = VALUES(value_chunks), value_encoding = VALUES(value_encoding), \
+                tags = {}, version = version + 1",
+            self.tables.items,
+            if options.merge_tags { "JSON_MERGE_PATCH(tags, VALUES(tags))" } else { "VALUES(tags)" }
+        );
+
+        let mut writer = self._value_writer(!stored.chunks.is_empty())?;
+
+        // With CLIENT_FOUND_ROWS an update that doesn't change the row also reports 1 affected row,
+        // but only an insert reports the generated id.
+        let inserted = {
+            let result = writer.prep_exec(
+                        query,
+                        params!{
+                            "type" => type_,
+                            "name" => id,
+                            "value" => &stored.value[..],
+                            "value_chunks" => stored.chunks.len() as u32,
+                            "value_encoding" => stored.encoding,
+                            "tags" => tags,
+                            "wallet_id" => self.wallet_id
+                        }
+                );
+
+            match result {
+                Err(Error::MySqlError(err)) => {
+                    warn!("MySQL Error while executing query. Err Code: {}", err.code);
+                    match err.code {
+                        3140 => return Err(ErrorCode::InvalidStructure), // Invalid JSON
+                        ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return Err(ErrorCode::Timeout),
+                        _ => return Err(ErrorCode::IOError),
+                    };
+                },
+                Err(err) => {
+                    warn!("Unexpected Error while executing query. Err: {:?}", err);
+                    return Err(ErrorCode::IOError)
+                },
+                Ok(result) => result.affected_rows() == 1 && result.last_insert_id() != 0,
+            }
+        };
+
+        value_chunks::store_chunks(&mut writer, &self.tables, self.wallet_id, type_, id, &stored.chunks, !inserted)?;
+        writer.log_record(if inserted { ChangeOperation::Add } else { ChangeOperation::Update }, type_, id)?;
+        writer.commit()?;
+
+        self._invalidate_record(type_, id);
+
+        trace!("Success Upserting Record with the type: {}, id: {}. Inserted: {}", type_, id, inserted);
+
+        Ok(inserted)
+    }
+
+    ///
+    /// Fetches a record identified by type and id.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `options` - options in the form of {"fetch_value": true, "fetch_tags": true} determining whether to fetch the value and/or tags
+    ///  * `record_handle_p` - output param - handle that will be used for accessing the fetched record
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> options
+    ///  * `ItemNotFound` - Record with the provided type and id does not exist in the DB
+    ///  * `Timeout` - Statement exceeded the `statementTimeout` option or the configured statement timeout
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidState` - Invalid encoding of a provided/fetched string
+    ///
+    pub fn fetch_record(&self, type_: &str, id: &str, options: &str, record_handle_p: *mut i32) -> ErrorCode {
+
+        trace!("Fetching Record -> type: {}, id: {}, options: {}", type_, id, options);
+
+        let options: FetchOptions = check_result!(serde_json::from_str(options), ErrorCode::InvalidStructure);
+
+        let record: Record;
+
+        let (db_value, tags, version) = match self.cache {
+            None => match self._read_record(type_, id, options.retrieve_value, options.retrieve_tags, options.statement_timeout) {
+                Err(err) => return err,
+                Ok(row) => row,
+            },
+            Some(ref cache) => {
+                let key = CacheKey::Record(type_.to_string(), id.to_string());
+
+                match cache.get(&key) {
+                    Some(cached) => match *cached {
+                        CachedItem::Record(ref value, ref tags, version) => (value.clone(), tags.clone(), version),
+                        CachedItem::Metadata(_) => return ErrorCode::InvalidState,
+                    },
+                    None => {
+                        let generation = cache.generation();
+
+                        let (value, tags, version) = match self._read_record(type_, id, true, true, options.statement_timeout) {
+                            Err(err) => return err,
+                            Ok(row) => row,
+                        };
+
+                        let size = type_.len() + id.len() + value.len() + tags.len() + CACHE_ENTRY_OVERHEAD;
+                        cache.insert(key, CachedItem::Record(value.clone(), tags.clone(), version), size, generation);
+
+                        (value, tags, version)
+                    }
+                }
+            }
+        };
+
+        record = Record::new(
+            check_result!(CString::new(id), ErrorCode::InvalidState),
+            if options.retrieve_value {Some(db_value)} else {None},
+            if options.retrieve_tags {Some(check_result!(CString::new(tags), ErrorCode::InvalidState))} else {None},
+            if options.retrieve_type {Some(check_result!(CString::new(type_), ErrorCode::InvalidState))} else {None},
+            Some(version)
+        );
+
+        let record_handle = self.records.insert(record);
+
+        unsafe { *record_handle_p = record_handle; }
+
+        trace!("Success Fetching Record with the type: {}, id: {}. Record Handle: {}", type_, id, record_handle);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Reads the value, tags and version of a record from the DB, the value and tags that are not retrieved are empty.
+    ///
+    /// A chunked value is read after the record, so the record is read again if it is changed in between.
+    /// A compressed or encrypted value is decompressed and decrypted.
+    ///
+    fn _read_record(&self, type_: &str, id: &str, retrieve_value: bool, retrieve_tags: bool, statement_timeout: Option<u64>) -> Result<(Vec<u8>, String, u64), ErrorCode> {
+        for _ in 0..MAX_CHUNKED_READ_ATTEMPTS {
+            let (db_value, tags, version, chunked_item_id, value_encoding) = self._read_record_row(type_, id, retrieve_value, retrieve_tags, statement_timeout)?;
+
+            let item_id = match chunked_item_id {
+                None => return Ok((value_chunks::decode(vec![db_value], value_encoding, self._encryption())?, tags, version)),
+                Some(item_id) => item_id,
+            };
+
+            if let Some(chunks) = value_chunks::read_chunks(&self.read_pool, &self.tables, item_id, version)? {
+                return Ok((value_chunks::decode(chunks, value_encoding, self._encryption())?, tags, version));
+            }
+        }
+
+        warn!("Record with the type: {}, id: {} kept changing while its chunked value was read", type_, id);
+        Err(ErrorCode::VersionConflict)
+    }
+
+    ///
+    /// Reads the row of a record, with the item id of a chunked value and the encoding of a value that is retrieved.
+    ///
+    fn _read_record_row(&self, type_: &str, id: &str, retrieve_value: bool, retrieve_tags: bool, statement_timeout: Option<u64>) -> Result<(Vec<u8>, String, u64, Option<u64>, u8), ErrorCode> {
+        let query = format!(
+            "SELECT {}{}, {}, version{} \
+             FROM {} i \
+             WHERE \
+                wallet_id = :wallet_id \
+                AND type = :type \
+                AND name = :name",
+            query_translator::max_execution_time_hint(statement_timeout),
+            if retrieve_value { "value" } else {"''"},
+            if retrieve_tags { "tags" } else {"''"},
+            if retrieve_value { query_translator::STORED_VALUE_COLUMNS } else {""},
+            self.tables.items
+        );
+
+        let mut result: QueryResult = check_query!(
+            self.read_pool.prep_exec(
+                &query,
+                params!{
+                    "wallet_id" => self.wallet_id,
+                    "type" => type_,
+                    "name" => id
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        let row = check_query!(check_option!(result.next(), Err(ErrorCode::ItemNotFound)), Err(ErrorCode::IOError));
+
+        // These 2 values cannot be NULL.
+        let db_value: Vec<u8> = check_option!(row.get(0), Err(ErrorCode::IOError));
+        let tags: String = check_option!(row.get(1), Err(ErrorCode::IOError));
+        let version: u64 = check_option!(row.get(2), Err(ErrorCode::IOError));
+        let chunked_item_id: Option<u64> = if retrieve_value { check_option!(row.get(3), Err(ErrorCode::IOError)) } else { None };
+        let value_encoding: u8 = if retrieve_value { check_option!(row.get(4), Err(ErrorCode::IOError)) } else { compression::RAW };
+
+        Ok((db_value, tags, version, chunked_item_id, value_encoding))
+    }
+
+    ///
+    /// Fetches multiple records of the same type with a single query.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - type of the records
+    ///  * `ids` - ids of the records, duplicates are fetched only once
+    ///  * `options` - fetch options, same as for `fetch_record`
+    ///  * `search_handle_p` - output param - handle that will be used for accessing the fetched records
+    ///     the same way as a search result, ids that were not found can be read with `get_search_missing_keys`
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> options
+    ///  * `QueryLimitExceeded` - Number of ids exceeds `query_limits.max_in_values`
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidState` - Invalid encoding of a provided/fetched string
+    ///
+    pub fn fetch_records(&self, type_: &str, ids: &Vec<String>, options: &str, search_handle_p: *mut i32) -> ErrorCode {
+
+        trace!("Fetching Records -> type: {}, ids: {:?}, options: {}", type_, ids, options);
+
+        let options: FetchOptions = check_result!(serde_json::from_str(options), ErrorCode::InvalidStructure);
+
+        let keys: Vec<RecordKey> = ids.iter().map(|id| RecordKey { type_: type_.to_string(), id: id.clone() }).collect();
+
+        self._fetch_records(&keys, &options, options.retrieve_type, search_handle_p)
+    }
+
+    ///
+    /// Fetches multiple records of any type with a single query.
+    ///
+    /// # Arguments
+    ///
+    ///  * `keys` - type and id pairs of the records, duplicates are fetched only once
+    ///  * `options` - fetch options, same as for `fetch_record`, the type of fetched records is always retrieved
+    ///  * `search_handle_p` - output param - handle that will be used for accessing the fetched records
+    ///     the same way as a search result, keys that were not found can be read with `get_search_missing_keys`
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> options
+    ///  * `QueryLimitExceeded` - Number of keys exceeds `query_limits.max_in_values`
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidState` - Invalid encoding of a provided/fetched string
+    ///
+    pub fn fetch_records_by_keys(&self, keys: &Vec<RecordKey>, options: &str, search_handle_p: *mut i32) -> ErrorCode {
+
+        trace!("Fetching Records By Keys -> keys: {:?}, options: {}", keys, options);
+
+        let options: FetchOptions = check_result!(serde_json::from_str(options), ErrorCode::InvalidStructure);
+
+        self._fetch_records(keys, &options, true, search_handle_p)
+    }
+
+    ///
+    /// Returns a record with the specified handle.
+    ///
+    /// # Arguments
+    ///
+    ///  * `record_handle` - unique identifier of a record
+    ///
+    /// # Returns
+    ///
+    ///  * `Option<Arc<Record>>`
+    ///
+    pub fn get_record(&self, record_handle: i32) -> Option<Arc<Record>> {
+
+        trace!("Getting Record -> record_handle: {}", record_handle);
+
+        self.records.get(record_handle)
+    }
+
+    ///
+    /// Deletes a record identified by type and id.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `expected_version` - if set, the record is deleted only if its version matches
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
+    ///  * `VersionConflict` - Version of the record does not match the expected one
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn delete_record(&self, type_: &str, id: &str, expected_version: Option<u64>) -> ErrorCode {
+
+        trace!("Deleting record -> type: {}, id: {}, expected_version: {:?}", type_, id, expected_version);
+
+        let mut writer = match self._writer() {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        let affected_rows = check_query!(
+            writer.prep_exec(
+                format!("DELETE FROM {} \
+                         WHERE type = :type AND name = :name AND wallet_id = :wallet_id \
+                            AND (:expected_version IS NULL OR version = :expected_version)", self.tables.items),
+                params! {
+                    "type" => type_,
+                    "name" => id,
+                    "wallet_id" => self.wallet_id,
+                    "expected_version" => expected_version,
+                }
+            ),
+            ErrorCode::IOError
+        ).affected_rows();
+
+        if affected_rows != 1 {
+            warn!("Trying to delete a non existent record or a record with a different version, type: {}, id: {}", type_, id);
+            return self._mismatch_error(type_, id);
+        }
+
+        if let Err(err) = writer.log_record(ChangeOperation::Delete, type_, id).and_then(|_| writer.commit()) {
+            return err;
+        }
+
+        self._invalidate_record(type_, id);
+
+        trace!("Success Deleting Record with the type: {}, id: {}", type_, id);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Updates the value of a record identified by type and id.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `value` - new value
+    ///  * `expected_version` - if set, the record is updated only if its version matches
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
+    ///  * `VersionConflict` - Version of the record does not match the expected one
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `ValueTooLarge` - Value exceeds the configured maximum value size
+    ///
+    pub fn update_record_value(&self, type_: &str, id: &str, value: &Vec<u8>, expected_version: Option<u64>) -> ErrorCode {
+
+        trace!("Updating Record Value -> type: {}, id: {}, value: {:?}, expected_version: {:?}", type_, id, value, expected_version);
+
+        let stored = match self._encode_value(type_, value) {
+            Err(err) => return err,
+            Ok(stored) => stored,
+        };
+
+        let mut writer = match self._value_writer(!stored.chunks.is_empty()) {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        let affected_rows = check_query!(
+            writer.prep_exec(
+                format!("UPDATE {} SET value = :value, value_chunks = :value_chunks, value_encoding = :value_encoding, version = version + 1 \
+                         WHERE type = :type AND name = :name AND wallet_id = :wallet_id \
+                            AND (:expected_version IS NULL OR version = :expected_version)", self.tables.items),
+                    params!{
+                        "value" => &stored.value[..],
+                        "value_chunks" => stored.chunks.len() as u32,
+                        "value_encoding" => stored.encoding,
+                        "type" => type_,
+                        "name" => id,
+                        "wallet_id" => self.wallet_id,
+                        "expected_version" => expected_version
+                    }
+            ),
+            ErrorCode::IOError
+        ).affected_rows();
+
+        if affected_rows != 1 {
+            warn!("Trying to update value of a non existent record or a record with a different version, type: {}, id: {}", type_, id);
+            return self._mismatch_error(type_, id);
+        }
+
+        if let Err(err) = value_chunks::store_chunks(&mut writer, &self.tables, self.wallet_id, type_, id, &stored.chunks, true)
+            .and_then(|_| writer.log_record(ChangeOperation::Update, type_, id))
+            .and_then(|_| writer.commit()) {
+            return err;
+        }
+
+        self._invalidate_record(type_, id);
+
+        trace!("Success Updating Value of a record with the type: {}, id: {}", type_, id);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Adds tags for a record identified by type and id.
+    /// If tag with that name exists it will be updated.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `tag_names` - a map containing (tag_name: tag_value) pairs
+    ///  * `expected_version` - if set, the record is updated only if its version matches
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
+    ///  * `VersionConflict` - Version of the record does not match the expected one
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tags
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn add_record_tags(&self, type_: &str, id: &str, tags: &HashMap<String, serde_json::Value>, expected_version: Option<u64>) -> ErrorCode {
+
+        trace!("Adding Record Tags -> type: {}, id: {}, tags:{:?}, expected_version: {:?}", type_, id, tags, expected_version);
+
+        if tags.is_empty() {
+            trace!("No tags to add. Checking if record exists...");
+            return self._check_if_record_exists(type_, id, expected_version);
+        }
+
+        let mut tag_name_value_paths: Vec<String> = Vec::new();
+
+        for (tag_name, tag_value) in tags {
+            let tag_name_path = format!(r#"'$."{}"', {}"#, tag_name, tag_value);
+            tag_name_value_paths.push(tag_name_path);
+        }
+
+        let tag_name_value_paths = tag_name_value_paths.join(",");
+
+        trace!("JSON Path argument given to JSON_SET is: {}", tag_name_value_paths);
+
+        let query = format!("UPDATE {} \
+                             SET tags = JSON_SET(tags, {}), version = version + 1 \
+                             WHERE type = :type \
+                             AND name = :name \
+                             AND wallet_id = :wallet_id \
+                             AND (:expected_version IS NULL OR version = :expected_version)",
+                             self.tables.items,
+                             tag_name_value_paths
+        );
+
+        let mut writer = match self._writer() {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        let affected_rows = {
+            let result = writer.prep_exec(
+                        query,
+                        params!{
+                            "type" => type_,
+                            "name" => id,
+                            "wallet_id" => self.wallet_id,
+                            "expected_version" => expected_version
+                        }
+                );
+
+            match result {
+                Err(Error::MySqlError(err)) => {
+                    warn!("MySQL Error while executing query. Err Code: {}", err.code);
+                    match err.code {
+                        1064 => return ErrorCode::InvalidStructure, // Invalid JSON
+                        ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                        _ => return ErrorCode::IOError,
+                    };
+                },
+                Err(err) => {
+                        warn!("Unexpected Error while executing query. Err: {:?}", err);
+                        return ErrorCode::IOError
+                },
+                Ok(result) => result.affected_rows(),
+            }
+        };
+
+        if affected_rows != 1 {
+            warn!("Trying to add tags to a non existent record or a record with a different version, type: {}, id: {}", type_, id);
+            return self._mismatch_error(type_, id);
+        }
+
+        if let Err(err) = writer.log_record(ChangeOperation::Update, type_, id).and_then(|_| writer.commit()) {
+            return err;
+        }
+
+        self._invalidate_record(type_, id);
+
+        trace!("Success Adding Tags for the record with the type: {}, id: {}", type_, id);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Updates tags of a record identified by type and id.
+    ///
+    /// `WARNING`: Performs a destructive update by replacing old JSON doc with a new one.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `tag_names` - a map containing (tag_name: new_tag_value) pairs
+    ///  * `expected_version` - if set, the record is updated only if its version matches
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
+    ///  * `VersionConflict` - Version of the record does not match the expected one
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tags
+    ///  * `Timeout` - Record stayed locked longer than the lock wait timeout
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn update_record_tags(&self, type_: &str, id: &str, tags: &str, expected_version: Option<u64>) -> ErrorCode {
+
+        trace!("Updating Record Tags -> type: {}, id: {}, tags: {}, expected_version: {:?}", type_, id, tags, expected_version);
+
+        // check if tags are a valid JSON
+        let _tags_json_chk: HashMap<String, serde_json::Value> = check_result!(serde_json::from_str(tags), ErrorCode::InvalidStructure);
+
+        let mut writer = match self._writer() {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        let affected_rows = {
+            let result = writer.prep_exec(
+                        format!("UPDATE {} SET tags = :tags, version = version + 1 \
+                                 WHERE type = :type AND name = :name AND wallet_id = :wallet_id \
+                                    AND (:expected_version IS NULL OR version = :expected_version)", self.tables.items),
+                        params!{
+                            "tags" => tags,
+                            "type" => type_,
+                            "name" => id,
+                            "wallet_id" => self.wallet_id,
+                            "expected_version" => expected_version
+                        }
+                );
+
+            match result {
+                Err(Error::MySqlError(err)) => {
+                    warn!("MySQL Error while executing query. Err Code: {}", err.code);
+                    match err.code {
+                        3140 => return ErrorCode::InvalidStructure, // Invalid JSON
+                        ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                        _ => return ErrorCode::IOError,
+                    };
+                },
+                Err(err) => {
+                        warn!("Unexpected Error while executing query. Err: {:?}", err);
+                        return ErrorCode::IOError
+                },
+                Ok(result) => result.affected_rows(),
+            }
+        };
+
+        if affected_rows != 1 {
+            warn!("Trying to update tags of a non existent record or a record with a different version, type: {}, id: {}", type_, id);
+            return self._mismatch_error(type_, id);
+        }
+
+        if let Err(err) = writer.log_record(ChangeOperation::Update, type_, id).and_then(|_| writer.commit()) {
+            return err;
+        }
+
+        self._invalidate_record(type_, id);
+
+        trace!("Success Updating Tags for the record with the type: {}, id: {}", type_, id);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Deletes tags of a record identified by type and id.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `tag_names` - list of tag names that need to be deleted
+    ///  * `expected_version` - if set, the record is updated only if its version matches
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tag_names
+    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
+    ///  * `VersionConflict` - Version of the record does not match the expected one
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn delete_record_tags(&self, type_: &str, id: &str, tag_names: &Vec<String>, expected_version: Option<u64>) -> ErrorCode {
+
+        trace!("Deleting Record Tags -> type: {}, id: {}, tag_names: {:?}, expected_version: {:?}", type_, id, tag_names, expected_version);
+
+        if tag_names.is_empty() {
+            trace!("No tags to delete. Checking if record exists...");
+            return self._check_if_record_exists(type_, id, expected_version);
+        }
+
+        let mut tag_name_paths: Vec<String> = Vec::new();
+
+        for tag_name in tag_names {
+            let tag_name_path = format!(r#"'$."{}"'"#, tag_name);
+            tag_name_paths.push(tag_name_path);
+        }
+
+        let tag_name_paths = tag_name_paths.join(",");
+
+        trace!("JSON Path argument given to JSON_REMOVE is: {}", tag_name_paths);
+
+        let query = format!("UPDATE {} \
+                            SET tags = JSON_REMOVE(tags, {}), version = version + 1 \
+                            WHERE type = :type \
+                            AND name = :name \
+                            AND wallet_id = :wallet_id \
+                            AND (:expected_version IS NULL OR version = :expected_version)",
+                            self.tables.items,
+                            tag_name_paths
+        );
+
+        let mut writer = match self._writer() {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        let affected_rows = {
+            let result = writer.prep_exec(
+                        query,
+                        params!{
+                            "type" => type_,
+                            "name" => id,
+                            "wallet_id" => self.wallet_id,
+                            "expected_version" => expected_version
+                        }
+                );
+
+            match result {
+                Err(Error::MySqlError(err)) => {
+                    warn!("MySQL Error while executing query. Err Code: {}", err.code);
+                    match err.code {
+                        1064 => return ErrorCode::InvalidStructure, // Invalid JSON
+                        ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                        _ => return ErrorCode::IOError,
+                    };
+                },
+                Err(err) => {
+                        warn!("Unexpected Error while executing query. Err: {:?}", err);
+                        return ErrorCode::IOError
+                },
+                Ok(result) => result.affected_rows(),
+            }
+        };
+
+        if affected_rows != 1 {
+            warn!("Trying to delete tags of a non existent record or a record with a different version, type: {}, id: {}", type_, id);
+            return self._mismatch_error(type_, id);
+        }
+
+        if let Err(err) = writer.log_record(ChangeOperation::Update, type_, id).and_then(|_| writer.commit()) {
+            return err;
+        }
+
+        self._invalidate_record(type_, id);
+
+        trace!("Success Deleting Tags for the record with the type: {}, id: {}", type_, id);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Updates the value and tags of a record identified by type and id in a single statement,
+    /// optionally only if the record matches a condition.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///  * `value` - new value, the value is left unchanged if not given
+    ///  * `tags` - tags to be added or replaced
+    ///  * `tag_names` - names of the tags to be removed, removal happens before the new tags are set
+    ///  * `condition_json` - query conditions, same as for `search_records`, that the record has to match
+    ///     before the update, e.g. `{"~state": "pending"}`
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
+    ///  * `ConditionNotMet` - Record does not match the condition
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tags | tag_names | condition
+    ///  * `QueryLimitExceeded` - Condition exceeds one of the configured query limits
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `ValueTooLarge` - Value exceeds the configured maximum value size
+    ///
+    pub fn update_record(&self, type_: &str, id: &str, value: Option<&Vec<u8>>, tags: &HashMap<String, String>, tag_names: &Vec<String>, condition_json: Option<&str>) -> ErrorCode {
+
+        trace!("Updating Record -> type: {}, id: {}, value: {:?}, tags: {:?}, tag_names: {:?}, condition_json: {:?}", type_, id, value, tags, tag_names, condition_json);
+
+        let (condition, query_limits) = match condition_json {
+            Some(condition_json) => match self._prepare_search_query(condition_json, None) {
+                Ok(prepared) => prepared,
+                Err(err) => return err
+            },
+            None => (query_translator::Operator::And(vec![]), self.query_limits)
+        };
+
+        let stored = match value.map(|value| self._encode_value(type_, value)) {
+            Some(Err(err)) => return err,
+            Some(Ok(stored)) => Some(stored),
+            None => None,
+        };
+
+        let (query, arguments) = match query_translator::wql_to_sql_update_record(
+            self.wallet_id, &self.tables.items, type_, id,
+            stored.as_ref().map(|stored| (&stored.value[..], stored.chunks.len() as u32, stored.encoding)),
+            tags, tag_names, &condition, &query_limits
+        ) {
+            Ok(translated) => translated,
+            Err(err) => return err
+        };
+
+        let chunked = stored.as_ref().map(|stored| !stored.chunks.is_empty()).unwrap_or(false);
+
+        let mut writer = match self._value_writer(chunked) {
+            Err(err) => return err,
+            Ok(writer) => writer,
+        };
+
+        let affected_rows = match writer.prep_exec(query, arguments) {
+            Err(Error::MySqlError(err)) => {
+                warn!("MySQL Error while executing query. Err Code: {}", err.code);
+                match err.code {
+                    1064 => return ErrorCode::InvalidStructure, // Invalid JSON path
+                    ER_QUERY_TIMEOUT | ER_LOCK_WAIT_TIMEOUT => return ErrorCode::Timeout,
+                    _ => return ErrorCode::IOError,
+                };
+            },
+            Err(err) => {
+                warn!("Unexpected Error while executing query. Err: {:?}", err);
+                return ErrorCode::IOError
+            },
+            Ok(result) => result.affected_rows(),
+        };
+
+        if affected_rows != 1 {
+            warn!("Trying to update a non existent record or a record that does not match the condition, type: {}, id: {}", type_, id);
+            return match self._check_if_record_exists(type_, id, None) {
+                ErrorCode::Success => ErrorCode::ConditionNotMet,
+                err => err
+            };
+        }
+
+        if let Some(ref stored) = stored {
+            if let Err(err) = value_chunks::store_chunks(&mut writer, &self.tables, self.wallet_id, type_, id, &stored.chunks, true) {
+                return err;
+            }
+        }
+
+        if let Err(err) = writer.log_record(ChangeOperation::Update, type_, id).and_then(|_| writer.commit()) {
+            return err;
+        }
+
+        self._invalidate_record(type_, id);
+
+        trace!("Success Updating Record with the type: {}, id: {}", type_, id);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Deletes all records of the given type that match the query, in a single statement.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `query_json` - query conditions specified in the form of a json, same as for `search_records`
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<u64, ErrorCode>` - number of deleted records
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> query
+    ///  * `QueryLimitExceeded` - Query exceeds one of the configured query limits
+    ///
+    pub fn delete_records_by_query(&self, type_: &str, query_json: &str) -> Result<u64, ErrorCode> {
+
+        trace!("Deleting Records By Query -> type: {}, query_json: {}", type_, query_json);
+
+        let (wql, query_limits) = self._prepare_search_query(query_json, None)?;
+
+        let type_filter = query_translator::TypeFilter::Type(type_.to_string());
+
+        let (query, arguments) = query_translator::wql_to_sql_delete(
+            self.wallet_id, &self.tables.items, &type_filter, &wql, &query_limits
+        )?;
+
+        let mut writer = self._writer()?;
+
+        writer.log_query(ChangeOperation::Delete, &type_filter, &wql, None, &query_limits)?;
+
+        let affected_rows = match writer.prep_exec(query, arguments) {
+            Err(Error::MySqlError(ref err)) if err.code == ER_PARSE_ERROR => {
+                warn!("Invalid query, Err: {}", err.message);
+                return Err(ErrorCode::InvalidStructure)
+            },
+            result => check_query!(result, Err(ErrorCode::IOError)).affected_rows(),
+        };
+
+        writer.commit()?;
+
+        self._invalidate_type(type_);
+
+        trace!("Success Deleting Records By Query with the type: {}, query_json: {}. Deleted: {}", type_, query_json, affected_rows);
+
+        Ok(affected_rows)
+    }
+
+    ///
+    /// Updates tags of all records of the given type that match the query, in a single statement.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `query_json` - query conditions specified in the form of a json, same as for `search_records`
+    ///  * `tags` - tags to be added or replaced
+    ///  * `tag_names` - names of the tags to be removed, removal happens before the new tags are set
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<u64, ErrorCode>` - number of records matched by the query, 0 without running the query
+    ///     when both `tags` and `tag_names` are empty
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> query | tags | tag_names
+    ///  * `QueryLimitExceeded` - Query exceeds one of the configured query limits
+    ///
+    pub fn update_tags_by_query(&self, type_: &str, query_json: &str, tags: &HashMap<String, String>, tag_names: &Vec<String>) -> Result<u64, ErrorCode> {
+
+        trace!("Updating Tags By Query -> type: {}, query_json: {}, tags: {:?}, tag_names: {:?}", type_, query_json, tags, tag_names);
+
+        let (wql, query_limits) = self._prepare_search_query(query_json, None)?;
+
+        if tags.is_empty() && tag_names.is_empty() {
+            trace!("No tags to update.");
+            return Ok(0);
+        }
+
+        let type_filter = query_translator::TypeFilter::Type(type_.to_string());
+
+        let (query, arguments) = query_translator::wql_to_sql_update_tags(
+            self.wallet_id, &self.tables.items, &type_filter, &wql, tags, tag_names, &query_limits
+        )?;
+
+        let mut writer = self._writer()?;
+
+        // the update increments the version of every matched record by 1, so each record is journaled with its version + 1
+        writer.log_query(ChangeOperation::Update, &type_filter, &wql, Some(1), &query_limits)?;
+
+        let affected_rows = match writer.prep_exec(query, arguments) {
+            Err(Error::MySqlError(ref err)) if err.code == ER_PARSE_ERROR => {
+                warn!("Invalid query, Err: {}", err.message);
+                return Err(ErrorCode::InvalidStructure)
+            },
+            result => check_query!(result, Err(ErrorCode::IOError)).affected_rows(),
+        };
+
+        writer.commit()?;
+
+        self._invalidate_type(type_);
+
+        trace!("Success Updating Tags By Query with the type: {}, query_json: {}. Updated: {}", type_, query_json, affected_rows);
+
+        Ok(affected_rows)
+    }
+
+    ///
+    /// Opens a streaming read of a record value, the value is read one chunk at a time with `read_value`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<i32, ErrorCode>` - handle of the value reader, to be closed with `close_value_reader`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `ItemNotFound` - Record with the provided type and id does not exist in the DB
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn open_value_reader(&self, type_: &str, id: &str) -> Result<i32, ErrorCode> {
+
+        trace!("Opening Value Reader -> type: {}, id: {}", type_, id);
+
+        let reader = ValueReader::open(self.read_pool.clone(), &self.tables, self.encryption.clone(), self.wallet_id, type_, id)?;
+
+        let reader_handle = self.value_readers.insert(Mutex::new(reader));
+
+        trace!("Success Opening Value Reader with the type: {}, id: {}. Reader Handle: {}", type_, id, reader_handle);
+
+        Ok(reader_handle)
+    }
+
+    ///
+    /// Reads the next part of a value into the buffer.
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<usize, ErrorCode>` - number of bytes read, 0 once the whole value is read
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidState` - Provided reader handle does not exist
+    ///  * `VersionConflict` - Record was changed since the reader was opened
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn read_value(&self, reader_handle: i32, buffer: &mut [u8]) -> Result<usize, ErrorCode> {
+
+        trace!("Reading Value -> reader_handle: {}, buffer_len: {}", reader_handle, buffer.len());
+
+        let reader = check_option!(self.value_readers.get(reader_handle), Err(ErrorCode::InvalidState));
+        let mut reader = check_result!(reader.lock(), Err(ErrorCode::InvalidState));
+
+        reader.read(buffer)
+    }
+
+    pub fn close_value_reader(&self, reader_handle: i32) -> ErrorCode {
+
+        trace!("Closing Value Reader -> reader_handle: {}", reader_handle);
+
+        if self.value_readers.remove(reader_handle) {
+            ErrorCode::Success
+        }
+        else {
+            warn!("Trying to close a non existent Value Reader, reader_handle: {}", reader_handle);
+            ErrorCode::InvalidState
+        }
+    }
+
+    ///
+    /// Opens a streaming write that replaces the value of a record, the value is written with `write_value`
+    /// and stored by `finish_value_writer`. Values longer than the configured `chunk_size` are stored chunked,
+    /// whether the chunked value mode is enabled or not.
+    ///
+    /// The record is locked until the writer is finished or aborted.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - record type
+    ///  * `id` - record id (name)
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<i32, ErrorCode>` - handle of the value writer
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `ItemNotFound` - Record with the provided type and id does not exist in the DB
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn open_value_writer(&self, type_: &str, id: &str) -> Result<i32, ErrorCode> {
+
+        trace!("Opening Value Writer -> type: {}, id: {}", type_, id);
+
+        let writer = ValueWriter::open(&self.write_pool, &self.tables, self.encryption.clone(), self.wallet_id, type_, id, &self.values, self.change_feed.enabled)?;
+
+        let writer_handle = self.value_writers.insert(Mutex::new(writer));
+
+        trace!("Success Opening Value Writer with the type: {}, id: {}. Writer Handle: {}", type_, id, writer_handle);
+
+        Ok(writer_handle)
+    }
+
+    ///
+    /// Appends data to the value of a value writer.
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidState` - Provided writer handle does not exist
+    ///  * `ValueTooLarge` - Value exceeds the configured maximum value size, the writer should be aborted
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn write_value(&self, writer_handle: i32, data: &[u8]) -> ErrorCode {
+
+        trace!("Writing Value -> writer_handle: {}, data_len: {}", writer_handle, data.len());
+
+        let writer = check_option!(self.value_writers.get(writer_handle), ErrorCode::InvalidState);
+        let mut writer = check_result!(writer.lock(), ErrorCode::InvalidState);
+
+        match writer.write(data) {
+            Err(err) => err,
+            Ok(()) => ErrorCode::Success,
+        }
+    }
+
+    ///
+    /// Stores the value of a value writer and closes the writer, the version of the record is incremented.
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidState` - Provided writer handle does not exist
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn finish_value_writer(&self, writer_handle: i32) -> ErrorCode {
+
+        trace!("Finishing Value Writer -> writer_handle: {}", writer_handle);
+
+        let writer = check_option!(self.value_writers.get(writer_handle), ErrorCode::InvalidState);
+
+        if !self.value_writers.remove(writer_handle) {
+            return ErrorCode::InvalidState;
+        }
+
+        // the handle store no longer holds the writer, so it is only held by a concurrent `write_value` call, if any
+        let writer = match Arc::try_unwrap(writer) {
+            Err(_) => {
+                warn!("Trying to finish a Value Writer that is being written to, writer_handle: {}", writer_handle);
+                return ErrorCode::InvalidState;
+            },
+            Ok(writer) => check_result!(writer.into_inner(), ErrorCode::InvalidState),
+        };
+
+        let (type_, id) = match writer.finish() {
+            Err(err) => return err,
+            Ok(record) => record,
+        };
+
+        self._invalidate_record(&type_, &id);
+
+        trace!("Success Finishing Value Writer with the type: {}, id: {}", type_, id);
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Closes a value writer without changing the value of the record.
+    ///
+    pub fn abort_value_writer(&self, writer_handle: i32) -> ErrorCode {
+
+        trace!("Aborting Value Writer -> writer_handle: {}", writer_handle);
+
+        // the write is rolled back when the writer is dropped
+        if self.value_writers.remove(writer_handle) {
+            ErrorCode::Success
+        }
+        else {
+            warn!("Trying to abort a non existent Value Writer, writer_handle: {}", writer_handle);
+            ErrorCode::InvalidState
+        }
+    }
+
+    ///
+    /// Reads the journaled record changes of the wallet, oldest first.
+    ///
+    /// # Arguments
+    ///
+    ///  * `since_seq` - sequence number of the last change already read, 0 to read from the start of the journal
+    ///  * `limit` - maximum number of changes to read, at most 1000
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<String, ErrorCode>` - json in the form of
+    ///         {"changes": [{"seq": 1, "type": "...", "id": "...", "operation": "add", "version": 1, "changedAt": 1792281600}], "lastSeq": 1}
+    ///     where `operation` is one of `add`, `update` or `delete`, `version` is the version of the record after the change
+    ///     (null for deleted records), `changedAt` is a unix timestamp in seconds and `lastSeq` is the sequence number
+    ///     of the latest change of the wallet. A gap between `since_seq` and the first returned change means
+    ///     that the changes in between were trimmed.
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidStructure` - `limit` is 0 or greater than 1000
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidState` - Invalid encoding of a fetched string
+    ///
+    pub fn read_changes(&self, since_seq: u64, limit: u64) -> Result<String, ErrorCode> {
+
+        trace!("Reading Changes -> since_seq: {}, limit: {}", since_seq, limit);
+
+        if limit == 0 || limit > MAX_READ_CHANGES_LIMIT {
+            warn!("Read Changes Error: limit must be between 1 and {}", MAX_READ_CHANGES_LIMIT);
+            return Err(ErrorCode::InvalidStructure);
+        }
+
+        let mut changes: Vec<serde_json::Value> = Vec::new();
+
+        {
+            let result = check_query!(
+                self.read_pool.prep_exec(
+                    format!("SELECT seq, type, name, operation, version, UNIX_TIMESTAMP(created_at) \
+                             FROM {} \
+                             WHERE wallet_id = :wallet_id AND seq > :since_seq \
+                             ORDER BY seq \
+                             LIMIT :limit", self.tables.changes),
+                    params!{
+                        "wallet_id" => self.wallet_id,
+                        "since_seq" => since_seq,
+                        "limit" => limit
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            for row in result {
+                let row = check_query!(row, Err(ErrorCode::IOError));
+
+                let seq: u64 = check_option!(row.get(0), Err(ErrorCode::InvalidState));
+                let type_: String = check_option!(row.get(1), Err(ErrorCode::InvalidState));
+                let id: String = check_option!(row.get(2), Err(ErrorCode::InvalidState));
+                let operation: String = check_option!(row.get(3), Err(ErrorCode::InvalidState));
+                let version: Option<u64> = check_option!(row.get(4), Err(ErrorCode::InvalidState));
+                let changed_at: u64 = check_option!(row.get(5), Err(ErrorCode::InvalidState));
+
+                changes.push(json!({"seq": seq, "type": type_, "id": id, "operation": operation, "version": version, "changedAt": changed_at}));
+            }
+        }
+
+        let last_seq: u64 = {
+            let mut result = check_query!(
+                self.read_pool.prep_exec(
+                    format!("SELECT change_seq FROM {} WHERE id = :wallet_id", self.tables.wallets),
+                    params!{
+                        "wallet_id" => self.wallet_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::WalletNotFound)), Err(ErrorCode::IOError));
+
+            check_option!(row.get(0), Err(ErrorCode::InvalidState))
+        };
+
+        trace!("Success Reading Changes -> since_seq: {}, limit: {}. Changes: {}", since_seq, limit, changes.len());
+
+        Ok(json!({"changes": changes, "lastSeq": last_seq}).to_string())
+    }
+
+    ///
+    /// Drops journaled record changes of the wallet that were read by every consumer or are expired
+    /// by the `max_age` and `max_changes` of the change feed config.
+    ///
+    /// # Arguments
+    ///
+    ///  * `up_to_seq` - changes up to and including this sequence number are dropped, 0 to apply only the config limits
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<u64, ErrorCode>` - number of dropped changes
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn trim_changes(&self, up_to_seq: u64) -> Result<u64, ErrorCode> {
+
+        trace!("Trimming Changes -> up_to_seq: {}", up_to_seq);
+
+        let mut conditions: Vec<String> = vec!["seq <= ?".to_string()];
+        let mut arguments: Vec<Value> = vec![up_to_seq.into()];
+
+        if let Some(max_age) = self.change_feed.max_age {
+            conditions.push("created_at < NOW() - INTERVAL ? SECOND".to_string());
+            arguments.push(max_age.into());
+        }
+
+        if let Some(max_changes) = self.change_feed.max_changes {
+            conditions.push(format!("seq + ? <= (SELECT change_seq FROM {} WHERE id = ?)", self.tables.wallets));
+            arguments.push(max_changes.into());
+            arguments.push(self.wallet_id.into());
+        }
+
+        arguments.push(self.wallet_id.into());
+
+        let trimmed = check_query!(
+            self.write_pool.prep_exec(
+                format!("DELETE FROM {} WHERE ({}) AND wallet_id = ?", self.tables.changes, conditions.join(" OR ")),
+                arguments
+            ),
+            Err(ErrorCode::IOError)
+        ).affected_rows();
+
+        trace!("Success Trimming Changes -> up_to_seq: {}. Trimmed: {}", up_to_seq, trimmed);
+
+        Ok(trimmed)
+    }
+
+    ///
+    /// Creates a new data key for the wallet, the values and metadata written from now on are encrypted with it.
+    /// The data encrypted with the older keys stays readable, `start_reencryption` encrypts it with the new key.
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<u32, ErrorCode>` - version of the new data key
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidState` - Encryption is not enabled in the config of the storage
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn rotate_data_key(&self) -> Result<u32, ErrorCode> {
+
+        trace!("Rotating Data Key");
+
+        let encryption = check_option!(self.encryption.as_ref(), Err(ErrorCode::InvalidState));
+        let version = encryption.rotate()?;
+
+        trace!("Success Rotating Data Key. Version: {}", version);
+
+        Ok(version)
+    }
+
+    ///
+    /// Wraps the data keys of a wallet with the `current_key` of the encryption config, so the key-encryption key
+    /// that wrapped them before can be removed from the config. The data itself is not re-encrypted.
+    ///
+    /// # Arguments
+    ///
+    ///  * `name` - name of the wallet
+    ///  * `config` - json containing information like db_host, db_port, db_name, with both the old and the current keys
+    ///  * `credentials` - json containing information about user and password for db access
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<u64, ErrorCode>` - number of re-wrapped data keys
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidStructure` -  Invalid structure of the JSON arguments -> config | credentials, or no `current_key` is configured
+    ///  * `WalletNotFound` - Wallet with the provided name does not exist in the DB
+    ///  * `EncryptionKeyNotFound` - Key-encryption key of a data key is not in the config
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn rewrap_data_keys(name: &str, config: &str, credentials: &str) -> Result<u64, ErrorCode> {
+
+        trace!("Re-wrapping Data Keys -> name: {}", name);
+
+        let config: StorageConfig = check_result!(serde_json::from_str(config), Err(ErrorCode::InvalidStructure));
+        let tables = check_option!(Tables::new(config.table_prefix), Err(ErrorCode::InvalidStructure));
+        let credentials: StorageCredentials = check_result!(serde_json::from_str(credentials), Err(ErrorCode::InvalidStructure));
+
+        let keks = check_option!(KeyEncryptionKeys::from_config(&config.encryption)?, Err(ErrorCode::InvalidStructure));
+
+        let shard = sharding::route_existing(name, &config, &credentials, &tables)?;
+        let write_pool = check_option!(CONNECTIONS.get(false, &shard.config, &credentials), Err(ErrorCode::IOError));
+
+        let wallet_id: u64 = {
+            let mut result = check_query!(
+                                write_pool.prep_exec(
+                                    format!("SELECT id FROM {} WHERE name = :name", tables.wallets),
+                                    params!{
+                                        name
+                                     }
+                                ), Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::WalletNotFound)), Err(ErrorCode::IOError));
+
+            check_option!(row.get(0), Err(ErrorCode::InvalidState))
+        };
+
+        let rewrapped = encryption::rewrap_data_keys(&keks, &write_pool, &tables, wallet_id)?;
+
+        trace!("Success Re-wrapping Data Keys with the name: {}. Re-wrapped: {}", name, rewrapped);
+
+        Ok(rewrapped)
+    }
+
+    ///
+    /// Starts a background job that encrypts the metadata and all the record values of the wallet
+    /// with its current data key, e.g. after a `rotate_data_key` or once encryption is enabled for a wallet
+    /// that holds unencrypted data. The progress is returned by `get_reencryption_status`.
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Job started
+    ///  * `InvalidState` - Encryption is not enabled in the config of the storage, or a job started by the storage is still running
+    ///
+    pub fn start_reencryption(&self) -> ErrorCode {
+
+        trace!("Starting Re-encryption");
+
+        let encryption = check_option!(self.encryption.as_ref(), ErrorCode::InvalidState);
+        let mut reencryption = check_result!(self.reencryption.lock(), ErrorCode::InvalidState);
+
+        if let Some(ref status) = *reencryption {
+            if check_result!(status.lock(), ErrorCode::InvalidState).running {
+                warn!("Re-encryption of the wallet {} is already running", self.wallet_id);
+                return ErrorCode::InvalidState;
+            }
+        }
+
+        match ReencryptionJob::start(self.write_pool.clone(), &self.tables, self.wallet_id, encryption.clone()) {
+            Err(err) => return err,
+            Ok(status) => *reencryption = Some(status),
+        }
+
+        trace!("Success Starting Re-encryption");
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Returns the progress of the last re-encryption job started by the storage.
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<String, ErrorCode>` - json in the form of
+    ///         {"running": false, "keyVersion": 2, "scannedRecords": 100, "reencryptedRecords": 40, "error": null}
+    ///     where `error` is the code of the error the job stopped with
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `InvalidState` - No re-encryption job was started by the storage
+    ///
+    pub fn get_reencryption_status(&self) -> Result<String, ErrorCode> {
+
+        trace!("Getting Re-encryption Status");
+
+        let reencryption = check_result!(self.reencryption.lock(), Err(ErrorCode::InvalidState));
+        let status = check_option!(reencryption.as_ref(), Err(ErrorCode::InvalidState));
+        let status = check_result!(status.lock(), Err(ErrorCode::InvalidState));
+
+        Ok(json!(*status).to_string())
+    }
+
+    ///
+    /// Gets wallet metadata.
+    ///
+    /// # Returns
+    ///
+    ///  * `Result<(Arc<CString>, i32), ErrorCode>` - metadata content or ErrorCode
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidState` - Invalid encoding of a provided/fetched string
+    ///  * `ItemNotFound` - Record with the provided type and id does not exist in the DB
+    ///  * `EncryptionKeyNotFound` - Metadata is encrypted with a key that can not be unwrapped with the configured keys
+    ///  * `DecryptionFailed` - Stored metadata could not be decrypted
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn get_metadata(&self) -> Result<(Arc<CString>, i32), ErrorCode> {
+
+        trace!("Getting Wallet Metadata");
+
+        let metadata = match self.cache {
+            None => self._read_metadata()?,
+            Some(ref cache) => match cache.get(&CacheKey::Metadata) {
+                Some(cached) => match *cached {
+                    CachedItem::Metadata(ref metadata) => metadata.clone(),
+                    CachedItem::Record(..) => return Err(ErrorCode::InvalidState),
+                },
+                None => {
+                    let generation = cache.generation();
+                    let metadata = self._read_metadata()?;
+
+                    cache.insert(CacheKey::Metadata, CachedItem::Metadata(metadata.clone()), metadata.len() + CACHE_ENTRY_OVERHEAD, generation);
+
+                    metadata
+                }
+            }
+        };
+
+        let metadata = check_result!(CString::new(metadata), Err(ErrorCode::InvalidState));
+
+        let handle = self.metadata.insert(metadata);
+        let metadata = check_option!(self.metadata.get(handle), Err(ErrorCode::ItemNotFound));
+
+        trace!("Success Getting Wallet Metadata");
+
+        Ok((metadata, handle))
+    }
+
+    fn _read_metadata(&self) -> Result<String, ErrorCode> {
+        let mut result: QueryResult = check_query!(
+            self.read_pool.prep_exec(
+                format!("SELECT metadata, metadata_encoding FROM {} WHERE id = :wallet_id", self.tables.wallets),
+                params! {
+                    "wallet_id" => self.wallet_id,
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        let row = check_query!(check_option!(result.next(), Err(ErrorCode::ItemNotFound)), Err(ErrorCode::IOError));
+
+        let metadata: String = check_option!(row.get(0), Err(ErrorCode::IOError));
+        let metadata_encoding: u8 = check_option!(row.get(1), Err(ErrorCode::IOError));
+
+        encryption::decode_metadata(self._encryption(), metadata, metadata_encoding)
+    }
+
+    ///
+    /// Returns the counters of the storage cache.
+    ///
+    /// # Returns
+    ///
+    ///  * `String` - json in the form of
+    ///         {"enabled": true, "maxBytes": 16777216, "hits": 10, "misses": 2, "evictions": 0, "expirations": 1,
+    ///          "invalidations": 1, "entries": 1, "bytes": 120}
+    ///     the counters are 0 if the cache is disabled
+    ///
+    pub fn get_cache_stats(&self) -> String {
+
+        trace!("Getting Cache Stats");
+
+        let (enabled, max_bytes, stats) = match self.cache {
+            None => (false, 0, CacheStats::default()),
+            Some(ref cache) => (true, cache.max_bytes(), cache.stats()),
+        };
+
+        let mut stats_json = json!(stats);
+        stats_json["enabled"] = json!(enabled);
+        stats_json["maxBytes"] = json!(max_bytes);
+
+        stats_json.to_string()
+    }
+
+    ///
+    /// Sets wallet metadata.
+    ///
+    /// # Arguments
+    ///
+    ///  * `metadata` - metadata to be stored
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///
+    pub fn set_metadata(&self, metadata: &str) -> ErrorCode {
+
+        trace!("Setting Wallet Metadata");
+
+        let (metadata, metadata_encoding) = match self.encryption {
+            None => (metadata.to_string(), 0),
+            Some(ref encryption) => match encryption.encrypt_metadata(metadata) {
+                Err(err) => return err,
+                Ok(metadata) => (metadata, encryption::ENCRYPTED),
+            }
+        };
+
+        check_query!(
+            self.write_pool.prep_exec(
+                format!("UPDATE {} SET metadata = :metadata, metadata_encoding = :metadata_encoding WHERE id = :wallet_id", self.tables.wallets),
+                params! {
+                    "wallet_id" => self.wallet_id,
+                    "metadata" => metadata,
+                    "metadata_encoding" => metadata_encoding,
+                }
+            ),
+            ErrorCode::IOError
+        );
+
+        if let Some(ref cache) = self.cache {
+            cache.invalidate(&CacheKey::Metadata);
+        }
+
+        trace!("Success Setting Wallet Metadata");
+
+        ErrorCode::Success
+    }
+
+    ///
+    /// Removes a metadata handle, thus removing the referenced object from memory.
+    ///
+    /// # Arguments
+    ///
+    ///  * `metadata_handle` - unique identifier of wallets metadata
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode`
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Success` - Execution successful
+    ///  * `InvalidState` - Provided metadata handle does not exist
+    ///
+    pub fn free_metadata(&self, metadata_handle: i32) -> ErrorCode {
+
+        trace!("Freeing Wallet Metadata -> metadata_handle: {}", metadata_handle);
+
+        if self.metadata.remove(metadata_handle) {
+            trace!("Success Freeing Wallet Metadata with the metadata_handle: {}", metadata_handle);
+            ErrorCode::Success
+        }
+        else {
+            warn!("Trying to free non existent Wallet Metadata, metadata_handle: {}", metadata_handle);
+            ErrorCode::InvalidState
+        }
+    }
+
+    ///
+    /// Performs a search defined by the user query and options.
+    ///
+    /// # Arguments
+    ///
+    ///  * `type_` - type of the record that we are searching for
+    ///  * `query_json` - query conditions specified in the form of a json
+    ///         {
+    ///             "tagName": "tagValue",
+    ///             $or: {
+    ///                 "tagName2": "tagValue2",
+    ///                 "tagName3": { $gte: 123 },
+    ///             },
+    ///         }
+    ///     The `$type` pseudo-field matches the record type instead of a tag.
+    ///  * `options_json` - options specifying what attributes ought to be fetched ex.
+    ///         {
+    ///             retireveType: (optional, true by default)
+    ///             retrieveValue: (optional, true by default)
+    ///             retireveTags: (optional, true by default)
+    ///             maxExecutionTime: (optional, overrides `query_limits.max_execution_time` from the config)
+    ///             statementTimeout: (optional, same as `maxExecutionTime`)
+    ///         }
+    ///  * `search_handle_p` - output param - handle that will be used for accessing the search result
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    ///  * `record_handle` - unique identifier of a record
+    ///  * `ErrorCode`
     ///
-    /// # Returns
+    /// # ErrorCodes
     ///
-    ///  * `Option<Arc<Record>>`
+    ///  * `Success` - Execution successful
+    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> query | options
+    ///  * `QueryLimitExceeded` - Query exceeds one of the configured query limits
+    ///  * `Timeout` - Search statement exceeded its execution time
     ///
-    pub fn get_record(&self, record_handle: i32) -> Option<Arc<Record>> {
+    pub fn search_records(&self, type_: &str, query_json: &str, options_json: &str, search_handle_p: *mut i32) -> ErrorCode {
 
-        trace!("Getting Record -> record_handle: {}", record_handle);
+        trace!("Searching Records -> type: {}, query_json: {}, options: {}", type_, query_json, options_json);
 
-        self.records.get(record_handle)
+        let search_options: SearchOptions = check_result!(serde_json::from_str(options_json), ErrorCode::InvalidStructure);
+
+        self._search_records(&query_translator::TypeFilter::Type(type_.to_string()), query_json, &search_options, &ChunkOptions::default(), search_handle_p)
     }
 
     ///
-    /// Deletes a record identified by type and id.
+    /// Performs a search across multiple record types.
     ///
     /// # Arguments
     ///
-    ///  * `type_` - record type
-    ///  * `id` - record id (name)
+    ///  * `types_json` - record types that we are searching for, one of
+    ///         "type"                  - a single type, same as `search_records`
+    ///         ["type1", "type2"]      - a list of types
+    ///         {"$like": "type%"}      - a `LIKE` pattern over the type
+    ///  * `query_json` - query conditions specified in the form of a json, same as for `search_records`.
+    ///     The `$type` pseudo-field can be used to match the record type inside the query.
+    ///  * `options_json` - search options, same as for `search_records`.
+    ///     The type of fetched records is always retrieved when the search spans multiple types.
+    ///  * `search_handle_p` - output param - handle that will be used for accessing the search result
     ///
     /// # Returns
     ///
@@ -478,176 +4308,167 @@ impl<'a> MySQLStorage<'a> {
     /// # ErrorCodes
     ///
     ///  * `Success` - Execution successful
-    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> types | query | options
+    ///  * `QueryLimitExceeded` - Query exceeds one of the configured query limits
     ///
-    pub fn delete_record(&self, type_: &str, id: &str) -> ErrorCode {
-
-        trace!("Deleting record -> type: {}, id: {}", type_, id);
-
-        let result: QueryResult = check_result!(
-            self.write_pool.prep_exec(
-                "DELETE FROM items WHERE type = :type AND name = :name AND wallet_id = :wallet_id",
-                params! {
-                    "type" => type_,
-                    "name" => id,
-                    "wallet_id" => self.wallet_id,
-                }
-            ),
-            ErrorCode::IOError
-        );
+    pub fn search_records_by_types(&self, types_json: &str, query_json: &str, options_json: &str, search_handle_p: *mut i32) -> ErrorCode {
 
-        if result.affected_rows() != 1 {
-            warn!("Trying to delete a non existent record, type: {}, id: {}", type_, id);
-            return ErrorCode::ItemNotFound;
-        }
+        trace!("Searching Records By Types -> types: {}, query_json: {}, options: {}", types_json, query_json, options_json);
 
-        trace!("Success Deleting Record with the type: {}, id: {}", type_, id);
+        let type_filter = check_result!(query_translator::parse_type_filter(types_json), ErrorCode::InvalidStructure);
+        let search_options: SearchOptions = check_result!(serde_json::from_str(options_json), ErrorCode::InvalidStructure);
 
-        ErrorCode::Success
+        self._search_records(&type_filter, query_json, &search_options, &ChunkOptions::default(), search_handle_p)
     }
 
     ///
-    /// Updates the value of a record identified by type and id.
+    /// Describes how a search would be executed without fetching any records.
     ///
     /// # Arguments
     ///
-    ///  * `type_` - record type
-    ///  * `id` - record id (name)
-    ///  * `value` - new value
+    ///  * `type_` - type of the record that we are searching for
+    ///  * `query_json` - query conditions specified in the form of a json, same as for `search_records`
+    ///  * `options_json` - search options, same as for `search_records`, plus
+    ///         {
+    ///             runExplain: (optional, false by default) include the output of MySQL `EXPLAIN FORMAT=JSON`
+    ///         }
     ///
     /// # Returns
     ///
-    ///  * `ErrorCode`
+    ///  * `Result<String, ErrorCode>` - JSON containing the optimised WQL and, for each statement that the search
+    ///     would run, the rendered SQL and its arguments. Values of encrypted tags and the type are redacted.
+    ///         {
+    ///             "wql": {...},
+    ///             "records_query": {"sql": "...", "arguments": [...], "explain": {...}},
+    ///             "count_query": {"sql": "...", "arguments": [...], "explain": {...}}
+    ///         }
     ///
     /// # ErrorCodes
     ///
-    ///  * `Success` - Execution successful
-    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> query | options
+    ///  * `QueryLimitExceeded` - Query exceeds one of the configured query limits
     ///
-    pub fn update_record_value(&self, type_: &str, id: &str, value: &Vec<u8>) -> ErrorCode {
+    pub fn explain_search(&self, type_: &str, query_json: &str, options_json: &str) -> Result<String, ErrorCode> {
 
-        trace!("Updating Record Value -> type: {}, id: {}, value: {:?}", type_, id, value);
+        trace!("Explaining Search -> type: {}, query_json: {}, options: {}", type_, query_json, options_json);
 
-        let result: QueryResult = check_result!(
-            self.write_pool.prep_exec(
-                "UPDATE items SET value = :value WHERE type = :type AND name = :name AND wallet_id = :wallet_id",
-                    params!{
-                        "value" => value,
-                        "type" => type_,
-                        "name" => id,
-                        "wallet_id" => self.wallet_id
-                    }
-            ),
-            ErrorCode::IOError
-        );
+        let explain_options: ExplainOptions = check_result!(serde_json::from_str(options_json), Err(ErrorCode::InvalidStructure));
+        let search_options = &explain_options.search_options;
 
-        if result.affected_rows() != 1 {
-            warn!("Trying to update value of a non existent record, type: {}, id: {}", type_, id);
-            return ErrorCode::ItemNotFound;
+        let (wql, query_limits) = self._prepare_search_query(query_json, search_options.max_execution_time)?;
+        let type_filter = query_translator::TypeFilter::Type(type_.to_string());
+
+        let mut arguments_json = query_translator::wql_to_redacted_arguments(&wql);
+        for _ in 0..type_filter.arguments_count() {
+            arguments_json.push(json!("<redacted>"));
         }
+        arguments_json.push(json!(self.wallet_id));
 
-        trace!("Success Updating Value of a record with the type: {}, id: {}", type_, id);
+        let mut explain = json!({
+            "wql": query_translator::wql_to_json(&wql)
+        });
 
-        ErrorCode::Success
+        if search_options.retrieve_records {
+            let (query, arguments) = query_translator::wql_to_sql(self.wallet_id, &self.tables.items, &type_filter, &wql, search_options, &query_limits)?;
+            explain["records_query"] = self._explain_query(query, arguments, &arguments_json, explain_options.run_explain)?;
+        }
+
+        if search_options.retrieve_total_count {
+            let (query, arguments) = query_translator::wql_to_sql_count(self.wallet_id, &self.tables.items, &type_filter, &wql, &query_limits)?;
+            explain["count_query"] = self._explain_query(query, arguments, &arguments_json, explain_options.run_explain)?;
+        }
+
+        trace!("Success Explaining Search with the type: {}, query_json: {}", type_, query_json);
+
+        Ok(explain.to_string())
     }
 
     ///
-    /// Adds tags for a record identified by type and id.
-    /// If tag with that name exists it will be updated.
+    /// Counts the records matching a query, grouped by the record type or a plaintext tag.
     ///
     /// # Arguments
     ///
-    ///  * `type_` - record type
-    ///  * `id` - record id (name)
-    ///  * `tag_names` - a map containing (tag_name: tag_value) pairs
+    ///  * `query_json` - query conditions specified in the form of a json, same as for `search_records`
+    ///  * `options_json` - aggregation options
+    ///         {
+    ///             groupBy: "$type" to group by the record type, or the name of a plaintext (`~`) tag
+    ///             types: (optional, all types by default) same as `types_json` of `search_records_by_types`
+    ///             distinct: (optional, false by default) only list the distinct values, without counts
+    ///             limit: (optional) maximum number of buckets
+    ///             maxExecutionTime: (optional, overrides `query_limits.max_execution_time` from the config)
+    ///             statementTimeout: (optional, same as `maxExecutionTime`)
+    ///         }
     ///
     /// # Returns
     ///
-    ///  * `ErrorCode`
+    ///  * `Result<String, ErrorCode>` - JSON with buckets ordered by the count, largest first.
+    ///     Records without the grouped tag are not counted.
+    ///         {"groupBy": "~tag", "buckets": [{"value": "value1", "count": 2}, ...]}
+    ///     or with `distinct` the values ordered by the value
+    ///         {"groupBy": "~tag", "values": ["value1", ...]}
     ///
     /// # ErrorCodes
     ///
-    ///  * `Success` - Execution successful
-    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
-    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tags
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> query | options, or `groupBy` is an encrypted tag
+    ///  * `QueryLimitExceeded` - Query exceeds one of the configured query limits
     ///
-    pub fn add_record_tags(&self, type_: &str, id: &str, tags: &HashMap<String, serde_json::Value>) -> ErrorCode {
-
-        trace!("Adding Record Tags -> type: {}, id: {}, tags:{:?}", type_, id, tags);
+    pub fn aggregate_records(&self, query_json: &str, options_json: &str) -> Result<String, ErrorCode> {
 
-        if tags.is_empty() {
-            trace!("No tags to add. Checking if record exists...");
-            return self._check_if_record_exists(type_, id);
-        }
+        trace!("Aggregating Records -> query_json: {}, options: {}", query_json, options_json);
 
-        let mut tag_name_value_paths: Vec<String> = Vec::new();
+        let options: AggregateOptions = check_result!(serde_json::from_str(options_json), Err(ErrorCode::InvalidStructure));
 
-        for (tag_name, tag_value) in tags {
-            let tag_name_path = format!(r#"'$."{}"', {}"#, tag_name, tag_value);
-            tag_name_value_paths.push(tag_name_path);
-        }
+        let type_filter = match options.types {
+            Some(ref types) => query_translator::parse_type_filter_value(types.clone())?,
+            None => query_translator::TypeFilter::Any
+        };
 
-        let tag_name_value_paths = tag_name_value_paths.join(",");
+        let (wql, query_limits) = self._prepare_search_query(query_json, options.max_execution_time)?;
+        query_translator::check_type_filter_limits(&type_filter, &query_limits)?;
 
-        trace!("JSON Path argument given to JSON_SET is: {}", tag_name_value_paths);
+        let (query, arguments) = query_translator::wql_to_sql_aggregate(
+            self.wallet_id, &self.tables.items, &type_filter, &wql, &options.group_by, options.distinct, options.limit, &query_limits
+        )?;
 
-        let query = format!("UPDATE items \
-                             SET tags = JSON_SET(tags, {}) \
-                             WHERE type = :type \
-                             AND name = :name \
-                             AND wallet_id = :wallet_id",
-                             tag_name_value_paths
+        let result: QueryResult = check_query!(
+            self.read_pool.prep_exec(query, arguments),
+            Err(ErrorCode::IOError)
         );
 
-        let result = {
-            self.write_pool.prep_exec(
-                        query,
-                        params!{
-                            "type" => type_,
-                            "name" => id,
-                            "wallet_id" => self.wallet_id
-                        }
-                )
-        };
+        let mut values: Vec<serde_json::Value> = Vec::new();
 
-        let result = match result {
-            Err(Error::MySqlError(err)) => {
-                warn!("MySQL Error while executing query. Err Code: {}", err.code);
-                match err.code {
-                    1064 => return ErrorCode::InvalidStructure, // Invalid JSON
-                    _ => return ErrorCode::IOError,
-                };
-            },
-            Err(err) => {
-                    warn!("Unexpected Error while executing query. Err: {:?}", err);
-                    return ErrorCode::IOError
-            },
-            Ok(result) => result,
-        };
+        for row in result {
+            let row = check_query!(row, Err(ErrorCode::IOError));
+            let value: String = check_option!(row.get(0), Err(ErrorCode::IOError));
 
-        if result.affected_rows() != 1 {
-            warn!("Trying to add tags to a non existent record, type: {}, id: {}", type_, id);
-            return ErrorCode::ItemNotFound;
+            if options.distinct {
+                values.push(json!(value));
+            } else {
+                let count: u64 = check_option!(row.get(1), Err(ErrorCode::IOError));
+                values.push(json!({"value": value, "count": count}));
+            }
         }
 
-        trace!("Success Adding Tags for the record with the type: {}, id: {}", type_, id);
+        let aggregate = if options.distinct {
+            json!({"groupBy": options.group_by, "values": values})
+        } else {
+            json!({"groupBy": options.group_by, "buckets": values})
+        };
 
-        ErrorCode::Success
+        trace!("Success Aggregating Records -> query_json: {}, options: {}", query_json, options_json);
+
+        Ok(aggregate.to_string())
     }
 
     ///
-    /// Updates tags of a record identified by type and id.
-    ///
-    /// `WARNING`: Performs a destructive update by replacing old JSON doc with a new one.
+    /// Performs a search that grabs all records of a wallet with all attributes from the DB.
     ///
     /// # Arguments
     ///
-    ///  * `type_` - record type
-    ///  * `id` - record id (name)
-    ///  * `tag_names` - a map containing (tag_name: new_tag_value) pairs
+    ///  * `search_handle_p` - output param - handle that will be used for accessing the search result
     ///
     /// # Returns
     ///
@@ -656,62 +4477,47 @@ impl<'a> MySQLStorage<'a> {
     /// # ErrorCodes
     ///
     ///  * `Success` - Execution successful
-    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
-    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tags
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
     ///
-    pub fn update_record_tags(&self, type_: &str, id: &str, tags: &str) -> ErrorCode {
-
-        trace!("Updating Record Tags -> type: {}, id: {}, tags: {}", type_, id, tags);
+    pub fn search_all_records(&self, search_handle_p: *mut i32) -> ErrorCode {
 
-        // check if tags are a valid JSON
-        let _tags_json_chk: HashMap<String, serde_json::Value> = check_result!(serde_json::from_str(tags), ErrorCode::InvalidStructure);
+        trace!("Searching All Records");
 
-        let result = {
-            self.write_pool.prep_exec(
-                        "UPDATE items SET tags = :tags WHERE type = :type AND name = :name AND wallet_id = :wallet_id",
-                        params!{
-                            "tags" => tags,
-                            "type" => type_,
-                            "name" => id,
-                            "wallet_id" => self.wallet_id
-                        }
-                )
-        };
+         let search_result: QueryResult = check_query!(
+            self.read_pool.prep_exec(
+                format!("SELECT type, name, value, tags, version{} FROM {} WHERE wallet_id = :wallet_id", query_translator::STORED_VALUE_COLUMNS, self.tables.items),
+                params! {
+                    "wallet_id" => self.wallet_id,
+                }
+            ),
+            ErrorCode::IOError
+        );
 
-        let result = match result {
-            Err(Error::MySqlError(err)) => {
-                warn!("MySQL Error while executing query. Err Code: {}", err.code);
-                match err.code {
-                    3140 => return ErrorCode::InvalidStructure, // Invalid JSON
-                    _ => return ErrorCode::IOError,
-                };
-            },
-            Err(err) => {
-                    warn!("Unexpected Error while executing query. Err: {:?}", err);
-                    return ErrorCode::IOError
-            },
-            Ok(result) => result,
-        };
+        let search_handle = self.searches.insert(Search::new(Some(search_result), None));
 
-        if result.affected_rows() != 1 {
-            warn!("Trying to update tags of a non existent record, type: {}, id: {}", type_, id);
-            return ErrorCode::ItemNotFound;
-        }
+        unsafe { *search_handle_p = search_handle; }
 
-        trace!("Success Updating Tags for the record with the type: {}, id: {}", type_, id);
+        trace!("Success Searching All Records. Search Handle: {}", search_handle);
 
         ErrorCode::Success
     }
 
     ///
-    /// Deletes tags of a record identified by type and id.
+    /// Performs a search over all records of a wallet, optionally filtered, projected and chunked.
     ///
     /// # Arguments
     ///
-    ///  * `type_` - record type
-    ///  * `id` - record id (name)
-    ///  * `tag_names` - list of tag names that need to be deleted
+    ///  * `query_json` - query conditions specified in the form of a json, same as for `search_records`.
+    ///     `{}` matches all records, the `$type` pseudo-field can be used to match the record type.
+    ///  * `options_json` - search options, same as for `search_records`, plus
+    ///         {
+    ///             chunkSize: (optional) maximum number of records to fetch
+    ///             after: (optional) {"type": "...", "id": "..."} of the last record of the previous chunk
+    ///         }
+    ///     The type of fetched records is always retrieved. When chunking, records are ordered by type and id,
+    ///     so the next chunk is fetched by passing the type and id of the last fetched record as `after`.
+    ///     The total count is not affected by chunking.
+    ///  * `search_handle_p` - output param - handle that will be used for accessing the search result
     ///
     /// # Returns
     ///
@@ -720,120 +4526,95 @@ impl<'a> MySQLStorage<'a> {
     /// # ErrorCodes
     ///
     ///  * `Success` - Execution successful
-    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> tag_names
-    ///  * `NotFoundError` - Record with the provided type and id does not exist in the DB
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> query | options
+    ///  * `QueryLimitExceeded` - Query exceeds one of the configured query limits
     ///
-    pub fn delete_record_tags(&self, type_: &str, id: &str, tag_names: &Vec<String>) -> ErrorCode {
-
-        trace!("Deleting Record Tags -> type: {}, id: {}, tag_names: {:?}", type_, id, tag_names);
-
-        if tag_names.is_empty() {
-            trace!("No tags to delete. Checking if record exists...");
-            return self._check_if_record_exists(type_, id);
-        }
-
-        let mut tag_name_paths: Vec<String> = Vec::new();
-
-        for tag_name in tag_names {
-            let tag_name_path = format!(r#"'$."{}"'"#, tag_name);
-            tag_name_paths.push(tag_name_path);
-        }
-
-        let tag_name_paths = tag_name_paths.join(",");
-
-        trace!("JSON Path argument given to JSON_REMOVE is: {}", tag_name_paths);
-
-        let query = format!("UPDATE items \
-                            SET tags = JSON_REMOVE(tags, {}) \
-                            WHERE type = :type \
-                            AND name = :name \
-                            AND wallet_id = :wallet_id",
-                            tag_name_paths
-        );
-
-        let result = {
-            self.write_pool.prep_exec(
-                        query,
-                        params!{
-                            "type" => type_,
-                            "name" => id,
-                            "wallet_id" => self.wallet_id
-                        }
-                )
-        };
-
-        let result = match result {
-            Err(Error::MySqlError(err)) => {
-                warn!("MySQL Error while executing query. Err Code: {}", err.code);
-                match err.code {
-                    1064 => return ErrorCode::InvalidStructure, // Invalid JSON
-                    _ => return ErrorCode::IOError,
-                };
-            },
-            Err(err) => {
-                    warn!("Unexpected Error while executing query. Err: {:?}", err);
-                    return ErrorCode::IOError
-            },
-            Ok(result) => result,
-        };
+    pub fn search_all_records_with_options(&self, query_json: &str, options_json: &str, search_handle_p: *mut i32) -> ErrorCode {
 
-        if result.affected_rows() != 1 {
-            warn!("Trying to delete tags of a non existent record, type: {}, id: {}", type_, id);
-            return ErrorCode::ItemNotFound;
-        }
+        trace!("Searching All Records With Options -> query_json: {}, options: {}", query_json, options_json);
 
-        trace!("Success Deleting Tags for the record with the type: {}, id: {}", type_, id);
+        let options: SearchAllOptions = check_result!(serde_json::from_str(options_json), ErrorCode::InvalidStructure);
 
-        ErrorCode::Success
+        self._search_records(&query_translator::TypeFilter::Any, query_json, &options.search_options, &options.chunk_options, search_handle_p)
     }
 
+
     ///
-    /// Gets wallet metadata.
+    /// Fetches a new record from the search result set.
+    ///
+    /// # Arguments
+    ///
+    ///  * `search_handle` - unique identifier of a search request
+    ///  * `record_handle_p` - output param - handle that will be used for accessing the record
     ///
     /// # Returns
     ///
-    ///  * `Result<(Arc<CString>, i32), ErrorCode>` - metadata content or ErrorCode
+    ///  * `ErrorCode`
     ///
     /// # ErrorCodes
     ///
     ///  * `Success` - Execution successful
-    ///  * `InvalidState` - Invalid encoding of a provided/fetched string
-    ///  * `ItemNotFound` - Record with the provided type and id does not exist in the DB
+    ///  * `InvalidState` - Provided search handle does not exist, or parsing of data has gone wrong
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `ItemNotFound` - Result set exhausted, no more records to fetch
+    ///  * `VersionConflict` - Chunked value of the record was changed after the record was read by the search
     ///
-    pub fn get_metadata(&self) -> Result<(Arc<CString>, i32), ErrorCode> {
+    pub fn fetch_search_next_record(&self, search_handle: i32, record_handle_p: *mut i32) -> ErrorCode {
+
+        trace!("Fetching Search Next Record -> search_handle: {}", search_handle);
+
+        let search = check_option!(self.searches.get(search_handle), ErrorCode::InvalidState);
+
+        if let Some(ref fetched_records) = search.fetched_records {
+            let mut fetched_records = check_result!(fetched_records.write(), ErrorCode::IOError);
+
+            let record = check_option!(fetched_records.pop_front(), ErrorCode::ItemNotFound);
+
+            let record_handle = self.records.insert(record);
+
+            unsafe { *record_handle_p = record_handle; }
+
+            trace!("Success Fetching Search Next Record with the search_handle: {}. Record Handle: {}", search_handle, record_handle);
+
+            return ErrorCode::Success;
+        }
+
+        match search.search_result {
+            None => {
+                warn!("Trying to fetch results for a search that wasn't meant to retrieve any records, search_handle: {}", search_handle);
+                ErrorCode::InvalidState
+            },
+            Some(ref search_result) => {
+                let mut search_result = check_result!(search_result.write(), ErrorCode::IOError);
 
-        trace!("Getting Wallet Metadata");
+                let next_result = check_option!(search_result.next(), ErrorCode::ItemNotFound);
 
-        let mut result: QueryResult = check_result!(
-            self.read_pool.prep_exec(
-                "SELECT metadata FROM wallets WHERE id = :wallet_id",
-                params! {
-                    "wallet_id" => self.wallet_id,
-                }
-            ),
-            Err(ErrorCode::IOError)
-        );
+                let row = check_query!(next_result, ErrorCode::IOError);
 
-        let row = check_result!(check_option!(result.next(), Err(ErrorCode::ItemNotFound)), Err(ErrorCode::IOError));
-        let metadata: String = check_option!(row.get(0), Err(ErrorCode::IOError));
-        let metadata = check_result!(CString::new(metadata), Err(ErrorCode::InvalidState));
+                let record = match Record::from_row(row).and_then(|(record, chunked_item_id, value_encoding)| self._read_stored_value(record, chunked_item_id, value_encoding)) {
+                    Ok(record) => record,
+                    Err(err) => return err
+                };
 
-        let handle = self.metadata.insert(metadata);
-        let metadata = check_option!(self.metadata.get(handle), Err(ErrorCode::ItemNotFound));
+                let record_handle = self.records.insert(record);
 
-        trace!("Success Getting Wallet Metadata");
+                unsafe { *record_handle_p = record_handle; }
 
-        Ok((metadata, handle))
+                trace!("Success Fetching Search Next Record with the search_handle: {}. Record Handle: {}", search_handle, record_handle);
+
+                ErrorCode::Success
+            }
+        }
     }
 
     ///
-    /// Sets wallet metadata.
+    /// Fetches a total count of search result set
     ///
     /// # Arguments
     ///
-    ///  * `metadata` - metadata to be stored
+    ///  * `search_handle` - unique identifier of a search request
+    ///  * `total_count_p` - output param - total count
     ///
     /// # Returns
     ///
@@ -842,34 +4623,35 @@ impl<'a> MySQLStorage<'a> {
     /// # ErrorCodes
     ///
     ///  * `Success` - Execution successful
-    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidState` - Provided search handle does not exist, or parsing of data has gone wrong
     ///
-    pub fn set_metadata(&self, metadata: &str) -> ErrorCode {
-
-        trace!("Setting Wallet Metadata");
+    pub fn get_search_total_count(&self, search_handle: i32, total_count_p: *mut usize) -> ErrorCode {
 
-        check_result!(
-            self.write_pool.prep_exec(
-                "UPDATE wallets SET metadata = :metadata WHERE id = :wallet_id",
-                params! {
-                    "wallet_id" => self.wallet_id,
-                    "metadata" => metadata,
-                }
-            ),
-            ErrorCode::IOError
-        );
+        trace!("Getting Search Total Count -> search_handle: {}", search_handle);
 
-        trace!("Success Setting Wallet Metadata");
+        let search = check_option!(self.searches.get(search_handle), ErrorCode::InvalidState);
 
-        ErrorCode::Success
+        match search.total_count {
+            None => {
+                warn!("Trying to get total count for a search that wasn't meant to retrieve total count, search_handle: {}", search_handle);
+                ErrorCode::InvalidState
+            },
+            Some(total_count) => {
+                unsafe { *total_count_p = total_count };
+                trace!("Success Getting Search Total Count with the search_handle: {}. Total Count: {}", search_handle, total_count);
+                ErrorCode::Success
+            }
+        }
     }
 
     ///
-    /// Removes a metadata handle, thus removing the referenced object from memory.
+    /// Returns the keys that were requested but not found by `fetch_records` or `fetch_records_by_keys`.
     ///
     /// # Arguments
     ///
-    ///  * `metadata_handle` - unique identifier of wallets metadata
+    ///  * `search_handle` - unique identifier of a search request
+    ///  * `missing_keys_p` - output param - json array of `{"type": "...", "id": "..."}` objects,
+    ///     valid until the search is freed
     ///
     /// # Returns
     ///
@@ -878,73 +4660,72 @@ impl<'a> MySQLStorage<'a> {
     /// # ErrorCodes
     ///
     ///  * `Success` - Execution successful
-    ///  * `InvalidState` - Provided metadata handle does not exist
+    ///  * `InvalidState` - Provided search handle does not exist, or it does not belong to a multi-record fetch
     ///
-    pub fn free_metadata(&self, metadata_handle: i32) -> ErrorCode {
+    pub fn get_search_missing_keys(&self, search_handle: i32, missing_keys_p: *mut *const c_char) -> ErrorCode {
 
-        trace!("Freeing Wallet Metadata -> metadata_handle: {}", metadata_handle);
+        trace!("Getting Search Missing Keys -> search_handle: {}", search_handle);
 
-        if self.metadata.remove(metadata_handle) {
-            trace!("Success Freeing Wallet Metadata with the metadata_handle: {}", metadata_handle);
-            ErrorCode::Success
-        }
-        else {
-            warn!("Trying to free non existent Wallet Metadata, metadata_handle: {}", metadata_handle);
-            ErrorCode::InvalidState
+        let search = check_option!(self.searches.get(search_handle), ErrorCode::InvalidState);
+
+        match search.missing_keys {
+            None => {
+                warn!("Trying to get missing keys for a search that is not a multi-record fetch, search_handle: {}", search_handle);
+                ErrorCode::InvalidState
+            },
+            Some(ref missing_keys) => {
+                unsafe { *missing_keys_p = missing_keys.as_ptr() };
+                trace!("Success Getting Search Missing Keys with the search_handle: {}", search_handle);
+                ErrorCode::Success
+            }
         }
     }
 
     ///
-    /// Performs a search defined by the user query and options.
+    /// Helper method that performs a search restricted to the given record types.
     ///
     /// # Arguments
     ///
-    ///  * `type_` - type of the record that we are searching for
+    ///  * `type_filter` - record types that we are searching for
     ///  * `query_json` - query conditions specified in the form of a json
-    ///         {
-    ///             "tagName": "tagValue",
-    ///             $or: {
-    ///                 "tagName2": "tagValue2",
-    ///                 "tagName3": { $gte: 123 },
-    ///             },
-    ///         }
-    ///  * `options_json` - options specifying what attributes ought to be fetched ex.
-    ///         {
-    ///             retireveType: (optional, true by default)
-    ///             retrieveValue: (optional, true by default)
-    ///             retireveTags: (optional, true by default)
-    ///         }
+    ///  * `search_options` - search options
+    ///  * `chunk_options` - limits the fetched records to a single chunk, does not affect the total count
     ///  * `search_handle_p` - output param - handle that will be used for accessing the search result
     ///
     /// # Returns
     ///
     ///  * `ErrorCode`
     ///
-    /// # ErrorCodes
-    ///
-    ///  * `Success` - Execution successful
-    ///  * `IOError` - Unexpected error occurred while communicating with the DB
-    ///  * `InvalidStructure` - Invalid structure of the JSON arguments -> query | options
-    ///
-    pub fn search_records(&self, type_: &str, query_json: &str, options_json: &str, search_handle_p: *mut i32) -> ErrorCode {
+    fn _search_records(&self, type_filter: &query_translator::TypeFilter, query_json: &str, search_options: &SearchOptions, chunk_options: &ChunkOptions, search_handle_p: *mut i32) -> ErrorCode {
 
-        trace!("Searching Records -> type: {}, query_json: {}, options: {}", type_, query_json, options_json);
+        if chunk_options.chunk_size == Some(0) {
+            warn!("Search Error: `chunkSize` must be greater than 0");
+            return ErrorCode::InvalidStructure;
+        }
 
-        let search_options: SearchOptions = check_result!(serde_json::from_str(options_json), ErrorCode::InvalidStructure);
+        let (wql, query_limits) = match self._prepare_search_query(query_json, search_options.max_execution_time) {
+            Ok(prepared) => prepared,
+            Err(err) => return err
+        };
 
-        let wql = check_result!(query_translator::parse_from_json(&query_json), ErrorCode::InvalidStructure);
+        if let Err(err) = query_translator::check_type_filter_limits(type_filter, &query_limits) {
+            return err;
+        }
 
         let total_count = if search_options.retrieve_total_count {
 
             trace!("Searching Records -> retrieve_total_count branch");
 
-            let (query, arguments) = check_result!(query_translator::wql_to_sql_count(self.wallet_id, type_, &wql), ErrorCode::InvalidStructure);
-            let mut result: QueryResult = check_result!(
+            let (query, arguments) = match query_translator::wql_to_sql_count(self.wallet_id, &self.tables.items, type_filter, &wql, &query_limits) {
+                Ok(query) => query,
+                Err(err) => return err
+            };
+            let mut result: QueryResult = check_query!(
                 self.read_pool.prep_exec(query, arguments),
                 ErrorCode::IOError
             );
 
-            let row = check_result!(check_option!(result.next(), ErrorCode::IOError), ErrorCode::IOError);
+            let row = check_query!(check_option!(result.next(), ErrorCode::IOError), ErrorCode::IOError);
             let count: usize = check_option!(row.get(0), ErrorCode::IOError);
 
             Some(count)
@@ -955,9 +4736,12 @@ impl<'a> MySQLStorage<'a> {
 
             trace!("Searching Records -> retrieve_records branch");
 
-            let (query, arguments) = check_result!(query_translator::wql_to_sql(self.wallet_id, type_, &wql, &search_options), ErrorCode::InvalidStructure);
+            let (query, arguments) = match query_translator::wql_to_sql_chunk(self.wallet_id, &self.tables.items, type_filter, &wql, search_options, chunk_options, &query_limits) {
+                Ok(query) => query,
+                Err(err) => return err
+            };
 
-            let search_result: QueryResult = check_result!(
+            let search_result: QueryResult = check_query!(
                 self.read_pool.prep_exec(query, arguments),
                 ErrorCode::IOError
             );
@@ -969,145 +4753,200 @@ impl<'a> MySQLStorage<'a> {
 
         unsafe { *search_handle_p = search_handle; }
 
-        trace!("Success Searching Records with the type: {}, query_json: {}. Search Handle: {}", type_, query_json, search_handle);
+        trace!("Success Searching Records with the type: {:?}, query_json: {}. Search Handle: {}", type_filter, query_json, search_handle);
 
         ErrorCode::Success
     }
 
     ///
-    /// Performs a search that grabs all records of a wallet with all attributes from the DB.
+    /// Helper method that parses a WQL query, checks it against the query limits and optimises it.
     ///
     /// # Arguments
     ///
-    ///  * `search_handle_p` - output param - handle that will be used for accessing the search result
+    ///  * `query_json` - query conditions specified in the form of a json
+    ///  * `max_execution_time` - `maxExecutionTime` search option, overrides the configured one
     ///
     /// # Returns
     ///
-    ///  * `ErrorCode`
+    ///  * `Result<(Operator, QueryLimits), ErrorCode>` - optimised query and the limits that apply to it
     ///
     /// # ErrorCodes
     ///
-    ///  * `Success` - Execution successful
-    ///  * `IOError` - Unexpected error occurred while communicating with the DB
+    ///  * `InvalidStructure` - Invalid structure of the query JSON
+    ///  * `QueryLimitExceeded` - Query exceeds one of the configured query limits
     ///
-    pub fn search_all_records(&self, search_handle_p: *mut i32) -> ErrorCode {
+    fn _prepare_search_query(&self, query_json: &str, max_execution_time: Option<u64>) -> Result<(query_translator::Operator, QueryLimits), ErrorCode> {
+        let wql = check_result!(query_translator::parse_from_json(&query_json), Err(ErrorCode::InvalidStructure));
 
-        trace!("Searching All Records");
+        let mut query_limits = self.query_limits;
+        if max_execution_time.is_some() {
+            query_limits.max_execution_time = max_execution_time;
+        }
 
-         let search_result: QueryResult = check_result!(
-            self.read_pool.prep_exec(
-                "SELECT type, name, value, tags FROM items WHERE wallet_id = :wallet_id",
-                params! {
-                    "wallet_id" => self.wallet_id,
-                }
-            ),
-            ErrorCode::IOError
-        );
+        query_translator::check_limits(&wql, &query_limits)?;
 
-        let search_handle = self.searches.insert(Search::new(Some(search_result), None));
+        Ok((query_translator::optimise(wql), query_limits))
+    }
 
-        unsafe { *search_handle_p = search_handle; }
+    ///
+    /// Helper method that describes a single search statement, optionally running `EXPLAIN FORMAT=JSON` on it.
+    ///
+    fn _explain_query(&self, query: String, arguments: Vec<Value>, arguments_json: &Vec<serde_json::Value>, run_explain: bool) -> Result<serde_json::Value, ErrorCode> {
+        let mut query_json = json!({
+            "sql": query,
+            "arguments": arguments_json
+        });
 
-        trace!("Success Searching All Records. Search Handle: {}", search_handle);
+        if run_explain {
+            let mut result: QueryResult = check_query!(
+                self.read_pool.prep_exec(format!("EXPLAIN FORMAT=JSON {}", query), arguments),
+                Err(ErrorCode::IOError)
+            );
 
-        ErrorCode::Success
-    }
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::IOError)), Err(ErrorCode::IOError));
+            let plan: String = check_option!(row.get(0), Err(ErrorCode::IOError));
 
+            query_json["explain"] = check_result!(serde_json::from_str(&plan), Err(ErrorCode::IOError));
+        }
+
+        Ok(query_json)
+    }
 
     ///
-    /// Fetches a new record from the search result set.
+    /// Helper method that fetches the records with the given keys using a single `IN (...)` query.
     ///
     /// # Arguments
     ///
-    ///  * `search_handle` - unique identifier of a search request
-    ///  * `record_handle_p` - output param - handle that will be used for accessing the record
+    ///  * `keys` - type and id pairs of the records
+    ///  * `options` - fetch options
+    ///  * `retrieve_type` - whether to set the type of fetched records
+    ///  * `search_handle_p` - output param - handle that will be used for accessing the fetched records
     ///
     /// # Returns
     ///
     ///  * `ErrorCode`
     ///
-    /// # ErrorCodes
-    ///
-    ///  * `Success` - Execution successful
-    ///  * `InvalidState` - Provided search handle does not exist, or parsing of data has gone wrong
-    ///  * `IOError` - Unexpected error occurred while communicating with the DB
-    ///  * `ItemNotFound` - Result set exhausted, no more records to fetch
-    ///
-    pub fn fetch_search_next_record(&self, search_handle: i32, record_handle_p: *mut i32) -> ErrorCode {
+    fn _fetch_records(&self, keys: &Vec<RecordKey>, options: &FetchOptions, retrieve_type: bool, search_handle_p: *mut i32) -> ErrorCode {
 
-        trace!("Fetching Search Next Record -> search_handle: {}", search_handle);
+        let mut unique_keys: Vec<&RecordKey> = Vec::new();
 
-        let search = check_option!(self.searches.get(search_handle), ErrorCode::InvalidState);
+        for key in keys {
+            if !unique_keys.iter().any(|k| k.type_ == key.type_ && k.id == key.id) {
+                unique_keys.push(key);
+            }
+        }
 
-        match search.search_result {
-            None => {
-                warn!("Trying to fetch results for a search that wasn't meant to retrieve any records, search_handle: {}", search_handle);
-                ErrorCode::InvalidState
-            },
-            Some(ref search_result) => {
-                let mut search_result = check_result!(search_result.write(), ErrorCode::IOError);
+        if unique_keys.len() > self.query_limits.max_in_values {
+            warn!("Fetch Records Error: {} keys exceed the limit of {}", unique_keys.len(), self.query_limits.max_in_values);
+            return ErrorCode::QueryLimitExceeded;
+        }
 
-                let next_result = check_option!(search_result.next(), ErrorCode::ItemNotFound);
+        let mut records: VecDeque<Record> = VecDeque::new();
+        let mut found_keys: Vec<RecordKey> = Vec::new();
+        let mut stored_records: Vec<(usize, Option<u64>, u8)> = Vec::new();
 
-                let row = check_result!(next_result, ErrorCode::IOError);
+        if !unique_keys.is_empty() {
+            let mut arguments: Vec<Value> = vec![Value::from(self.wallet_id)];
 
-                let record_type: Option<String> = check_option!(row.get(0), ErrorCode::IOError);
-                let record_id: String = check_option!(row.get(1), ErrorCode::IOError);
-                let record_value: Option<Vec<u8>> = check_option!(row.get(2), ErrorCode::IOError);
-                let record_tags: Option<String> = check_option!(row.get(3), ErrorCode::IOError);
+            let single_type = unique_keys.iter().all(|k| k.type_ == unique_keys[0].type_);
 
-                let record = Record::new(
-                    check_result!(CString::new(record_id), ErrorCode::InvalidState),
-                    record_value,
-                    if let Some(record_tags) = record_tags { Some(check_result!(CString::new(record_tags), ErrorCode::InvalidState)) } else { None },
-                    if let Some(record_type) = record_type { Some(check_result!(CString::new(record_type), ErrorCode::InvalidState)) } else { None },
-                );
+            let key_condition = if single_type {
+                arguments.push(Value::from(unique_keys[0].type_.clone()));
+                arguments.extend(unique_keys.iter().map(|k| Value::from(k.id.clone())));
+                format!("type = ? AND name IN ({})", vec!["?"; unique_keys.len()].join(","))
+            } else {
+                for key in unique_keys.iter() {
+                    arguments.push(Value::from(key.type_.clone()));
+                    arguments.push(Value::from(key.id.clone()));
+                }
+                format!("(type, name) IN ({})", vec!["(?, ?)"; unique_keys.len()].join(", "))
+            };
+
+            let query = format!(
+                "SELECT {}type, name, {}, {}, version{} FROM {} WHERE wallet_id = ? AND {}",
+                query_translator::max_execution_time_hint(options.statement_timeout),
+                if options.retrieve_value { "value" } else { "NULL" },
+                if options.retrieve_tags { "tags" } else { "NULL" },
+                if options.retrieve_value { query_translator::STORED_VALUE_COLUMNS } else { "" },
+                self.tables.items,
+                key_condition
+            );
 
-                let record_handle = self.records.insert(record);
+            let result: QueryResult = check_query!(self.read_pool.prep_exec(query, arguments), ErrorCode::IOError);
 
-                unsafe { *record_handle_p = record_handle; }
+            for row in result {
+                let row = check_query!(row, ErrorCode::IOError);
 
-                trace!("Success Fetching Search Next Record with the search_handle: {}. Record Handle: {}", search_handle, record_handle);
+                let (mut record, chunked_item_id, value_encoding) = match Record::from_row(row) {
+                    Ok(row) => row,
+                    Err(err) => return err
+                };
 
-                ErrorCode::Success
+                // the stored values are restored once all the rows are read, see `_read_stored_value`
+                if chunked_item_id.is_some() || value_encoding != compression::RAW {
+                    stored_records.push((records.len(), chunked_item_id, value_encoding));
+                }
+
+                found_keys.push(RecordKey {
+                    type_: check_result!(check_option!(record.type_.clone(), ErrorCode::IOError).into_string(), ErrorCode::InvalidState),
+                    id: check_result!(record.id.clone().into_string(), ErrorCode::InvalidState),
+                });
+
+                if !retrieve_type {
+                    record.type_ = None;
+                }
+
+                records.push_back(record);
+            }
+
+            for (index, chunked_item_id, value_encoding) in stored_records {
+                let record = check_option!(records.remove(index), ErrorCode::InvalidState);
+
+                match self._read_stored_value(record, chunked_item_id, value_encoding) {
+                    Ok(record) => records.insert(index, record),
+                    Err(err) => return err
+                }
             }
         }
+
+        // types and ids are compared case-insensitively, the same way as the DB collation does
+        let missing_keys: Vec<&RecordKey> = unique_keys.into_iter()
+            .filter(|key| !found_keys.iter().any(|k| k.type_.eq_ignore_ascii_case(&key.type_) && k.id.eq_ignore_ascii_case(&key.id)))
+            .collect();
+
+        let missing_keys = check_result!(serde_json::to_string(&missing_keys), ErrorCode::InvalidState);
+        let missing_keys = check_result!(CString::new(missing_keys), ErrorCode::InvalidState);
+
+        let search_handle = self.searches.insert(Search::from_records(records, missing_keys));
+
+        unsafe { *search_handle_p = search_handle; }
+
+        trace!("Success Fetching Records. Search Handle: {}", search_handle);
+
+        ErrorCode::Success
     }
 
     ///
-    /// Fetches a total count of search result set
-    ///
-    /// # Arguments
-    ///
-    ///  * `search_handle` - unique identifier of a search request
-    ///  * `total_count_p` - output param - total count
-    ///
-    /// # Returns
-    ///
-    ///  * `ErrorCode`
-    ///
-    /// # ErrorCodes
+    /// Restores the value of a record that was fetched by a search as it was given to the storage, see `Record::from_row`.
     ///
-    ///  * `Success` - Execution successful
-    ///  * `InvalidState` - Provided search handle does not exist, or parsing of data has gone wrong
+    /// The chunks of a chunked value are read after the row, so a record changed in between fails with `VersionConflict`.
     ///
-    pub fn get_search_total_count(&self, search_handle: i32, total_count_p: *mut usize) -> ErrorCode {
-
-        trace!("Getting Search Total Count -> search_handle: {}", search_handle);
-
-        let search = check_option!(self.searches.get(search_handle), ErrorCode::InvalidState);
+    fn _read_stored_value(&self, mut record: Record, chunked_item_id: Option<u64>, value_encoding: u8) -> Result<Record, ErrorCode> {
+        let parts = match chunked_item_id {
+            Some(item_id) => {
+                let version = check_option!(record.version, Err(ErrorCode::InvalidState));
 
-        match search.total_count {
-            None => {
-                warn!("Trying to get total count for a search that wasn't meant to retrieve total count, search_handle: {}", search_handle);
-                ErrorCode::InvalidState
+                check_option!(value_chunks::read_chunks(&self.read_pool, &self.tables, item_id, version)?, Err(ErrorCode::VersionConflict))
             },
-            Some(total_count) => {
-                unsafe { *total_count_p = total_count };
-                trace!("Success Getting Search Total Count with the search_handle: {}. Total Count: {}", search_handle, total_count);
-                ErrorCode::Success
+            None => match record.value.take() {
+                Some(value) => vec![value],
+                None => return Ok(record),
             }
-        }
+        };
+
+        record.value = Some(value_chunks::decode(parts, value_encoding, self._encryption())?);
+
+        Ok(record)
     }
 
     ///
@@ -1117,6 +4956,7 @@ impl<'a> MySQLStorage<'a> {
     ///
     ///  * `type_` - record type
     ///  * `id` - record id (name)
+    ///  * `expected_version` - if set, the version the record is expected to have
     ///
     /// # Returns
     ///
@@ -1127,15 +4967,16 @@ impl<'a> MySQLStorage<'a> {
     ///  * `Success` - Execution successful
     ///  * `IOError` - Unexpected error occurred while communicating with the DB
     ///  * `ItemNotFound` - Record with provided `type_` and `id` does not exists
+    ///  * `VersionConflict` - Version of the record does not match the expected one
     ///
-    fn _check_if_record_exists(&self, type_: &str, id: &str) -> ErrorCode {
-        let mut result = check_result!(
+    fn _check_if_record_exists(&self, type_: &str, id: &str, expected_version: Option<u64>) -> ErrorCode {
+        let mut result = check_query!(
             self.read_pool.prep_exec(
-                "SELECT 1 \
-                FROM items \
-                WHERE type = :type \
-                    AND name = :name \
-                    AND wallet_id = :wallet_id",
+                format!("SELECT version \
+                        FROM {} \
+                        WHERE type = :type \
+                            AND name = :name \
+                            AND wallet_id = :wallet_id", self.tables.items),
                 params!{
                     "type" => type_,
                     "name" => id,
@@ -1143,8 +4984,81 @@ impl<'a> MySQLStorage<'a> {
                 }),
             ErrorCode::IOError);
 
-        check_result!(check_option!(result.next(), ErrorCode::ItemNotFound), ErrorCode::IOError);
+        let row = check_query!(check_option!(result.next(), ErrorCode::ItemNotFound), ErrorCode::IOError);
+        let version: u64 = check_option!(row.get(0), ErrorCode::IOError);
+
+        if let Some(expected_version) = expected_version {
+            if version != expected_version {
+                warn!("Record with the type: {}, id: {} has the version {}, expected {}", type_, id, version, expected_version);
+                return ErrorCode::VersionConflict;
+            }
+        }
 
         return ErrorCode::Success;
     }
+
+    ///
+    /// Helper method that determines why a statement restricted to a single record and its expected version
+    /// did not match any row.
+    ///
+    /// # Returns
+    ///
+    ///  * `ErrorCode` - `VersionConflict` if the record exists, `ItemNotFound` or `IOError` otherwise
+    ///
+    fn _mismatch_error(&self, type_: &str, id: &str) -> ErrorCode {
+        match self._check_if_record_exists(type_, id, None) {
+            ErrorCode::Success => ErrorCode::VersionConflict,
+            err => err
+        }
+    }
+
+    ///
+    /// Drops a record from the cache, called after every mutation of the record.
+    ///
+    fn _invalidate_record(&self, type_: &str, id: &str) {
+        if let Some(ref cache) = self.cache {
+            cache.invalidate(&CacheKey::Record(type_.to_string(), id.to_string()));
+        }
+    }
+
+    ///
+    /// Invalidates all the cached records of a type, used after the statements that change records matching a query.
+    ///
+    fn _invalidate_type(&self, type_: &str) {
+        if let Some(ref cache) = self.cache {
+            cache.invalidate_matching(|key| match *key {
+                CacheKey::Record(ref record_type, _) => record_type == type_,
+                CacheKey::Metadata => false,
+            });
+        }
+    }
+
+    ///
+    /// Connection for a record mutation, a transaction that journals the changes if the change feed is enabled.
+    ///
+    fn _writer(&self) -> Result<Writer, ErrorCode> {
+        Writer::start(&self.write_pool, &self.tables, self.wallet_id, self.change_feed.enabled)
+    }
+
+    ///
+    /// Connection for a mutation that writes a value, a transaction whenever the value is chunked.
+    ///
+    fn _value_writer(&self, chunked: bool) -> Result<Writer, ErrorCode> {
+        if chunked {
+            Writer::start_transaction(&self.write_pool, &self.tables, self.wallet_id, self.change_feed.enabled)
+        } else {
+            self._writer()
+        }
+    }
+
+    ///
+    /// Value as it is stored, compressed, chunked and encrypted as configured.
+    ///
+    fn _encode_value(&self, type_: &str, value: &[u8]) -> Result<value_chunks::StoredValue, ErrorCode> {
+        value_chunks::encode(type_, value, &self.values, &self.compression, self._encryption())
+    }
+
+    fn _encryption(&self) -> Option<&Encryption> {
+        self.encryption.as_ref().map(|encryption| &**encryption)
+    }
 }
@@ -1,7 +1,9 @@
 use serde_json;
 use mysql::Value;
+use std::collections::HashMap;
 
-use mysql_storage::SearchOptions;
+use mysql_storage::{SearchOptions, ChunkOptions};
+use utils::multi_pool::QueryLimits;
 use errors::error_code::ErrorCode;
 
 #[derive(Debug, Hash, Clone)]
@@ -45,6 +47,257 @@ impl Operator {
             _ => self
         }
     }
+
+    ///
+    /// Structural comparison used for removing duplicate suboperators.
+    /// Order of suboperators and `In` targets is significant.
+    ///
+    fn is_same(&self, other: &Operator) -> bool {
+        match (self, other) {
+            (&Operator::Eq(ref name, ref value), &Operator::Eq(ref other_name, ref other_value))
+            | (&Operator::Neq(ref name, ref value), &Operator::Neq(ref other_name, ref other_value))
+            | (&Operator::Gt(ref name, ref value), &Operator::Gt(ref other_name, ref other_value))
+            | (&Operator::Gte(ref name, ref value), &Operator::Gte(ref other_name, ref other_value))
+            | (&Operator::Lt(ref name, ref value), &Operator::Lt(ref other_name, ref other_value))
+            | (&Operator::Lte(ref name, ref value), &Operator::Lte(ref other_name, ref other_value))
+            | (&Operator::Like(ref name, ref value), &Operator::Like(ref other_name, ref other_value)) => {
+                name == other_name && value == other_value
+            },
+            (&Operator::In(ref name, ref values), &Operator::In(ref other_name, ref other_values)) => {
+                name == other_name && values == other_values
+            },
+            (&Operator::Not(ref operator), &Operator::Not(ref other_operator)) => operator.is_same(other_operator),
+            (&Operator::And(ref operators), &Operator::And(ref other_operators))
+            | (&Operator::Or(ref operators), &Operator::Or(ref other_operators)) => {
+                operators.len() == other_operators.len()
+                    && operators.iter().zip(other_operators.iter()).all(|(a, b)| a.is_same(b))
+            },
+            (_, _) => false
+        }
+    }
+
+    fn is_true(&self) -> bool {
+        match *self {
+            Operator::And(ref suboperators) => suboperators.is_empty(),
+            _ => false
+        }
+    }
+
+    fn is_false(&self) -> bool {
+        match *self {
+            Operator::Or(ref suboperators) => suboperators.is_empty(),
+            _ => false
+        }
+    }
+}
+
+///
+/// Record types a search is restricted to.
+///
+#[derive(Debug, Clone)]
+pub enum TypeFilter {
+    Type(String),
+    Types(Vec<String>),
+    Pattern(String),
+    Any,
+}
+
+impl TypeFilter {
+    pub fn is_single(&self) -> bool {
+        match *self {
+            TypeFilter::Type(_) => true,
+            _ => false
+        }
+    }
+
+    fn to_sql(&self, type_column: &str, arguments: &mut Vec<Value>) -> Option<String> {
+        match *self {
+            TypeFilter::Type(ref type_) => {
+                arguments.push(type_.into());
+                Some(format!("{} = ?", type_column))
+            },
+            TypeFilter::Types(ref types) => {
+                for type_ in types {
+                    arguments.push(type_.into());
+                }
+                Some(format!("{} IN ({})", type_column, vec!["?"; types.len()].join(",")))
+            },
+            TypeFilter::Pattern(ref pattern) => {
+                arguments.push(pattern.into());
+                Some(format!("{} LIKE ?", type_column))
+            },
+            TypeFilter::Any => None
+        }
+    }
+
+    ///
+    /// Number of arguments bound by the filter, all of them are redacted in explain output.
+    ///
+    pub fn arguments_count(&self) -> usize {
+        match *self {
+            TypeFilter::Types(ref types) => types.len(),
+            TypeFilter::Any => 0,
+            _ => 1
+        }
+    }
+}
+
+///
+/// Recursively simplifies a parsed WQL query.
+///
+/// Empty `And` is used as the constant `true` and empty `Or` as the constant `false`.
+///
+///  * nested `And`/`And` and `Or`/`Or` are flattened
+///  * `Eq` and `In` on the same tag under `Or` are merged into a single `In`
+///  * `Not` is pushed through `And`/`Or`, flipping `Eq`/`Neq` and removing double negation
+///  * duplicate suboperators and `In` targets are removed
+///  * contradictions (empty `In`, conflicting `Eq`/`In`/`Neq` on one tag under `And`) fold to `false`
+///    and `false`/`true` are propagated through `And`/`Or`
+///
+pub fn optimise(wql: Operator) -> Operator {
+    match wql {
+        Operator::And(suboperators) => optimise_and(suboperators.into_iter().map(optimise).collect()),
+        Operator::Or(suboperators) => optimise_or(suboperators.into_iter().map(optimise).collect()),
+        Operator::Not(suboperator) => negate(optimise(*suboperator)),
+        Operator::In(key, targets) => optimise_in(key, targets),
+        _ => wql
+    }
+}
+
+fn negate(operator: Operator) -> Operator {
+    match operator {
+        Operator::And(suboperators) => optimise_or(suboperators.into_iter().map(negate).collect()),
+        Operator::Or(suboperators) => optimise_and(suboperators.into_iter().map(negate).collect()),
+        Operator::Not(suboperator) => *suboperator,
+        Operator::Eq(key, value) => Operator::Neq(key, value),
+        Operator::Neq(key, value) => Operator::Eq(key, value),
+        _ => Operator::Not(Box::new(operator))
+    }
+}
+
+fn optimise_in(key: String, targets: Vec<String>) -> Operator {
+    let mut unique_targets: Vec<String> = Vec::new();
+
+    for target in targets.into_iter() {
+        if !unique_targets.contains(&target) {
+            unique_targets.push(target);
+        }
+    }
+
+    if unique_targets.is_empty() {
+        Operator::Or(vec![])
+    } else {
+        Operator::In(key, unique_targets).optimise()
+    }
+}
+
+fn optimise_and(suboperators: Vec<Operator>) -> Operator {
+    let mut operators: Vec<Operator> = Vec::new();
+
+    for suboperator in suboperators.into_iter() {
+        match suboperator {
+            Operator::And(nested) => operators.extend(nested),
+            _ => operators.push(suboperator)
+        }
+    }
+
+    if operators.iter().any(Operator::is_false) || is_contradiction(&operators) {
+        return Operator::Or(vec![]);
+    }
+
+    Operator::And(remove_duplicates(operators)).optimise()
+}
+
+fn optimise_or(suboperators: Vec<Operator>) -> Operator {
+    let mut operators: Vec<Operator> = Vec::new();
+
+    for suboperator in suboperators.into_iter() {
+        match suboperator {
+            Operator::Or(nested) => operators.extend(nested),
+            _ => operators.push(suboperator)
+        }
+    }
+
+    if operators.iter().any(Operator::is_true) {
+        return Operator::And(vec![]);
+    }
+
+    // Merge every `Eq`/`In` on the same tag into one `In`, kept at the position of the first occurrence
+    let mut merged: Vec<Operator> = Vec::new();
+
+    for operator in operators.into_iter() {
+        let (key, targets) = match operator {
+            Operator::Eq(key, value) => (key, vec![value]),
+            Operator::In(key, values) => (key, values),
+            _ => {
+                merged.push(operator);
+                continue;
+            }
+        };
+
+        let position = merged.iter().position(|m| match *m {
+            Operator::Eq(ref k, _) | Operator::In(ref k, _) => *k == key,
+            _ => false
+        });
+
+        match position {
+            Some(position) => {
+                let existing = merged.remove(position);
+                let mut existing_targets = match existing {
+                    Operator::Eq(_, value) => vec![value],
+                    Operator::In(_, values) => values,
+                    _ => unreachable!()
+                };
+                existing_targets.extend(targets);
+                merged.insert(position, optimise_in(key, existing_targets));
+            },
+            None => merged.push(optimise_in(key, targets))
+        }
+    }
+
+    Operator::Or(remove_duplicates(merged)).optimise()
+}
+
+fn remove_duplicates(operators: Vec<Operator>) -> Vec<Operator> {
+    let mut unique: Vec<Operator> = Vec::new();
+
+    for operator in operators.into_iter() {
+        if !unique.iter().any(|u| u.is_same(&operator)) {
+            unique.push(operator);
+        }
+    }
+
+    unique
+}
+
+///
+/// Checks whether the `Eq`, `In` and `Neq` conditions of an `And` leave no possible value for some tag.
+///
+fn is_contradiction(operators: &[Operator]) -> bool {
+    let mut allowed: Vec<(&String, Vec<&String>)> = Vec::new();
+
+    for operator in operators {
+        let (key, targets): (&String, Vec<&String>) = match *operator {
+            Operator::Eq(ref key, ref value) => (key, vec![value]),
+            Operator::In(ref key, ref values) => (key, values.iter().collect()),
+            _ => continue
+        };
+
+        match allowed.iter().position(|&(k, _)| k == key) {
+            Some(position) => allowed[position].1.retain(|v| targets.contains(v)),
+            None => allowed.push((key, targets))
+        }
+    }
+
+    for operator in operators {
+        if let Operator::Neq(ref key, ref value) = *operator {
+            if let Some(position) = allowed.iter().position(|&(k, _)| k == key) {
+                allowed[position].1.retain(|v| *v != value);
+            }
+        }
+    }
+
+    allowed.iter().any(|&(_, ref values)| values.is_empty())
 }
 
 pub fn parse_from_json(json: &str) -> Result<Operator, ErrorCode> {
@@ -128,6 +381,65 @@ fn parse_operator(key: String, value: serde_json::Value) -> Result<Operator, Err
     }
 }
 
+///
+/// Parses the record type filter of a multi type search.
+///
+/// Accepts a single type `"type"`, a non empty list of types `["type1", "type2"]`
+/// or a `LIKE` pattern `{"$like": "type%"}`.
+///
+pub fn parse_type_filter(json: &str) -> Result<TypeFilter, ErrorCode> {
+    let parsed_json = match serde_json::from_str(json) {
+        Ok(value) => value,
+        Err(err) => {
+            trace!("Search Query Translation Error: Could not parse JSON type filter because: {}", err);
+            return Err(ErrorCode::InvalidStructure)
+        }
+    };
+
+    parse_type_filter_value(parsed_json)
+}
+
+///
+/// Same as `parse_type_filter`, for a type filter embedded in other JSON options.
+///
+pub fn parse_type_filter_value(value: serde_json::Value) -> Result<TypeFilter, ErrorCode> {
+    match value {
+        serde_json::Value::String(type_) => Ok(TypeFilter::Type(type_)),
+        serde_json::Value::Array(values) => {
+            let mut types: Vec<String> = Vec::new();
+
+            for value in values.into_iter() {
+                if let serde_json::Value::String(type_) = value {
+                    types.push(type_);
+                } else {
+                    warn!("Search Query Translation Error: Type list must be an array of strings");
+                    return Err(ErrorCode::InvalidStructure);
+                }
+            }
+
+            if types.is_empty() {
+                warn!("Search Query Translation Error: Type list must not be empty");
+                return Err(ErrorCode::InvalidStructure);
+            }
+
+            Ok(TypeFilter::Types(types))
+        },
+        serde_json::Value::Object(mut map) => {
+            match (map.len(), map.remove("$like")) {
+                (1, Some(serde_json::Value::String(pattern))) => Ok(TypeFilter::Pattern(pattern)),
+                (_, _) => {
+                    warn!("Search Query Translation Error: Type pattern must be in the form of {{\"$like\": \"pattern\"}}");
+                    Err(ErrorCode::InvalidStructure)
+                }
+            }
+        },
+        value => {
+            warn!("Search Query Translation Error: Unsupported type filter: {}", value);
+            Err(ErrorCode::InvalidStructure)
+        }
+    }
+}
+
 fn parse_single_operator(operator_name: String, key: String, value: serde_json::Value) -> Result<Operator, ErrorCode> {
     match (&*operator_name, value) {
         ("$neq", serde_json::Value::String(s)) => Ok(Operator::Neq(key, s)),
@@ -157,6 +469,23 @@ fn parse_single_operator(operator_name: String, key: String, value: serde_json::
     }
 }
 
+///
+/// Pseudo-field that matches the record type instead of a tag, e.g. `{"$type": {"$in": ["type1", "type2"]}}`.
+///
+const TYPE_FIELD: &'static str = "$type";
+
+fn tag_to_column(tag_name: &str) -> String {
+    if tag_name == TYPE_FIELD {
+        "type".to_string()
+    } else {
+        format!(r#"JSON_UNQUOTE(JSON_EXTRACT(tags, '$."{}"'))"#, tag_name)
+    }
+}
+
+fn is_plaintext_field(tag_name: &str) -> bool {
+    is_plaintext_tag(tag_name) || tag_name == TYPE_FIELD
+}
+
 fn operator_to_sql(op: &Operator, arguments: &mut Vec<Value>) -> Result<String, ErrorCode> {
     match *op {
         Operator::Eq(ref tag_name, ref target_value) => Ok(eq_to_sql(tag_name, target_value, arguments)),
@@ -175,99 +504,84 @@ fn operator_to_sql(op: &Operator, arguments: &mut Vec<Value>) -> Result<String,
 
 fn eq_to_sql(tag_name: &String, tag_value: &String, arguments: &mut Vec<Value>) -> String {
 
-    let tag_path = format!(r#"'$."{}"'"#, tag_name);
+    let tag_column = tag_to_column(tag_name);
 
     arguments.push(tag_value.into());
-    format!("(JSON_UNQUOTE(JSON_EXTRACT(tags, {})) = ?)", tag_path)
+    format!("({} = ?)", tag_column)
 }
 
 fn neq_to_sql(tag_name: &String, tag_value: &String, arguments: &mut Vec<Value>) -> String {
 
-    let tag_path = format!(r#"'$."{}"'"#, tag_name);
+    let tag_column = tag_to_column(tag_name);
 
     arguments.push(tag_value.into());
-    format!("(JSON_UNQUOTE(JSON_EXTRACT(tags, {})) != ?)", tag_path)
+    format!("({} != ?)", tag_column)
 }
 
 fn gt_to_sql(tag_name: &String, tag_value: &String, arguments: &mut Vec<Value>) -> Result<String, ErrorCode> {
-    match tag_name.chars().next().unwrap_or('\0') {
-        '~' => {
-            let tag_path = format!(r#"'$."{}"'"#, tag_name);
+    if is_plaintext_field(tag_name) {
+        let tag_column = tag_to_column(tag_name);
 
-            arguments.push(tag_value.into());
-            Ok(format!("(JSON_UNQUOTE(JSON_EXTRACT(tags, {})) > ?)", tag_path))
-        },
-        _ => {
-            warn!("Search Query Translation Error: Trying to use `gt` operator with a encrypted tag");
-            Err(ErrorCode::InvalidStructure)
-        }
+        arguments.push(tag_value.into());
+        Ok(format!("({} > ?)", tag_column))
+    } else {
+        warn!("Search Query Translation Error: Trying to use `gt` operator with a encrypted tag");
+        Err(ErrorCode::InvalidStructure)
     }
 }
 
 fn gte_to_sql(tag_name: &String, tag_value: &String, arguments: &mut Vec<Value>) -> Result<String, ErrorCode> {
-    match tag_name.chars().next().unwrap_or('\0') {
-        '~' => {
-            let tag_path = format!(r#"'$."{}"'"#, tag_name);
+    if is_plaintext_field(tag_name) {
+        let tag_column = tag_to_column(tag_name);
 
-            arguments.push(tag_value.into());
-            Ok(format!("(JSON_UNQUOTE(JSON_EXTRACT(tags, {})) >= ?)", tag_path))
-        },
-        _ => {
-            warn!("Search Query Translation Error: Trying to use `gte` operator with a encrypted tag");
-            Err(ErrorCode::InvalidStructure)
-        }
+        arguments.push(tag_value.into());
+        Ok(format!("({} >= ?)", tag_column))
+    } else {
+        warn!("Search Query Translation Error: Trying to use `gte` operator with a encrypted tag");
+        Err(ErrorCode::InvalidStructure)
     }
 }
 
 fn lt_to_sql(tag_name: &String, tag_value: &String, arguments: &mut Vec<Value>) -> Result<String, ErrorCode> {
-    match tag_name.chars().next().unwrap_or('\0') {
-        '~' => {
-            let tag_path = format!(r#"'$."{}"'"#, tag_name);
+    if is_plaintext_field(tag_name) {
+        let tag_column = tag_to_column(tag_name);
 
-            arguments.push(tag_value.into());
-            Ok(format!("JSON_UNQUOTE(JSON_EXTRACT(tags, {})) < ?)", tag_path))
-        },
-        _ => {
-            warn!("Search Query Translation Error: Trying to use `lt` operator with a encrypted tag");
-            Err(ErrorCode::InvalidStructure)
-        }
+        arguments.push(tag_value.into());
+        Ok(format!("({} < ?)", tag_column))
+    } else {
+        warn!("Search Query Translation Error: Trying to use `lt` operator with a encrypted tag");
+        Err(ErrorCode::InvalidStructure)
     }
 }
 
 fn lte_to_sql(tag_name: &String, tag_value: &String, arguments: &mut Vec<Value>) -> Result<String, ErrorCode> {
-    match tag_name.chars().next().unwrap_or('\0') {
-        '~' => {
-            let tag_path = format!(r#"'$."{}"'"#, tag_name);
+    if is_plaintext_field(tag_name) {
+        let tag_column = tag_to_column(tag_name);
 
-            arguments.push(tag_value.into());
-            Ok(format!("(JSON_UNQUOTE(JSON_EXTRACT(tags, {})) <= ?)", tag_path))
-        },
-        _ => {
-            warn!("Search Query Translation Error: Trying to use `lte` operator with a encrypted tag");
-            Err(ErrorCode::InvalidStructure)
-        }
+        arguments.push(tag_value.into());
+        Ok(format!("({} <= ?)", tag_column))
+    } else {
+        warn!("Search Query Translation Error: Trying to use `lte` operator with a encrypted tag");
+        Err(ErrorCode::InvalidStructure)
     }
 }
 
 fn like_to_sql(tag_name: &String, tag_value: &String, arguments: &mut Vec<Value>) -> Result<String, ErrorCode> {
-   match tag_name.chars().next().unwrap_or('\0') {
-        '~' => {
-            let tag_path = format!(r#"'$."{}"'"#, tag_name);
+    if is_plaintext_field(tag_name) {
+        let tag_column = tag_to_column(tag_name);
 
-            arguments.push(tag_value.into());
-            Ok(format!("(JSON_UNQUOTE(JSON_EXTRACT(tags, {})) LIKE ?)", tag_path))
-        },
-        _ => {
-            warn!("Search Query Translation Error: Trying to use `like` operator with a encrypted tag");
-            Err(ErrorCode::InvalidStructure)
-        }
+        arguments.push(tag_value.into());
+        Ok(format!("({} LIKE ?)", tag_column))
+    } else {
+        warn!("Search Query Translation Error: Trying to use `like` operator with a encrypted tag");
+        Err(ErrorCode::InvalidStructure)
     }
 }
 
 fn in_to_sql(tag_name: &String, tag_values: &Vec<String>, arguments: &mut Vec<Value>) -> String {
 
-    let tag_path = format!(r#"'$."{}"'"#, tag_name);
-    let mut in_string = format!("JSON_UNQUOTE(JSON_EXTRACT(tags, {})) IN (", tag_path);
+    let tag_column = tag_to_column(tag_name);
+    let mut in_string = format!("{} IN (", tag_column);
 
     for (index, tag_value) in tag_values.iter().enumerate() {
         in_string.push_str("?");
@@ -289,6 +603,11 @@ fn and_to_sql(suboperators: &[Operator], arguments: &mut Vec<Value>) -> Result<S
 }
 
 fn or_to_sql(suboperators: &[Operator], arguments: &mut Vec<Value>) -> Result<String, ErrorCode> {
+    if suboperators.is_empty() {
+        // empty disjunction is the `false` constant produced by the optimiser
+        return Ok("(FALSE)".to_string());
+    }
+
     join_operators(suboperators, " OR ", arguments)
 }
 
@@ -324,25 +643,163 @@ fn join_operators(operators: &[Operator], join_str: &str, arguments: &mut Vec<Va
     Ok(s)
 }
 
-pub fn wql_to_sql(wallet_id: u64, type_: &str, wql: &Operator, options: &SearchOptions) -> Result<(String, Vec<Value>), ErrorCode> {
+///
+/// Checks the parsed query against the nesting depth, operator count and `$in` cardinality limits.
+///
+pub fn check_limits(wql: &Operator, limits: &QueryLimits) -> Result<(), ErrorCode> {
+    let mut operator_count: usize = 0;
+
+    let depth = check_operator_limits(wql, limits, &mut operator_count)?;
+
+    if depth > limits.max_depth {
+        warn!("Search Query Translation Error: Query nesting depth {} exceeds the limit of {}", depth, limits.max_depth);
+        return Err(ErrorCode::QueryLimitExceeded);
+    }
+
+    if operator_count > limits.max_operators {
+        warn!("Search Query Translation Error: Query has {} operators which exceeds the limit of {}", operator_count, limits.max_operators);
+        return Err(ErrorCode::QueryLimitExceeded);
+    }
+
+    Ok(())
+}
+
+fn check_operator_limits(op: &Operator, limits: &QueryLimits, operator_count: &mut usize) -> Result<usize, ErrorCode> {
+    *operator_count += 1;
+
+    match *op {
+        Operator::And(ref suboperators) | Operator::Or(ref suboperators) => {
+            let mut max_depth: usize = 0;
+
+            for suboperator in suboperators {
+                let depth = check_operator_limits(suboperator, limits, operator_count)?;
+                if depth > max_depth {
+                    max_depth = depth;
+                }
+            }
+
+            Ok(max_depth + 1)
+        },
+        Operator::Not(ref suboperator) => Ok(check_operator_limits(suboperator, limits, operator_count)? + 1),
+        Operator::In(ref tag_name, ref target_values) => {
+            if target_values.len() > limits.max_in_values {
+                warn!("Search Query Translation Error: `$in` for the tag `{}` has {} values which exceeds the limit of {}", tag_name, target_values.len(), limits.max_in_values);
+                return Err(ErrorCode::QueryLimitExceeded);
+            }
+
+            Ok(0)
+        },
+        _ => Ok(0)
+    }
+}
+
+///
+/// Checks the number of types in a type list against the `$in` cardinality limit.
+///
+pub fn check_type_filter_limits(type_filter: &TypeFilter, limits: &QueryLimits) -> Result<(), ErrorCode> {
+    if let TypeFilter::Types(ref types) = *type_filter {
+        if types.len() > limits.max_in_values {
+            warn!("Search Query Translation Error: Type list has {} values which exceeds the limit of {}", types.len(), limits.max_in_values);
+            return Err(ErrorCode::QueryLimitExceeded);
+        }
+    }
+
+    Ok(())
+}
+
+fn check_sql_length(query_string: &str, limits: &QueryLimits) -> Result<(), ErrorCode> {
+    if query_string.len() > limits.max_sql_length {
+        warn!("Search Query Translation Error: Rendered SQL has {} characters which exceeds the limit of {}", query_string.len(), limits.max_sql_length);
+        return Err(ErrorCode::QueryLimitExceeded);
+    }
+
+    Ok(())
+}
+
+///
+/// Optimizer hint bounding the execution time (in milliseconds) of a SELECT statement, empty if there is no bound.
+///
+pub fn max_execution_time_hint(max_execution_time: Option<u64>) -> String {
+    match max_execution_time {
+        Some(max_execution_time) => format!("/*+ MAX_EXECUTION_TIME({}) */ ", max_execution_time),
+        None => "".to_string()
+    }
+}
+
+///
+/// Renders the WQL query and the type filter as a list of conditions to be joined with `AND`.
+///
+fn search_conditions(type_filter: &TypeFilter, wql: &Operator, type_column: &str, arguments: &mut Vec<Value>) -> Result<Vec<String>, ErrorCode> {
+    let query_condition = operator_to_sql(wql, arguments)?;
+
+    let mut conditions: Vec<String> = Vec::new();
+
+    if !query_condition.is_empty() {
+        conditions.push(query_condition);
+    }
+
+    if let Some(type_condition) = type_filter.to_sql(type_column, arguments) {
+        conditions.push(type_condition);
+    }
+
+    Ok(conditions)
+}
+
+///
+/// Id of the record when its value is chunked and the encoding of the value, appended to the fetch queries
+/// that retrieve the value, so the chunks can be read and the value decompressed after the row. See `Record::from_row`.
+///
+pub const STORED_VALUE_COLUMNS: &'static str = ", IF(value_chunks > 0, id, NULL), value_encoding";
+
+///
+/// Translates a WQL query to a fetch query. The type is always fetched when the search spans multiple types.
+///
+pub fn wql_to_sql(wallet_id: u64, items_table: &str, type_filter: &TypeFilter, wql: &Operator, options: &SearchOptions, limits: &QueryLimits) -> Result<(String, Vec<Value>), ErrorCode> {
+    wql_to_sql_chunk(wallet_id, items_table, type_filter, wql, options, &ChunkOptions::default(), limits)
+}
+
+///
+/// Translates a WQL query to a fetch query that reads a single chunk of the result set.
+///
+/// Chunks are walked with keyset pagination over `(type, name)`, which follows the
+/// `ux_items_wallet_id_type_name` index, so every chunk is a range scan no matter how deep into the wallet it is.
+///
+pub fn wql_to_sql_chunk(wallet_id: u64, items_table: &str, type_filter: &TypeFilter, wql: &Operator, options: &SearchOptions, chunk_options: &ChunkOptions, limits: &QueryLimits) -> Result<(String, Vec<Value>), ErrorCode> {
 
-    trace!("Translating WQL to SQL Fetch Query -> type: {}, wql: {:?}, options: {:?}", type_, wql, options);
+    trace!("Translating WQL to SQL Fetch Query -> type: {:?}, wql: {:?}, options: {:?}, chunk_options: {:?}", type_filter, wql, options, chunk_options);
 
     let mut arguments: Vec<Value> = Vec::new();
-    let query_condition = match operator_to_sql(wql, &mut arguments) {
-        Ok(query_condition) => query_condition,
-        Err(err) => return Err(err)
-    };
+    let mut conditions = search_conditions(type_filter, wql, "type", &mut arguments)?;
 
-    let query_string = format!(
-        "SELECT {}, name, {}, {} FROM items WHERE {} type = ? AND wallet_id = ?",
-        if options.retrieve_type { "type" } else {"NULL"},
+    if let Some(ref after) = chunk_options.after {
+        arguments.push((&after.type_).into());
+        arguments.push((&after.id).into());
+        conditions.push("(type, name) > (?, ?)".to_string());
+    }
+
+    conditions.push("wallet_id = ?".to_string());
+
+    let mut query_string = format!(
+        "SELECT {}{}, name, {}, {}, version{} FROM {} WHERE {}",
+        max_execution_time_hint(limits.max_execution_time),
+        if options.retrieve_type || !type_filter.is_single() { "type" } else {"NULL"},
         if options.retrieve_value { "value" } else {"NULL"},
         if options.retrieve_tags { "tags" } else {"NULL"},
-        if !query_condition.is_empty() {query_condition + " AND"} else {"".to_string()}
+        if options.retrieve_value { STORED_VALUE_COLUMNS } else {""},
+        items_table,
+        conditions.join(" AND ")
     );
 
-    arguments.push(type_.into());
+    if chunk_options.is_chunked() {
+        query_string.push_str(" ORDER BY type, name");
+    }
+
+    if let Some(chunk_size) = chunk_options.chunk_size {
+        query_string.push_str(&format!(" LIMIT {}", chunk_size));
+    }
+
+    check_sql_length(&query_string, limits)?;
+
     arguments.push(wallet_id.into());
 
     trace!("Success Translating WQL: {:?} to SQL Fetch Query -> query: {}, args: {:?}", wql, query_string, arguments);
@@ -350,22 +807,24 @@ pub fn wql_to_sql(wallet_id: u64, type_: &str, wql: &Operator, options: &SearchO
     Ok((query_string, arguments))
 }
 
-pub fn wql_to_sql_count(wallet_id: u64, type_: &str, wql: &Operator) -> Result<(String, Vec<Value>), ErrorCode> {
+pub fn wql_to_sql_count(wallet_id: u64, items_table: &str, type_filter: &TypeFilter, wql: &Operator, limits: &QueryLimits) -> Result<(String, Vec<Value>), ErrorCode> {
 
-    trace!("Translating WQL to SQL Count Query -> type: {}, wql: {:?}", type_, wql);
+    trace!("Translating WQL to SQL Count Query -> type: {:?}, wql: {:?}", type_filter, wql);
 
     let mut arguments: Vec<Value> = Vec::new();
-    let query_condition = match operator_to_sql(wql, &mut arguments) {
-        Ok(query_condition) => query_condition,
-        Err(err) => return Err(err)
-    };
+    let mut conditions = search_conditions(type_filter, wql, "i.type", &mut arguments)?;
+
+    conditions.push("i.wallet_id = ?".to_string());
 
     let query_string = format!(
-        "SELECT count(*) FROM items i WHERE {} i.type = ? AND i.wallet_id = ?",
-        if !query_condition.is_empty() {query_condition + " AND"} else {"".to_string()}
+        "SELECT {}count(*) FROM {} i WHERE {}",
+        max_execution_time_hint(limits.max_execution_time),
+        items_table,
+        conditions.join(" AND ")
     );
 
-    arguments.push(type_.into());
+    check_sql_length(&query_string, limits)?;
+
     arguments.push(wallet_id.into());
 
     trace!("Success Translating WQL: {:?} to SQL Count Query -> query: {}, args: {:?}", wql, query_string, arguments);
@@ -373,57 +832,333 @@ pub fn wql_to_sql_count(wallet_id: u64, type_: &str, wql: &Operator) -> Result<(
     Ok((query_string, arguments))
 }
 
-#[cfg(test)]
-mod tests {
-    extern crate rand;
+///
+/// Translates a WQL query to a query that groups the matching records by the type or a plaintext tag.
+///
+/// Records without the grouped tag are skipped. Buckets are ordered by the count, largest first,
+/// and distinct values are ordered by the value.
+///
+pub fn wql_to_sql_aggregate(wallet_id: u64, items_table: &str, type_filter: &TypeFilter, wql: &Operator, group_by: &str, distinct: bool, limit: Option<u64>, limits: &QueryLimits) -> Result<(String, Vec<Value>), ErrorCode> {
 
-    use super::*;
-    use self::rand::{thread_rng, Rng};
-    use std::hash::Hash;
-    use std::collections::HashSet;
+    trace!("Translating WQL to SQL Aggregate Query -> type: {:?}, wql: {:?}, group_by: {}, distinct: {}, limit: {:?}", type_filter, wql, group_by, distinct, limit);
 
-    fn random_string(len: usize) -> String {
-        thread_rng().gen_ascii_chars().take(len).collect()
+    if !is_plaintext_field(group_by) {
+        warn!("Search Query Translation Error: Trying to group by a encrypted tag");
+        return Err(ErrorCode::InvalidStructure);
     }
 
-    fn vec_to_set<T>(vec: &Vec<T>) -> HashSet<T> where T: Eq + Hash + Clone {
-        let mut result: HashSet<T> = HashSet::new();
-        for x in vec {
-            result.insert((*x).clone());
-        }
-        result
-    }
+    let group_column = tag_to_column(group_by);
 
-    impl PartialEq for Operator {
-        fn eq(&self, other: &Operator) -> bool {
-            match (self, other) {
-                (Operator::Eq(name, value), Operator::Eq(other_name, other_value))
-                | (Operator::Neq(name, value), Operator::Neq(other_name, other_value))
-                | (Operator::Gt(name, value), Operator::Gt(other_name, other_value))
-                | (Operator::Gte(name, value), Operator::Gte(other_name, other_value))
-                | (Operator::Lt(name, value), Operator::Lt(other_name, other_value))
-                | (Operator::Lte(name, value), Operator::Lte(other_name, other_value))
-                | (Operator::Like(name, value), Operator::Like(other_name, other_value)) => {
-                    name == other_name && value == other_value
-                },
-                (Operator::In(name, values), Operator::In(other_name, other_values)) => {
-                    name == other_name && vec_to_set(values) == vec_to_set(other_values)
-                },
-                (Operator::Not(operator), Operator::Not(other_operator)) => operator == other_operator,
-                (Operator::And(operators), Operator::And(other_operators))
-                | (Operator::Or(operators), Operator::Or(other_operators)) => {
-                    vec_to_set(operators) == vec_to_set(other_operators)
-                },
-                (_, _) => false
-            }
-        }
+    let mut arguments: Vec<Value> = Vec::new();
+    let mut conditions = search_conditions(type_filter, wql, "type", &mut arguments)?;
+
+    conditions.push(format!("{} IS NOT NULL", group_column));
+    conditions.push("wallet_id = ?".to_string());
+
+    let mut query_string = format!(
+        "SELECT {}{}{} FROM {} WHERE {} GROUP BY 1 ORDER BY {}",
+        max_execution_time_hint(limits.max_execution_time),
+        group_column,
+        if distinct { "" } else { ", count(*)" },
+        items_table,
+        conditions.join(" AND "),
+        if distinct { "1" } else { "2 DESC, 1" }
+    );
+
+    if let Some(limit) = limit {
+        query_string.push_str(&format!(" LIMIT {}", limit));
     }
 
-    impl Eq for Operator {}
+    check_sql_length(&query_string, limits)?;
 
-    #[test]
-    fn test_simple_operator_empty_json_parse() {
-        let json = "{}";
+    arguments.push(wallet_id.into());
+
+    trace!("Success Translating WQL: {:?} to SQL Aggregate Query -> query: {}, args: {:?}", wql, query_string, arguments);
+
+    Ok((query_string, arguments))
+}
+
+///
+/// Translates a WQL query to a statement that deletes all the matching records.
+///
+pub fn wql_to_sql_delete(wallet_id: u64, items_table: &str, type_filter: &TypeFilter, wql: &Operator, limits: &QueryLimits) -> Result<(String, Vec<Value>), ErrorCode> {
+
+    trace!("Translating WQL to SQL Delete Query -> type: {:?}, wql: {:?}", type_filter, wql);
+
+    let mut arguments: Vec<Value> = Vec::new();
+    let mut conditions = search_conditions(type_filter, wql, "type", &mut arguments)?;
+
+    conditions.push("wallet_id = ?".to_string());
+
+    let query_string = format!("DELETE FROM {} WHERE {}", items_table, conditions.join(" AND "));
+
+    check_sql_length(&query_string, limits)?;
+
+    arguments.push(wallet_id.into());
+
+    trace!("Success Translating WQL: {:?} to SQL Delete Query -> query: {}, args: {:?}", wql, query_string, arguments);
+
+    Ok((query_string, arguments))
+}
+
+///
+/// Renders the new value of the `tags` column, tags from `tag_names_to_remove` are removed first,
+/// then tags from `tags_to_set` are added or replaced.
+///
+fn tags_update_expression(tags_to_set: &HashMap<String, String>, tag_names_to_remove: &Vec<String>, arguments: &mut Vec<Value>) -> String {
+    let mut tags_expression = "tags".to_string();
+
+    if !tag_names_to_remove.is_empty() {
+        let tag_name_paths: Vec<String> = tag_names_to_remove.iter().map(|tag_name| format!(r#"'$."{}"'"#, tag_name)).collect();
+        tags_expression = format!("JSON_REMOVE({}, {})", tags_expression, tag_name_paths.join(", "));
+    }
+
+    if !tags_to_set.is_empty() {
+        let mut tag_name_value_paths: Vec<String> = Vec::new();
+
+        for (tag_name, tag_value) in tags_to_set {
+            tag_name_value_paths.push(format!(r#"'$."{}"', ?"#, tag_name));
+            arguments.push(tag_value.into());
+        }
+
+        tags_expression = format!("JSON_SET({}, {})", tags_expression, tag_name_value_paths.join(", "));
+    }
+
+    tags_expression
+}
+
+///
+/// Translates a WQL query to a statement that updates the tags of all the matching records.
+///
+/// Tags from `tag_names_to_remove` are removed first, then tags from `tags_to_set` are added or replaced.
+///
+pub fn wql_to_sql_update_tags(wallet_id: u64, items_table: &str, type_filter: &TypeFilter, wql: &Operator, tags_to_set: &HashMap<String, String>, tag_names_to_remove: &Vec<String>, limits: &QueryLimits) -> Result<(String, Vec<Value>), ErrorCode> {
+
+    trace!("Translating WQL to SQL Update Tags Query -> type: {:?}, wql: {:?}, tags_to_set: {:?}, tag_names_to_remove: {:?}", type_filter, wql, tags_to_set, tag_names_to_remove);
+
+    let mut arguments: Vec<Value> = Vec::new();
+    let tags_expression = tags_update_expression(tags_to_set, tag_names_to_remove, &mut arguments);
+
+    let mut conditions = search_conditions(type_filter, wql, "type", &mut arguments)?;
+
+    conditions.push("wallet_id = ?".to_string());
+
+    let query_string = format!("UPDATE {} SET tags = {}, version = version + 1 WHERE {}", items_table, tags_expression, conditions.join(" AND "));
+
+    check_sql_length(&query_string, limits)?;
+
+    arguments.push(wallet_id.into());
+
+    trace!("Success Translating WQL: {:?} to SQL Update Tags Query -> query: {}, args: {:?}", wql, query_string, arguments);
+
+    Ok((query_string, arguments))
+}
+
+///
+/// Translates an update of a single record to a statement that is executed only if the record matches the WQL condition.
+///
+/// The value is replaced if given, together with its number of chunks and its encoding, the tags are updated the same way
+/// as by `wql_to_sql_update_tags` and the version is always incremented.
+///
+pub fn wql_to_sql_update_record(wallet_id: u64, items_table: &str, type_: &str, id: &str, value: Option<(&[u8], u32, u8)>, tags_to_set: &HashMap<String, String>, tag_names_to_remove: &Vec<String>, condition: &Operator, limits: &QueryLimits) -> Result<(String, Vec<Value>), ErrorCode> {
+
+    trace!("Translating WQL to SQL Update Record Query -> type: {}, id: {}, tags_to_set: {:?}, tag_names_to_remove: {:?}, condition: {:?}", type_, id, tags_to_set, tag_names_to_remove, condition);
+
+    let mut arguments: Vec<Value> = Vec::new();
+    let mut assignments: Vec<String> = Vec::new();
+
+    if let Some((value, value_chunks, value_encoding)) = value {
+        assignments.push("value = ?".to_string());
+        arguments.push(value.into());
+        assignments.push("value_chunks = ?".to_string());
+        arguments.push(value_chunks.into());
+        assignments.push("value_encoding = ?".to_string());
+        arguments.push(value_encoding.into());
+    }
+
+    if !tags_to_set.is_empty() || !tag_names_to_remove.is_empty() {
+        assignments.push(format!("tags = {}", tags_update_expression(tags_to_set, tag_names_to_remove, &mut arguments)));
+    }
+
+    assignments.push("version = version + 1".to_string());
+
+    let mut conditions = search_conditions(&TypeFilter::Type(type_.to_string()), condition, "type", &mut arguments)?;
+
+    conditions.push("name = ?".to_string());
+    conditions.push("wallet_id = ?".to_string());
+
+    let query_string = format!("UPDATE {} SET {} WHERE {}", items_table, assignments.join(", "), conditions.join(" AND "));
+
+    check_sql_length(&query_string, limits)?;
+
+    arguments.push(id.into());
+    arguments.push(wallet_id.into());
+
+    trace!("Success Translating WQL: {:?} to SQL Update Record Query -> query: {}, args: {:?}", condition, query_string, arguments);
+
+    Ok((query_string, arguments))
+}
+
+///
+/// Translates a WQL query to a statement that journals a change of every matching record in the `changes_table`.
+///
+/// The changes get consecutive sequence numbers following `last_seq`. The journaled version is the version
+/// of the record incremented by `version_increment`, or NULL without one, e.g. for deleted records.
+///
+pub fn wql_to_sql_log_changes(wallet_id: u64, items_table: &str, changes_table: &str, type_filter: &TypeFilter, wql: &Operator, operation: &str, version_increment: Option<u64>, last_seq: u64, limits: &QueryLimits) -> Result<(String, Vec<Value>), ErrorCode> {
+
+    trace!("Translating WQL to SQL Log Changes Query -> type: {:?}, wql: {:?}, operation: {}, version_increment: {:?}", type_filter, wql, operation, version_increment);
+
+    let mut arguments: Vec<Value> = vec![operation.into()];
+
+    let version_expression = match version_increment {
+        Some(version_increment) => {
+            arguments.push(version_increment.into());
+            "version + ?"
+        },
+        None => "NULL"
+    };
+
+    arguments.push(last_seq.into());
+
+    let mut conditions = search_conditions(type_filter, wql, "type", &mut arguments)?;
+
+    conditions.push("wallet_id = ?".to_string());
+
+    let query_string = format!(
+        "INSERT INTO {} (wallet_id, seq, type, name, operation, version) \
+         SELECT wallet_id, @change_seq := @change_seq + 1, type, name, ?, {} \
+         FROM {}, (SELECT @change_seq := ?) change_seq \
+         WHERE {}",
+        changes_table, version_expression, items_table, conditions.join(" AND ")
+    );
+
+    check_sql_length(&query_string, limits)?;
+
+    arguments.push(wallet_id.into());
+
+    trace!("Success Translating WQL: {:?} to SQL Log Changes Query -> query: {}, args: {:?}", wql, query_string, arguments);
+
+    Ok((query_string, arguments))
+}
+
+const REDACTED: &'static str = "<redacted>";
+
+fn is_plaintext_tag(tag_name: &str) -> bool {
+    tag_name.starts_with('~')
+}
+
+fn tag_value_to_json(tag_name: &str, tag_value: &str) -> serde_json::Value {
+    if is_plaintext_tag(tag_name) {
+        serde_json::Value::String(tag_value.to_string())
+    } else {
+        serde_json::Value::String(REDACTED.to_string())
+    }
+}
+
+///
+/// Renders an operator tree back to its WQL JSON form, with values of encrypted tags redacted.
+///
+pub fn wql_to_json(wql: &Operator) -> serde_json::Value {
+    match *wql {
+        Operator::And(ref suboperators) => json!({"$and": suboperators.iter().map(wql_to_json).collect::<Vec<_>>()}),
+        Operator::Or(ref suboperators) => json!({"$or": suboperators.iter().map(wql_to_json).collect::<Vec<_>>()}),
+        Operator::Not(ref suboperator) => json!({"$not": wql_to_json(suboperator)}),
+        Operator::Eq(ref tag_name, ref tag_value) => json!({tag_name.clone(): tag_value_to_json(tag_name, tag_value)}),
+        Operator::Neq(ref tag_name, ref tag_value) => json!({tag_name.clone(): {"$neq": tag_value_to_json(tag_name, tag_value)}}),
+        Operator::Gt(ref tag_name, ref tag_value) => json!({tag_name.clone(): {"$gt": tag_value_to_json(tag_name, tag_value)}}),
+        Operator::Gte(ref tag_name, ref tag_value) => json!({tag_name.clone(): {"$gte": tag_value_to_json(tag_name, tag_value)}}),
+        Operator::Lt(ref tag_name, ref tag_value) => json!({tag_name.clone(): {"$lt": tag_value_to_json(tag_name, tag_value)}}),
+        Operator::Lte(ref tag_name, ref tag_value) => json!({tag_name.clone(): {"$lte": tag_value_to_json(tag_name, tag_value)}}),
+        Operator::Like(ref tag_name, ref tag_value) => json!({tag_name.clone(): {"$like": tag_value_to_json(tag_name, tag_value)}}),
+        Operator::In(ref tag_name, ref tag_values) => json!({tag_name.clone(): {"$in": tag_values.iter().map(|v| tag_value_to_json(tag_name, v)).collect::<Vec<_>>()}}),
+    }
+}
+
+///
+/// Lists the WQL condition arguments in the same order as they are bound by `wql_to_sql`,
+/// with values of encrypted tags redacted.
+///
+pub fn wql_to_redacted_arguments(wql: &Operator) -> Vec<serde_json::Value> {
+    let mut arguments: Vec<serde_json::Value> = Vec::new();
+    collect_redacted_arguments(wql, &mut arguments);
+    arguments
+}
+
+fn collect_redacted_arguments(op: &Operator, arguments: &mut Vec<serde_json::Value>) {
+    match *op {
+        Operator::And(ref suboperators) | Operator::Or(ref suboperators) => {
+            for suboperator in suboperators {
+                collect_redacted_arguments(suboperator, arguments);
+            }
+        },
+        Operator::Not(ref suboperator) => collect_redacted_arguments(suboperator, arguments),
+        Operator::Eq(ref tag_name, ref tag_value)
+        | Operator::Neq(ref tag_name, ref tag_value)
+        | Operator::Gt(ref tag_name, ref tag_value)
+        | Operator::Gte(ref tag_name, ref tag_value)
+        | Operator::Lt(ref tag_name, ref tag_value)
+        | Operator::Lte(ref tag_name, ref tag_value)
+        | Operator::Like(ref tag_name, ref tag_value) => arguments.push(tag_value_to_json(tag_name, tag_value)),
+        Operator::In(ref tag_name, ref tag_values) => {
+            for tag_value in tag_values {
+                arguments.push(tag_value_to_json(tag_name, tag_value));
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::*;
+    use self::rand::{thread_rng, Rng};
+    use std::hash::Hash;
+    use std::collections::HashSet;
+
+    fn random_string(len: usize) -> String {
+        thread_rng().gen_ascii_chars().take(len).collect()
+    }
+
+    fn vec_to_set<T>(vec: &Vec<T>) -> HashSet<T> where T: Eq + Hash + Clone {
+        let mut result: HashSet<T> = HashSet::new();
+        for x in vec {
+            result.insert((*x).clone());
+        }
+        result
+    }
+
+    impl PartialEq for Operator {
+        fn eq(&self, other: &Operator) -> bool {
+            match (self, other) {
+                (Operator::Eq(name, value), Operator::Eq(other_name, other_value))
+                | (Operator::Neq(name, value), Operator::Neq(other_name, other_value))
+                | (Operator::Gt(name, value), Operator::Gt(other_name, other_value))
+                | (Operator::Gte(name, value), Operator::Gte(other_name, other_value))
+                | (Operator::Lt(name, value), Operator::Lt(other_name, other_value))
+                | (Operator::Lte(name, value), Operator::Lte(other_name, other_value))
+                | (Operator::Like(name, value), Operator::Like(other_name, other_value)) => {
+                    name == other_name && value == other_value
+                },
+                (Operator::In(name, values), Operator::In(other_name, other_values)) => {
+                    name == other_name && vec_to_set(values) == vec_to_set(other_values)
+                },
+                (Operator::Not(operator), Operator::Not(other_operator)) => operator == other_operator,
+                (Operator::And(operators), Operator::And(other_operators))
+                | (Operator::Or(operators), Operator::Or(other_operators)) => {
+                    vec_to_set(operators) == vec_to_set(other_operators)
+                },
+                (_, _) => false
+            }
+        }
+    }
+
+    impl Eq for Operator {}
+
+    #[test]
+    fn test_simple_operator_empty_json_parse() {
+        let json = "{}";
 
         let query = parse_from_json(json).unwrap();
 
@@ -1818,4 +2553,712 @@ mod tests {
 
         assert_eq!(query, expected);
     }
+    #[test]
+    fn test_optimise_flattens_nested_and() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+        let value2 = random_string(10);
+        let name3 = random_string(10);
+        let value3 = random_string(10);
+
+        let json = format!(r#"{{"$and":[{{"{}":"{}"}},{{"$and":[{{"{}":"{}"}},{{"$and":[{{"{}":"{}"}}]}}]}}]}}"#,
+                           name1, value1,
+                           name2, value2,
+                           name3, value3,
+        );
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        let expected = Operator::And(
+            vec![
+                Operator::Eq(name1, value1),
+                Operator::Eq(name2, value2),
+                Operator::Eq(name3, value3)
+            ]
+        );
+
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn test_optimise_flattens_nested_or() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+        let value2 = random_string(10);
+        let name3 = random_string(10);
+        let value3 = random_string(10);
+
+        let json = format!(r#"{{"$or":[{{"{}":{{"$gt":"{}"}}}},{{"$or":[{{"{}":{{"$lt":"{}"}}}},{{"$or":[{{"{}":{{"$like":"{}"}}}}]}}]}}]}}"#,
+                           name1, value1,
+                           name2, value2,
+                           name3, value3,
+        );
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        let expected = Operator::Or(
+            vec![
+                Operator::Gt(name1, value1),
+                Operator::Lt(name2, value2),
+                Operator::Like(name3, value3)
+            ]
+        );
+
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn test_optimise_merges_eq_under_or_into_in() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let value2 = random_string(10);
+        let value3 = random_string(10);
+        let name2 = random_string(10);
+        let value4 = random_string(10);
+
+        let json = format!(r#"{{"$or":[{{"{}":"{}"}},{{"{}":"{}"}},{{"{}":"{}"}},{{"{}":{{"$in":["{}","{}"]}}}}]}}"#,
+                           name1, value1,
+                           name2, value4,
+                           name1, value2,
+                           name1, value2, value3,
+        );
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        let expected = Operator::Or(
+            vec![
+                Operator::In(name1, vec![value1, value2, value3]),
+                Operator::Eq(name2, value4)
+            ]
+        );
+
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn test_optimise_pushes_not_through_and() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+        let value2 = random_string(10);
+        let name3 = random_string(10);
+        let value3 = random_string(10);
+
+        let json = format!(r#"{{"$not":{{"$and":[{{"{}":"{}"}},{{"{}":{{"$neq":"{}"}}}},{{"{}":{{"$gt":"{}"}}}}]}}}}"#,
+                           name1, value1,
+                           name2, value2,
+                           name3, value3,
+        );
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        let expected = Operator::Or(
+            vec![
+                Operator::Neq(name1, value1),
+                Operator::Eq(name2, value2),
+                Operator::Not(
+                    Box::new(
+                        Operator::Gt(name3, value3)
+                    )
+                )
+            ]
+        );
+
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn test_optimise_pushes_not_through_or() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+        let value2 = random_string(10);
+
+        let json = format!(r#"{{"$not":{{"$or":[{{"{}":"{}"}},{{"$not":{{"{}":{{"$like":"{}"}}}}}}]}}}}"#,
+                           name1, value1,
+                           name2, value2,
+        );
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        let expected = Operator::And(
+            vec![
+                Operator::Neq(name1, value1),
+                Operator::Like(name2, value2)
+            ]
+        );
+
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn test_optimise_not_of_merged_neq_becomes_in() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let value2 = random_string(10);
+
+        let json = format!(r#"{{"$not":{{"$and":[{{"{}":{{"$neq":"{}"}}}},{{"{}":{{"$neq":"{}"}}}}]}}}}"#,
+                           name1, value1,
+                           name1, value2,
+        );
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        let expected = Operator::In(name1, vec![value1, value2]);
+
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn test_optimise_removes_duplicates() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+        let value2 = random_string(10);
+        let name3 = random_string(10);
+        let value3 = random_string(10);
+
+        let json = format!(r#"{{"$and":[{{"{}":"{}"}},{{"{}":"{}"}},{{"{}":{{"$like":"{}"}}}},{{"{}":{{"$like":"{}"}}}},{{"{}":{{"$in":["{}","{}"]}}}}]}}"#,
+                           name1, value1,
+                           name1, value1,
+                           name2, value2,
+                           name2, value2,
+                           name3, value3, value3,
+        );
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        let expected = Operator::And(
+            vec![
+                Operator::Eq(name1, value1),
+                Operator::Like(name2, value2),
+                Operator::Eq(name3, value3)
+            ]
+        );
+
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn test_optimise_empty_in_under_and_is_false() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+
+        let json = format!(r#"{{"$and":[{{"{}":"{}"}},{{"{}":{{"$in":[]}}}}]}}"#, name1, value1, name2);
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        assert_eq!(query, Operator::Or(vec![]));
+    }
+
+    #[test]
+    fn test_optimise_empty_in_under_or_is_dropped() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+
+        let json = format!(r#"{{"$or":[{{"{}":"{}"}},{{"{}":{{"$in":[]}}}}]}}"#, name1, value1, name2);
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        assert_eq!(query, Operator::Eq(name1, value1));
+    }
+
+    #[test]
+    fn test_optimise_conflicting_eq_is_false() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let value2 = random_string(10);
+
+        let json = format!(r#"{{"$and":[{{"{}":"{}"}},{{"{}":"{}"}}]}}"#, name1, value1, name1, value2);
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        assert_eq!(query, Operator::Or(vec![]));
+    }
+
+    #[test]
+    fn test_optimise_eq_outside_in_is_false() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let value2 = random_string(10);
+        let value3 = random_string(10);
+
+        let json = format!(r#"{{"$and":[{{"{}":"{}"}},{{"{}":{{"$in":["{}","{}"]}}}}]}}"#, name1, value1, name1, value2, value3);
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        assert_eq!(query, Operator::Or(vec![]));
+    }
+
+    #[test]
+    fn test_optimise_eq_and_neq_same_value_is_false() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+
+        let json = format!(r#"{{"$and":[{{"{}":"{}"}},{{"{}":{{"$neq":"{}"}}}}]}}"#, name1, value1, name1, value1);
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        assert_eq!(query, Operator::Or(vec![]));
+    }
+
+    #[test]
+    fn test_optimise_empty_and_under_or_is_true() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+
+        let json = format!(r#"{{"$or":[{{"{}":"{}"}},{{"$and":[]}}]}}"#, name1, value1);
+
+        let query = optimise(parse_from_json(&json).unwrap());
+
+        assert_eq!(query, Operator::And(vec![]));
+    }
+
+    #[test]
+    fn test_optimise_not_of_empty_query_is_false() {
+        let json = r#"{"$not":{}}"#;
+
+        let query = optimise(parse_from_json(json).unwrap());
+
+        assert_eq!(query, Operator::Or(vec![]));
+    }
+
+    #[test]
+    fn test_false_to_sql() {
+        let options: SearchOptions = serde_json::from_str("{}").unwrap();
+
+        let (query, arguments) = wql_to_sql(1, "items", &TypeFilter::Type("type".to_string()), &Operator::Or(vec![]), &options, &QueryLimits::default()).unwrap();
+
+        assert!(query.contains("WHERE (FALSE) AND type = ?"));
+        assert_eq!(arguments.len(), 2);
+    }
+
+    #[test]
+    fn test_check_limits_within_limits() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+        let value2 = random_string(10);
+
+        let json = format!(r#"{{"$or":[{{"{}":"{}"}},{{"$not":{{"{}":{{"$in":["{}"]}}}}}}]}}"#, name1, value1, name2, value2);
+
+        let query = parse_from_json(&json).unwrap();
+
+        assert!(check_limits(&query, &QueryLimits::default()).is_ok());
+    }
+
+    #[test]
+    fn test_check_limits_max_depth_exceeded() {
+        let mut json = format!(r#"{{"{}":"{}"}}"#, random_string(10), random_string(10));
+        for _ in 0..5 {
+            json = format!(r#"{{"$not":{}}}"#, json);
+        }
+
+        let query = parse_from_json(&json).unwrap();
+
+        let mut limits = QueryLimits::default();
+        limits.max_depth = 4;
+
+        assert_eq!(check_limits(&query, &limits).unwrap_err(), ErrorCode::QueryLimitExceeded);
+
+        limits.max_depth = 5;
+
+        assert!(check_limits(&query, &limits).is_ok());
+    }
+
+    #[test]
+    fn test_check_limits_max_operators_exceeded() {
+        let json = format!(r#"{{"$or":[{{"{}":"{}"}},{{"{}":"{}"}},{{"{}":"{}"}}]}}"#,
+                           random_string(10), random_string(10),
+                           random_string(10), random_string(10),
+                           random_string(10), random_string(10),
+        );
+
+        let query = parse_from_json(&json).unwrap();
+
+        let mut limits = QueryLimits::default();
+        limits.max_operators = 3;
+
+        assert_eq!(check_limits(&query, &limits).unwrap_err(), ErrorCode::QueryLimitExceeded);
+
+        limits.max_operators = 4;
+
+        assert!(check_limits(&query, &limits).is_ok());
+    }
+
+    #[test]
+    fn test_check_limits_max_in_values_exceeded() {
+        let name1 = random_string(10);
+        let values: Vec<String> = (0..11).map(|_| format!(r#""{}""#, random_string(10))).collect();
+
+        let json = format!(r#"{{"$not":{{"{}":{{"$in":[{}]}}}}}}"#, name1, values.join(","));
+
+        let query = parse_from_json(&json).unwrap();
+
+        let mut limits = QueryLimits::default();
+        limits.max_in_values = 10;
+
+        assert_eq!(check_limits(&query, &limits).unwrap_err(), ErrorCode::QueryLimitExceeded);
+    }
+
+    #[test]
+    fn test_wql_to_sql_max_sql_length_exceeded() {
+        let options: SearchOptions = serde_json::from_str("{}").unwrap();
+        let query = Operator::Eq(random_string(100), random_string(10));
+
+        let mut limits = QueryLimits::default();
+        limits.max_sql_length = 100;
+
+        assert_eq!(wql_to_sql(1, "items", &TypeFilter::Type("type".to_string()), &query, &options, &limits).unwrap_err(), ErrorCode::QueryLimitExceeded);
+        assert_eq!(wql_to_sql_count(1, "items", &TypeFilter::Type("type".to_string()), &query, &limits).unwrap_err(), ErrorCode::QueryLimitExceeded);
+    }
+
+    #[test]
+    fn test_wql_to_sql_max_execution_time_hint() {
+        let options: SearchOptions = serde_json::from_str("{}").unwrap();
+        let query = Operator::And(vec![]);
+
+        let mut limits = QueryLimits::default();
+
+        let (sql, _) = wql_to_sql(1, "items", &TypeFilter::Type("type".to_string()), &query, &options, &limits).unwrap();
+        assert!(!sql.contains("MAX_EXECUTION_TIME"));
+
+        limits.max_execution_time = Some(1500);
+
+        let (sql, _) = wql_to_sql(1, "items", &TypeFilter::Type("type".to_string()), &query, &options, &limits).unwrap();
+        assert!(sql.starts_with("SELECT /*+ MAX_EXECUTION_TIME(1500) */ NULL, name"));
+
+        let (sql, _) = wql_to_sql_count(1, "items", &TypeFilter::Type("type".to_string()), &query, &limits).unwrap();
+        assert!(sql.starts_with("SELECT /*+ MAX_EXECUTION_TIME(1500) */ count(*)"));
+    }
+
+    #[test]
+    fn test_search_options_statement_timeout() {
+        let options: SearchOptions = serde_json::from_str(r#"{"statementTimeout": 250}"#).unwrap();
+        assert_eq!(options.max_execution_time, Some(250));
+
+        assert_eq!(max_execution_time_hint(options.max_execution_time), "/*+ MAX_EXECUTION_TIME(250) */ ");
+        assert_eq!(max_execution_time_hint(None), "");
+    }
+
+    #[test]
+    fn test_wql_to_json_redacts_encrypted_values() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = format!("~{}", random_string(10));
+        let value2 = random_string(10);
+        let value3 = random_string(10);
+
+        let query = Operator::Or(
+            vec![
+                Operator::Eq(name1.clone(), value1),
+                Operator::Not(
+                    Box::new(
+                        Operator::In(name2.clone(), vec![value2.clone(), value3.clone()])
+                    )
+                )
+            ]
+        );
+
+        let expected = json!({
+            "$or": [
+                {name1: "<redacted>"},
+                {"$not": {name2: {"$in": [value2, value3]}}}
+            ]
+        });
+
+        assert_eq!(wql_to_json(&query), expected);
+    }
+
+    #[test]
+    fn test_wql_to_redacted_arguments_follow_binding_order() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = format!("~{}", random_string(10));
+        let value2 = random_string(10);
+        let value3 = random_string(10);
+        let value4 = random_string(10);
+
+        let query = Operator::And(
+            vec![
+                Operator::Gt(name2.clone(), value2.clone()),
+                Operator::Eq(name1.clone(), value1),
+                Operator::In(name2.clone(), vec![value3.clone(), value4.clone()])
+            ]
+        );
+
+        let options: SearchOptions = serde_json::from_str("{}").unwrap();
+        let (_, arguments) = wql_to_sql(1, "items", &TypeFilter::Type("type".to_string()), &query, &options, &QueryLimits::default()).unwrap();
+        let redacted_arguments = wql_to_redacted_arguments(&query);
+
+        assert_eq!(redacted_arguments, vec![json!(value2), json!("<redacted>"), json!(value3), json!(value4)]);
+        assert_eq!(arguments.len(), redacted_arguments.len() + 2);
+    }
+
+    #[test]
+    fn test_parse_type_filter() {
+        let type1 = random_string(10);
+        let type2 = random_string(10);
+
+        match parse_type_filter(&format!(r#""{}""#, type1)).unwrap() {
+            TypeFilter::Type(type_) => assert_eq!(type_, type1),
+            type_filter => panic!("Unexpected type filter: {:?}", type_filter)
+        }
+
+        match parse_type_filter(&format!(r#"["{}", "{}"]"#, type1, type2)).unwrap() {
+            TypeFilter::Types(types) => assert_eq!(types, vec![type1.clone(), type2.clone()]),
+            type_filter => panic!("Unexpected type filter: {:?}", type_filter)
+        }
+
+        match parse_type_filter(&format!(r#"{{"$like": "{}%"}}"#, type1)).unwrap() {
+            TypeFilter::Pattern(pattern) => assert_eq!(pattern, format!("{}%", type1)),
+            type_filter => panic!("Unexpected type filter: {:?}", type_filter)
+        }
+    }
+
+    #[test]
+    fn test_parse_type_filter_invalid() {
+        assert_eq!(parse_type_filter("[]").unwrap_err(), ErrorCode::InvalidStructure);
+        assert_eq!(parse_type_filter(r#"["type", 1]"#).unwrap_err(), ErrorCode::InvalidStructure);
+        assert_eq!(parse_type_filter(r#"{"$in": ["type"]}"#).unwrap_err(), ErrorCode::InvalidStructure);
+        assert_eq!(parse_type_filter(r#"{"$like": "type%", "$neq": "type"}"#).unwrap_err(), ErrorCode::InvalidStructure);
+        assert_eq!(parse_type_filter("1").unwrap_err(), ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_wql_to_sql_type_list() {
+        let options: SearchOptions = serde_json::from_str("{}").unwrap();
+        let type1 = random_string(10);
+        let type2 = random_string(10);
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+
+        let type_filter = TypeFilter::Types(vec![type1.clone(), type2.clone()]);
+        let query = Operator::Eq(name1, value1.clone());
+
+        let (sql, arguments) = wql_to_sql(1, "items", &type_filter, &query, &options, &QueryLimits::default()).unwrap();
+
+        assert!(sql.starts_with("SELECT type, name"));
+        assert!(sql.contains("AND type IN (?,?) AND wallet_id = ?"));
+        assert_eq!(arguments, vec![Value::from(value1), Value::from(type1), Value::from(type2), Value::from(1u64)]);
+
+        let (sql, arguments) = wql_to_sql_count(1, "items", &type_filter, &query, &QueryLimits::default()).unwrap();
+
+        assert!(sql.contains("AND i.type IN (?,?) AND i.wallet_id = ?"));
+        assert_eq!(arguments.len(), 4);
+    }
+
+    #[test]
+    fn test_wql_to_sql_type_pattern() {
+        let options: SearchOptions = serde_json::from_str("{}").unwrap();
+        let pattern = format!("{}%", random_string(10));
+
+        let (sql, arguments) = wql_to_sql(1, "items", &TypeFilter::Pattern(pattern.clone()), &Operator::And(vec![]), &options, &QueryLimits::default()).unwrap();
+
+        assert!(sql.starts_with("SELECT type, name"));
+        assert!(sql.contains("type LIKE ? AND wallet_id = ?"));
+        assert_eq!(arguments, vec![Value::from(pattern), Value::from(1u64)]);
+    }
+
+    #[test]
+    fn test_type_field_to_sql() {
+        let type1 = random_string(10);
+        let type2 = random_string(10);
+        let pattern = format!("{}%", random_string(10));
+
+        let json = format!(r#"{{"$or":[{{"$type":{{"$in":["{}","{}"]}}}},{{"$type":{{"$like":"{}"}}}}]}}"#, type1, type2, pattern);
+        let query = parse_from_json(&json).unwrap();
+
+        let mut arguments: Vec<Value> = Vec::new();
+        let sql = operator_to_sql(&query, &mut arguments).unwrap();
+
+        assert_eq!(sql, "(type IN (?,?) OR (type LIKE ?))");
+        assert_eq!(arguments, vec![Value::from(type1), Value::from(type2), Value::from(pattern)]);
+    }
+
+    #[test]
+    fn test_type_field_is_redacted() {
+        let query = Operator::Eq(TYPE_FIELD.to_string(), random_string(10));
+
+        assert_eq!(wql_to_json(&query), json!({"$type": "<redacted>"}));
+        assert_eq!(wql_to_redacted_arguments(&query), vec![json!("<redacted>")]);
+    }
+
+    #[test]
+    fn test_check_type_filter_limits() {
+        let types: Vec<String> = (0..11).map(|_| random_string(10)).collect();
+
+        let mut limits = QueryLimits::default();
+        limits.max_in_values = 10;
+
+        assert_eq!(check_type_filter_limits(&TypeFilter::Types(types), &limits).unwrap_err(), ErrorCode::QueryLimitExceeded);
+        assert!(check_type_filter_limits(&TypeFilter::Pattern("%".to_string()), &limits).is_ok());
+    }
+
+    #[test]
+    fn test_wql_to_sql_any_type() {
+        let options: SearchOptions = serde_json::from_str(r#"{"retrieveValue": false}"#).unwrap();
+
+        let (sql, arguments) = wql_to_sql(1, "items", &TypeFilter::Any, &Operator::And(vec![]), &options, &QueryLimits::default()).unwrap();
+
+        assert_eq!(sql, "SELECT type, name, NULL, NULL, version FROM items WHERE wallet_id = ?");
+        assert_eq!(arguments, vec![Value::from(1u64)]);
+
+        let (sql, _) = wql_to_sql_count(1, "items", &TypeFilter::Any, &Operator::And(vec![]), &QueryLimits::default()).unwrap();
+
+        assert_eq!(sql, "SELECT count(*) FROM items i WHERE i.wallet_id = ?");
+    }
+
+    #[test]
+    fn test_wql_to_sql_chunk() {
+        let options: SearchOptions = serde_json::from_str("{}").unwrap();
+        let name1 = format!("~{}", random_string(10));
+        let value1 = random_string(10);
+        let type1 = random_string(10);
+        let id1 = random_string(10);
+
+        let chunk_options: ChunkOptions = serde_json::from_str(&format!(r#"{{"chunkSize": 10, "after": {{"type": "{}", "id": "{}"}}}}"#, type1, id1)).unwrap();
+        let query = Operator::Eq(name1, value1.clone());
+
+        let (sql, arguments) = wql_to_sql_chunk(1, "items", &TypeFilter::Any, &query, &options, &chunk_options, &QueryLimits::default()).unwrap();
+
+        assert!(sql.ends_with(" AND (type, name) > (?, ?) AND wallet_id = ? ORDER BY type, name LIMIT 10"));
+        assert_eq!(arguments, vec![Value::from(value1), Value::from(type1), Value::from(id1), Value::from(1u64)]);
+    }
+
+    #[test]
+    fn test_wql_to_sql_chunk_first() {
+        let options: SearchOptions = serde_json::from_str("{}").unwrap();
+
+        let chunk_options: ChunkOptions = serde_json::from_str(r#"{"chunkSize": 10}"#).unwrap();
+
+        let (sql, arguments) = wql_to_sql_chunk(1, "items", &TypeFilter::Any, &Operator::And(vec![]), &options, &chunk_options, &QueryLimits::default()).unwrap();
+
+        assert!(sql.ends_with(" WHERE wallet_id = ? ORDER BY type, name LIMIT 10"));
+        assert_eq!(arguments.len(), 1);
+    }
+
+    #[test]
+    fn test_wql_to_sql_aggregate_by_type() {
+        let name1 = format!("~{}", random_string(10));
+        let value1 = random_string(10);
+
+        let query = Operator::Eq(name1, value1.clone());
+
+        let (sql, arguments) = wql_to_sql_aggregate(1, "items", &TypeFilter::Any, &query, "$type", false, Some(10), &QueryLimits::default()).unwrap();
+
+        assert!(sql.starts_with("SELECT type, count(*) FROM items WHERE "));
+        assert!(sql.ends_with(" AND type IS NOT NULL AND wallet_id = ? GROUP BY 1 ORDER BY 2 DESC, 1 LIMIT 10"));
+        assert_eq!(arguments, vec![Value::from(value1), Value::from(1u64)]);
+    }
+
+    #[test]
+    fn test_wql_to_sql_aggregate_distinct_tag_values() {
+        let name1 = format!("~{}", random_string(10));
+        let type1 = random_string(10);
+
+        let (sql, arguments) = wql_to_sql_aggregate(1, "items", &TypeFilter::Type(type1.clone()), &Operator::And(vec![]), &name1, true, None, &QueryLimits::default()).unwrap();
+
+        let tag_column = format!(r#"JSON_UNQUOTE(JSON_EXTRACT(tags, '$."{}"'))"#, name1);
+
+        assert_eq!(sql, format!("SELECT {} FROM items WHERE type = ? AND {} IS NOT NULL AND wallet_id = ? GROUP BY 1 ORDER BY 1", tag_column, tag_column));
+        assert_eq!(arguments, vec![Value::from(type1), Value::from(1u64)]);
+    }
+
+    #[test]
+    fn test_wql_to_sql_aggregate_by_encrypted_tag() {
+        let name1 = random_string(10);
+
+        assert_eq!(wql_to_sql_aggregate(1, "items", &TypeFilter::Any, &Operator::And(vec![]), &name1, false, None, &QueryLimits::default()).unwrap_err(), ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_wql_to_sql_delete() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let type1 = random_string(10);
+
+        let query = Operator::Eq(name1.clone(), value1.clone());
+
+        let (sql, arguments) = wql_to_sql_delete(1, "items", &TypeFilter::Type(type1.clone()), &query, &QueryLimits::default()).unwrap();
+
+        assert_eq!(sql, format!(r#"DELETE FROM items WHERE (JSON_UNQUOTE(JSON_EXTRACT(tags, '$."{}"')) = ?) AND type = ? AND wallet_id = ?"#, name1));
+        assert_eq!(arguments, vec![Value::from(value1), Value::from(type1), Value::from(1u64)]);
+    }
+
+    #[test]
+    fn test_wql_to_sql_log_changes() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let type1 = random_string(10);
+
+        let query = Operator::Eq(name1.clone(), value1.clone());
+
+        let (sql, arguments) = wql_to_sql_log_changes(1, "items", "item_changes", &TypeFilter::Type(type1.clone()), &query, "update", Some(1), 5, &QueryLimits::default()).unwrap();
+
+        assert_eq!(sql, format!(r#"INSERT INTO item_changes (wallet_id, seq, type, name, operation, version) SELECT wallet_id, @change_seq := @change_seq + 1, type, name, ?, version + ? FROM items, (SELECT @change_seq := ?) change_seq WHERE (JSON_UNQUOTE(JSON_EXTRACT(tags, '$."{}"')) = ?) AND type = ? AND wallet_id = ?"#, name1));
+        assert_eq!(arguments, vec![Value::from("update"), Value::from(1u64), Value::from(5u64), Value::from(value1), Value::from(type1), Value::from(1u64)]);
+
+        let (sql, arguments) = wql_to_sql_log_changes(1, "items", "item_changes", &TypeFilter::Any, &Operator::And(vec![]), "delete", None, 0, &QueryLimits::default()).unwrap();
+
+        assert_eq!(sql, "INSERT INTO item_changes (wallet_id, seq, type, name, operation, version) SELECT wallet_id, @change_seq := @change_seq + 1, type, name, ?, NULL FROM items, (SELECT @change_seq := ?) change_seq WHERE wallet_id = ?");
+        assert_eq!(arguments, vec![Value::from("delete"), Value::from(0u64), Value::from(1u64)]);
+    }
+
+    #[test]
+    fn test_wql_to_sql_update_record() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+        let value2 = random_string(10);
+        let type1 = random_string(10);
+        let id1 = random_string(10);
+        let record_value = vec![1u8, 2, 3];
+
+        let condition = Operator::Eq(name1.clone(), value1.clone());
+
+        let mut tags_to_set: HashMap<String, String> = HashMap::new();
+        tags_to_set.insert(name2.clone(), value2.clone());
+
+        let (sql, arguments) = wql_to_sql_update_record(1, "items", &type1, &id1, Some((&record_value, 0, 0)), &tags_to_set, &vec![], &condition, &QueryLimits::default()).unwrap();
+
+        assert_eq!(sql, format!(r#"UPDATE items SET value = ?, value_chunks = ?, value_encoding = ?, tags = JSON_SET(tags, '$."{}"', ?), version = version + 1 WHERE (JSON_UNQUOTE(JSON_EXTRACT(tags, '$."{}"')) = ?) AND type = ? AND name = ? AND wallet_id = ?"#, name2, name1));
+        assert_eq!(arguments, vec![Value::from(record_value), Value::from(0u32), Value::from(0u8), Value::from(value2), Value::from(value1), Value::from(type1), Value::from(id1), Value::from(1u64)]);
+    }
+
+    #[test]
+    fn test_wql_to_sql_update_record_without_condition() {
+        let type1 = random_string(10);
+        let id1 = random_string(10);
+
+        let (sql, arguments) = wql_to_sql_update_record(1, "items", &type1, &id1, None, &HashMap::new(), &vec![], &Operator::And(vec![]), &QueryLimits::default()).unwrap();
+
+        assert_eq!(sql, "UPDATE items SET version = version + 1 WHERE type = ? AND name = ? AND wallet_id = ?");
+        assert_eq!(arguments, vec![Value::from(type1), Value::from(id1), Value::from(1u64)]);
+    }
+
+    #[test]
+    fn test_wql_to_sql_update_tags() {
+        let name1 = random_string(10);
+        let value1 = random_string(10);
+        let name2 = random_string(10);
+        let value2 = random_string(10);
+        let name3 = random_string(10);
+        let type1 = random_string(10);
+
+        let query = Operator::Eq(name1.clone(), value1.clone());
+
+        let mut tags_to_set: HashMap<String, String> = HashMap::new();
+        tags_to_set.insert(name2.clone(), value2.clone());
+
+        let (sql, arguments) = wql_to_sql_update_tags(1, "items", &TypeFilter::Type(type1.clone()), &query, &tags_to_set, &vec![name3.clone()], &QueryLimits::default()).unwrap();
+
+        assert!(sql.starts_with(&format!(r#"UPDATE items SET tags = JSON_SET(JSON_REMOVE(tags, '$."{}"'), '$."{}"', ?), version = version + 1 WHERE "#, name3, name2)));
+        assert!(sql.ends_with("AND type = ? AND wallet_id = ?"));
+        assert_eq!(arguments, vec![Value::from(value2), Value::from(value1), Value::from(type1), Value::from(1u64)]);
+    }
 }
\ No newline at end of file
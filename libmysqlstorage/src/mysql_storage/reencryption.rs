@@ -0,0 +1,292 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use mysql::Pool;
+use base64;
+
+use mysql_storage::Tables;
+use mysql_storage::encryption::{self, Encryption};
+use errors::error_code::ErrorCode;
+
+const REENCRYPTION_BATCH_SIZE: usize = 500;
+
+///
+/// Progress of a re-encryption job, returned as JSON by `MySQLStorage::get_reencryption_status`.
+///
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReencryptionStatus {
+    pub running: bool,
+    /// version of the data key the data is re-encrypted with
+    pub key_version: u32,
+    pub scanned_records: u64,
+    pub reencrypted_records: u64,
+    /// error code the job stopped with
+    pub error: Option<i32>,
+}
+
+///
+/// Background job encrypting the metadata and all the record values of a wallet with its current data key,
+/// the data stored before the encryption was enabled included.
+///
+/// Every value part, the inline value or a chunk, is re-encrypted on its own, so the values keep their chunks
+/// and compression. The records read the same before and after, so their versions are kept and no changes
+/// are journaled, and a streaming read going on meanwhile decrypts the chunks with either key.
+///
+pub struct ReencryptionJob {
+    pool: Arc<Pool>,
+    tables: Tables,
+    wallet_id: u64,
+    encryption: Arc<Encryption>,
+    status: Arc<Mutex<ReencryptionStatus>>,
+}
+
+impl ReencryptionJob {
+    ///
+    /// Starts the job on its own thread.
+    ///
+    /// # Returns
+    ///
+    ///  * `Arc<Mutex<ReencryptionStatus>>` - status updated by the job as it goes
+    ///
+    pub fn start(pool: Arc<Pool>, tables: &Tables, wallet_id: u64, encryption: Arc<Encryption>) -> Result<Arc<Mutex<ReencryptionStatus>>, ErrorCode> {
+        let key_version = encryption.current_version()?;
+        let status = Arc::new(Mutex::new(ReencryptionStatus { running: true, key_version, ..Default::default() }));
+
+        let job = ReencryptionJob { pool, tables: tables.clone(), wallet_id, encryption, status: status.clone() };
+
+        check_result!(
+            thread::Builder::new().name(format!("reencryption-{}", wallet_id)).spawn(move || job.run(key_version)),
+            Err(ErrorCode::InvalidState)
+        );
+
+        Ok(status)
+    }
+
+    fn run(&self, key_version: u32) {
+        let result = self._reencrypt_metadata(key_version).and_then(|_| self._reencrypt_records(key_version));
+
+        if let Err(ref err) = result {
+            warn!("Re-encryption of the wallet {} stopped. Err: {:?}", self.wallet_id, err);
+        }
+
+        if let Ok(mut status) = self.status.lock() {
+            status.running = false;
+            status.error = result.err().map(|err| err as i32);
+        }
+    }
+
+    fn _reencrypt_metadata(&self, key_version: u32) -> Result<(), ErrorCode> {
+        let mut transaction = check_query!(self.pool.start_transaction(false, None, None), Err(ErrorCode::IOError));
+
+        let (metadata, metadata_encoding): (String, u8) = {
+            let mut result = check_query!(
+                transaction.prep_exec(
+                    format!("SELECT metadata, metadata_encoding FROM {} WHERE id = :wallet_id FOR UPDATE", self.tables.wallets),
+                    params!{
+                        "wallet_id" => self.wallet_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::WalletNotFound)), Err(ErrorCode::IOError));
+
+            (check_option!(row.get(0), Err(ErrorCode::IOError)), check_option!(row.get(1), Err(ErrorCode::IOError)))
+        };
+
+        if metadata_encoding & encryption::ENCRYPTED != 0 {
+            let envelope = check_result!(base64::decode(&metadata), Err(ErrorCode::DecryptionFailed));
+
+            if encryption::envelope_version(&envelope) == Some(key_version) {
+                return Ok(());
+            }
+        }
+
+        let metadata = encryption::decode_metadata(Some(&*self.encryption), metadata, metadata_encoding)?;
+
+        check_query!(
+            transaction.prep_exec(
+                format!("UPDATE {} SET metadata = :metadata, metadata_encoding = :metadata_encoding WHERE id = :wallet_id", self.tables.wallets),
+                params!{
+                    "metadata" => self.encryption.encrypt_metadata(&metadata)?,
+                    "metadata_encoding" => encryption::ENCRYPTED,
+                    "wallet_id" => self.wallet_id
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        check_query!(transaction.commit(), Err(ErrorCode::IOError));
+
+        Ok(())
+    }
+
+    ///
+    /// Scans the records by id in batches, reading only the key version header of every value.
+    ///
+    fn _reencrypt_records(&self, key_version: u32) -> Result<(), ErrorCode> {
+        let mut last_id: u64 = 0;
+
+        loop {
+            let result = check_query!(
+                self.pool.prep_exec(
+                    format!("SELECT i.id, i.value_encoding, \
+                                IF(i.value_chunks > 0, (SELECT SUBSTRING(c.data, 1, 4) FROM {} c WHERE c.item_id = i.id AND c.seq = 0), SUBSTRING(i.value, 1, 4)) \
+                             FROM {} i WHERE i.wallet_id = :wallet_id AND i.id > :last_id ORDER BY i.id LIMIT {}",
+                            self.tables.value_chunks, self.tables.items, REENCRYPTION_BATCH_SIZE),
+                    params!{
+                        "wallet_id" => self.wallet_id,
+                        "last_id" => last_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            let mut stale_ids: Vec<u64> = Vec::new();
+            let mut scanned_records: u64 = 0;
+
+            for row in result {
+                let row = check_query!(row, Err(ErrorCode::IOError));
+
+                last_id = check_option!(row.get(0), Err(ErrorCode::IOError));
+                let value_encoding: u8 = check_option!(row.get(1), Err(ErrorCode::IOError));
+                let header: Option<Vec<u8>> = check_option!(row.get(2), Err(ErrorCode::IOError));
+
+                scanned_records += 1;
+
+                if !is_current(value_encoding, header.as_ref().map(|header| &header[..]), key_version) {
+                    stale_ids.push(last_id);
+                }
+            }
+
+            if scanned_records == 0 {
+                return Ok(());
+            }
+
+            let mut reencrypted_records: u64 = 0;
+
+            for item_id in stale_ids {
+                if self._reencrypt_record(item_id, key_version)? {
+                    reencrypted_records += 1;
+                }
+            }
+
+            let mut status = check_result!(self.status.lock(), Err(ErrorCode::InvalidState));
+            status.scanned_records += scanned_records;
+            status.reencrypted_records += reencrypted_records;
+        }
+    }
+
+    ///
+    /// Re-encrypts the value of a record with the record locked, false if the record was deleted
+    /// or rewritten with the current key in the meantime.
+    ///
+    fn _reencrypt_record(&self, item_id: u64, key_version: u32) -> Result<bool, ErrorCode> {
+        let mut transaction = check_query!(self.pool.start_transaction(false, None, None), Err(ErrorCode::IOError));
+
+        let (value, value_chunks, value_encoding): (Vec<u8>, u32, u8) = {
+            let mut result = check_query!(
+                transaction.prep_exec(
+                    format!("SELECT value, value_chunks, value_encoding FROM {} WHERE id = :item_id FOR UPDATE", self.tables.items),
+                    params!{
+                        item_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            let row = match result.next() {
+                None => return Ok(false),
+                Some(row) => check_query!(row, Err(ErrorCode::IOError)),
+            };
+
+            (check_option!(row.get(0), Err(ErrorCode::IOError)), check_option!(row.get(1), Err(ErrorCode::IOError)), check_option!(row.get(2), Err(ErrorCode::IOError)))
+        };
+
+        let parts: Vec<Vec<u8>> = if value_chunks > 0 {
+            let result = check_query!(
+                transaction.prep_exec(
+                    format!("SELECT data FROM {} WHERE item_id = :item_id ORDER BY seq", self.tables.value_chunks),
+                    params!{
+                        item_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            let mut chunks = Vec::new();
+
+            for row in result {
+                let row = check_query!(row, Err(ErrorCode::IOError));
+                chunks.push(check_option!(row.get(0), Err(ErrorCode::IOError)));
+            }
+
+            chunks
+        } else {
+            vec![value]
+        };
+
+        if is_current(value_encoding, parts.first().map(|part| &part[..]), key_version) {
+            return Ok(false);
+        }
+
+        let mut reencrypted_parts = Vec::new();
+
+        for part in parts {
+            let part = if value_encoding & encryption::ENCRYPTED != 0 { self.encryption.decrypt(&part, encryption::VALUE)? } else { part };
+            reencrypted_parts.push(self.encryption.encrypt(&part, encryption::VALUE)?);
+        }
+
+        if value_chunks > 0 {
+            for (seq, data) in reencrypted_parts.into_iter().enumerate() {
+                check_query!(
+                    transaction.prep_exec(
+                        format!("UPDATE {} SET data = :data WHERE item_id = :item_id AND seq = :seq", self.tables.value_chunks),
+                        params!{
+                            "data" => data,
+                            "item_id" => item_id,
+                            "seq" => seq as u32
+                        }
+                    ),
+                    Err(ErrorCode::IOError)
+                );
+            }
+
+            check_query!(
+                transaction.prep_exec(
+                    format!("UPDATE {} SET value_encoding = :value_encoding WHERE id = :item_id", self.tables.items),
+                    params!{
+                        "value_encoding" => value_encoding | encryption::ENCRYPTED,
+                        "item_id" => item_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+        } else {
+            let value = check_option!(reencrypted_parts.pop(), Err(ErrorCode::InvalidState));
+
+            check_query!(
+                transaction.prep_exec(
+                    format!("UPDATE {} SET value = :value, value_encoding = :value_encoding WHERE id = :item_id", self.tables.items),
+                    params!{
+                        "value" => value,
+                        "value_encoding" => value_encoding | encryption::ENCRYPTED,
+                        "item_id" => item_id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+        }
+
+        check_query!(transaction.commit(), Err(ErrorCode::IOError));
+
+        Ok(true)
+    }
+}
+
+///
+/// Whether a value is encrypted with the given data key version, judging by the header of its first part.
+///
+fn is_current(value_encoding: u8, header: Option<&[u8]>, key_version: u32) -> bool {
+    value_encoding & encryption::ENCRYPTED != 0 && header.and_then(encryption::envelope_version) == Some(key_version)
+}
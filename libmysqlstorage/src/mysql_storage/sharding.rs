@@ -0,0 +1,397 @@
+use mysql::Error;
+use std::collections::BTreeMap;
+
+use mysql_storage::{CONNECTIONS, Tables};
+use utils::multi_pool::{StorageConfig, StorageCredentials, ShardPlacement};
+use errors::error_code::ErrorCode;
+
+/// points of every shard on the hash ring, more points give a more even spread of the wallets
+const VIRTUAL_NODES: u32 = 128;
+
+///
+/// 64 bit FNV-1a followed by the murmur3 finalizer, stable across platforms and compiler releases
+/// unlike the std hasher, since the placement of a wallet must never change.
+///
+fn hash(data: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+
+    for byte in data {
+        h ^= *byte as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51afd7ed558ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+    h ^= h >> 33;
+
+    h
+}
+
+///
+/// Consistent hashing ring over the shard names, adding or removing a shard only moves
+/// the wallets placed on the points of that shard.
+///
+pub struct HashRing {
+    ring: BTreeMap<u64, usize>,
+}
+
+impl HashRing {
+    pub fn new(shard_names: &Vec<&str>) -> Self {
+        let mut ring = BTreeMap::new();
+
+        for (index, shard_name) in shard_names.iter().enumerate() {
+            for node in 0..VIRTUAL_NODES {
+                ring.insert(hash(format!("{}#{}", shard_name, node).as_bytes()), index);
+            }
+        }
+
+        HashRing { ring }
+    }
+
+    ///
+    /// Index of the shard owning the first point at or after the hash of the name.
+    ///
+    pub fn get(&self, name: &str) -> Option<usize> {
+        let point = hash(name.as_bytes());
+
+        self.ring.range(point..).next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, index)| *index)
+    }
+}
+
+///
+/// Shard a wallet is routed to, `name` is None when the config lists no shards.
+///
+pub struct Shard<'c> {
+    pub name: Option<&'c str>,
+    pub config: StorageConfig<'c>,
+}
+
+fn is_sharded(config: &StorageConfig) -> bool {
+    !config.shards.is_empty()
+}
+
+///
+/// Shard with the given index in the config.
+///
+pub fn shard<'c>(config: &StorageConfig<'c>, index: usize) -> Shard<'c> {
+    Shard {
+        name: Some(config.shards[index].name),
+        config: config.for_shard(&config.shards[index]),
+    }
+}
+
+fn unsharded<'c>(config: &StorageConfig<'c>) -> Shard<'c> {
+    Shard { name: None, config: config.clone() }
+}
+
+///
+/// Index of the shard with the given name.
+///
+pub fn shard_index(config: &StorageConfig, shard_name: &str) -> Option<usize> {
+    config.shards.iter().position(|shard| shard.name == shard_name)
+}
+
+///
+/// Index of the shard a wallet is placed on by consistent hashing.
+///
+pub fn hashed_shard_index(config: &StorageConfig, name: &str) -> usize {
+    let shard_names = config.shards.iter().map(|shard| shard.name).collect();
+
+    HashRing::new(&shard_names).get(name).unwrap_or(0)
+}
+
+///
+/// Every shard of the config, or the config itself if it lists no shards.
+///
+pub fn all_shards<'c>(config: &StorageConfig<'c>) -> Vec<Shard<'c>> {
+    if !is_sharded(config) {
+        return vec![unsharded(config)];
+    }
+
+    (0..config.shards.len()).map(|index| shard(config, index)).collect()
+}
+
+fn lookup_directory(name: &str, config: &StorageConfig, credentials: &StorageCredentials, tables: &Tables) -> Result<Option<usize>, ErrorCode> {
+    let read_pool = check_option!(CONNECTIONS.get(true, config, credentials), Err(ErrorCode::IOError));
+
+    let mut result = check_query!(
+                        read_pool.prep_exec(
+                            format!("SELECT shard FROM {} WHERE name = :name", tables.shards),
+                            params!{
+                                name
+                             }
+                        ), Err(ErrorCode::IOError)
+    );
+
+    let shard_name: String = match result.next() {
+        None => return Ok(None),
+        Some(row) => check_option!(check_query!(row, Err(ErrorCode::IOError)).get(0), Err(ErrorCode::IOError)),
+    };
+
+    match shard_index(config, &shard_name) {
+        None => {
+            warn!("Wallet {} is placed on the shard {} which is not in the config", name, shard_name);
+            Err(ErrorCode::InvalidState)
+        },
+        Some(index) => Ok(Some(index)),
+    }
+}
+
+///
+/// Looks for the wallet on every shard, starting with the one it is placed on by consistent hashing,
+/// so that wallets not yet rebalanced after a change of the shards are still found.
+///
+fn probe_shards(name: &str, config: &StorageConfig, credentials: &StorageCredentials, tables: &Tables) -> Result<Option<usize>, ErrorCode> {
+    let hashed = hashed_shard_index(config, name);
+
+    let mut indexes = vec![hashed];
+    indexes.extend((0..config.shards.len()).filter(|index| *index != hashed));
+
+    for index in indexes {
+        let shard_config = config.for_shard(&config.shards[index]);
+        let read_pool = check_option!(CONNECTIONS.get(true, &shard_config, credentials), Err(ErrorCode::IOError));
+
+        let mut result = check_query!(
+                            read_pool.prep_exec(
+                                format!("SELECT id FROM {} WHERE name = :name", tables.wallets),
+                                params!{
+                                    name
+                                 }
+                            ), Err(ErrorCode::IOError)
+        );
+
+        if let Some(row) = result.next() {
+            check_query!(row, Err(ErrorCode::IOError));
+            return Ok(Some(index));
+        }
+    }
+
+    Ok(None)
+}
+
+///
+/// Index of the shard holding the wallet, None if the wallet is on none of them.
+///
+fn locate(name: &str, config: &StorageConfig, credentials: &StorageCredentials, tables: &Tables) -> Result<Option<usize>, ErrorCode> {
+    match config.shard_placement {
+        ShardPlacement::Directory => lookup_directory(name, config, credentials, tables),
+        ShardPlacement::ConsistentHash => probe_shards(name, config, credentials, tables),
+    }
+}
+
+///
+/// Routes an existing wallet to the shard holding it.
+///
+/// # ErrorCodes
+///
+///  * `WalletNotFound` - The wallet is on none of the shards
+///  * `InvalidState` - The directory places the wallet on a shard which is not in the config
+///  * `IOError` - Unexpected error occurred while communicating with the DB
+///
+pub fn route_existing<'c>(name: &str, config: &StorageConfig<'c>, credentials: &StorageCredentials, tables: &Tables) -> Result<Shard<'c>, ErrorCode> {
+    if !is_sharded(config) {
+        return Ok(unsharded(config));
+    }
+
+    match locate(name, config, credentials, tables)? {
+        None => Err(ErrorCode::WalletNotFound),
+        Some(index) => Ok(shard(config, index)),
+    }
+}
+
+///
+/// Routes a wallet which is about to be created.
+///
+/// With the directory placement an existing entry, e.g. one added by an operator to pin the wallet,
+/// is honoured and a missing one is added for the consistent hashing shard.
+///
+/// # ErrorCodes
+///
+///  * `WalletAlreadyExistsError` - The wallet already exists on one of the shards
+///  * `InvalidState` - The directory places the wallet on a shard which is not in the config
+///  * `IOError` - Unexpected error occurred while communicating with the DB
+///
+pub fn route_new<'c>(name: &str, config: &StorageConfig<'c>, credentials: &StorageCredentials, tables: &Tables) -> Result<Shard<'c>, ErrorCode> {
+    if !is_sharded(config) {
+        return Ok(unsharded(config));
+    }
+
+    match config.shard_placement {
+        ShardPlacement::ConsistentHash => {
+            if probe_shards(name, config, credentials, tables)?.is_some() {
+                return Err(ErrorCode::WalletAlreadyExistsError);
+            }
+
+            Ok(shard(config, hashed_shard_index(config, name)))
+        },
+        ShardPlacement::Directory => {
+            if let Some(index) = lookup_directory(name, config, credentials, tables)? {
+                return Ok(shard(config, index));
+            }
+
+            let write_pool = check_option!(CONNECTIONS.get(false, config, credentials), Err(ErrorCode::IOError));
+            let shard_name = config.shards[hashed_shard_index(config, name)].name;
+
+            // a concurrent create may have added the entry in the meantime, the entry that made it wins
+            check_query!(
+                write_pool.prep_exec(
+                    format!("INSERT IGNORE INTO {}(name, shard) VALUES (:name, :shard_name)", tables.shards),
+                    params!{
+                        name,
+                        shard_name
+                     }
+                ), Err(ErrorCode::IOError)
+            );
+
+            // the entry is read back from the write host, the read host may be lagging behind
+            match lookup_directory(name, &StorageConfig { read_host: config.write_host, ..config.clone() }, credentials, tables)? {
+                None => Err(ErrorCode::IOError),
+                Some(index) => Ok(shard(config, index)),
+            }
+        },
+    }
+}
+
+///
+/// Checks that no shard holds a wallet with the name, before a wallet gets the name by a rename or a copy.
+///
+pub fn check_name_free(name: &str, config: &StorageConfig, credentials: &StorageCredentials, tables: &Tables) -> ErrorCode {
+    if !is_sharded(config) {
+        return ErrorCode::Success;
+    }
+
+    match locate(name, config, credentials, tables) {
+        Err(err) => err,
+        Ok(Some(_)) => ErrorCode::WalletAlreadyExistsError,
+        Ok(None) => ErrorCode::Success,
+    }
+}
+
+///
+/// Points the directory entry of the wallet to the shard, nothing to do unless the directory placement is used.
+///
+pub fn register(name: &str, shard: &Shard, config: &StorageConfig, credentials: &StorageCredentials, tables: &Tables) -> ErrorCode {
+    let shard_name = match shard.name {
+        Some(shard_name) if config.shard_placement == ShardPlacement::Directory => shard_name,
+        _ => return ErrorCode::Success,
+    };
+
+    let write_pool = check_option!(CONNECTIONS.get(false, config, credentials), ErrorCode::IOError);
+
+    let result = write_pool.prep_exec(
+                    format!("INSERT INTO {}(name, shard) VALUES (:name, :shard_name) ON DUPLICATE KEY UPDATE shard = VALUES(shard)", tables.shards),
+                    params!{
+                        name,
+                        shard_name
+                     }
+    );
+
+    match result {
+        Err(Error::MySqlError(err)) => {
+            warn!("MySQL Error while executing query. Err Code: {}, Err State: {}", err.code, err.state);
+            ErrorCode::IOError
+        },
+        Err(err) => {
+            warn!("Unexpected Error while executing query. Err: {:?}", err);
+            ErrorCode::IOError
+        },
+        Ok(_) => ErrorCode::Success,
+    }
+}
+
+///
+/// Whether both configs use the same directory table, only relevant with the directory placement.
+///
+pub fn same_directory(config: &StorageConfig, tables: &Tables, other_config: &StorageConfig, other_tables: &Tables) -> bool {
+    config.write_host == other_config.write_host && config.port == other_config.port
+        && config.db_name == other_config.db_name && tables.shards == other_tables.shards
+}
+
+///
+/// Removes the directory entry of the wallet, nothing to do unless the directory placement is used.
+///
+pub fn unregister(name: &str, config: &StorageConfig, credentials: &StorageCredentials, tables: &Tables) -> ErrorCode {
+    if !is_sharded(config) || config.shard_placement != ShardPlacement::Directory {
+        return ErrorCode::Success;
+    }
+
+    let write_pool = check_option!(CONNECTIONS.get(false, config, credentials), ErrorCode::IOError);
+
+    check_query!(
+        write_pool.prep_exec(
+            format!("DELETE FROM {} WHERE name = :name", tables.shards),
+            params!{
+                name
+             }
+        ), ErrorCode::IOError
+    );
+
+    ErrorCode::Success
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring_spread(shard_names: &Vec<&str>, wallets: usize) -> Vec<usize> {
+        let ring = HashRing::new(shard_names);
+        let mut counts = vec![0; shard_names.len()];
+
+        for i in 0..wallets {
+            counts[ring.get(&format!("wallet-{}", i)).unwrap()] += 1;
+        }
+
+        counts
+    }
+
+    #[test]
+    fn hash_ring_is_deterministic() {
+        let shard_names = vec!["shard-a", "shard-b", "shard-c"];
+
+        let first = HashRing::new(&shard_names);
+        let second = HashRing::new(&shard_names);
+
+        for i in 0..100 {
+            let name = format!("wallet-{}", i);
+            assert_eq!(first.get(&name), second.get(&name));
+        }
+    }
+
+    #[test]
+    fn hash_ring_spreads_wallets_evenly() {
+        let counts = ring_spread(&vec!["shard-a", "shard-b", "shard-c", "shard-d"], 10000);
+
+        for count in counts {
+            assert!(count > 1500 && count < 3500, "uneven spread: {}", count);
+        }
+    }
+
+    #[test]
+    fn hash_ring_moves_only_wallets_of_added_shard() {
+        let before = HashRing::new(&vec!["shard-a", "shard-b", "shard-c"]);
+        let after = HashRing::new(&vec!["shard-a", "shard-b", "shard-c", "shard-d"]);
+
+        let mut moved = 0;
+
+        for i in 0..10000 {
+            let name = format!("wallet-{}", i);
+            let (old, new) = (before.get(&name).unwrap(), after.get(&name).unwrap());
+
+            if old != new {
+                assert_eq!(new, 3);
+                moved += 1;
+            }
+        }
+
+        assert!(moved > 1500 && moved < 3500, "unexpected number of moved wallets: {}", moved);
+    }
+
+    #[test]
+    fn hash_ring_with_no_shards_places_nothing() {
+        assert_eq!(HashRing::new(&vec![]).get("wallet"), None);
+    }
+}
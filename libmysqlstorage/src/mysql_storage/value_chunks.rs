@@ -0,0 +1,451 @@
+use std::mem;
+use std::sync::Arc;
+use mysql::Pool;
+
+use mysql_storage::Tables;
+use mysql_storage::change_feed::{Writer, ChangeOperation};
+use mysql_storage::compression;
+use mysql_storage::encryption::{self, Encryption};
+use utils::multi_pool::{ValueConfig, CompressionConfig};
+use errors::error_code::ErrorCode;
+
+///
+/// Splits a value into the part stored in `items.value` and the chunks stored in `item_value_chunks`.
+/// Values are only chunked in the chunked value mode, and only when they are longer than a chunk.
+///
+pub fn split<'v>(value: &'v [u8], config: &ValueConfig) -> (&'v [u8], Vec<&'v [u8]>) {
+    if !config.chunked || value.len() <= config.chunk_size {
+        return (value, Vec::new());
+    }
+
+    (&[], value.chunks(config.chunk_size).collect())
+}
+
+pub fn check_size(size: u64, config: &ValueConfig) -> Result<(), ErrorCode> {
+    match config.max_size {
+        Some(max_size) if size > max_size => {
+            warn!("Value of {} bytes exceeds the maximum value size of {} bytes", size, max_size);
+            Err(ErrorCode::ValueTooLarge)
+        },
+        _ => Ok(())
+    }
+}
+
+///
+/// A record value as it is stored, the part in `items.value`, the chunks and the `value_encoding`.
+///
+pub struct StoredValue {
+    pub value: Vec<u8>,
+    pub chunks: Vec<Vec<u8>>,
+    pub encoding: u8,
+}
+
+///
+/// Turns a value into the stored one: the value is compressed, split into chunks and every stored part,
+/// the inline value or each chunk, is encrypted on its own, so the chunks can be decrypted as they are read.
+///
+pub fn encode(type_: &str, value: &[u8], config: &ValueConfig, compression_config: &CompressionConfig, encryption: Option<&Encryption>) -> Result<StoredValue, ErrorCode> {
+    check_size(value.len() as u64, config)?;
+
+    let (compressed_value, encoding) = compression::compress(type_, value, compression_config);
+    let (value, chunks) = split(&compressed_value, config);
+
+    let encryption = match encryption {
+        None => return Ok(StoredValue { value: value.to_vec(), chunks: chunks.iter().map(|chunk| chunk.to_vec()).collect(), encoding }),
+        Some(encryption) => encryption,
+    };
+
+    let mut encrypted_chunks = Vec::new();
+    for chunk in chunks.iter() {
+        encrypted_chunks.push(encryption.encrypt(chunk, encryption::VALUE)?);
+    }
+
+    Ok(StoredValue {
+        value: if encrypted_chunks.is_empty() { encryption.encrypt(value, encryption::VALUE)? } else { Vec::new() },
+        chunks: encrypted_chunks,
+        encoding: encoding | encryption::ENCRYPTED,
+    })
+}
+
+///
+/// Restores a value from its stored parts, the inline value or the chunks of a chunked one.
+///
+pub fn decode(parts: Vec<Vec<u8>>, encoding: u8, encryption: Option<&Encryption>) -> Result<Vec<u8>, ErrorCode> {
+    let mut value: Vec<u8> = Vec::new();
+
+    for part in parts {
+        value.extend(decrypt_part(part, encoding, encryption)?);
+    }
+
+    compression::decompress(value, encoding & !encryption::ENCRYPTED)
+}
+
+fn decrypt_part(part: Vec<u8>, encoding: u8, encryption: Option<&Encryption>) -> Result<Vec<u8>, ErrorCode> {
+    if encoding & encryption::ENCRYPTED == 0 {
+        return Ok(part);
+    }
+
+    match encryption {
+        None => {
+            warn!("Value is encrypted, but no encryption keys are configured");
+            Err(ErrorCode::EncryptionKeyNotFound)
+        },
+        Some(encryption) => encryption.decrypt(&part, encryption::VALUE),
+    }
+}
+
+///
+/// Stores the chunks of a record value, the record must already hold its new `value_chunks` count.
+///
+/// With `replace` the chunks of the previous value are deleted first, which is needed whenever
+/// the value of an existing record is replaced, chunked or not.
+///
+pub fn store_chunks(writer: &mut Writer, tables: &Tables, wallet_id: u64, type_: &str, id: &str, chunks: &[Vec<u8>], replace: bool) -> Result<(), ErrorCode> {
+    if replace {
+        check_query!(
+            writer.prep_exec(
+                format!("DELETE c FROM {} c JOIN {} i ON i.id = c.item_id \
+                         WHERE i.wallet_id = :wallet_id AND i.type = :type AND i.name = :name", tables.value_chunks, tables.items),
+                params!{
+                    "wallet_id" => wallet_id,
+                    "type" => type_,
+                    "name" => id
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+    }
+
+    if chunks.is_empty() {
+        return Ok(());
+    }
+
+    let item_id: u64 = {
+        let mut result = check_query!(
+            writer.prep_exec(
+                format!("SELECT id FROM {} WHERE wallet_id = :wallet_id AND type = :type AND name = :name", tables.items),
+                params!{
+                    "wallet_id" => wallet_id,
+                    "type" => type_,
+                    "name" => id
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        let row = check_query!(check_option!(result.next(), Err(ErrorCode::ItemNotFound)), Err(ErrorCode::IOError));
+
+        check_option!(row.get(0), Err(ErrorCode::InvalidState))
+    };
+
+    for (seq, chunk) in chunks.iter().enumerate() {
+        insert_chunk(writer, tables, item_id, seq as u32, chunk)?;
+    }
+
+    Ok(())
+}
+
+fn insert_chunk(writer: &mut Writer, tables: &Tables, item_id: u64, seq: u32, data: &[u8]) -> Result<(), ErrorCode> {
+    check_query!(
+        writer.prep_exec(
+            format!("INSERT INTO {} (item_id, seq, data) VALUE (:item_id, :seq, :data)", tables.value_chunks),
+            params!{
+                item_id,
+                seq,
+                data
+            }
+        ),
+        Err(ErrorCode::IOError)
+    );
+
+    Ok(())
+}
+
+///
+/// Reads all the chunks of a value with a single query. The chunks of a value are written in the same transaction
+/// as the version of the record, so they are all found as long as the record still has the given version.
+///
+/// # Returns
+///
+///  * `Option<Vec<Vec<u8>>>` - the chunks as stored, None if the record was changed since its `version` was read
+///
+pub fn read_chunks(pool: &Pool, tables: &Tables, item_id: u64, version: u64) -> Result<Option<Vec<Vec<u8>>>, ErrorCode> {
+    let result = check_query!(
+        pool.prep_exec(
+            format!("SELECT c.data FROM {} c JOIN {} i ON i.id = c.item_id \
+                     WHERE c.item_id = :item_id AND i.version = :version \
+                     ORDER BY c.seq", tables.value_chunks, tables.items),
+            params!{
+                item_id,
+                version
+            }
+        ),
+        Err(ErrorCode::IOError)
+    );
+
+    let mut chunks: Vec<Vec<u8>> = Vec::new();
+
+    for row in result {
+        let row = check_query!(row, Err(ErrorCode::IOError));
+
+        chunks.push(check_option!(row.get(0), Err(ErrorCode::IOError)));
+    }
+
+    if chunks.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(chunks))
+}
+
+///
+/// Streaming read of a record value, one chunk is held in memory at a time.
+///
+/// Every chunk is read together with the version of the record, so a value that is changed while it is read
+/// fails with `VersionConflict` instead of returning a mix of the old and new chunks. A compressed value
+/// is read and decompressed as a whole when the reader is opened, encrypted chunks are decrypted one by one.
+///
+pub struct ValueReader {
+    pool: Arc<Pool>,
+    tables: Tables,
+    encryption: Option<Arc<Encryption>>,
+    item_id: u64,
+    version: u64,
+    value_chunks: u32,
+    value_encoding: u8,
+    next_seq: u32,
+    /// the value of a record that is not chunked, or the chunk being read
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl ValueReader {
+    pub fn open(pool: Arc<Pool>, tables: &Tables, encryption: Option<Arc<Encryption>>, wallet_id: u64, type_: &str, id: &str) -> Result<ValueReader, ErrorCode> {
+        let (item_id, value, value_chunks, value_encoding, version) = {
+            let mut result = check_query!(
+                pool.prep_exec(
+                    format!("SELECT id, value, value_chunks, value_encoding, version FROM {} \
+                             WHERE wallet_id = :wallet_id AND type = :type AND name = :name", tables.items),
+                    params!{
+                        "wallet_id" => wallet_id,
+                        "type" => type_,
+                        "name" => id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::ItemNotFound)), Err(ErrorCode::IOError));
+
+            let item_id: u64 = check_option!(row.get(0), Err(ErrorCode::IOError));
+            let value: Vec<u8> = check_option!(row.get(1), Err(ErrorCode::IOError));
+            let value_chunks: u32 = check_option!(row.get(2), Err(ErrorCode::IOError));
+            let value_encoding: u8 = check_option!(row.get(3), Err(ErrorCode::IOError));
+            let version: u64 = check_option!(row.get(4), Err(ErrorCode::IOError));
+
+            (item_id, value, value_chunks, value_encoding, version)
+        };
+
+        if value_encoding & !encryption::ENCRYPTED != compression::RAW {
+            let parts = if value_chunks > 0 {
+                check_option!(read_chunks(&pool, tables, item_id, version)?, Err(ErrorCode::VersionConflict))
+            } else {
+                vec![value]
+            };
+
+            let value = decode(parts, value_encoding, encryption.as_ref().map(|encryption| &**encryption))?;
+
+            return Ok(ValueReader { pool, tables: tables.clone(), encryption, item_id, version, value_chunks: 0, value_encoding, next_seq: 0, buffer: value, position: 0 });
+        }
+
+        let value = if value_chunks > 0 { value } else { decrypt_part(value, value_encoding, encryption.as_ref().map(|encryption| &**encryption))? };
+
+        Ok(ValueReader { pool, tables: tables.clone(), encryption, item_id, version, value_chunks, value_encoding, next_seq: 0, buffer: value, position: 0 })
+    }
+
+    ///
+    /// Reads the next part of the value into the buffer.
+    ///
+    /// # Returns
+    ///
+    ///  * `usize` - number of bytes read, 0 once the whole value is read
+    ///
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ErrorCode> {
+        while self.position == self.buffer.len() && self.next_seq < self.value_chunks {
+            self.buffer = self._read_chunk(self.next_seq)?;
+            self.position = 0;
+            self.next_seq += 1;
+        }
+
+        let read = (self.buffer.len() - self.position).min(buffer.len());
+
+        buffer[..read].copy_from_slice(&self.buffer[self.position..self.position + read]);
+        self.position += read;
+
+        Ok(read)
+    }
+
+    fn _read_chunk(&self, seq: u32) -> Result<Vec<u8>, ErrorCode> {
+        let mut result = check_query!(
+            self.pool.prep_exec(
+                format!("SELECT c.data FROM {} c JOIN {} i ON i.id = c.item_id \
+                         WHERE c.item_id = :item_id AND c.seq = :seq AND i.version = :version", self.tables.value_chunks, self.tables.items),
+                params!{
+                    "item_id" => self.item_id,
+                    "seq" => seq,
+                    "version" => self.version
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        let row = check_query!(check_option!(result.next(), Err(ErrorCode::VersionConflict)), Err(ErrorCode::IOError));
+        let chunk: Vec<u8> = check_option!(row.get(0), Err(ErrorCode::IOError));
+
+        decrypt_part(chunk, self.value_encoding, self.encryption.as_ref().map(|encryption| &**encryption))
+    }
+}
+
+///
+/// Streaming write of a record value, which replaces the value of an existing record.
+///
+/// The chunks are written in a transaction that holds the lock of the record until `finish` commits it,
+/// dropping an unfinished writer rolls the write back. A value that fits a single chunk is stored unchunked,
+/// streamed values are never compressed, but they are encrypted chunk by chunk when the storage encrypts values.
+///
+pub struct ValueWriter {
+    writer: Writer,
+    tables: Tables,
+    encryption: Option<Arc<Encryption>>,
+    type_: String,
+    id: String,
+    item_id: u64,
+    config: ValueConfig,
+    next_seq: u32,
+    buffer: Vec<u8>,
+    size: u64,
+}
+
+impl ValueWriter {
+    pub fn open(pool: &Pool, tables: &Tables, encryption: Option<Arc<Encryption>>, wallet_id: u64, type_: &str, id: &str, config: &ValueConfig, journal_changes: bool) -> Result<ValueWriter, ErrorCode> {
+        let mut writer = Writer::start_transaction(pool, tables, wallet_id, journal_changes)?;
+
+        let item_id: u64 = {
+            let mut result = check_query!(
+                writer.prep_exec(
+                    format!("SELECT id FROM {} WHERE wallet_id = :wallet_id AND type = :type AND name = :name FOR UPDATE", tables.items),
+                    params!{
+                        "wallet_id" => wallet_id,
+                        "type" => type_,
+                        "name" => id
+                    }
+                ),
+                Err(ErrorCode::IOError)
+            );
+
+            let row = check_query!(check_option!(result.next(), Err(ErrorCode::ItemNotFound)), Err(ErrorCode::IOError));
+
+            check_option!(row.get(0), Err(ErrorCode::InvalidState))
+        };
+
+        check_query!(
+            writer.prep_exec(
+                format!("DELETE FROM {} WHERE item_id = :item_id", tables.value_chunks),
+                params!{
+                    item_id
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        Ok(ValueWriter {
+            writer,
+            tables: tables.clone(),
+            encryption,
+            type_: type_.to_string(),
+            id: id.to_string(),
+            item_id,
+            config: *config,
+            next_seq: 0,
+            buffer: Vec::new(),
+            size: 0,
+        })
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> Result<(), ErrorCode> {
+        check_size(self.size + data.len() as u64, &self.config)?;
+
+        self.size += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+
+        // a full chunk is kept in the buffer until more data arrives, so a value of a single chunk stays unchunked
+        while self.buffer.len() > self.config.chunk_size {
+            let rest = self.buffer.split_off(self.config.chunk_size);
+            let chunk = mem::replace(&mut self.buffer, rest);
+
+            self._insert_chunk(&chunk)?;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Stores the rest of the value, increments the version of the record and commits the write.
+    ///
+    /// # Returns
+    ///
+    ///  * `(String, String)` - type and id of the written record
+    ///
+    pub fn finish(mut self) -> Result<(String, String), ErrorCode> {
+        let value = if self.next_seq == 0 {
+            let value = mem::replace(&mut self.buffer, Vec::new());
+            self._encrypt(value)?
+        } else {
+            let chunk = mem::replace(&mut self.buffer, Vec::new());
+
+            if !chunk.is_empty() {
+                self._insert_chunk(&chunk)?;
+            }
+
+            Vec::new()
+        };
+
+        let (item_id, value_chunks) = (self.item_id, self.next_seq);
+        let value_encoding = if self.encryption.is_some() { encryption::ENCRYPTED } else { compression::RAW };
+
+        check_query!(
+            self.writer.prep_exec(
+                format!("UPDATE {} SET value = :value, value_chunks = :value_chunks, value_encoding = :value_encoding, version = version + 1 \
+                         WHERE id = :item_id", self.tables.items),
+                params!{
+                    "value" => value,
+                    "value_chunks" => value_chunks,
+                    "value_encoding" => value_encoding,
+                    "item_id" => item_id
+                }
+            ),
+            Err(ErrorCode::IOError)
+        );
+
+        self.writer.log_record(ChangeOperation::Update, &self.type_, &self.id)?;
+        self.writer.commit()?;
+
+        Ok((self.type_, self.id))
+    }
+
+    fn _insert_chunk(&mut self, chunk: &[u8]) -> Result<(), ErrorCode> {
+        let chunk = self._encrypt(chunk.to_vec())?;
+
+        insert_chunk(&mut self.writer, &self.tables, self.item_id, self.next_seq, &chunk)?;
+        self.next_seq += 1;
+
+        Ok(())
+    }
+
+    fn _encrypt(&self, data: Vec<u8>) -> Result<Vec<u8>, ErrorCode> {
+        match self.encryption {
+            Some(ref encryption) => encryption.encrypt(&data, encryption::VALUE),
+            None => Ok(data),
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::sync::{Mutex, Arc, Weak};
+use std::collections::{HashMap, BTreeMap};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+///
+/// Counters of a cache, since the cache was created.
+///
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// entries dropped to stay within the size limit
+    pub evictions: u64,
+    /// entries dropped because they expired
+    pub expirations: u64,
+    /// entries dropped because the cached data was changed
+    pub invalidations: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+struct CacheEntry<V> {
+    value: Arc<V>,
+    size: usize,
+    expires_at: Instant,
+    /// position of the entry in the `lru` map
+    tick: u64,
+}
+
+struct CacheState<K, V> {
+    entries: HashMap<K, CacheEntry<V>>,
+    /// keys ordered from the least to the most recently used
+    lru: BTreeMap<u64, K>,
+    tick: u64,
+    generation: u64,
+    stats: CacheStats,
+}
+
+impl<K: Hash + Eq + Clone, V> CacheState<K, V> {
+    fn remove(&mut self, key: &K) -> bool {
+        match self.entries.remove(key) {
+            None => false,
+            Some(entry) => {
+                self.lru.remove(&entry.tick);
+                self.stats.bytes -= entry.size;
+                self.stats.entries -= 1;
+                true
+            }
+        }
+    }
+}
+
+///
+/// Least recently used cache bounded by the total size of the entries, entries expire after a fixed time to live.
+///
+/// Every invalidation starts a new generation. A value read from the DB is only cached if no invalidation happened
+/// since the read started, so a read racing with a write can not cache the data from before the write.
+///
+pub struct Cache<K, V> {
+    state: Mutex<CacheState<K, V>>,
+    max_bytes: usize,
+    ttl: Duration,
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> {
+    pub fn new(max_bytes: usize, ttl: Duration) -> Self {
+        Cache {
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                lru: BTreeMap::new(),
+                tick: 0,
+                generation: 0,
+                stats: CacheStats::default(),
+            }),
+            max_bytes,
+            ttl,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<Arc<V>> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let (value, old_tick, expired) = match state.entries.get(key) {
+            None => (None, 0, false),
+            Some(entry) => (Some(entry.value.clone()), entry.tick, entry.expires_at <= now),
+        };
+
+        if expired {
+            state.remove(key);
+            state.stats.expirations += 1;
+            state.stats.misses += 1;
+            return None;
+        }
+
+        match value {
+            None => {
+                state.stats.misses += 1;
+                None
+            },
+            Some(value) => {
+                state.tick += 1;
+                let tick = state.tick;
+
+                state.lru.remove(&old_tick);
+                state.lru.insert(tick, key.clone());
+                if let Some(entry) = state.entries.get_mut(key) {
+                    entry.tick = tick;
+                }
+
+                state.stats.hits += 1;
+                Some(value)
+            }
+        }
+    }
+
+    ///
+    /// Generation to pass to `insert` for a value that is about to be read from the DB.
+    ///
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    ///
+    /// Caches a value read in the given generation, values bigger than the whole cache are not cached.
+    ///
+    pub fn insert(&self, key: K, value: V, size: usize, generation: u64) {
+        if size > self.max_bytes {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        if state.generation != generation {
+            return;
+        }
+
+        state.remove(&key);
+
+        while state.stats.bytes + size > self.max_bytes {
+            let oldest = match state.lru.iter().next() {
+                None => break,
+                Some((_, oldest)) => oldest.clone(),
+            };
+
+            state.remove(&oldest);
+            state.stats.evictions += 1;
+        }
+
+        state.tick += 1;
+        let tick = state.tick;
+
+        state.lru.insert(tick, key.clone());
+        state.entries.insert(key, CacheEntry { value: Arc::new(value), size, expires_at: Instant::now() + self.ttl, tick });
+        state.stats.bytes += size;
+        state.stats.entries += 1;
+    }
+
+    pub fn invalidate(&self, key: &K) {
+        let mut state = self.state.lock().unwrap();
+
+        state.generation += 1;
+
+        if state.remove(key) {
+            state.stats.invalidations += 1;
+        }
+    }
+
+    ///
+    /// Invalidates all the entries whose key matches the predicate.
+    ///
+    pub fn invalidate_matching<F: Fn(&K) -> bool>(&self, predicate: F) {
+        let mut state = self.state.lock().unwrap();
+
+        state.generation += 1;
+
+        let keys: Vec<K> = state.entries.keys().filter(|key| predicate(key)).cloned().collect();
+
+        for key in keys {
+            state.remove(&key);
+            state.stats.invalidations += 1;
+        }
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.state.lock().unwrap().stats
+    }
+}
+
+///
+/// Caches of the storages opened in this process, grouped by the wallet they were opened on.
+///
+/// Lets the functions that replace the data of a whole wallet, e.g. restoring a snapshot, clear the caches
+/// of the storages that are still open on it. The registry only holds weak references, so the cache of
+/// a closed storage is dropped with the storage.
+///
+pub struct CacheRegistry<K, V> {
+    caches: Mutex<HashMap<String, Vec<Weak<Cache<K, V>>>>>,
+}
+
+impl<K: Hash + Eq + Clone, V> CacheRegistry<K, V> {
+    pub fn new() -> Self {
+        CacheRegistry { caches: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn register(&self, wallet: String, cache: &Arc<Cache<K, V>>) {
+        let mut caches = self.caches.lock().unwrap();
+
+        let wallet_caches = caches.entry(wallet).or_insert_with(Vec::new);
+        wallet_caches.retain(|cache| cache.upgrade().is_some());
+        wallet_caches.push(Arc::downgrade(cache));
+    }
+
+    ///
+    /// Invalidates all the entries of the caches registered for the wallet.
+    ///
+    pub fn clear(&self, wallet: &str) {
+        let mut caches = self.caches.lock().unwrap();
+
+        let is_empty = match caches.get_mut(wallet) {
+            None => return,
+            Some(wallet_caches) => {
+                wallet_caches.retain(|cache| cache.upgrade().is_some());
+
+                for cache in wallet_caches.iter().filter_map(|cache| cache.upgrade()) {
+                    cache.invalidate_matching(|_| true);
+                }
+
+                wallet_caches.is_empty()
+            }
+        };
+
+        if is_empty {
+            caches.remove(wallet);
+        }
+    }
+
+    ///
+    /// Clears the caches of the wallet and moves them to its new name, the open storages keep working on the renamed wallet.
+    ///
+    pub fn rename(&self, wallet: &str, new_wallet: String) {
+        self.clear(wallet);
+        self.clear(&new_wallet);
+
+        let mut caches = self.caches.lock().unwrap();
+
+        if let Some(wallet_caches) = caches.remove(wallet) {
+            caches.entry(new_wallet).or_insert_with(Vec::new).extend(wallet_caches);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_bytes: usize) -> Cache<String, String> {
+        Cache::new(max_bytes, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn cache_returns_inserted_values() {
+        let cache = cache(100);
+
+        cache.insert("a".to_string(), "1".to_string(), 10, cache.generation());
+
+        assert_eq!(cache.get(&"a".to_string()).map(|value| (*value).clone()), Some("1".to_string()));
+        assert!(cache.get(&"b".to_string()).is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries, stats.bytes), (1, 1, 1, 10));
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let cache = cache(30);
+
+        for key in &["a", "b", "c"] {
+            cache.insert(key.to_string(), key.to_string(), 10, cache.generation());
+        }
+
+        cache.get(&"a".to_string());
+        cache.insert("d".to_string(), "d".to_string(), 10, cache.generation());
+
+        assert!(cache.get(&"a".to_string()).is_some());
+        assert!(cache.get(&"b".to_string()).is_none());
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().bytes, 30);
+    }
+
+    #[test]
+    fn cache_skips_values_bigger_than_cache() {
+        let cache = cache(10);
+
+        cache.insert("a".to_string(), "a".to_string(), 11, cache.generation());
+
+        assert!(cache.get(&"a".to_string()).is_none());
+    }
+
+    #[test]
+    fn cache_expires_entries() {
+        let cache: Cache<String, String> = Cache::new(100, Duration::from_secs(0));
+
+        cache.insert("a".to_string(), "a".to_string(), 10, cache.generation());
+
+        assert!(cache.get(&"a".to_string()).is_none());
+        assert_eq!(cache.stats().expirations, 1);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn cache_rejects_values_read_before_invalidation() {
+        let cache = cache(100);
+
+        let generation = cache.generation();
+        cache.invalidate(&"a".to_string());
+        cache.insert("a".to_string(), "stale".to_string(), 10, generation);
+
+        assert!(cache.get(&"a".to_string()).is_none());
+    }
+
+    #[test]
+    fn cache_invalidates_matching_entries() {
+        let cache = cache(100);
+
+        for key in &["a1", "a2", "b1"] {
+            cache.insert(key.to_string(), key.to_string(), 10, cache.generation());
+        }
+
+        cache.invalidate_matching(|key| key.starts_with("a"));
+
+        assert!(cache.get(&"a1".to_string()).is_none());
+        assert!(cache.get(&"b1".to_string()).is_some());
+        assert_eq!(cache.stats().invalidations, 2);
+    }
+
+    #[test]
+    fn cache_registry_clears_caches_of_wallet() {
+        let registry = CacheRegistry::new();
+        let first = Arc::new(cache(100));
+        let other = Arc::new(cache(100));
+
+        registry.register("wallet".to_string(), &first);
+        registry.register("other".to_string(), &other);
+
+        first.insert("a".to_string(), "a".to_string(), 10, first.generation());
+        other.insert("a".to_string(), "a".to_string(), 10, other.generation());
+
+        registry.clear("wallet");
+
+        assert!(first.get(&"a".to_string()).is_none());
+        assert!(other.get(&"a".to_string()).is_some());
+    }
+
+    #[test]
+    fn cache_registry_moves_caches_on_rename() {
+        let registry = CacheRegistry::new();
+        let cache = Arc::new(cache(100));
+
+        registry.register("wallet".to_string(), &cache);
+        cache.insert("a".to_string(), "a".to_string(), 10, cache.generation());
+
+        registry.rename("wallet", "renamed".to_string());
+        assert!(cache.get(&"a".to_string()).is_none());
+
+        cache.insert("a".to_string(), "a".to_string(), 10, cache.generation());
+        registry.clear("wallet");
+        assert!(cache.get(&"a".to_string()).is_some());
+
+        registry.clear("renamed");
+        assert!(cache.get(&"a".to_string()).is_none());
+    }
+}
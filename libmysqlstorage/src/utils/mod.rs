@@ -1,4 +1,6 @@
 pub mod handle_store;
 pub mod multi_pool;
 pub mod callbacks;
-pub mod logger;
\ No newline at end of file
+pub mod logger;
+pub mod cache;
+pub mod worker_runtime;
\ No newline at end of file
@@ -3,24 +3,344 @@ use std::collections::HashMap;
 use mysql::{Pool, OptsBuilder, Opts};
 use mysql::consts::CapabilityFlags;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct StorageConfig <'a> {
     pub read_host: &'a str,
     pub write_host: &'a str,
     pub port: u16,
     pub db_name: &'a str,
     #[serde(default="default_use_ssl")]
-    pub use_ssl: bool
+    pub use_ssl: bool,
+    #[serde(default)]
+    pub query_limits: QueryLimits,
+    /// default timeout (in milliseconds) of the statements, set as the `max_execution_time` and
+    /// `innodb_lock_wait_timeout` (rounded up to seconds) of every connection, none if not set
+    #[serde(default)]
+    pub statement_timeout: Option<u64>,
+    /// prefix of the `wallets` and `items` table names, allows hosting several tenants in one DB
+    #[serde(default)]
+    pub table_prefix: &'a str,
+    /// clusters the wallets are spread across, the hosts above hold the `wallet_shards` directory table
+    #[serde(default, borrow)]
+    pub shards: Vec<ShardConfig<'a>>,
+    /// how a new wallet is assigned to one of the `shards`
+    #[serde(default)]
+    pub shard_placement: ShardPlacement,
+    /// how long the wallet snapshots are kept, applied whenever a snapshot is taken
+    #[serde(default)]
+    pub snapshot_retention: SnapshotRetention,
+    /// opt-in journal of the record changes of every wallet, see `ChangeFeedConfig`
+    #[serde(default)]
+    pub change_feed: ChangeFeedConfig,
+    /// in-process cache of the metadata and the fetched records of every opened storage
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// chunked storage and maximum size of the record values
+    #[serde(default)]
+    pub values: ValueConfig,
+    /// record types whose values are compressed before they are stored
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    /// encryption of the record values and the metadata with per-wallet data keys
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+    /// worker threads the storage calls of libindy are executed on, see `RuntimeConfig`
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
 }
 
 fn default_use_ssl() -> bool { false }
 
+impl<'a> StorageConfig<'a> {
+    ///
+    /// Config for a single shard, the hosts of the shard with the rest of the settings of this config.
+    ///
+    pub fn for_shard(&self, shard: &ShardConfig<'a>) -> StorageConfig<'a> {
+        StorageConfig {
+            read_host: shard.read_host,
+            write_host: shard.write_host,
+            port: shard.port.unwrap_or(self.port),
+            db_name: shard.db_name.unwrap_or(self.db_name),
+            use_ssl: self.use_ssl,
+            query_limits: self.query_limits,
+            statement_timeout: self.statement_timeout,
+            table_prefix: self.table_prefix,
+            shards: Vec::new(),
+            shard_placement: self.shard_placement,
+            snapshot_retention: self.snapshot_retention,
+            change_feed: self.change_feed,
+            cache: self.cache,
+            values: self.values,
+            compression: self.compression.clone(),
+            encryption: self.encryption.clone(),
+            runtime: self.runtime,
+        }
+    }
+}
+
+///
+/// One cluster of a sharded storage, `port` and `db_name` default to the ones of the storage config.
+///
+#[derive(Deserialize, Debug, Clone)]
+pub struct ShardConfig <'a> {
+    /// stable name of the shard, used for the placement and stored in the `wallet_shards` table
+    pub name: &'a str,
+    pub read_host: &'a str,
+    pub write_host: &'a str,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default, borrow)]
+    pub db_name: Option<&'a str>,
+}
+
+///
+/// Placement of new wallets on the shards.
+///
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardPlacement {
+    /// the shard is picked by consistent hashing of the wallet name
+    ConsistentHash,
+    /// the shard is looked up in the `wallet_shards` table, wallets without an entry are placed
+    /// by consistent hashing and get an entry when created
+    Directory,
+}
+
+impl Default for ShardPlacement {
+    fn default() -> Self { ShardPlacement::ConsistentHash }
+}
+
+///
+/// Bounds applied to WQL search queries before they are sent to the DB.
+///
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct QueryLimits {
+    /// maximum nesting depth of `$and`, `$or` and `$not` operators
+    #[serde(default="default_max_depth")]
+    pub max_depth: usize,
+    /// maximum total number of operators in a query
+    #[serde(default="default_max_operators")]
+    pub max_operators: usize,
+    /// maximum number of values in a single `$in` operator
+    #[serde(default="default_max_in_values")]
+    pub max_in_values: usize,
+    /// maximum length of the rendered SQL statement
+    #[serde(default="default_max_sql_length")]
+    pub max_sql_length: usize,
+    /// default `MAX_EXECUTION_TIME` hint (in milliseconds) for search statements, none if not set
+    #[serde(default)]
+    pub max_execution_time: Option<u64>,
+}
+
+fn default_max_depth() -> usize { 32 }
+fn default_max_operators() -> usize { 512 }
+fn default_max_in_values() -> usize { 1024 }
+fn default_max_sql_length() -> usize { 1048576 }
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        QueryLimits {
+            max_depth: default_max_depth(),
+            max_operators: default_max_operators(),
+            max_in_values: default_max_in_values(),
+            max_sql_length: default_max_sql_length(),
+            max_execution_time: None,
+        }
+    }
+}
+
+///
+/// Retention of the snapshots of a wallet, snapshots are kept forever if neither limit is set.
+///
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct SnapshotRetention {
+    /// maximum number of snapshots of a wallet, the oldest ones are dropped first
+    #[serde(default)]
+    pub max_snapshots: Option<usize>,
+    /// maximum age of a snapshot in seconds
+    #[serde(default)]
+    pub max_age: Option<u64>,
+}
+
+///
+/// Journal of the record changes in the `item_changes` table, read by the downstream services with `read_changes`.
+///
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct ChangeFeedConfig {
+    /// every record mutation writes its changes to the journal in the same transaction
+    #[serde(default)]
+    pub enabled: bool,
+    /// maximum age of a change in seconds, older changes are dropped by `trim_changes`
+    #[serde(default)]
+    pub max_age: Option<u64>,
+    /// maximum number of changes kept per wallet by `trim_changes`, the oldest ones are dropped first
+    #[serde(default)]
+    pub max_changes: Option<u64>,
+}
+
+///
+/// Cache of the metadata and fetched records of a storage. The cache only sees the writes made through the same
+/// storage, so deployments where other processes write to the wallets should disable it or keep the `ttl` short.
+///
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// maximum total size of the cached values, tags and names
+    #[serde(default="default_cache_max_bytes")]
+    pub max_bytes: usize,
+    /// time (in milliseconds) a cached entry is used before it is read from the DB again
+    #[serde(default="default_cache_ttl")]
+    pub ttl: u64,
+}
+
+fn default_cache_max_bytes() -> usize { 16777216 }
+fn default_cache_ttl() -> u64 { 5000 }
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: false,
+            max_bytes: default_cache_max_bytes(),
+            ttl: default_cache_ttl(),
+        }
+    }
+}
+
+///
+/// Storage of the record values. Chunked values are split into the `item_value_chunks` table, so no single
+/// statement or row carries more than `chunk_size` bytes of a value, which keeps large values within `max_allowed_packet`.
+///
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ValueConfig {
+    /// values longer than `chunk_size` are stored in chunks
+    #[serde(default)]
+    pub chunked: bool,
+    /// size of a chunk in bytes, also used by the streaming writes whether `chunked` is set or not
+    #[serde(default="default_chunk_size")]
+    pub chunk_size: usize,
+    /// maximum size of a value in bytes, larger values are rejected with `ValueTooLarge`
+    #[serde(default)]
+    pub max_size: Option<u64>,
+}
+
+fn default_chunk_size() -> usize { 1048576 }
+
+impl Default for ValueConfig {
+    fn default() -> Self {
+        ValueConfig {
+            chunked: false,
+            chunk_size: default_chunk_size(),
+            max_size: None,
+        }
+    }
+}
+
+///
+/// Compression of the record values of the listed types. Whether a stored value is compressed, and how,
+/// is kept per record, so changing this config leaves the values that are already stored readable.
+///
+#[derive(Deserialize, Debug, Clone)]
+pub struct CompressionConfig {
+    /// compression algorithm by record type, values of the other types are stored as given
+    #[serde(default)]
+    pub types: HashMap<String, CompressionAlgorithm>,
+    /// values shorter than this (in bytes) are not worth compressing and are stored as given
+    #[serde(default="default_compression_min_size")]
+    pub min_size: usize,
+}
+
+fn default_compression_min_size() -> usize { 256 }
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            types: HashMap::new(),
+            min_size: default_compression_min_size(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionAlgorithm {
+    Deflate,
+    Zstd,
+}
+
+///
+/// Envelope encryption of the record values and the metadata. Every wallet has data keys that encrypt its data,
+/// which are stored wrapped by a key-encryption key (KEK). The KEKs are given by id, so they can be rotated
+/// by adding a new one as the `current_key` and re-wrapping the data keys, see `MySQLStorage::rewrap_data_keys`.
+///
+/// Not `Debug`, so the keys can not end up in the logs.
+///
+#[derive(Deserialize, Clone, Default)]
+pub struct EncryptionConfig {
+    /// id of the KEK that wraps new data keys, the data is stored unencrypted if not set
+    #[serde(default)]
+    pub current_key: Option<String>,
+    /// KEKs by id, base64 encoded 256-bit keys
+    #[serde(default)]
+    pub keys: HashMap<String, String>,
+    /// path of a JSON file with more KEKs, in the same form as `keys`
+    #[serde(default)]
+    pub key_file: Option<String>,
+}
+
+///
+/// Worker threads that execute the storage calls made by libindy, so the threads of libindy wait for a call
+/// at most `timeout` and several calls run concurrently. The calls made on the runtime use connection pools
+/// of their own, of up to `max_connections` connections per host.
+///
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RuntimeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// number of worker threads
+    #[serde(default="default_runtime_workers")]
+    pub workers: usize,
+    /// maximum number of calls waiting for a worker, calls made when the queue is full fail with `QueueFull`
+    #[serde(default="default_runtime_queue_size")]
+    pub queue_size: usize,
+    /// time (in milliseconds) a call waits for its result, queueing included, before it fails with `Timeout`
+    #[serde(default="default_runtime_timeout")]
+    pub timeout: u64,
+    /// maximum number of connections of a connection pool of the runtime
+    #[serde(default="default_max_connections")]
+    pub max_connections: usize,
+}
+
+fn default_runtime_workers() -> usize { 8 }
+fn default_runtime_queue_size() -> usize { 256 }
+fn default_runtime_timeout() -> u64 { 30000 }
+fn default_max_connections() -> usize { 100 }
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        RuntimeConfig {
+            enabled: false,
+            workers: default_runtime_workers(),
+            queue_size: default_runtime_queue_size(),
+            timeout: default_runtime_timeout(),
+            max_connections: default_max_connections(),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct StorageCredentials <'a> {
     pub user: &'a str,
     pub pass: &'a str,
 }
 
+///
+/// `innodb_lock_wait_timeout` for a statement timeout, which is in whole seconds and at least 1.
+///
+fn lock_wait_timeout(statement_timeout: u64) -> u64 {
+    ::std::cmp::max(1, (statement_timeout + 999) / 1000)
+}
+
 pub struct MultiPool {
     map: RwLock<HashMap<String, Arc<Pool>>>,
 }
@@ -33,7 +353,18 @@ impl MultiPool {
     pub fn get(&self, read_only: bool, config: &StorageConfig, credentials: &StorageCredentials) -> Option<Arc<Pool>> {
 
         let host_addr = if read_only {config.read_host} else {config.write_host};
-        let connection_string = format!("{}:{}@{}:{}/{}", credentials.user, credentials.pass, host_addr, config.port, config.db_name);
+        let mut connection_string = format!("{}:{}@{}:{}/{}", credentials.user, credentials.pass, host_addr, config.port, config.db_name);
+
+        let max_connections = if config.runtime.enabled {
+            connection_string.push_str(&format!("#runtime:{}", config.runtime.max_connections));
+            config.runtime.max_connections
+        } else {
+            default_max_connections()
+        };
+
+        if let Some(statement_timeout) = config.statement_timeout {
+            connection_string.push_str(&format!("#statement_timeout:{}", statement_timeout));
+        }
 
         let mut c = match self.map.read() {
             Err(_) => None,
@@ -58,9 +389,16 @@ impl MultiPool {
                 builder.ssl_opts(mysql::SslOpts::default());
             }
 
+            if let Some(statement_timeout) = config.statement_timeout {
+                builder.init(vec![
+                    format!("SET SESSION max_execution_time = {}", statement_timeout),
+                    format!("SET SESSION innodb_lock_wait_timeout = {}", lock_wait_timeout(statement_timeout)),
+                ]);
+            }
+
             let opts: Opts = builder.into();
 
-            let pool = Pool::new_manual(1, 100, opts);
+            let pool = Pool::new_manual(1, max_connections, opts);
             if pool.is_err() {
                 let err = pool.unwrap_err();
                 error!("Error while connecting to the pool: {:?}", err);
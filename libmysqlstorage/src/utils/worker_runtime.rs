@@ -0,0 +1,260 @@
+use std::sync::{Mutex, Arc};
+use std::sync::mpsc::{self, SyncSender, Receiver, TrySendError, RecvTimeoutError};
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use utils::multi_pool::RuntimeConfig;
+use errors::error_code::ErrorCode;
+
+///
+/// Call queued for a worker, dropped without being executed if no worker picked it up before its deadline,
+/// since the caller has already given up on it by then.
+///
+struct Job {
+    deadline: Instant,
+    call: Box<dyn FnMut() + Send>,
+}
+
+///
+/// Fixed set of worker threads executing the queued calls, the calling thread waits for the result of its call.
+///
+/// A call that does not complete within the timeout fails with `Timeout`, a call that finds the queue full fails
+/// with `QueueFull` right away. The timeout does not interrupt a call that is already executing, it completes
+/// on its worker and its result is discarded, see `run_or_discard`.
+///
+pub struct WorkerRuntime {
+    queue: Mutex<SyncSender<Job>>,
+    timeout: Duration,
+}
+
+impl WorkerRuntime {
+    pub fn start(config: &RuntimeConfig) -> Result<WorkerRuntime, ErrorCode> {
+        if config.workers == 0 || config.queue_size == 0 || config.max_connections == 0 {
+            warn!("Invalid worker runtime config: {:?}", config);
+            return Err(ErrorCode::InvalidStructure);
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(config.queue_size);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for worker in 0..config.workers {
+            let receiver = receiver.clone();
+
+            check_result!(
+                thread::Builder::new().name(format!("mysql-storage-worker-{}", worker)).spawn(move || WorkerRuntime::work(receiver)),
+                Err(ErrorCode::InvalidState)
+            );
+        }
+
+        Ok(WorkerRuntime { queue: Mutex::new(sender), timeout: Duration::from_millis(config.timeout) })
+    }
+
+    fn work(receiver: Arc<Mutex<Receiver<Job>>>) {
+        loop {
+            let mut job = {
+                let receiver = match receiver.lock() {
+                    Err(_) => return,
+                    Ok(receiver) => receiver,
+                };
+
+                match receiver.recv() {
+                    Err(_) => return,
+                    Ok(job) => job,
+                }
+            };
+
+            if Instant::now() >= job.deadline {
+                warn!("Dropping a call that waited in the queue past its deadline");
+                continue;
+            }
+
+            // a panicking call drops its result sender, which fails the call with InvalidState, the worker goes on
+            if panic::catch_unwind(AssertUnwindSafe(|| (job.call)())).is_err() {
+                error!("Call panicked on a worker thread");
+            }
+        }
+    }
+
+    ///
+    /// Executes the call on a worker and waits for its result.
+    ///
+    /// # ErrorCodes
+    ///
+    ///  * `Timeout` - Call did not complete within the timeout of the runtime
+    ///  * `QueueFull` - All the workers are busy and the queue is full
+    ///  * `InvalidState` - Call panicked or the workers are gone
+    ///
+    pub fn run<T, F>(&self, call: F) -> Result<T, ErrorCode> where T: Send + 'static, F: FnOnce() -> T + Send + 'static {
+        self.run_or_discard(call, |_| ())
+    }
+
+    ///
+    /// Executes the call like `run`. The result of a call that completes after the caller timed out is passed to
+    /// `discard` on the worker, so the handles it holds can be released.
+    ///
+    pub fn run_or_discard<T, F, D>(&self, call: F, discard: D) -> Result<T, ErrorCode>
+        where T: Send + 'static, F: FnOnce() -> T + Send + 'static, D: FnOnce(T) + Send + 'static {
+        let (sender, receiver) = mpsc::channel();
+        // set by the caller once it gives up on the call, the worker discards the result from then on
+        let abandoned = Arc::new(Mutex::new(false));
+        let job_abandoned = abandoned.clone();
+        let mut call = Some((call, discard));
+
+        let job = Job {
+            deadline: Instant::now() + self.timeout,
+            call: Box::new(move || {
+                if let Some((call, discard)) = call.take() {
+                    let result = call();
+
+                    let unsent = match job_abandoned.lock() {
+                        Ok(abandoned) if !*abandoned => sender.send(result).err().map(|err| err.0),
+                        _ => Some(result),
+                    };
+
+                    if let Some(result) = unsent {
+                        discard(result);
+                    }
+                }
+            }),
+        };
+
+        match check_result!(self.queue.lock(), Err(ErrorCode::InvalidState)).try_send(job) {
+            Err(TrySendError::Full(_)) => {
+                warn!("Worker runtime queue is full");
+                return Err(ErrorCode::QueueFull);
+            },
+            Err(TrySendError::Disconnected(_)) => return Err(ErrorCode::InvalidState),
+            Ok(()) => (),
+        }
+
+        match receiver.recv_timeout(self.timeout) {
+            Err(RecvTimeoutError::Timeout) => {
+                *check_result!(abandoned.lock(), Err(ErrorCode::InvalidState)) = true;
+
+                // the result may have been sent right before the call was abandoned
+                match receiver.try_recv() {
+                    Ok(result) => Ok(result),
+                    Err(_) => {
+                        warn!("Call did not complete within {:?}", self.timeout);
+                        Err(ErrorCode::Timeout)
+                    },
+                }
+            },
+            Err(RecvTimeoutError::Disconnected) => Err(ErrorCode::InvalidState),
+            Ok(result) => Ok(result),
+        }
+    }
+}
+
+///
+/// Worker runtimes by their config, started on first use and shared by the storages with the same config.
+///
+pub struct WorkerRuntimes {
+    map: Mutex<HashMap<RuntimeConfig, Arc<WorkerRuntime>>>,
+}
+
+impl WorkerRuntimes {
+    pub fn new() -> Self {
+        WorkerRuntimes { map: Mutex::new(HashMap::new()) }
+    }
+
+    ///
+    /// Runtime for the config, None if the runtime is not enabled.
+    ///
+    pub fn get(&self, config: &RuntimeConfig) -> Result<Option<Arc<WorkerRuntime>>, ErrorCode> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let mut map = check_result!(self.map.lock(), Err(ErrorCode::InvalidState));
+
+        if let Some(runtime) = map.get(config) {
+            return Ok(Some(runtime.clone()));
+        }
+
+        let runtime = Arc::new(WorkerRuntime::start(config)?);
+        map.insert(*config, runtime.clone());
+
+        Ok(Some(runtime))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    fn config(workers: usize, queue_size: usize, timeout: u64) -> RuntimeConfig {
+        RuntimeConfig { enabled: true, workers, queue_size, timeout, ..Default::default() }
+    }
+
+    #[test]
+    fn runtime_returns_call_results() {
+        let runtime = WorkerRuntime::start(&config(2, 4, 1000)).unwrap();
+
+        assert_eq!(runtime.run(|| 2 + 2).unwrap(), 4);
+        assert_eq!(runtime.run(|| "done".to_string()).unwrap(), "done");
+    }
+
+    #[test]
+    fn runtime_times_out_slow_calls() {
+        let runtime = WorkerRuntime::start(&config(1, 4, 50)).unwrap();
+
+        assert_eq!(runtime.run(|| thread::sleep(Duration::from_millis(500))).unwrap_err(), ErrorCode::Timeout);
+    }
+
+    #[test]
+    fn runtime_discards_results_of_timed_out_calls() {
+        let runtime = WorkerRuntime::start(&config(1, 4, 50)).unwrap();
+        let (discarded, discarded_results) = channel();
+
+        let result = runtime.run_or_discard(|| { thread::sleep(Duration::from_millis(200)); 7 }, move |result| discarded.send(result).unwrap());
+        assert_eq!(result.unwrap_err(), ErrorCode::Timeout);
+        assert_eq!(discarded_results.recv_timeout(Duration::from_millis(1000)).unwrap(), 7);
+
+        assert_eq!(runtime.run_or_discard(|| 8, |_| panic!("result of a completed call discarded")).unwrap(), 8);
+    }
+
+    #[test]
+    fn runtime_rejects_calls_when_queue_is_full() {
+        let runtime = Arc::new(WorkerRuntime::start(&config(1, 1, 5000)).unwrap());
+        let (release, blocked) = channel::<()>();
+
+        let busy = {
+            let runtime = runtime.clone();
+            thread::spawn(move || runtime.run(move || { let _ = blocked.recv(); }))
+        };
+
+        // wait for the worker to pick up the blocking call, then fill the queue
+        thread::sleep(Duration::from_millis(100));
+
+        let queued = {
+            let runtime = runtime.clone();
+            thread::spawn(move || runtime.run(|| 1))
+        };
+
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(runtime.run(|| 2).unwrap_err(), ErrorCode::QueueFull);
+
+        release.send(()).unwrap();
+        busy.join().unwrap().unwrap();
+        assert_eq!(queued.join().unwrap().unwrap(), 1);
+    }
+
+    #[test]
+    fn runtime_survives_panicking_calls() {
+        let runtime = WorkerRuntime::start(&config(1, 4, 1000)).unwrap();
+
+        assert_eq!(runtime.run(|| -> u32 { panic!("call failed") }).unwrap_err(), ErrorCode::InvalidState);
+        assert_eq!(runtime.run(|| 1).unwrap(), 1);
+    }
+
+    #[test]
+    fn runtime_rejects_empty_config() {
+        assert_eq!(WorkerRuntime::start(&config(0, 4, 1000)).err(), Some(ErrorCode::InvalidStructure));
+        assert_eq!(WorkerRuntime::start(&config(1, 0, 1000)).err(), Some(ErrorCode::InvalidStructure));
+    }
+}
@@ -12,6 +12,9 @@ use test_utils::helper_functions::{random_string, random_name};
 // External dependencies
 extern crate libc;
 use libc::c_char;
+
+extern crate mysql;
+use mysql::Conn;
 use std::collections::HashMap;
 
 #[macro_use]
@@ -67,6 +70,27 @@ mod high_casees {
             test_wallet
         }
 
+        ///
+        ///  Wallet created and opened with the config option `key` set to `value`
+        ///
+        fn new_with_config(key: &str, value: serde_json::Value) -> Self {
+            let mut test_wallet = TestWallet::new_default(true);
+            test_wallet.config = CString::new(TEST_ENV.get_config_with(key, value)).unwrap();
+            test_wallet._create();
+            test_wallet.is_mock = false;
+            test_wallet.handle = test_wallet._open();
+
+            test_wallet
+        }
+
+        ///
+        ///  Opens the wallet again with the config option `key` set to `value`
+        ///
+        fn reopen_with_config(&mut self, key: &str, value: serde_json::Value) {
+            self.config = CString::new(TEST_ENV.get_config_with(key, value)).unwrap();
+            self.handle = self._open();
+        }
+
         fn _create(&self) {
             let err = api::create_storage(self.name.as_ptr(), self.config.as_ptr(), self.credentials.as_ptr(), self.metadata.as_ptr());
             assert_eq!(err, ErrorCode::Success);
@@ -316,1435 +340,3766 @@ mod high_casees {
         assert_eq!(err, ErrorCode::InvalidStructure);
     }
 
-    /** Storage OPEN Tests */
+    /** Storage RENAME and COPY Tests */
 
-    #[test]
-    fn test_open() {
-        let wallet = TestWallet::new_default(false);
+    fn get_storage_metadata(wallet: &TestWallet) -> CString {
+        let mut metadata_p: *const c_char = ptr::null_mut();
+        let mut metadata_handle: i32 = -1;
 
-        let mut handle: i32 = -1;
+        let err = api::get_metadata(wallet.handle, &mut metadata_p, &mut metadata_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        let metadata = unsafe { CStr::from_ptr(metadata_p) }.to_owned();
+
+        let err = api::free_metadata(wallet.handle, metadata_handle);
         assert_eq!(err, ErrorCode::Success);
+
+        metadata
     }
 
     #[test]
-    fn test_open_invalid_wallet_name() {
-        let wallet = TestWallet::new_default(true);
+    fn test_rename_storage() {
+        let mut wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let mut handle: i32 = -1;
+        let old_name = wallet.name.clone();
+        let new_name = CString::new(random_name()).unwrap();
 
-        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        let err = api::mysql_storage_rename_storage(old_name.as_ptr(), new_name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut handle: i32 = -1;
+        let err = api::open_storage(old_name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
         assert_eq!(err, ErrorCode::WalletNotFound);
+
+        wallet.name = new_name;
+        wallet.handle = wallet._open();
+
+        assert_eq!(get_storage_metadata(&wallet), wallet.metadata);
+        assert_eq!(get_record_version(&wallet, &record), 1);
     }
 
     #[test]
-    fn test_open_bad_config_format() {
-        let wallet = TestWallet::new_default(true);
-
-        let bad_config = CString::new("..").unwrap();
-        let mut handle: i32 = -1;
+    fn test_rename_storage_name_conflict() {
+        let wallet = TestWallet::new_default(false);
+        let other_wallet = TestWallet::new_default(false);
 
-        let err = api::open_storage(wallet.name.as_ptr(), bad_config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        let err = api::mysql_storage_rename_storage(wallet.name.as_ptr(), other_wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::WalletAlreadyExistsError);
     }
 
     #[test]
-    fn test_open_null_config() {
+    fn test_rename_storage_not_found() {
         let wallet = TestWallet::new_default(true);
+        let new_name = CString::new(random_name()).unwrap();
 
-        let mut handle: i32 = -1;
-
-        let err = api::open_storage(wallet.name.as_ptr(), std::ptr::null(), wallet.credentials.as_ptr(), &mut handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        let err = api::mysql_storage_rename_storage(wallet.name.as_ptr(), new_name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::WalletNotFound);
     }
 
     #[test]
-    fn test_open_bad_credentials_format() {
+    fn test_rename_storage_null_config() {
         let wallet = TestWallet::new_default(true);
+        let new_name = CString::new(random_name()).unwrap();
 
-        let bad_credentials = CString::new("..").unwrap();
-        let mut handle: i32 = -1;
-
-        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), bad_credentials.as_ptr(), &mut handle);
+        let err = api::mysql_storage_rename_storage(wallet.name.as_ptr(), new_name.as_ptr(), std::ptr::null(), wallet.credentials.as_ptr());
         assert_eq!(err, ErrorCode::InvalidStructure);
     }
 
     #[test]
-    fn test_open_null_credentials() {
-        let wallet = TestWallet::new_default(true);
+    fn test_copy_storage() {
+        let wallet = TestWallet::new_default(false);
+        let records = add_records_with_type_prefix(&wallet, &random_string(10), 3);
 
-        let mut handle: i32 = -1;
+        let mut copy = TestWallet::new_default(true);
 
-        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), std::ptr::null(), &mut handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        let err = api::mysql_storage_copy_storage(wallet.name.as_ptr(), copy.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        copy.is_mock = false;
+        copy.handle = copy._open();
+
+        assert_eq!(get_storage_metadata(&copy), wallet.metadata);
+
+        let mut search_handle: i32 = -1;
+        let err = api::search_all_records(copy.handle, &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut found = fetch_search_types_and_ids(&copy, search_handle);
+        found.sort();
+
+        let mut expected: Vec<(CString, CString)> = records.iter().map(|record| (record.type_.clone(), record.id.clone())).collect();
+        expected.sort();
+
+        assert_eq!(found, expected);
+
+        // the copy is independent of the source wallet
+        let err = api::delete_record(copy.handle, records[0].type_.as_ptr(), records[0].id.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(get_record_version(&wallet, &records[0]), 1);
     }
 
     #[test]
-    fn test_open_bad_data_in_config() {
-        let wallet = TestWallet::new_default(true);
+    fn test_copy_storage_name_conflict() {
+        let wallet = TestWallet::new_default(false);
+        let other_wallet = TestWallet::new_default(false);
 
-        let bad_config = CString::new(r#"{"key": "value"}"#).unwrap();
-        let mut handle: i32 = -1;
+        let err = api::mysql_storage_copy_storage(wallet.name.as_ptr(), other_wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::WalletAlreadyExistsError);
+    }
 
-        let err = api::open_storage(wallet.name.as_ptr(), bad_config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+    #[test]
+    fn test_copy_storage_not_found() {
+        let wallet = TestWallet::new_default(true);
+        let copy = TestWallet::new_default(true);
+
+        let err = api::mysql_storage_copy_storage(wallet.name.as_ptr(), copy.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::WalletNotFound);
     }
 
     #[test]
-    fn test_open_bad_data_in_credentials() {
+    fn test_copy_storage_bad_credentials_format() {
         let wallet = TestWallet::new_default(true);
+        let copy = TestWallet::new_default(true);
 
-        let bad_credentials = CString::new(r#"{"key": "value"}"#).unwrap();
-        let mut handle: i32 = -1;
+        let bad_credentials = CString::new("..").unwrap();
 
-        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), bad_credentials.as_ptr(), &mut handle);
+        let err = api::mysql_storage_copy_storage(wallet.name.as_ptr(), copy.name.as_ptr(), wallet.config.as_ptr(), bad_credentials.as_ptr());
         assert_eq!(err, ErrorCode::InvalidStructure);
     }
 
-    /** Storage ADD_RECORD, GET_RECORD, DELETE_RECORD Tests WITH TAGS */
-
-    #[test]
-    fn test_add_record_with_tags_then_fetch_all() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    /** Storage LIST and STATS Tests */
 
-        let options_json = fetch_options(true, true, true);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+    fn list_storages(wallet: &TestWallet, options_json: &str) -> Result<serde_json::Value, ErrorCode> {
+        let options_json = CString::new(options_json).unwrap();
+        let mut list_json_p: *const c_char = ptr::null_mut();
+        let mut list_handle: i32 = -1;
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        let err = api::mysql_storage_list_storages(wallet.config.as_ptr(), wallet.credentials.as_ptr(), options_json.as_ptr(), &mut list_json_p, &mut list_handle);
+        if err != ErrorCode::Success {
+            return Err(err);
+        }
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let list_json = unsafe { CStr::from_ptr(list_json_p).to_str().unwrap().to_string() };
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let err = api::mysql_storage_free_json(list_handle);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+        Ok(serde_json::from_str(&list_json).unwrap())
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+    fn get_storage_stats(wallet: &TestWallet) -> Result<serde_json::Value, ErrorCode> {
+        let mut stats_json_p: *const c_char = ptr::null_mut();
+        let mut stats_handle: i32 = -1;
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        let err = api::mysql_storage_get_storage_stats(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), &mut stats_json_p, &mut stats_handle);
+        if err != ErrorCode::Success {
+            return Err(err);
+        }
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        let stats_json = unsafe { CStr::from_ptr(stats_json_p).to_str().unwrap().to_string() };
 
-        let err = api::free_record(wallet.handle, record_handle);
+        let err = api::mysql_storage_free_json(stats_handle);
         assert_eq!(err, ErrorCode::Success);
+
+        Ok(serde_json::from_str(&stats_json).unwrap())
     }
 
-    #[test]
-    fn test_add_record_with_tags_then_fetch_only_type() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    fn create_wallets_with_prefix(prefix: &str, num_of_wallets: usize) -> Vec<TestWallet> {
+        let mut wallets: Vec<TestWallet> = Vec::new();
 
-        let options_json = fetch_options(true, false, false);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        for i in 0..num_of_wallets {
+            let mut wallet = TestWallet::new_default(true);
+            wallet.name = CString::new(format!("{}{}", prefix, i)).unwrap();
+            wallet.is_mock = false;
+            wallet._create();
+            wallet.handle = wallet._open();
+            wallets.push(wallet);
+        }
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        wallets
+    }
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+    #[test]
+    fn test_list_storages_with_prefix_in_pages() {
+        let prefix = random_name();
+        let wallets = create_wallets_with_prefix(&prefix, 3);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        let page = list_storages(&wallets[0], &json!({"prefix": prefix, "limit": 2}).to_string()).unwrap();
+        assert_eq!(page["wallets"], json!([format!("{}0", prefix), format!("{}1", prefix)]));
+        assert_eq!(page["next"], json!(format!("{}1", prefix)));
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+        let page = list_storages(&wallets[0], &json!({"prefix": prefix, "limit": 2, "after": page["next"]}).to_string()).unwrap();
+        assert_eq!(page["wallets"], json!([format!("{}2", prefix)]));
+        assert_eq!(page["next"], serde_json::Value::Null);
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    #[test]
+    fn test_list_storages_prefix_is_not_a_pattern() {
+        let wallet = TestWallet::new_default(false);
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let page = list_storages(&wallet, r#"{"prefix": "%"}"#).unwrap();
+        assert_eq!(page["wallets"], json!([]));
+    }
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+    #[test]
+    fn test_list_storages_invalid_options() {
+        let wallet = TestWallet::new_default(true);
+
+        assert_eq!(list_storages(&wallet, r#"{"limit": 0}"#).unwrap_err(), ErrorCode::InvalidStructure);
+        assert_eq!(list_storages(&wallet, r#"{"limit": "ten"}"#).unwrap_err(), ErrorCode::InvalidStructure);
     }
 
     #[test]
-    fn test_add_record_with_tags_then_fetch_only_value() {
+    fn test_get_storage_stats() {
         let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
 
-        let options_json = fetch_options(false, true, false);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let record1 = TestRecord::new_default(true);
+        let mut record2 = TestRecord::new_default(false);
+        record2.type_ = record1.type_.clone();
+        let record3 = TestRecord::new_default(true);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        wallet.add_record(&record1);
+        wallet.add_record(&record2);
+        wallet.add_record(&record3);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let stats = get_storage_stats(&wallet).unwrap();
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        assert_eq!(stats["name"], json!(wallet.name.to_str().unwrap()));
+        assert_eq!(stats["metadataBytes"], json!(wallet.metadata.as_bytes().len()));
+        assert_eq!(stats["itemCount"], json!(3));
+        assert_eq!(stats["valueBytes"], json!(record1.value.len() + record2.value.len() + record3.value.len()));
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let types = stats["types"].as_array().unwrap();
+        assert_eq!(types.len(), 2);
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+        let type1_stats = types.iter().find(|type_stats| type_stats["type"] == json!(record1.type_.to_str().unwrap())).unwrap();
+        assert_eq!(type1_stats["itemCount"], json!(2));
+        assert_eq!(type1_stats["valueBytes"], json!(record1.value.len() + record2.value.len()));
+    }
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    #[test]
+    fn test_get_storage_stats_empty() {
+        let wallet = TestWallet::new_default(false);
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let stats = get_storage_stats(&wallet).unwrap();
+
+        assert_eq!(stats["itemCount"], json!(0));
+        assert_eq!(stats["valueBytes"], json!(0));
+        assert_eq!(stats["tagsBytes"], json!(0));
+        assert_eq!(stats["types"], json!([]));
     }
 
     #[test]
-    fn test_add_record_with_tags_then_fetch_only_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    fn test_get_storage_stats_not_found() {
+        let wallet = TestWallet::new_default(true);
 
-        let options_json = fetch_options(false, false, true);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        assert_eq!(get_storage_stats(&wallet).unwrap_err(), ErrorCode::WalletNotFound);
+    }
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+    /** Storage VERIFY Tests */
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+    fn verify_storage(wallet: &TestWallet) -> Result<serde_json::Value, ErrorCode> {
+        let mut report_json_p: *const c_char = ptr::null_mut();
+        let mut report_handle: i32 = -1;
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let err = api::mysql_storage_verify_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), &mut report_json_p, &mut report_handle);
+        if err != ErrorCode::Success {
+            return Err(err);
+        }
+
+        let report_json = unsafe { CStr::from_ptr(report_json_p).to_str().unwrap().to_string() };
+
+        let err = api::mysql_storage_free_json(report_handle);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        Ok(serde_json::from_str(&report_json).unwrap())
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    fn copy_of(wallet: &TestWallet) -> TestWallet {
+        let mut copy = TestWallet::new_default(true);
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let err = api::mysql_storage_copy_storage(wallet.name.as_ptr(), copy.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
         assert_eq!(err, ErrorCode::Success);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        copy.is_mock = false;
+        copy.handle = copy._open();
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        copy
     }
 
     #[test]
-    fn test_add_record_with_tags_then_fetch_type_value() {
+    fn test_verify_storage_copy_matches() {
         let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+        for _ in 0..10 {
+            wallet.add_record(&TestRecord::new_default(true));
+        }
 
-        let options_json = fetch_options(true, true, false);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let copy = copy_of(&wallet);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        let report = verify_storage(&wallet).unwrap();
+        let copy_report = verify_storage(&copy).unwrap();
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(report["itemCount"], json!(10));
+        assert_eq!(report["invalidTagsCount"], json!(0));
+        assert_eq!(report["root"], copy_report["root"]);
+        assert_eq!(report["batches"], copy_report["batches"]);
+        assert_eq!(report["metadataHash"], copy_report["metadataHash"]);
+    }
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+    #[test]
+    fn test_verify_storage_detects_changed_record() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+        let copy = copy_of(&wallet);
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        let value = vec![5, 6, 7];
+        let err = api::update_record_value(copy.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let report = verify_storage(&wallet).unwrap();
+        let copy_report = verify_storage(&copy).unwrap();
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(report["itemCount"], copy_report["itemCount"]);
+        assert_ne!(report["root"], copy_report["root"]);
+        assert_ne!(report["batches"][0]["hash"], copy_report["batches"][0]["hash"]);
     }
 
     #[test]
-    fn test_add_record_with_tags_then_fetch_type_tags() {
+    fn test_verify_storage_invalid_tags() {
         let wallet = TestWallet::new_default(false);
         let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let tags_json = CString::new(r#"{"count": 5}"#).unwrap();
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let report = verify_storage(&wallet).unwrap();
+
+        assert_eq!(report["invalidTagsCount"], json!(1));
+        assert_eq!(report["invalidTags"][0]["id"], json!(record.id.to_str().unwrap()));
+        assert_eq!(report["invalidTags"][0]["error"], json!("value of the tag count is not a string"));
+    }
+
+    #[test]
+    fn test_verify_storage_empty() {
+        let wallet = TestWallet::new_default(false);
+
+        let report = verify_storage(&wallet).unwrap();
+
+        assert_eq!(report["itemCount"], json!(0));
+        assert_eq!(report["batches"], json!([]));
+        assert_eq!(report["root"], json!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"));
+    }
+
+    #[test]
+    fn test_verify_storage_not_found() {
+        let wallet = TestWallet::new_default(true);
+
+        assert_eq!(verify_storage(&wallet).unwrap_err(), ErrorCode::WalletNotFound);
+    }
+
+    /** Storage SNAPSHOT Tests */
+
+    fn snapshot_storage(wallet: &TestWallet, label: &str) -> ErrorCode {
+        let label = CString::new(label).unwrap();
+        api::mysql_storage_snapshot_storage(wallet.name.as_ptr(), label.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr())
+    }
+
+    fn restore_snapshot(wallet: &TestWallet, label: &str) -> ErrorCode {
+        let label = CString::new(label).unwrap();
+        api::mysql_storage_restore_snapshot(wallet.name.as_ptr(), label.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr())
+    }
+
+    fn drop_snapshot(wallet: &TestWallet, label: &str) -> ErrorCode {
+        let label = CString::new(label).unwrap();
+        api::mysql_storage_drop_snapshot(wallet.name.as_ptr(), label.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr())
+    }
+
+    fn list_snapshots(wallet: &TestWallet) -> Result<serde_json::Value, ErrorCode> {
+        let mut list_json_p: *const c_char = ptr::null_mut();
+        let mut list_handle: i32 = -1;
+
+        let err = api::mysql_storage_list_snapshots(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), &mut list_json_p, &mut list_handle);
+        if err != ErrorCode::Success {
+            return Err(err);
+        }
+
+        let list_json = unsafe { CStr::from_ptr(list_json_p).to_str().unwrap().to_string() };
+
+        let err = api::mysql_storage_free_json(list_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        Ok(serde_json::from_str(&list_json).unwrap())
+    }
+
+    fn snapshot_labels(wallet: &TestWallet) -> Vec<String> {
+        let list = list_snapshots(wallet).unwrap();
+
+        list["snapshots"].as_array().unwrap().iter().map(|snapshot| snapshot["label"].as_str().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn test_snapshot_and_restore_storage() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+        let metadata = get_storage_metadata(&wallet);
+
+        assert_eq!(snapshot_storage(&wallet, "before-rotation"), ErrorCode::Success);
+
+        let value = vec![5, 6, 7];
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
+        assert_eq!(err, ErrorCode::Success);
+
+        let new_record = TestRecord::new_default(false);
+        wallet.add_record(&new_record);
+
+        let new_metadata = CString::new(random_string(100)).unwrap();
+        let err = api::set_metadata(wallet.handle, new_metadata.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        assert_eq!(restore_snapshot(&wallet, "before-rotation"), ErrorCode::Success);
+
+        let (restored_value, restored_tags) = get_record_value_and_tags(&wallet, &record);
+        assert_eq!(restored_value, record.value);
+        assert_eq!(restored_tags, serde_json::from_str::<serde_json::Value>(record.tags_json.to_str().unwrap()).unwrap());
+        assert_eq!(get_storage_metadata(&wallet), metadata);
+
+        let options_json = fetch_options(false, false, false);
+        let mut record_handle = -1;
+        let err = api::get_record(wallet.handle, new_record.type_.as_ptr(), new_record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::ItemNotFound);
+
+        assert_eq!(snapshot_labels(&wallet), vec!["before-rotation"]);
+    }
+
+    #[test]
+    fn test_restore_snapshot_conflicts_with_stale_version() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        assert_eq!(snapshot_storage(&wallet, "v1"), ErrorCode::Success);
+
+        let value = vec![5, 6, 7];
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
+        assert_eq!(err, ErrorCode::Success);
+        let stale_version = get_record_version(&wallet, &record);
+
+        assert_eq!(restore_snapshot(&wallet, "v1"), ErrorCode::Success);
+        assert!(get_record_version(&wallet, &record) > stale_version);
+
+        let err = api::mysql_storage_update_record_value_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len(), stale_version);
+        assert_eq!(err, ErrorCode::VersionConflict);
+    }
+
+    #[test]
+    fn test_list_snapshots() {
+        let wallet = TestWallet::new_default(false);
+        for _ in 0..3 {
+            wallet.add_record(&TestRecord::new_default(true));
+        }
+
+        assert_eq!(list_snapshots(&wallet).unwrap(), json!({"snapshots": []}));
+
+        assert_eq!(snapshot_storage(&wallet, "first"), ErrorCode::Success);
+        wallet.add_record(&TestRecord::new_default(true));
+        assert_eq!(snapshot_storage(&wallet, "second"), ErrorCode::Success);
+
+        let list = list_snapshots(&wallet).unwrap();
+        assert_eq!(list["snapshots"][0]["label"], json!("first"));
+        assert_eq!(list["snapshots"][0]["itemCount"], json!(3));
+        assert_eq!(list["snapshots"][1]["label"], json!("second"));
+        assert_eq!(list["snapshots"][1]["itemCount"], json!(4));
+        assert!(list["snapshots"][1]["createdAt"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_drop_snapshot() {
+        let wallet = TestWallet::new_default(false);
+
+        assert_eq!(snapshot_storage(&wallet, "first"), ErrorCode::Success);
+        assert_eq!(snapshot_storage(&wallet, "second"), ErrorCode::Success);
+
+        assert_eq!(drop_snapshot(&wallet, "first"), ErrorCode::Success);
+        assert_eq!(snapshot_labels(&wallet), vec!["second"]);
+
+        assert_eq!(drop_snapshot(&wallet, "first"), ErrorCode::SnapshotNotFound);
+        assert_eq!(restore_snapshot(&wallet, "first"), ErrorCode::SnapshotNotFound);
+    }
+
+    #[test]
+    fn test_snapshot_storage_label_already_exists() {
+        let wallet = TestWallet::new_default(false);
+
+        assert_eq!(snapshot_storage(&wallet, "label"), ErrorCode::Success);
+        assert_eq!(snapshot_storage(&wallet, "label"), ErrorCode::SnapshotAlreadyExists);
+    }
+
+    #[test]
+    fn test_snapshot_storage_invalid_label() {
+        let wallet = TestWallet::new_default(false);
+
+        assert_eq!(snapshot_storage(&wallet, ""), ErrorCode::InvalidStructure);
+        assert_eq!(snapshot_storage(&wallet, &random_string(256)), ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_snapshot_storage_max_snapshots_retention() {
+        let mut wallet = TestWallet::new_default(false);
+
+        let mut config: serde_json::Value = serde_json::from_str(&TEST_ENV.get_config()).unwrap();
+        config["snapshot_retention"] = json!({"max_snapshots": 2});
+        wallet.config = CString::new(config.to_string()).unwrap();
+
+        for label in ["first", "second", "third"].iter() {
+            assert_eq!(snapshot_storage(&wallet, label), ErrorCode::Success);
+        }
+
+        assert_eq!(snapshot_labels(&wallet), vec!["second", "third"]);
+    }
+
+    #[test]
+    fn test_snapshot_storage_not_found() {
+        let wallet = TestWallet::new_default(true);
+
+        assert_eq!(snapshot_storage(&wallet, "label"), ErrorCode::WalletNotFound);
+        assert_eq!(list_snapshots(&wallet).unwrap_err(), ErrorCode::WalletNotFound);
+        assert_eq!(restore_snapshot(&wallet, "label"), ErrorCode::WalletNotFound);
+        assert_eq!(drop_snapshot(&wallet, "label"), ErrorCode::WalletNotFound);
+    }
+
+    /** Storage SHARDING Tests */
+
+    fn sharded_wallet(shard_placement: &str) -> TestWallet {
+        let mut wallet = TestWallet::new_default(true);
+        wallet.config = CString::new(TEST_ENV.get_sharded_config(shard_placement)).unwrap();
+        wallet.is_mock = false;
+        wallet._create();
+        wallet.handle = wallet._open();
+
+        wallet
+    }
+
+    fn open_on_shard(wallet: &TestWallet, index: usize) -> ErrorCode {
+        let config = CString::new(TEST_ENV.get_shard_config(index)).unwrap();
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        if err == ErrorCode::Success {
+            api::close_storage(handle);
+        }
+
+        err
+    }
+
+    fn current_shard(wallet: &TestWallet) -> usize {
+        let shards: Vec<usize> = (0..2).filter(|index| open_on_shard(wallet, *index) == ErrorCode::Success).collect();
+        assert_eq!(shards.len(), 1);
+
+        shards[0]
+    }
+
+    fn rebalance_storage(wallet: &TestWallet, shard_name: Option<&str>) -> ErrorCode {
+        let shard_name = shard_name.map(|shard_name| CString::new(shard_name).unwrap());
+
+        api::mysql_storage_rebalance_storage(
+            wallet.name.as_ptr(),
+            shard_name.as_ref().map(|shard_name| shard_name.as_ptr()).unwrap_or(ptr::null()),
+            wallet.config.as_ptr(),
+            wallet.credentials.as_ptr()
+        )
+    }
+
+    #[test]
+    fn test_sharded_create_places_wallet_on_one_shard() {
+        let wallet = sharded_wallet("consistent_hash");
+
+        current_shard(&wallet);
+    }
+
+    #[test]
+    fn test_sharded_delete() {
+        let wallet = sharded_wallet("directory");
+
+        let err = api::delete_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        assert_eq!(open_on_shard(&wallet, 0), ErrorCode::WalletNotFound);
+        assert_eq!(open_on_shard(&wallet, 1), ErrorCode::WalletNotFound);
+
+        // the directory entry is removed as well, so the name can be used again
+        wallet._create();
+    }
+
+    #[test]
+    fn test_sharded_create_existing_on_other_shard() {
+        let wallet = sharded_wallet("consistent_hash");
+        let other_shard = 1 - current_shard(&wallet);
+
+        let err = api::delete_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let shard_config = CString::new(TEST_ENV.get_shard_config(other_shard)).unwrap();
+        let err = api::create_storage(wallet.name.as_ptr(), shard_config.as_ptr(), wallet.credentials.as_ptr(), wallet.metadata.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::create_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), wallet.metadata.as_ptr());
+        assert_eq!(err, ErrorCode::WalletAlreadyExistsError);
+    }
+
+    #[test]
+    fn test_rebalance_storage() {
+        for shard_placement in vec!["consistent_hash", "directory"] {
+            let mut wallet = sharded_wallet(shard_placement);
+            let record = TestRecord::new_default(true);
+            wallet.add_record(&record);
+
+            let hashed_shard = current_shard(&wallet);
+            let other_shard = 1 - hashed_shard;
+
+            let err = rebalance_storage(&wallet, Some(&format!("shard-{}", other_shard)));
+            assert_eq!(err, ErrorCode::Success);
+            assert_eq!(current_shard(&wallet), other_shard);
+
+            api::close_storage(wallet.handle);
+            wallet.handle = wallet._open();
+            assert_eq!(get_record_value_and_tags(&wallet, &record).0, record.value);
+
+            // without a shard the wallet goes back to the shard it is placed on by consistent hashing
+            let err = rebalance_storage(&wallet, None);
+            assert_eq!(err, ErrorCode::Success);
+            assert_eq!(current_shard(&wallet), hashed_shard);
+
+            let err = rebalance_storage(&wallet, None);
+            assert_eq!(err, ErrorCode::Success);
+        }
+    }
+
+    #[test]
+    fn test_rebalance_storage_unknown_shard() {
+        let wallet = sharded_wallet("consistent_hash");
+
+        assert_eq!(rebalance_storage(&wallet, Some("unknown")), ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_rebalance_storage_not_sharded() {
+        let wallet = TestWallet::new_default(false);
+
+        assert_eq!(rebalance_storage(&wallet, None), ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_rebalance_storage_not_found() {
+        let mut wallet = TestWallet::new_default(true);
+        wallet.config = CString::new(TEST_ENV.get_sharded_config("consistent_hash")).unwrap();
+
+        assert_eq!(rebalance_storage(&wallet, None), ErrorCode::WalletNotFound);
+    }
+
+    #[test]
+    fn test_list_storages_across_shards() {
+        let prefix = random_name();
+        let mut wallets: Vec<TestWallet> = Vec::new();
+
+        for i in 0..3 {
+            let mut wallet = TestWallet::new_default(true);
+            wallet.name = CString::new(format!("{}{}", prefix, i)).unwrap();
+            wallet.config = CString::new(TEST_ENV.get_sharded_config("consistent_hash")).unwrap();
+            wallet.is_mock = false;
+            wallet._create();
+
+            let err = rebalance_storage(&wallet, Some(&format!("shard-{}", i % 2)));
+            assert_eq!(err, ErrorCode::Success);
+
+            wallets.push(wallet);
+        }
+
+        let page = list_storages(&wallets[0], &json!({"prefix": prefix, "limit": 2}).to_string()).unwrap();
+        assert_eq!(page["wallets"], json!([format!("{}0", prefix), format!("{}1", prefix)]));
+
+        let page = list_storages(&wallets[0], &json!({"prefix": prefix, "limit": 2, "after": page["next"]}).to_string()).unwrap();
+        assert_eq!(page["wallets"], json!([format!("{}2", prefix)]));
+        assert_eq!(page["next"], json!(null));
+    }
+
+    /** Storage MOVE Tests */
+
+    fn move_storage(wallet: &TestWallet, target_config: &CString) -> ErrorCode {
+        api::mysql_storage_move_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), target_config.as_ptr(), wallet.credentials.as_ptr())
+    }
+
+    #[test]
+    fn test_move_storage() {
+        let mut wallet = TestWallet::new_default(false);
+        let records: Vec<TestRecord> = (0..250).map(|_| TestRecord::new_default(true)).collect();
+        for record in records.iter() {
+            wallet.add_record(record);
+        }
+        let metadata = get_storage_metadata(&wallet);
+
+        let target_config = CString::new(TEST_ENV.get_shard_config(1)).unwrap();
+
+        let err = move_storage(&wallet, &target_config);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(open_on_shard(&wallet, 0), ErrorCode::WalletNotFound);
+
+        api::close_storage(wallet.handle);
+        wallet.config = target_config;
+        wallet.handle = wallet._open();
+
+        assert_eq!(get_storage_metadata(&wallet), metadata);
+        for record in records.iter() {
+            assert_eq!(get_record_value_and_tags(&wallet, record).0, record.value);
+        }
+        assert_eq!(get_storage_stats(&wallet).unwrap()["itemCount"], json!(250));
+    }
+
+    #[test]
+    fn test_move_storage_already_exists_in_target() {
+        let wallet = TestWallet::new_default(false);
+
+        let target_config = CString::new(TEST_ENV.get_shard_config(1)).unwrap();
+        let err = api::create_storage(wallet.name.as_ptr(), target_config.as_ptr(), wallet.credentials.as_ptr(), wallet.metadata.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = move_storage(&wallet, &target_config);
+        assert_eq!(err, ErrorCode::WalletAlreadyExistsError);
+        assert_eq!(open_on_shard(&wallet, 0), ErrorCode::Success);
+
+        let err = api::delete_storage(wallet.name.as_ptr(), target_config.as_ptr(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_move_storage_not_found() {
+        let wallet = TestWallet::new_default(true);
+
+        let target_config = CString::new(TEST_ENV.get_shard_config(1)).unwrap();
+
+        assert_eq!(move_storage(&wallet, &target_config), ErrorCode::WalletNotFound);
+    }
+
+    #[test]
+    fn test_move_storage_null_target_config() {
+        let wallet = TestWallet::new_default(false);
+
+        let err = api::mysql_storage_move_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), ptr::null(), wallet.credentials.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    /** Storage OPEN Tests */
+
+    #[test]
+    fn test_open() {
+        let wallet = TestWallet::new_default(false);
+
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_open_invalid_wallet_name() {
+        let wallet = TestWallet::new_default(true);
+
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::WalletNotFound);
+    }
+
+    #[test]
+    fn test_open_bad_config_format() {
+        let wallet = TestWallet::new_default(true);
+
+        let bad_config = CString::new("..").unwrap();
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), bad_config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_open_null_config() {
+        let wallet = TestWallet::new_default(true);
+
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), std::ptr::null(), wallet.credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_open_bad_credentials_format() {
+        let wallet = TestWallet::new_default(true);
+
+        let bad_credentials = CString::new("..").unwrap();
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), bad_credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_open_null_credentials() {
+        let wallet = TestWallet::new_default(true);
+
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), std::ptr::null(), &mut handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_open_bad_data_in_config() {
+        let wallet = TestWallet::new_default(true);
+
+        let bad_config = CString::new(r#"{"key": "value"}"#).unwrap();
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), bad_config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_open_invalid_table_prefix() {
+        let wallet = TestWallet::new_default(false);
+
+        let mut config: serde_json::Value = serde_json::from_str(&TEST_ENV.get_config()).unwrap();
+        config["table_prefix"] = json!("items; DROP TABLE wallets; --");
+        let bad_config = CString::new(config.to_string()).unwrap();
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), bad_config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_open_bad_data_in_credentials() {
+        let wallet = TestWallet::new_default(true);
+
+        let bad_credentials = CString::new(r#"{"key": "value"}"#).unwrap();
+        let mut handle: i32 = -1;
+
+        let err = api::open_storage(wallet.name.as_ptr(), wallet.config.as_ptr(), bad_credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    /** Storage ADD_RECORD, GET_RECORD, DELETE_RECORD Tests WITH TAGS */
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_all() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(true, true, true);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_only_type() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(true, false, false);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_only_value() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(false, true, false);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_only_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(false, false, true);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_type_value() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(true, true, false);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_type_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(true, false, true);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_value_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(false, true, true);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_none() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(false, false, false);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_then_fetch_default() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = CString::new(r#"{}"#).unwrap();
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    /** Storage ADD_RECORD, GET_RECORD, DELETE_RECORD Tests WITHOUT TAGS */
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_all() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let options_json = fetch_options(true, true, true);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_only_type() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = fetch_options(true, false, false);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_only_value() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = fetch_options(false, true, false);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_only_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = fetch_options(false, false, true);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_type_value() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = fetch_options(true, true, false);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_type_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = fetch_options(true, false, true);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_value_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = fetch_options(false, true, true);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+       let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_none() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = fetch_options(false, false, false);
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_add_record_without_tags_then_fetch_default() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = CString::new(r#"{}"#).unwrap();
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_duplicate_add_record_without_tags_then_fetch_default() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let options_json = CString::new(r#"{}"#).unwrap();
+        let mut record_handle = -1;
+        let mut id_p: *const c_char = ptr::null_mut();
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let mut type_p: *const c_char = ptr::null_mut();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::ItemAlreadyExists);
+    }
+
+    #[test]
+    fn test_add_record_invalid_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let record = TestRecord::new_default(false);
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_add_record_with_large_type_value() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let large_type = CString::new(random_string(150)).unwrap();
+
+        let err = api::add_record(wallet.handle, large_type.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::IOError);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_invalid_json_format() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let invalid_tags_json = CString::new("...").unwrap();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), invalid_tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_add_record_with_tags_invalid_json_format_1() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let invalid_tags_json = CString::new(r#""'''{"key": "value"}"#).unwrap();
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), invalid_tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_get_record_invalid_options_format() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        wallet.add_record(&record);
+
+        let options_json = CString::new("//").unwrap();
+        let mut record_handle = -1;
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_get_record_record_not_exists() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let mut record_handle = -1;
+
+        let options_json = fetch_options(false, false, false);
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
+
+    #[test]
+    fn test_delete_record() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        wallet.add_record(&record);
+
+        let err = api::delete_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_delete_unknown_record() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let err = api::delete_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr());
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
+
+    #[test]
+    fn test_delete_record_invalid_handle() {
+        let wallet = TestWallet::new_default(true);
+        let record = TestRecord::new_default(false);
+
+        let err = api::delete_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_free_record_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let record_handle = -1;
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_free_record_invalid_record_handle() {
+        let wallet = TestWallet::new_default(false);
+
+        let record_handle = -1;
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    /** Storage UPSERT_RECORD Tests */
+
+    fn upsert_record(wallet: &TestWallet, record: &TestRecord, value: &Vec<u8>, tags_json: &str, options_json: &str) -> Result<bool, ErrorCode> {
+        let tags_json = CString::new(tags_json).unwrap();
+        let options_json = CString::new(options_json).unwrap();
+        let mut inserted = false;
+
+        match api::mysql_storage_upsert_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len(), tags_json.as_ptr(), options_json.as_ptr(), &mut inserted) {
+            ErrorCode::Success => Ok(inserted),
+            err => Err(err)
+        }
+    }
+
+    fn get_record_value_and_tags(wallet: &TestWallet, record: &TestRecord) -> (Vec<u8>, serde_json::Value) {
+        let options_json = fetch_options(false, true, true);
+        let mut record_handle = -1;
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len = 0;
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len);
+        assert_eq!(err, ErrorCode::Success);
+        let value = unsafe { slice::from_raw_parts(value_p, value_len) }.to_vec();
+
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+        let tags: serde_json::Value = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        (value, tags)
+    }
+
+    #[test]
+    fn test_upsert_record_inserts_then_replaces() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let inserted = upsert_record(&wallet, &record, &record.value, record.tags_json.to_str().unwrap(), "{}").unwrap();
+        assert!(inserted);
+
+        let new_value = random_string(20).into_bytes();
+        let inserted = upsert_record(&wallet, &record, &new_value, r#"{"tag4": "value4"}"#, "{}").unwrap();
+        assert!(!inserted);
+
+        let (value, tags) = get_record_value_and_tags(&wallet, &record);
+        assert_eq!(value, new_value);
+        assert_eq!(tags, json!({"tag4": "value4"}));
+    }
+
+    #[test]
+    fn test_upsert_record_merges_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let inserted = upsert_record(&wallet, &record, &record.value, r#"{"tag1": "new_value1", "tag4": "value4"}"#, r#"{"mergeTags": true}"#).unwrap();
+        assert!(!inserted);
+
+        let (value, tags) = get_record_value_and_tags(&wallet, &record);
+        assert_eq!(value, record.value);
+        assert_eq!(tags, json!({"tag1": "new_value1", "tag2": "value2", "~tag3": "value3", "tag4": "value4"}));
+    }
+
+    #[test]
+    fn test_upsert_record_without_changes() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let inserted = upsert_record(&wallet, &record, &record.value, record.tags_json.to_str().unwrap(), "{}").unwrap();
+        assert!(!inserted);
+    }
+
+    #[test]
+    fn test_upsert_record_invalid_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let err = upsert_record(&wallet, &record, &record.value, "{tag1: value1", "{}").unwrap_err();
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_upsert_record_invalid_options() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let err = upsert_record(&wallet, &record, &record.value, record.tags_json.to_str().unwrap(), r#"{"mergeTags": "yes"}"#).unwrap_err();
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_upsert_record_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+        let record = TestRecord::new_default(true);
+
+        let err = upsert_record(&wallet, &record, &record.value, record.tags_json.to_str().unwrap(), "{}").unwrap_err();
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    /** Storage UPDATE_RECORD_VALUE Tests */
+
+    #[test]
+    fn test_update_record_value() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        wallet.add_record(&record);
+
+        let new_value = vec![2, 5, 8, 13];
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, false);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, new_value.as_slice());
+    }
+
+    #[test]
+    fn test_update_record_value_with_same_value() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        wallet.add_record(&record);
+
+        let new_value = vec![1, 2, 3, 4];
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, false);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let options_json = fetch_options(true, false, true);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
         let mut value_p: *const u8 = ptr::null_mut();
         let mut value_len_p = 0;
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, new_value.as_slice());
+    }
+
+    #[test]
+    fn test_update_value_invalid_handle() {
+        let wallet = TestWallet::new_default(true);
+        let record = TestRecord::new_default(true);
+
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len());
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_update_value_for_unknown_record() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len());
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
+
+    /** Storage ADD_RECORD_TAGS Tests */
+
+    #[test]
+    fn test_add_record_tags_record_exists_with_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        wallet.add_record(&record);
+
+        let tags_json = CString::new(r#"{"tag1": "value1", "tag2": "value2", "~tag3": "value3"}"#).unwrap();
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(),record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_add_record_tags_record_exists_without_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        wallet.add_record(&record);
+
+        let tags_json = CString::new(r#"{"tag1": "value1", "tag2": "value2", "~tag3": "value3"}"#).unwrap();
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(),record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_add_empty_record_tags_record_with_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        wallet.add_record(&record);
+
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_add_empty_record_tags_record_without_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        wallet.add_record(&record);
+
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_add_duplicate_record_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        wallet.add_record(&record);
+
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_add_record_tags_unknown_record() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
+
+    #[test]
+    fn test_add_record_tags_invalid_format() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let tags_json = CString::new(r#"{"'''tag1": "value1", "tag2": "value2", "~tag3": "value3"}"#).unwrap();
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_add_tags_invalid_handle() {
+        let wallet = TestWallet::new_default(true);
+        let record = TestRecord::new_default(true);
+
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_add_tags_for_unknown_record() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
+
+    /** Storage UPDATE_RECORD_TAGS Tests */
+
+    #[test]
+    fn test_update_tags_record_with_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+
+        wallet.add_record(&record);
+
+        let new_tags_json = CString::new(r#"{"tag1": "value1_new", "tag2": "value2_new", "~tag3": "value3_new"}"#).unwrap();
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
         let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(new_tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_update_tags_record_without_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        wallet.add_record(&record);
+
+        let new_tags_json = CString::new(r#"{"tag1": "value1_new", "tag2": "value2_new", "~tag3": "value3_new"}"#).unwrap();
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, true, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(new_tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_update_record_tags_same_value() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        wallet.add_record(&record);
+
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_update_record_tags_invalid_format() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let invalid_tags = CString::new("...").unwrap();
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), invalid_tags.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_update_record_tag_unknown_record() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
+
+    #[test]
+    fn test_update_record_tag_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+        let record = TestRecord::new_default(false);
+
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    /** Storage DELETE_RECORD_TAGS Tests */
+
+    #[test]
+    fn test_delete_record_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        wallet.add_record(&record);
+
+        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, false, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let tags_json_empty = CString::new(r#"{}"#).unwrap();
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(tags_json_empty.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_delete_unknown_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        wallet.add_record(&record);
+
+        let unknown_tags = CString::new(r#"["bogus1", "bogus2", "~bogus3"]"#).unwrap();
+        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), unknown_tags.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle = -1;
+        let options_json = fetch_options(false, false, true);
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut tags_json_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
+        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
+        assert_eq!(tags_map, expected_tags_map);
+    }
+
+    #[test]
+    fn test_delete_tags_invalid_json_format() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+
+        wallet.add_record(&record);
+
+        let tag_names = CString::new(r#"["'tag11", "tag22", "~tag33"]"#).unwrap();
+        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tag_names.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_delete_tags_empty_tag_name_list() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        wallet.add_record(&record);
+
+        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_delete_tags_unknown_record() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+
+        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
+
+    #[test]
+    fn test_delete_unknown_tags_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+        let record = TestRecord::new_default(false);
+
+        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    /** Storage RECORD VERSION Tests */
+
+    fn get_record_version(wallet: &TestWallet, record: &TestRecord) -> u64 {
+        let options_json = fetch_options(false, false, false);
+        let mut record_handle = -1;
+        let mut version: u64 = 0;
+
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::mysql_storage_get_record_version(wallet.handle, record_handle, &mut version);
+        assert_eq!(err, ErrorCode::Success);
+
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        version
+    }
+
+    #[test]
+    fn test_record_version_incremented_on_mutations() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        assert_eq!(get_record_version(&wallet, &record), 1);
+
+        let new_value = random_string(20).into_bytes();
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len());
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(get_record_version(&wallet, &record), 2);
+
+        let tags_json = CString::new(r#"{"tag4": "value4"}"#).unwrap();
+        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(get_record_version(&wallet, &record), 3);
+
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(get_record_version(&wallet, &record), 4);
+
+        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(get_record_version(&wallet, &record), 5);
+    }
+
+    #[test]
+    fn test_record_version_returned_from_search() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let new_value = random_string(20).into_bytes();
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len());
+        assert_eq!(err, ErrorCode::Success);
+
+        let query_json = CString::new("{}").unwrap();
+        let options_json = search_options(true, false, false, false, false);
+        let mut search_handle: i32 = -1;
+        let mut record_handle: i32 = -1;
+        let mut version: u64 = 0;
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        let err = api::search_records(wallet.handle, record.type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let err = api::fetch_search_next_record(wallet.handle, search_handle, &mut record_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let err = api::mysql_storage_get_record_version(wallet.handle, record_handle, &mut version);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        assert_eq!(version, 2);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        let err = api::free_search(wallet.handle, search_handle);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    #[test]
+    fn test_update_record_value_with_version() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let new_value = random_string(20).into_bytes();
+
+        let err = api::mysql_storage_update_record_value_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len(), 1);
         assert_eq!(err, ErrorCode::Success);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        let err = api::mysql_storage_update_record_value_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len(), 1);
+        assert_eq!(err, ErrorCode::VersionConflict);
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(get_record_version(&wallet, &record), 2);
     }
 
     #[test]
-    fn test_add_record_with_tags_then_fetch_value_tags() {
+    fn test_update_record_tags_with_version_conflict() {
         let wallet = TestWallet::new_default(false);
         let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let options_json = fetch_options(false, true, true);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let tags_json = CString::new(r#"{"tag4": "value4"}"#).unwrap();
+        let tag_names_json = CString::new("[]").unwrap();
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        let err = api::mysql_storage_update_record_tags_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr(), 2);
+        assert_eq!(err, ErrorCode::VersionConflict);
+
+        let err = api::mysql_storage_add_record_tags_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr(), 2);
+        assert_eq!(err, ErrorCode::VersionConflict);
+
+        let err = api::mysql_storage_delete_record_tags_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr(), 2);
+        assert_eq!(err, ErrorCode::VersionConflict);
+
+        let err = api::mysql_storage_delete_record_tags_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tag_names_json.as_ptr(), 2);
+        assert_eq!(err, ErrorCode::VersionConflict);
+
+        assert_eq!(get_record_version(&wallet, &record), 1);
+    }
+
+    #[test]
+    fn test_empty_record_tags_with_version() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(false);
+        wallet.add_record(&record);
+
+        let err = api::mysql_storage_add_record_tags_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr(), 1);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let err = api::mysql_storage_delete_record_tags_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr(), 1);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let err = api::mysql_storage_add_record_tags_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr(), 2);
+        assert_eq!(err, ErrorCode::VersionConflict);
+
+        let err = api::mysql_storage_delete_record_tags_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr(), 2);
+        assert_eq!(err, ErrorCode::VersionConflict);
+
+        assert_eq!(get_record_version(&wallet, &record), 1);
+
+        let unknown_id = CString::new("unknown-id").unwrap();
+
+        let err = api::mysql_storage_add_record_tags_with_version(wallet.handle, record.type_.as_ptr(), unknown_id.as_ptr(), record.tags_json.as_ptr(), 1);
+        assert_eq!(err, ErrorCode::ItemNotFound);
+
+        let err = api::mysql_storage_delete_record_tags_with_version(wallet.handle, record.type_.as_ptr(), unknown_id.as_ptr(), record.tag_names.as_ptr(), 1);
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
+
+    #[test]
+    fn test_delete_record_with_version() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let err = api::mysql_storage_delete_record_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), 2);
+        assert_eq!(err, ErrorCode::VersionConflict);
+
+        let err = api::mysql_storage_delete_record_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), 1);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let err = api::mysql_storage_delete_record_with_version(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), 1);
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+    /** Storage UPDATE_RECORD Tests */
+
+    fn update_record(wallet: &TestWallet, record: &TestRecord, value: Option<&Vec<u8>>, tags_json: &str, tag_names_json: &str, condition_json: Option<&str>) -> ErrorCode {
+        let tags_json = CString::new(tags_json).unwrap();
+        let tag_names_json = CString::new(tag_names_json).unwrap();
+        let condition_json = condition_json.map(|condition_json| CString::new(condition_json).unwrap());
+
+        api::mysql_storage_update_record(
+            wallet.handle,
+            record.type_.as_ptr(),
+            record.id.as_ptr(),
+            value.map_or(ptr::null(), |value| value.as_ptr()),
+            value.map_or(0, |value| value.len()),
+            tags_json.as_ptr(),
+            tag_names_json.as_ptr(),
+            condition_json.as_ref().map_or(ptr::null(), |condition_json| condition_json.as_ptr())
+        )
+    }
+
+    #[test]
+    fn test_update_record_value_and_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let new_value = random_string(20).into_bytes();
+
+        let err = update_record(&wallet, &record, Some(&new_value), r#"{"tag1": "new_value1", "tag4": "value4"}"#, r#"["tag2"]"#, None);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let (value, tags) = get_record_value_and_tags(&wallet, &record);
+        assert_eq!(value, new_value);
+        assert_eq!(tags, json!({"tag1": "new_value1", "~tag3": "value3", "tag4": "value4"}));
+        assert_eq!(get_record_version(&wallet, &record), 2);
+    }
+
+    #[test]
+    fn test_update_record_only_tags() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let err = update_record(&wallet, &record, None, r#"{"~tag3": "new_value3"}"#, "[]", None);
         assert_eq!(err, ErrorCode::Success);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        let (value, tags) = get_record_value_and_tags(&wallet, &record);
+        assert_eq!(value, record.value);
+        assert_eq!(tags, json!({"tag1": "value1", "tag2": "value2", "~tag3": "new_value3"}));
+    }
 
-        let err = api::free_record(wallet.handle, record_handle);
+    #[test]
+    fn test_update_record_with_condition() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let err = update_record(&wallet, &record, None, r#"{"~tag3": "value4"}"#, "[]", Some(r#"{"~tag3": "value3"}"#));
         assert_eq!(err, ErrorCode::Success);
+
+        let err = update_record(&wallet, &record, None, r#"{"~tag3": "value5"}"#, "[]", Some(r#"{"~tag3": "value3"}"#));
+        assert_eq!(err, ErrorCode::ConditionNotMet);
+
+        let (_, tags) = get_record_value_and_tags(&wallet, &record);
+        assert_eq!(tags["~tag3"], json!("value4"));
     }
 
     #[test]
-    fn test_add_record_with_tags_then_fetch_none() {
+    fn test_update_record_not_found() {
         let wallet = TestWallet::new_default(false);
         let record = TestRecord::new_default(true);
 
-        let options_json = fetch_options(false, false, false);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let err = update_record(&wallet, &record, None, r#"{"tag1": "value1"}"#, "[]", Some(r#"{"tag1": "value1"}"#));
+        assert_eq!(err, ErrorCode::ItemNotFound);
+    }
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+    #[test]
+    fn test_update_record_invalid_condition() {
+        let wallet = TestWallet::new_default(false);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let err = update_record(&wallet, &record, None, "{}", "[]", Some(r#"{"tag1": {"$unknown": "value1"}}"#));
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_update_record_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+        let record = TestRecord::new_default(true);
+
+        let err = update_record(&wallet, &record, None, "{}", "[]", None);
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    /** Storage METADATA Tests */
+
+    #[test]
+    fn test_set_get_metadata() {
+        let wallet = TestWallet::new_default(false);
+
+        let mut metadata_handle = -1;
+        let mut metadata_ptr: *const c_char = ptr::null_mut();
+
+        let new_metadata = random_string(512);
+        let new_metadata_cstring = CString::new(new_metadata.clone()).unwrap();
+
+        let err = api::set_metadata(wallet.handle, new_metadata_cstring.as_ptr());
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let err = api::get_metadata(wallet.handle, &mut metadata_ptr, &mut metadata_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let metadata = unsafe { CStr::from_ptr(metadata_ptr).to_str().unwrap() };
+
+        assert_eq!(new_metadata, metadata);
+
+        let err = api::free_metadata(wallet.handle, metadata_handle);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+    }
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+    #[test]
+    fn test_set_metadata_invalid_metadata_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let new_metadata = random_string(1);
+        let new_metadata_cstring = CString::new(new_metadata.clone()).unwrap();
+
+        let err = api::set_metadata(wallet.handle, new_metadata_cstring.as_ptr());
         assert_eq!(err, ErrorCode::InvalidState);
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+    #[test]
+    fn test_free_metadata_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let metadata_handle = -1;
+
+        let err = api::free_metadata(wallet.handle, metadata_handle);
         assert_eq!(err, ErrorCode::InvalidState);
+    }
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+    #[test]
+    fn test_free_metadata_invalid_metadata_handle() {
+        let wallet = TestWallet::new_default(false);
+
+        let metadata_handle = -1;
+
+        let err = api::free_metadata(wallet.handle, metadata_handle);
         assert_eq!(err, ErrorCode::InvalidState);
+    }
 
-        let err = api::free_record(wallet.handle, record_handle);
+    /** Bulk Delete/Update By Query Tests */
+
+    fn search_records_count(wallet: &TestWallet, type_: &CString, query_json: &str) -> usize {
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = search_options(false, true, false, false, false);
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut total_count: usize = 0;
+        let err = api::get_search_total_count(wallet.handle, search_handle, &mut total_count);
         assert_eq!(err, ErrorCode::Success);
+
+        let err = api::free_search(wallet.handle, search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        total_count
+    }
+
+    fn add_records_with_tags(wallet: &TestWallet, type_: &CString, tags_jsons: &[&str]) {
+        for tags_json in tags_jsons {
+            let mut record = TestRecord::new_default(false);
+            record.type_ = type_.clone();
+            record.tags_json = CString::new(*tags_json).unwrap();
+            wallet.add_record(&record);
+        }
     }
 
     #[test]
-    fn test_add_record_with_tags_then_fetch_default() {
+    fn test_delete_records_by_query() {
         let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
 
-        let options_json = CString::new(r#"{}"#).unwrap();
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let type_ = CString::new(random_string(10)).unwrap();
+        add_records_with_tags(&wallet, &type_, &[r#"{"~expired": "1"}"#, r#"{"~expired": "1"}"#, r#"{"~expired": "0"}"#]);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        // records of other types are not affected
+        let other_type = CString::new(random_string(10)).unwrap();
+        add_records_with_tags(&wallet, &other_type, &[r#"{"~expired": "1"}"#]);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let query_json = CString::new(r#"{"~expired": "1"}"#).unwrap();
+        let mut affected_rows: u64 = 0;
+
+        let err = api::mysql_storage_delete_records_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), &mut affected_rows);
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(affected_rows, 2);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(search_records_count(&wallet, &type_, "{}"), 1);
+        assert_eq!(search_records_count(&wallet, &other_type, "{}"), 1);
+
+        // nothing left to delete
+        let err = api::mysql_storage_delete_records_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), &mut affected_rows);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        assert_eq!(affected_rows, 0);
+    }
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+    #[test]
+    fn test_delete_records_by_query_invalid_query_format() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("...").unwrap();
+        let mut affected_rows: u64 = 0;
+
+        let err = api::mysql_storage_delete_records_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), &mut affected_rows);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_delete_records_by_query_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("{}").unwrap();
+        let mut affected_rows: u64 = 0;
+
+        let err = api::mysql_storage_delete_records_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), &mut affected_rows);
         assert_eq!(err, ErrorCode::InvalidState);
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+    #[test]
+    fn test_update_tags_by_query() {
+        let wallet = TestWallet::new_default(false);
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        let type_ = CString::new(random_string(10)).unwrap();
+        add_records_with_tags(&wallet, &type_, &[r#"{"~state": "offered", "tag1": "value1"}"#, r#"{"~state": "offered"}"#, r#"{"~state": "accepted"}"#]);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        let query_json = CString::new(r#"{"~state": "offered"}"#).unwrap();
+        let tags_json = CString::new(r#"{"~state": "expired"}"#).unwrap();
+        let tag_names_json = CString::new(r#"["tag1"]"#).unwrap();
+        let mut affected_rows: u64 = 0;
 
-        let err = api::free_record(wallet.handle, record_handle);
+        let err = api::mysql_storage_update_tags_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), tags_json.as_ptr(), tag_names_json.as_ptr(), &mut affected_rows);
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(affected_rows, 2);
+
+        assert_eq!(search_records_count(&wallet, &type_, r#"{"~state": "expired"}"#), 2);
+        assert_eq!(search_records_count(&wallet, &type_, r#"{"~state": "accepted"}"#), 1);
+        assert_eq!(search_records_count(&wallet, &type_, r#"{"tag1": "value1"}"#), 0);
+    }
+
+    #[test]
+    fn test_update_tags_by_query_invalid_tags_format() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("{}").unwrap();
+        let tag_names_json = CString::new("[]").unwrap();
+        let mut affected_rows: u64 = 0;
+
+        for tags_json in &[r#"{"tag1": 1}"#, "[]", "..."] {
+            let tags_json = CString::new(*tags_json).unwrap();
+
+            let err = api::mysql_storage_update_tags_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), tags_json.as_ptr(), tag_names_json.as_ptr(), &mut affected_rows);
+            assert_eq!(err, ErrorCode::InvalidStructure);
+        }
+    }
+
+    #[test]
+    fn test_update_tags_by_query_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("{}").unwrap();
+        let tags_json = CString::new("{}").unwrap();
+        let tag_names_json = CString::new("[]").unwrap();
+        let mut affected_rows: u64 = 0;
+
+        let err = api::mysql_storage_update_tags_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), tags_json.as_ptr(), tag_names_json.as_ptr(), &mut affected_rows);
+        assert_eq!(err, ErrorCode::InvalidState);
     }
 
-    /** Storage ADD_RECORD, GET_RECORD, DELETE_RECORD Tests WITHOUT TAGS */
+    /** Change Feed Tests */
 
-    #[test]
-    fn test_add_record_without_tags_then_fetch_all() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    fn read_changes(wallet: &TestWallet, since_seq: u64, limit: u64) -> Result<serde_json::Value, ErrorCode> {
+        let mut changes_json_p: *const c_char = ptr::null_mut();
+        let mut changes_handle: i32 = -1;
 
-        let options_json = fetch_options(true, true, true);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let err = api::mysql_storage_read_changes(wallet.handle, since_seq, limit, &mut changes_json_p, &mut changes_handle);
+        if err != ErrorCode::Success {
+            return Err(err);
+        }
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        let changes: serde_json::Value = serde_json::from_str(unsafe { CStr::from_ptr(changes_json_p).to_str().unwrap() }).unwrap();
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let err = api::mysql_storage_free_json(changes_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        Ok(changes)
+    }
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+    fn change_operations(changes: &serde_json::Value) -> Vec<(u64, String)> {
+        changes["changes"].as_array().unwrap().iter()
+            .map(|change| (change["seq"].as_u64().unwrap(), change["operation"].as_str().unwrap().to_string()))
+            .collect()
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+    #[test]
+    fn test_read_changes() {
+        let wallet = TestWallet::new_with_config("change_feed", json!({"enabled": true}));
+
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let value = vec![5, 6, 7];
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let err = api::delete_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr());
         assert_eq!(err, ErrorCode::Success);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        let changes = read_changes(&wallet, 0, 100).unwrap();
+        assert_eq!(change_operations(&changes), vec![(1, "add".to_string()), (2, "update".to_string()), (3, "delete".to_string())]);
+        assert_eq!(changes["lastSeq"], json!(3));
+        assert_eq!(changes["changes"][0]["type"], json!(record.type_.to_str().unwrap()));
+        assert_eq!(changes["changes"][0]["id"], json!(record.id.to_str().unwrap()));
+        assert_eq!(changes["changes"][1]["version"], json!(2));
+        assert_eq!(changes["changes"][2]["version"], json!(null));
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let changes = read_changes(&wallet, 1, 1).unwrap();
+        assert_eq!(change_operations(&changes), vec![(2, "update".to_string())]);
     }
 
     #[test]
-    fn test_add_record_without_tags_then_fetch_only_type() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
-
-        let options_json = fetch_options(true, false, false);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
-
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+    fn test_read_changes_of_bulk_updates() {
+        let wallet = TestWallet::new_with_config("change_feed", json!({"enabled": true}));
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let type_ = CString::new(random_string(10)).unwrap();
+        add_records_with_tags(&wallet, &type_, &[r#"{"~state": "offered"}"#, r#"{"~state": "offered"}"#, r#"{"~state": "accepted"}"#]);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        let query_json = CString::new(r#"{"~state": "offered"}"#).unwrap();
+        let tags_json = CString::new(r#"{"~state": "expired"}"#).unwrap();
+        let tag_names_json = CString::new("[]").unwrap();
+        let mut affected_rows: u64 = 0;
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        let err = api::mysql_storage_update_tags_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), tags_json.as_ptr(), tag_names_json.as_ptr(), &mut affected_rows);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let changes = read_changes(&wallet, 3, 100).unwrap();
+        assert_eq!(change_operations(&changes), vec![(4, "update".to_string()), (5, "update".to_string())]);
+        assert_eq!(changes["changes"][0]["version"], json!(2));
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let query_json = CString::new("{}").unwrap();
 
-        let err = api::free_record(wallet.handle, record_handle);
+        let err = api::mysql_storage_delete_records_by_query(wallet.handle, type_.as_ptr(), query_json.as_ptr(), &mut affected_rows);
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(affected_rows, 3);
+
+        let changes = read_changes(&wallet, 5, 100).unwrap();
+        assert_eq!(change_operations(&changes).len(), 3);
+        assert_eq!(changes["lastSeq"], json!(8));
     }
 
     #[test]
-    fn test_add_record_without_tags_then_fetch_only_value() {
+    fn test_read_changes_disabled() {
         let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
-
-        let options_json = fetch_options(false, true, false);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
-
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        assert_eq!(read_changes(&wallet, 0, 100).unwrap(), json!({"changes": [], "lastSeq": 0}));
+    }
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    #[test]
+    fn test_read_changes_invalid_limit() {
+        let wallet = TestWallet::new_with_config("change_feed", json!({"enabled": true}));
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+        assert_eq!(read_changes(&wallet, 0, 0).unwrap_err(), ErrorCode::InvalidStructure);
+        assert_eq!(read_changes(&wallet, 0, 1001).unwrap_err(), ErrorCode::InvalidStructure);
+    }
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    #[test]
+    fn test_read_changes_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(read_changes(&wallet, 0, 100).unwrap_err(), ErrorCode::InvalidState);
     }
 
     #[test]
-    fn test_add_record_without_tags_then_fetch_only_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_trim_changes() {
+        let wallet = TestWallet::new_with_config("change_feed", json!({"enabled": true, "max_changes": 3}));
 
-        let options_json = fetch_options(false, false, true);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let type_ = CString::new(random_string(10)).unwrap();
+        add_records_with_tags(&wallet, &type_, &["{}", "{}", "{}", "{}", "{}"]);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        let mut trimmed: u64 = 0;
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let err = api::mysql_storage_trim_changes(wallet.handle, 0, &mut trimmed);
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(trimmed, 2);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let err = api::mysql_storage_trim_changes(wallet.handle, 4, &mut trimmed);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        assert_eq!(trimmed, 2);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let changes = read_changes(&wallet, 0, 100).unwrap();
+        assert_eq!(change_operations(&changes), vec![(5, "add".to_string())]);
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    #[test]
+    fn test_restore_snapshot_is_journaled() {
+        let wallet = TestWallet::new_with_config("change_feed", json!({"enabled": true}));
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        assert_eq!(snapshot_storage(&wallet, "before"), ErrorCode::Success);
+        assert_eq!(restore_snapshot(&wallet, "before"), ErrorCode::Success);
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let changes = read_changes(&wallet, 1, 100).unwrap();
+        assert_eq!(change_operations(&changes), vec![(2, "delete".to_string()), (3, "add".to_string())]);
     }
 
-    #[test]
-    fn test_add_record_without_tags_then_fetch_type_value() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    /** Cache Tests */
 
-        let options_json = fetch_options(true, true, false);
+    fn fetch_value(wallet: &TestWallet, record: &TestRecord) -> Result<Vec<u8>, ErrorCode> {
+        let options_json = CString::new(r#"{"retrieveType": false, "retrieveValue": true, "retrieveTags": false}"#).unwrap();
         let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
         let mut value_p: *const u8 = ptr::null_mut();
         let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
-
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
 
         let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
-
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
-
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+        if err != ErrorCode::Success {
+            return Err(err);
+        }
 
         let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let value = unsafe { slice::from_raw_parts(value_p, value_len_p) }.to_vec();
 
         let err = api::free_record(wallet.handle, record_handle);
         assert_eq!(err, ErrorCode::Success);
-    }
 
-    #[test]
-    fn test_add_record_without_tags_then_fetch_type_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+        Ok(value)
+    }
 
-        let options_json = fetch_options(true, false, true);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+    fn get_cache_stats(wallet: &TestWallet) -> serde_json::Value {
+        let mut stats_json_p: *const c_char = ptr::null_mut();
+        let mut stats_handle: i32 = -1;
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        let err = api::mysql_storage_get_cache_stats(wallet.handle, &mut stats_json_p, &mut stats_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let stats: serde_json::Value = serde_json::from_str(unsafe { CStr::from_ptr(stats_json_p).to_str().unwrap() }).unwrap();
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let err = api::mysql_storage_free_json(stats_handle);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(type_p) }.to_owned(), record.type_);
+        stats
+    }
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    #[test]
+    fn test_cache_serves_repeated_fetches() {
+        let wallet = TestWallet::new_with_config("cache", json!({"enabled": true}));
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let stats = get_cache_stats(&wallet);
+        assert_eq!(stats["enabled"], json!(true));
+        assert_eq!(stats["hits"], json!(1));
+        assert_eq!(stats["misses"], json!(1));
+        assert_eq!(stats["entries"], json!(1));
     }
 
     #[test]
-    fn test_add_record_without_tags_then_fetch_value_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_cache_is_invalidated_by_writes() {
+        let wallet = TestWallet::new_with_config("cache", json!({"enabled": true}));
 
-        let options_json = fetch_options(false, true, true);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
 
-       let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        let value = vec![5, 6, 7];
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), value);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let query_json = CString::new("{}").unwrap();
+        let mut affected_rows: u64 = 0;
+        let err = api::mysql_storage_delete_records_by_query(wallet.handle, record.type_.as_ptr(), query_json.as_ptr(), &mut affected_rows);
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record), Err(ErrorCode::ItemNotFound));
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        assert_eq!(get_cache_stats(&wallet)["invalidations"], json!(2));
+    }
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+    #[test]
+    fn test_cache_is_invalidated_by_set_metadata() {
+        let wallet = TestWallet::new_with_config("cache", json!({"enabled": true}));
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+        let metadata = get_storage_metadata(&wallet);
+        assert_eq!(get_storage_metadata(&wallet), metadata);
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let new_metadata = CString::new(random_string(20)).unwrap();
+        let err = api::set_metadata(wallet.handle, new_metadata.as_ptr());
         assert_eq!(err, ErrorCode::Success);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
-
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(get_storage_metadata(&wallet), new_metadata);
     }
 
     #[test]
-    fn test_add_record_without_tags_then_fetch_none() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_cache_is_cleared_by_restore_snapshot() {
+        let wallet = TestWallet::new_with_config("cache", json!({"enabled": true}));
 
-        let options_json = fetch_options(false, false, false);
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+        let metadata = get_storage_metadata(&wallet);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(snapshot_storage(&wallet, "cached"), ErrorCode::Success);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let value = vec![5, 6, 7];
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), value);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let new_metadata = CString::new(random_string(20)).unwrap();
+        let err = api::set_metadata(wallet.handle, new_metadata.as_ptr());
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        assert_eq!(get_storage_metadata(&wallet), new_metadata);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        assert_eq!(restore_snapshot(&wallet, "cached"), ErrorCode::Success);
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+        assert_eq!(get_storage_metadata(&wallet), metadata);
+    }
+
+    #[test]
+    fn test_cache_stats_disabled() {
+        let wallet = TestWallet::new_default(false);
+
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+
+        let stats = get_cache_stats(&wallet);
+        assert_eq!(stats["enabled"], json!(false));
+        assert_eq!(stats["misses"], json!(0));
+    }
+
+    #[test]
+    fn test_cache_stats_invalid_storage_handle() {
+        let mut stats_json_p: *const c_char = ptr::null_mut();
+        let mut stats_handle: i32 = -1;
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let err = api::mysql_storage_get_cache_stats(-1, &mut stats_json_p, &mut stats_handle);
         assert_eq!(err, ErrorCode::InvalidState);
-
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::Success);
     }
 
-    #[test]
-    fn test_add_record_without_tags_then_fetch_default() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    /** Chunked Value Tests */
 
-        let options_json = CString::new(r#"{}"#).unwrap();
+    fn search_value(wallet: &TestWallet, record: &TestRecord) -> Vec<u8> {
+        let query_json = CString::new("{}").unwrap();
+        let options_json = search_options(true, false, false, true, false);
+        let mut search_handle = -1;
         let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
         let mut value_p: *const u8 = ptr::null_mut();
         let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        let err = api::search_records(wallet.handle, record.type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let err = api::fetch_search_next_record(wallet.handle, search_handle, &mut record_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let value = unsafe { slice::from_raw_parts(value_p, value_len_p) }.to_vec();
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        let err = api::free_search(wallet.handle, search_handle);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        value
+    }
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+    fn write_value(wallet: &TestWallet, record: &TestRecord, parts: &[&[u8]]) -> i32 {
+        let mut writer_handle = -1;
 
-        let err = api::free_record(wallet.handle, record_handle);
+        let err = api::mysql_storage_open_value_writer(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), &mut writer_handle);
         assert_eq!(err, ErrorCode::Success);
-    }
 
-    #[test]
-    fn test_duplicate_add_record_without_tags_then_fetch_default() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+        for part in parts {
+            let err = api::mysql_storage_write_value(wallet.handle, writer_handle, part.as_ptr(), part.len());
+            assert_eq!(err, ErrorCode::Success);
+        }
 
-        let options_json = CString::new(r#"{}"#).unwrap();
-        let mut record_handle = -1;
-        let mut id_p: *const c_char = ptr::null_mut();
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let mut type_p: *const c_char = ptr::null_mut();
+        writer_handle
+    }
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+    fn read_value(wallet: &TestWallet, record: &TestRecord, buffer_len: usize) -> Result<Vec<u8>, ErrorCode> {
+        let mut reader_handle = -1;
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        let err = api::mysql_storage_open_value_reader(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), &mut reader_handle);
+        if err != ErrorCode::Success {
+            return Err(err);
+        }
 
-        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { CStr::from_ptr(id_p) }.to_owned(), record.id);
+        let mut value: Vec<u8> = Vec::new();
+        let mut buffer = vec![0u8; buffer_len];
 
-        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
-        assert_eq!(err, ErrorCode::InvalidState);
+        loop {
+            let mut read_len: usize = 0;
 
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, record.value.as_slice());
+            let err = api::mysql_storage_read_value(wallet.handle, reader_handle, buffer.as_mut_ptr(), buffer.len(), &mut read_len);
+            if err != ErrorCode::Success {
+                return Err(err);
+            }
 
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+            if read_len == 0 {
+                break;
+            }
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+            value.extend_from_slice(&buffer[..read_len]);
+        }
 
-        let err = api::free_record(wallet.handle, record_handle);
+        let err = api::mysql_storage_close_value_reader(wallet.handle, reader_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::ItemAlreadyExists);
+        Ok(value)
     }
 
     #[test]
-    fn test_add_record_invalid_handle() {
-        let wallet = TestWallet::new_default(true);
+    fn test_chunked_values() {
+        let wallet = TestWallet::new_with_config("values", json!({"chunked": true, "chunk_size": 4}));
 
-        let record = TestRecord::new_default(false);
+        let mut record = TestRecord::new_default(true);
+        record.value = (0..10).collect();
+        wallet.add_record(&record);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidState);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+        assert_eq!(search_value(&wallet, &record), record.value);
+
+        for value in vec![vec![1u8, 2, 3], (0..9).collect::<Vec<u8>>()] {
+            let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
+            assert_eq!(err, ErrorCode::Success);
+
+            assert_eq!(fetch_value(&wallet, &record).unwrap(), value);
+            assert_eq!(read_value(&wallet, &record, 3).unwrap(), value);
+        }
     }
 
     #[test]
-    fn test_add_record_with_large_type_value() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_copy_storage_copies_chunked_values() {
+        let wallet = TestWallet::new_with_config("values", json!({"chunked": true, "chunk_size": 4}));
 
-        let large_type = CString::new(random_string(150)).unwrap();
+        let mut record = TestRecord::new_default(true);
+        record.value = (0..10).collect();
+        wallet.add_record(&record);
 
-        let err = api::add_record(wallet.handle, large_type.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::IOError);
+        let copy = copy_of(&wallet);
+
+        assert_eq!(fetch_value(&copy, &record).unwrap(), record.value);
     }
 
     #[test]
-    fn test_add_record_with_tags_invalid_json_format() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_storage_stats_chunked_values() {
+        let wallet = TestWallet::new_with_config("values", json!({"chunked": true, "chunk_size": 4}));
 
-        let invalid_tags_json = CString::new("...").unwrap();
+        let mut record = TestRecord::new_default(true);
+        record.value = (0..10).collect();
+        wallet.add_record(&record);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), invalid_tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        let stats = get_storage_stats(&wallet).unwrap();
+        assert_eq!(stats["valueBytes"], json!(record.value.len()));
+        assert_eq!(stats["types"][0]["valueBytes"], json!(record.value.len()));
     }
 
     #[test]
-    fn test_add_record_with_tags_invalid_json_format_1() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_snapshot_restores_chunked_values() {
+        let wallet = TestWallet::new_with_config("values", json!({"chunked": true, "chunk_size": 4}));
 
-        let invalid_tags_json = CString::new(r#""'''{"key": "value"}"#).unwrap();
+        let mut record = TestRecord::new_default(true);
+        record.value = (0..10).collect();
+        wallet.add_record(&record);
 
-        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), invalid_tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        assert_eq!(snapshot_storage(&wallet, "label"), ErrorCode::Success);
+
+        let value: Vec<u8> = (10..25).collect();
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
+        assert_eq!(err, ErrorCode::Success);
+
+        assert_eq!(restore_snapshot(&wallet, "label"), ErrorCode::Success);
+
+        assert_eq!(search_value(&wallet, &record), record.value);
+        assert_eq!(read_value(&wallet, &record, 3).unwrap(), record.value);
     }
 
     #[test]
-    fn test_get_record_invalid_options_format() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_verify_storage_chunked_values() {
+        let wallet = TestWallet::new_with_config("values", json!({"chunked": true, "chunk_size": 4}));
 
+        let mut record = TestRecord::new_default(true);
+        record.value = (0..10).collect();
         wallet.add_record(&record);
 
-        let options_json = CString::new("//").unwrap();
-        let mut record_handle = -1;
+        let copy = copy_of(&wallet);
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        assert_eq!(verify_storage(&wallet).unwrap()["root"], verify_storage(&copy).unwrap()["root"]);
+
+        // only the last chunk differs
+        let value: Vec<u8> = (0..11).collect();
+        let err = api::update_record_value(copy.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
+        assert_eq!(err, ErrorCode::Success);
+
+        assert_ne!(verify_storage(&wallet).unwrap()["root"], verify_storage(&copy).unwrap()["root"]);
     }
 
     #[test]
-    fn test_get_record_record_not_exists() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_move_storage_chunked_values() {
+        let mut wallet = TestWallet::new_with_config("values", json!({"chunked": true, "chunk_size": 4}));
 
-        let mut record_handle = -1;
+        let mut records: Vec<TestRecord> = Vec::new();
+        for size in vec![3, 10, 1000] {
+            let mut record = TestRecord::new_default(true);
+            record.value = (0..size).map(|i| i as u8).collect();
+            wallet.add_record(&record);
+            records.push(record);
+        }
 
-        let options_json = fetch_options(false, false, false);
+        let report = verify_storage(&wallet).unwrap();
 
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::ItemNotFound);
+        let target_config = CString::new(TEST_ENV.get_shard_config(1)).unwrap();
+
+        let err = move_storage(&wallet, &target_config);
+        assert_eq!(err, ErrorCode::Success);
+
+        api::close_storage(wallet.handle);
+        wallet.config = target_config;
+        wallet.handle = wallet._open();
+
+        for record in records.iter() {
+            assert_eq!(fetch_value(&wallet, record).unwrap(), record.value);
+        }
+        assert_eq!(verify_storage(&wallet).unwrap()["root"], report["root"]);
     }
 
     #[test]
-    fn test_delete_record() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_value_too_large() {
+        let wallet = TestWallet::new_with_config("values", json!({"max_size": 8}));
+
+        let mut record = TestRecord::new_default(true);
+        record.value = vec![0u8; 9];
+
+        let err = api::add_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len(), record.tags_json.as_ptr());
+        assert_eq!(err, ErrorCode::ValueTooLarge);
 
+        record.value = vec![0u8; 8];
         wallet.add_record(&record);
 
-        let err = api::delete_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
-    }
+        let writer_handle = write_value(&wallet, &record, &[&[0u8; 5]]);
 
-    #[test]
-    fn test_delete_unknown_record() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+        let data = [0u8; 4];
+        let err = api::mysql_storage_write_value(wallet.handle, writer_handle, data.as_ptr(), data.len());
+        assert_eq!(err, ErrorCode::ValueTooLarge);
 
-        let err = api::delete_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr());
-        assert_eq!(err, ErrorCode::ItemNotFound);
+        let err = api::mysql_storage_abort_value_writer(wallet.handle, writer_handle);
+        assert_eq!(err, ErrorCode::Success);
     }
 
     #[test]
-    fn test_delete_record_invalid_handle() {
-        let wallet = TestWallet::new_default(true);
-        let record = TestRecord::new_default(false);
+    fn test_streaming_value() {
+        let wallet = TestWallet::new_with_config("values", json!({"chunk_size": 4}));
 
-        let err = api::delete_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidState);
-    }
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-    #[test]
-    fn test_free_record_invalid_storage_handle() {
-        let wallet = TestWallet::new_default(true);
+        let writer_handle = write_value(&wallet, &record, &[&[1, 2, 3], &[4, 5, 6, 7, 8, 9], &[10]]);
 
-        let record_handle = -1;
+        let err = api::mysql_storage_finish_value_writer(wallet.handle, writer_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let err = api::free_record(wallet.handle, record_handle);
+        let value: Vec<u8> = (1..11).collect();
+        assert_eq!(read_value(&wallet, &record, 3).unwrap(), value);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), value);
+
+        let err = api::mysql_storage_finish_value_writer(wallet.handle, writer_handle);
         assert_eq!(err, ErrorCode::InvalidState);
     }
 
     #[test]
-    fn test_free_record_invalid_record_handle() {
-        let wallet = TestWallet::new_default(false);
+    fn test_abort_value_writer() {
+        let wallet = TestWallet::new_with_config("values", json!({"chunk_size": 4}));
 
-        let record_handle = -1;
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let err = api::free_record(wallet.handle, record_handle);
-        assert_eq!(err, ErrorCode::InvalidState);
-    }
+        let writer_handle = write_value(&wallet, &record, &[&[1, 2, 3, 4, 5, 6, 7, 8, 9]]);
 
-    /** Storage UPDATE_RECORD_VALUE Tests */
+        let err = api::mysql_storage_abort_value_writer(wallet.handle, writer_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+    }
 
     #[test]
-    fn test_update_record_value() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    fn test_value_reader_detects_changed_value() {
+        let wallet = TestWallet::new_with_config("values", json!({"chunked": true, "chunk_size": 4}));
 
+        let mut record = TestRecord::new_default(true);
+        record.value = (0..10).collect();
         wallet.add_record(&record);
 
-        let new_value = vec![2, 5, 8, 13];
-        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len());
+        let mut reader_handle = -1;
+        let err = api::mysql_storage_open_value_reader(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), &mut reader_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, false);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let value: Vec<u8> = (10..20).collect();
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), value.as_ptr(), value.len());
         assert_eq!(err, ErrorCode::Success);
 
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        let mut buffer = [0u8; 4];
+        let mut read_len: usize = 0;
+        let err = api::mysql_storage_read_value(wallet.handle, reader_handle, buffer.as_mut_ptr(), buffer.len(), &mut read_len);
+        assert_eq!(err, ErrorCode::VersionConflict);
+
+        let err = api::mysql_storage_close_value_reader(wallet.handle, reader_handle);
         assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, new_value.as_slice());
     }
 
     #[test]
-    fn test_update_record_value_with_same_value() {
+    fn test_open_value_reader_not_found() {
         let wallet = TestWallet::new_default(false);
         let record = TestRecord::new_default(true);
 
-        wallet.add_record(&record);
+        assert_eq!(read_value(&wallet, &record, 4), Err(ErrorCode::ItemNotFound));
+    }
 
-        let new_value = vec![1, 2, 3, 4];
-        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len());
-        assert_eq!(err, ErrorCode::Success);
+    /** Compression Tests */
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, false);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+    fn compression_config(type_: &CString, algorithm: &str) -> serde_json::Value {
+        json!({"types": {type_.to_str().unwrap(): algorithm}, "min_size": 16})
+    }
 
-        let mut value_p: *const u8 = ptr::null_mut();
-        let mut value_len_p = 0;
-        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
-        assert_eq!(err, ErrorCode::Success);
-        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len_p) }, new_value.as_slice());
+    fn compressible_record() -> TestRecord {
+        let mut record = TestRecord::new_default(true);
+        record.value = vec![7u8; 1000];
+        record
     }
 
     #[test]
-    fn test_update_value_invalid_handle() {
-        let wallet = TestWallet::new_default(true);
-        let record = TestRecord::new_default(true);
+    fn test_compressed_values() {
+        for algorithm in &["deflate", "zstd"] {
+            let mut wallet = TestWallet::new_default(false);
+            let mut record = compressible_record();
+            wallet.reopen_with_config("compression", compression_config(&record.type_, algorithm));
 
-        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len());
-        assert_eq!(err, ErrorCode::InvalidState);
+            wallet.add_record(&record);
+
+            assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+            assert_eq!(search_value(&wallet, &record), record.value);
+            assert!(get_storage_stats(&wallet).unwrap()["valueBytes"].as_u64().unwrap() < record.value.len() as u64);
+
+            record.value = vec![8u8; 2000];
+            let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len());
+            assert_eq!(err, ErrorCode::Success);
+
+            assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+            assert_eq!(read_value(&wallet, &record, 100).unwrap(), record.value);
+        }
     }
 
     #[test]
-    fn test_update_value_for_unknown_record() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    fn test_compression_skips_other_types() {
+        let mut wallet = TestWallet::new_default(false);
+        let record = compressible_record();
+        wallet.reopen_with_config("compression", compression_config(&CString::new("other").unwrap(), "zstd"));
 
-        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.value.as_ptr(), record.value.len());
-        assert_eq!(err, ErrorCode::ItemNotFound);
+        wallet.add_record(&record);
+
+        assert_eq!(get_storage_stats(&wallet).unwrap()["valueBytes"], json!(record.value.len()));
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
     }
 
-    /** Storage ADD_RECORD_TAGS Tests */
+    #[test]
+    fn test_compressed_and_uncompressed_values_stay_readable() {
+        let mut wallet = TestWallet::new_default(false);
+
+        let uncompressed = compressible_record();
+        wallet.add_record(&uncompressed);
+
+        let mut compressed = compressible_record();
+        compressed.type_ = uncompressed.type_.clone();
+        wallet.reopen_with_config("compression", compression_config(&compressed.type_, "zstd"));
+        wallet.add_record(&compressed);
+
+        assert_eq!(fetch_value(&wallet, &uncompressed).unwrap(), uncompressed.value);
+        assert_eq!(fetch_value(&wallet, &compressed).unwrap(), compressed.value);
+
+        wallet.reopen_with_config("compression", json!({}));
+
+        assert_eq!(fetch_value(&wallet, &uncompressed).unwrap(), uncompressed.value);
+        assert_eq!(fetch_value(&wallet, &compressed).unwrap(), compressed.value);
+
+        let copy = copy_of(&wallet);
+
+        assert_eq!(fetch_value(&copy, &compressed).unwrap(), compressed.value);
+    }
 
     #[test]
-    fn test_add_record_tags_record_exists_with_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_compressed_chunked_values() {
+        let mut wallet = TestWallet::new_default(false);
+
+        let mut record = compressible_record();
+        record.value = (0..5000).map(|i| (i % 7) as u8).collect();
+
+        let mut config: serde_json::Value = serde_json::from_str(&TEST_ENV.get_config()).unwrap();
+        config["compression"] = compression_config(&record.type_, "deflate");
+        config["values"] = json!({"chunked": true, "chunk_size": 16});
+        wallet.config = CString::new(config.to_string()).unwrap();
+        wallet.handle = wallet._open();
 
         wallet.add_record(&record);
 
-        let tags_json = CString::new(r#"{"tag1": "value1", "tag2": "value2", "~tag3": "value3"}"#).unwrap();
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+        assert_eq!(search_value(&wallet, &record), record.value);
+        assert_eq!(read_value(&wallet, &record, 100).unwrap(), record.value);
+    }
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(),record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+    /** Encryption Tests */
+
+    fn encryption_config(current_key: &str) -> serde_json::Value {
+        json!({
+            "current_key": current_key,
+            "keys": {
+                "kek-1": "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=",
+                "kek-2": "AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI="
+            }
+        })
+    }
+
+    fn get_reencryption_status(wallet: &TestWallet) -> serde_json::Value {
+        let mut status_json_p: *const c_char = ptr::null_mut();
+        let mut status_handle: i32 = -1;
+
+        let err = api::mysql_storage_get_reencryption_status(wallet.handle, &mut status_json_p, &mut status_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let status: serde_json::Value = serde_json::from_str(unsafe { CStr::from_ptr(status_json_p).to_str().unwrap() }).unwrap();
+
+        let err = api::mysql_storage_free_json(status_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        status
+    }
+
+    fn wait_for_reencryption(wallet: &TestWallet) -> serde_json::Value {
+        for _ in 0..100 {
+            let status = get_reencryption_status(wallet);
+            if status["running"] == json!(false) {
+                return status;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+
+        panic!("Re-encryption did not finish");
     }
 
     #[test]
-    fn test_add_record_tags_record_exists_without_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    fn test_encrypted_values_and_metadata() {
+        let mut wallet = TestWallet::new_with_config("encryption", encryption_config("kek-1"));
 
+        let record = TestRecord::new_default(true);
         wallet.add_record(&record);
 
-        let tags_json = CString::new(r#"{"tag1": "value1", "tag2": "value2", "~tag3": "value3"}"#).unwrap();
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+        assert_eq!(search_value(&wallet, &record), record.value);
+        assert_eq!(get_storage_metadata(&wallet), wallet.metadata);
+        assert!(get_storage_stats(&wallet).unwrap()["valueBytes"].as_u64().unwrap() > record.value.len() as u64);
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(),record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let new_metadata = CString::new(random_string(20)).unwrap();
+        let err = api::set_metadata(wallet.handle, new_metadata.as_ptr());
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(get_storage_metadata(&wallet), new_metadata);
 
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        // without the keys the stored data can not be read
+        wallet.reopen_with_config("encryption", json!({}));
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        assert_eq!(fetch_value(&wallet, &record), Err(ErrorCode::EncryptionKeyNotFound));
+
+        let mut metadata_p: *const c_char = ptr::null_mut();
+        let mut metadata_handle: i32 = -1;
+        let err = api::get_metadata(wallet.handle, &mut metadata_p, &mut metadata_handle);
+        assert_eq!(err, ErrorCode::EncryptionKeyNotFound);
     }
 
     #[test]
-    fn test_add_empty_record_tags_record_with_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    fn test_verify_storage_hashes_plaintext() {
+        let encrypted = TestWallet::new_with_config("encryption", encryption_config("kek-1"));
+        let mut compressed = TestWallet::new_default(false);
+        let plain = TestWallet::new_default(false);
 
-        wallet.add_record(&record);
+        let record = compressible_record();
+        compressed.reopen_with_config("compression", compression_config(&record.type_, "zstd"));
 
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        for wallet in &[&encrypted, &compressed, &plain] {
+            wallet.add_record(&record);
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+            let err = api::set_metadata(wallet.handle, encrypted.metadata.as_ptr());
+            assert_eq!(err, ErrorCode::Success);
+        }
 
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        let report = verify_storage(&plain).unwrap();
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        for wallet in &[&encrypted, &compressed] {
+            let wallet_report = verify_storage(wallet).unwrap();
+
+            assert_eq!(wallet_report["root"], report["root"]);
+            assert_eq!(wallet_report["metadataHash"], report["metadataHash"]);
+        }
+
+        // the copy holds the data encrypted with the data keys of its source
+        let copy = copy_of(&encrypted);
+
+        assert_eq!(verify_storage(&copy).unwrap()["root"], report["root"]);
     }
 
     #[test]
-    fn test_add_empty_record_tags_record_without_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_encrypted_chunked_values() {
+        let mut wallet = TestWallet::new_with_config("encryption", encryption_config("kek-1"));
+
+        let mut record = TestRecord::new_default(true);
+        record.value = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        let mut config: serde_json::Value = serde_json::from_str(wallet.config.to_str().unwrap()).unwrap();
+        config["values"] = json!({"chunked": true, "chunk_size": 64});
+        wallet.config = CString::new(config.to_string()).unwrap();
+        wallet.handle = wallet._open();
 
         wallet.add_record(&record);
 
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+        assert_eq!(search_value(&wallet, &record), record.value);
+        assert_eq!(read_value(&wallet, &record, 100).unwrap(), record.value);
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let writer_handle = write_value(&wallet, &record, &[&[1u8; 100], &[2u8; 100]]);
+        let err = api::mysql_storage_finish_value_writer(wallet.handle, writer_handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        let mut expected_value = vec![1u8; 100];
+        expected_value.extend_from_slice(&[2u8; 100]);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), expected_value);
+        assert_eq!(read_value(&wallet, &record, 30).unwrap(), expected_value);
     }
 
     #[test]
-    fn test_add_duplicate_record_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+    fn test_rotate_data_key_keeps_old_values_readable() {
+        let wallet = TestWallet::new_with_config("encryption", encryption_config("kek-1"));
 
-        wallet.add_record(&record);
+        let old_record = TestRecord::new_default(true);
+        wallet.add_record(&old_record);
 
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        let mut version: u32 = 0;
+        let err = api::mysql_storage_rotate_data_key(wallet.handle, &mut version);
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(version, 2);
 
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        let new_record = TestRecord::new_default(true);
+        wallet.add_record(&new_record);
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &old_record).unwrap(), old_record.value);
+        assert_eq!(fetch_value(&wallet, &new_record).unwrap(), new_record.value);
 
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        let copy = copy_of(&wallet);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        assert_eq!(fetch_value(&copy, &old_record).unwrap(), old_record.value);
+        assert_eq!(fetch_value(&copy, &new_record).unwrap(), new_record.value);
+        assert_eq!(get_storage_metadata(&copy), wallet.metadata);
     }
 
     #[test]
-    fn test_add_record_tags_unknown_record() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_reencryption_encrypts_existing_data() {
+        let mut wallet = TestWallet::new_default(false);
 
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::ItemNotFound);
-    }
+        let records: Vec<TestRecord> = (0..5).map(|_| TestRecord::new_default(true)).collect();
+        for record in records.iter() {
+            wallet.add_record(record);
+        }
 
-    #[test]
-    fn test_add_record_tags_invalid_format() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+        wallet.reopen_with_config("encryption", encryption_config("kek-1"));
 
-        let tags_json = CString::new(r#"{"'''tag1": "value1", "tag2": "value2", "~tag3": "value3"}"#).unwrap();
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidStructure);
-    }
+        // the data stored before the encryption was enabled stays readable
+        assert_eq!(fetch_value(&wallet, &records[0]).unwrap(), records[0].value);
 
-    #[test]
-    fn test_add_tags_invalid_handle() {
-        let wallet = TestWallet::new_default(true);
-        let record = TestRecord::new_default(true);
+        let err = api::mysql_storage_start_reencryption(wallet.handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidState);
-    }
+        let status = wait_for_reencryption(&wallet);
+        assert_eq!(status["error"], json!(null));
+        assert_eq!(status["keyVersion"], json!(1));
+        assert_eq!(status["scannedRecords"], json!(5));
+        assert_eq!(status["reencryptedRecords"], json!(5));
 
-    #[test]
-    fn test_add_tags_for_unknown_record() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+        for record in records.iter() {
+            assert_eq!(fetch_value(&wallet, record).unwrap(), record.value);
+        }
+        assert_eq!(get_storage_metadata(&wallet), wallet.metadata);
 
-        let err = api::add_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::ItemNotFound);
+        // once re-encrypted the data can only be read with the keys
+        wallet.reopen_with_config("encryption", json!({}));
+        assert_eq!(fetch_value(&wallet, &records[0]), Err(ErrorCode::EncryptionKeyNotFound));
     }
 
-    /** Storage UPDATE_RECORD_TAGS Tests */
-
     #[test]
-    fn test_update_tags_record_with_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
-
+    fn test_reencryption_after_rotation() {
+        let wallet = TestWallet::new_with_config("encryption", encryption_config("kek-1"));
 
+        let record = TestRecord::new_default(true);
         wallet.add_record(&record);
 
-        let new_tags_json = CString::new(r#"{"tag1": "value1_new", "tag2": "value2_new", "~tag3": "value3_new"}"#).unwrap();
-        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_tags_json.as_ptr());
+        let mut version: u32 = 0;
+        let err = api::mysql_storage_rotate_data_key(wallet.handle, &mut version);
         assert_eq!(err, ErrorCode::Success);
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let err = api::mysql_storage_start_reencryption(wallet.handle);
         assert_eq!(err, ErrorCode::Success);
 
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+        let status = wait_for_reencryption(&wallet);
+        assert_eq!(status["keyVersion"], json!(version));
+        assert_eq!(status["reencryptedRecords"], json!(1));
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(new_tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+
+        // a second run finds nothing left to re-encrypt
+        let err = api::mysql_storage_start_reencryption(wallet.handle);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(wait_for_reencryption(&wallet)["reencryptedRecords"], json!(0));
     }
 
     #[test]
-    fn test_update_tags_record_without_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_rewrap_data_keys() {
+        let mut wallet = TestWallet::new_with_config("encryption", encryption_config("kek-1"));
 
+        let record = TestRecord::new_default(true);
         wallet.add_record(&record);
 
-        let new_tags_json = CString::new(r#"{"tag1": "value1_new", "tag2": "value2_new", "~tag3": "value3_new"}"#).unwrap();
-        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        let config = CString::new(TEST_ENV.get_config_with("encryption", encryption_config("kek-2"))).unwrap();
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, true, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let mut rewrapped: u64 = 0;
+        let err = api::mysql_storage_rewrap_data_keys(wallet.name.as_ptr(), config.as_ptr(), wallet.credentials.as_ptr(), &mut rewrapped);
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(rewrapped, 1);
 
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let err = api::mysql_storage_rewrap_data_keys(wallet.name.as_ptr(), config.as_ptr(), wallet.credentials.as_ptr(), &mut rewrapped);
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(rewrapped, 0);
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(new_tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
-    }
-
-    #[test]
-    fn test_update_record_tags_same_value() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
-
-        wallet.add_record(&record);
+        // the old key-encryption key is no longer needed
+        let only_new_key = json!({"current_key": "kek-2", "keys": {"kek-2": "AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI="}});
+        wallet.reopen_with_config("encryption", only_new_key);
 
-        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+        assert_eq!(get_storage_metadata(&wallet), wallet.metadata);
     }
 
     #[test]
-    fn test_update_record_tags_invalid_format() {
+    fn test_open_storage_unknown_current_key() {
         let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+        let config = CString::new(TEST_ENV.get_config_with("encryption", json!({"current_key": "kek-3", "keys": {}}))).unwrap();
 
-        let invalid_tags = CString::new("...").unwrap();
-        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), invalid_tags.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        let mut handle: i32 = -1;
+        let err = api::open_storage(wallet.name.as_ptr(), config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::EncryptionKeyNotFound);
     }
 
     #[test]
-    fn test_update_record_tag_unknown_record() {
+    fn test_encryption_not_enabled() {
         let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
 
-        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
-        assert_eq!(err, ErrorCode::ItemNotFound);
-    }
+        let mut version: u32 = 0;
+        let err = api::mysql_storage_rotate_data_key(wallet.handle, &mut version);
+        assert_eq!(err, ErrorCode::InvalidState);
 
-    #[test]
-    fn test_update_record_tag_invalid_storage_handle() {
-        let wallet = TestWallet::new_default(true);
-        let record = TestRecord::new_default(false);
+        let err = api::mysql_storage_start_reencryption(wallet.handle);
+        assert_eq!(err, ErrorCode::InvalidState);
 
-        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tags_json.as_ptr());
+        let mut status_json_p: *const c_char = ptr::null_mut();
+        let mut status_handle: i32 = -1;
+        let err = api::mysql_storage_get_reencryption_status(wallet.handle, &mut status_json_p, &mut status_handle);
         assert_eq!(err, ErrorCode::InvalidState);
     }
 
-    /** Storage DELETE_RECORD_TAGS Tests */
+    /** Worker Runtime Tests */
 
     #[test]
-    fn test_delete_record_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_runtime_executes_storage_calls() {
+        let wallet = TestWallet::new_with_config("runtime", json!({"enabled": true, "workers": 2}));
 
+        let record = TestRecord::new_default(true);
         wallet.add_record(&record);
 
-        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), record.value);
+        assert_eq!(search_value(&wallet, &record), record.value);
+        assert_eq!(get_storage_metadata(&wallet), wallet.metadata);
 
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, false, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        let new_value = vec![5, 6, 7];
+        let err = api::update_record_value(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_value.as_ptr(), new_value.len());
         assert_eq!(err, ErrorCode::Success);
+        assert_eq!(fetch_value(&wallet, &record).unwrap(), new_value);
 
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
+        let err = api::delete_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr());
         assert_eq!(err, ErrorCode::Success);
-
-        let tags_json_empty = CString::new(r#"{}"#).unwrap();
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(tags_json_empty.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        assert_eq!(fetch_value(&wallet, &record), Err(ErrorCode::ItemNotFound));
     }
 
     #[test]
-    fn test_delete_unknown_tags() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
-
-        wallet.add_record(&record);
-
-        let unknown_tags = CString::new(r#"["bogus1", "bogus2", "~bogus3"]"#).unwrap();
-        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), unknown_tags.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
-
-        let mut record_handle = -1;
-        let options_json = fetch_options(false, false, true);
-        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
-        assert_eq!(err, ErrorCode::Success);
-
-        let mut tags_json_p: *const c_char = ptr::null_mut();
-        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_json_p);
-        assert_eq!(err, ErrorCode::Success);
+    fn test_runtime_executes_calls_concurrently() {
+        let wallet = TestWallet::new_with_config("runtime", json!({"enabled": true, "workers": 4, "queue_size": 64}));
+        let type_ = random_string(10);
+
+        let threads: Vec<_> = (0..8).map(|_| {
+            let (handle, type_) = (wallet.handle, CString::new(type_.clone()).unwrap());
+
+            std::thread::spawn(move || {
+                for _ in 0..10 {
+                    let id = CString::new(random_name()).unwrap();
+                    let value = vec![1, 2, 3];
+                    let tags = CString::new("{}").unwrap();
+
+                    let err = api::add_record(handle, type_.as_ptr(), id.as_ptr(), value.as_ptr(), value.len(), tags.as_ptr());
+                    assert_eq!(err, ErrorCode::Success);
+                }
+            })
+        }).collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
 
-        let expected_tags_map: HashMap<String, serde_json::Value> = serde_json::from_slice(record.tags_json.as_bytes()).unwrap();
-        let tags_map: HashMap<String, serde_json::Value> = serde_json::from_str(unsafe { CStr::from_ptr(tags_json_p) }.to_str().unwrap()).unwrap();
-        assert_eq!(tags_map, expected_tags_map);
+        assert_eq!(get_storage_stats(&wallet).unwrap()["itemCount"], json!(80));
     }
 
     #[test]
-    fn test_delete_tags_invalid_json_format() {
+    fn test_runtime_invalid_config() {
         let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(true);
+        let config = CString::new(TEST_ENV.get_config_with("runtime", json!({"enabled": true, "workers": 0}))).unwrap();
 
-        wallet.add_record(&record);
-
-        let tag_names = CString::new(r#"["'tag11", "tag22", "~tag33"]"#).unwrap();
-        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), tag_names.as_ptr());
+        let mut handle: i32 = -1;
+        let err = api::open_storage(wallet.name.as_ptr(), config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
         assert_eq!(err, ErrorCode::InvalidStructure);
     }
 
-    #[test]
-    fn test_delete_tags_empty_tag_name_list() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    /** Statement Timeout Tests */
 
-        wallet.add_record(&record);
+    ///
+    ///  Connection holding the write lock of the items table, which blocks the reads of every other connection
+    ///
+    fn lock_items_table() -> Conn {
+        let config: serde_json::Value = serde_json::from_str(&TEST_ENV.get_config()).unwrap();
+        let credentials: serde_json::Value = serde_json::from_str(&TEST_ENV.get_credentials()).unwrap();
 
-        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        let connection_string = format!("mysql://{}:{}@{}:{}/{}",
+                                        credentials["user"].as_str().unwrap(), credentials["pass"].as_str().unwrap(),
+                                        config["write_host"].as_str().unwrap(), config["port"], config["db_name"].as_str().unwrap());
+        let mut connection = Conn::new(connection_string).unwrap();
+        connection.query(format!("LOCK TABLES `{}items` WRITE", config["table_prefix"].as_str().unwrap())).unwrap();
+
+        connection
     }
 
     #[test]
-    fn test_delete_tags_unknown_record() {
-        let wallet = TestWallet::new_default(false);
-        let record = TestRecord::new_default(false);
+    fn test_statement_timeout_on_locked_record() {
+        let wallet = TestWallet::new_with_config("statement_timeout", json!(1000));
 
-        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
-        assert_eq!(err, ErrorCode::ItemNotFound);
-    }
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-    #[test]
-    fn test_delete_unknown_tags_invalid_storage_handle() {
-        let wallet = TestWallet::new_default(true);
-        let record = TestRecord::new_default(false);
+        // an open value writer keeps the record locked
+        let writer_handle = write_value(&wallet, &record, &[&[1u8; 10]]);
 
-        let err = api::delete_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), record.tag_names.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidState);
-    }
+        let new_tags = CString::new(r#"{"tag1": "new_value"}"#).unwrap();
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_tags.as_ptr());
+        assert_eq!(err, ErrorCode::Timeout);
 
-    /** Storage METADATA Tests */
+        let err = api::mysql_storage_abort_value_writer(wallet.handle, writer_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-    #[test]
-    fn test_set_get_metadata() {
-        let wallet = TestWallet::new_default(false);
+        let err = api::update_record_tags(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), new_tags.as_ptr());
+        assert_eq!(err, ErrorCode::Success);
+    }
 
-        let mut metadata_handle = -1;
-        let mut metadata_ptr: *const c_char = ptr::null_mut();
+    #[test]
+    fn test_statement_timeout_options() {
+        let wallet = TestWallet::new_with_config("statement_timeout", json!(5000));
 
-        let new_metadata = random_string(512);
-        let new_metadata_cstring = CString::new(new_metadata.clone()).unwrap();
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-        let err = api::set_metadata(wallet.handle, new_metadata_cstring.as_ptr());
-        assert_eq!(err, ErrorCode::Success);
+        let mut connection = lock_items_table();
 
-        let err = api::get_metadata(wallet.handle, &mut metadata_ptr, &mut metadata_handle);
-        assert_eq!(err, ErrorCode::Success);
+        // the locked read is interrupted after 1 ms
+        let options_json = CString::new(r#"{"retrieveValue": true, "statementTimeout": 1}"#).unwrap();
+        let mut record_handle = -1;
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
+        assert_eq!(err, ErrorCode::Timeout);
 
-        let metadata = unsafe { CStr::from_ptr(metadata_ptr).to_str().unwrap() };
+        let ids_json = CString::new(json!([record.id.to_str().unwrap()]).to_string()).unwrap();
+        let mut search_handle = -1;
+        let err = api::mysql_storage_fetch_records(wallet.handle, record.type_.as_ptr(), ids_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Timeout);
 
-        assert_eq!(new_metadata, metadata);
+        let unlock = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            connection.query("UNLOCK TABLES").unwrap();
+        });
 
-        let err = api::free_metadata(wallet.handle, metadata_handle);
+        // with the 5000 ms of the config the read waits for the lock to be released
+        let options_json = CString::new(r#"{"retrieveValue": true}"#).unwrap();
+        let err = api::get_record(wallet.handle, record.type_.as_ptr(), record.id.as_ptr(), options_json.as_ptr(), &mut record_handle);
         assert_eq!(err, ErrorCode::Success);
-    }
-
-    #[test]
-    fn test_set_metadata_invalid_metadata_handle() {
-        let wallet = TestWallet::new_default(true);
 
-        let new_metadata = random_string(1);
-        let new_metadata_cstring = CString::new(new_metadata.clone()).unwrap();
+        let err = api::free_record(wallet.handle, record_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let err = api::set_metadata(wallet.handle, new_metadata_cstring.as_ptr());
-        assert_eq!(err, ErrorCode::InvalidState);
-    }
+        unlock.join().unwrap();
 
-    #[test]
-    fn test_free_metadata_invalid_storage_handle() {
-        let wallet = TestWallet::new_default(true);
+        let options_json = CString::new(r#"{"retrieveValue": true, "statementTimeout": 1000}"#).unwrap();
+        let query_json = CString::new(r#"{"tag1": "value1"}"#).unwrap();
+        let err = api::search_records(wallet.handle, record.type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let metadata_handle = -1;
+        let err = api::fetch_search_next_record(wallet.handle, search_handle, &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let err = api::free_metadata(wallet.handle, metadata_handle);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let err = api::free_search(wallet.handle, search_handle);
+        assert_eq!(err, ErrorCode::Success);
     }
 
     #[test]
-    fn test_free_metadata_invalid_metadata_handle() {
+    fn test_statement_timeout_invalid_config() {
         let wallet = TestWallet::new_default(false);
+        let config = CString::new(TEST_ENV.get_config_with("statement_timeout", json!(0))).unwrap();
 
-        let metadata_handle = -1;
-
-        let err = api::free_metadata(wallet.handle, metadata_handle);
-        assert_eq!(err, ErrorCode::InvalidState);
+        let mut handle: i32 = -1;
+        let err = api::open_storage(wallet.name.as_ptr(), config.as_ptr(), wallet.credentials.as_ptr(), &mut handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
     }
 
     /** Search Record Tests */
@@ -2497,11 +4852,238 @@ mod high_casees {
     }
 
     #[test]
-    fn test_search_records_invalid_query_format() {
+    fn test_search_records_invalid_query_format() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("").unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_options_format() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("{}").unwrap();
+        let options_json = CString::new("").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_eq_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "tag_name": 1
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_neq_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "tag_name": {"$neq": 1}
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_gt_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "k3": {"$gt": 1}
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_gte_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "k3": {"$gte": 1}
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_lt_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "k3": {"$lt": 1}
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_lte_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "k3": {"$lte":  1}
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_like_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "k2": {"$like": 1},
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_not_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "$in": [1, "value_nem33"]
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_or_with_non_string_arg() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+
+        let query_json = json!({
+
+            "$or": [
+                {
+                    "k2": 1,
+                },
+            ],
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_search_records_invalid_query_in_with_non_string_arg() {
         let wallet = TestWallet::new_default(false);
 
         let type_ = CString::new(random_string(10)).unwrap();
-        let query_json = CString::new("").unwrap();
+
+        let query_json = json!({
+
+            "$in": [1, "value_nem33"]
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
         let options_json = CString::new("{}").unwrap();
         let mut search_handle: i32 = -1;
 
@@ -2510,12 +5092,20 @@ mod high_casees {
     }
 
     #[test]
-    fn test_search_records_invalid_options_format() {
+    fn test_search_records_invalid_query_gt_with_encrypted_tag_name() {
         let wallet = TestWallet::new_default(false);
 
         let type_ = CString::new(random_string(10)).unwrap();
-        let query_json = CString::new("{}").unwrap();
-        let options_json = CString::new("").unwrap();
+
+        let query_json = json!({
+
+            "k3": {"$gt": "a"}
+
+        });
+
+        let query_json = serde_json::to_string(&query_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new("{}").unwrap();
         let mut search_handle: i32 = -1;
 
         let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
@@ -2523,14 +5113,14 @@ mod high_casees {
     }
 
     #[test]
-    fn test_search_records_invalid_query_eq_with_non_string_arg() {
+    fn test_search_records_invalid_query_gte_with_encrypted_tag_name() {
         let wallet = TestWallet::new_default(false);
 
         let type_ = CString::new(random_string(10)).unwrap();
 
         let query_json = json!({
 
-            "tag_name": 1
+            "k3": {"$gte": "a"}
 
         });
 
@@ -2544,14 +5134,14 @@ mod high_casees {
     }
 
     #[test]
-    fn test_search_records_invalid_query_neq_with_non_string_arg() {
+    fn test_search_records_invalid_query_lt_with_encrypted_tag_name() {
         let wallet = TestWallet::new_default(false);
 
         let type_ = CString::new(random_string(10)).unwrap();
 
         let query_json = json!({
 
-            "tag_name": {"$neq": 1}
+            "k3": {"$lt": "a"}
 
         });
 
@@ -2565,14 +5155,14 @@ mod high_casees {
     }
 
     #[test]
-    fn test_search_records_invalid_query_gt_with_non_string_arg() {
+    fn test_search_records_invalid_query_lte_with_encrypted_tag_name() {
         let wallet = TestWallet::new_default(false);
 
         let type_ = CString::new(random_string(10)).unwrap();
 
         let query_json = json!({
 
-            "k3": {"$gt": 1}
+            "k3": {"$lte": "a"}
 
         });
 
@@ -2586,14 +5176,14 @@ mod high_casees {
     }
 
     #[test]
-    fn test_search_records_invalid_query_gte_with_non_string_arg() {
+    fn test_search_records_invalid_query_like_with_encrypted_tag_name() {
         let wallet = TestWallet::new_default(false);
 
         let type_ = CString::new(random_string(10)).unwrap();
 
         let query_json = json!({
 
-            "k3": {"$gte": 1}
+            "k2": {"$like": "like_target"},
 
         });
 
@@ -2607,14 +5197,29 @@ mod high_casees {
     }
 
     #[test]
-    fn test_search_records_invalid_query_lt_with_non_string_arg() {
+    fn test_search_records_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("{}").unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_search_records_query_limit_exceeded() {
         let wallet = TestWallet::new_default(false);
 
         let type_ = CString::new(random_string(10)).unwrap();
 
+        let values: Vec<String> = (0..2000).map(|_| random_string(10)).collect();
+
         let query_json = json!({
 
-            "k3": {"$lt": 1}
+            "tag1": {"$in": values}
 
         });
 
@@ -2624,234 +5229,552 @@ mod high_casees {
         let mut search_handle: i32 = -1;
 
         let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::QueryLimitExceeded);
+    }
+
+    #[test]
+    fn test_search_records_with_max_execution_time() {
+        let wallet = TestWallet::new_default(false);
+
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let query_json = CString::new(r#"{"tag1": "value1"}"#).unwrap();
+        let options_json = CString::new(r#"{"retrieveTotalCount": true, "maxExecutionTime": 5000}"#).unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::search_records(wallet.handle, record.type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut total_count: usize = 0;
+
+        let err = api::get_search_total_count(wallet.handle, search_handle, &mut total_count);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(total_count, 1);
+
+        let err = api::free_search(wallet.handle, search_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    /** Explain Search Tests */
+
+    fn explain_search(wallet: &TestWallet, type_: &CString, query_json: &str, options_json: &str) -> serde_json::Value {
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new(options_json).unwrap();
+        let mut explain_json_p: *const c_char = ptr::null_mut();
+        let mut explain_handle: i32 = -1;
+
+        let err = api::mysql_storage_explain_search(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut explain_json_p, &mut explain_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let explain_json = unsafe { CStr::from_ptr(explain_json_p).to_str().unwrap().to_string() };
+
+        let err = api::mysql_storage_free_json(explain_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        serde_json::from_str(&explain_json).unwrap()
+    }
+
+    #[test]
+    fn test_explain_search() {
+        let wallet = TestWallet::new_default(false);
+
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let explain = explain_search(&wallet, &record.type_, r#"{"$or": [{"tag1": "value1"}, {"tag1": "value2"}], "~tag3": "value3"}"#, r#"{"retrieveTotalCount": true}"#);
+
+        assert_eq!(explain["wql"], json!({"$and": [{"tag1": {"$in": ["<redacted>", "<redacted>"]}}, {"~tag3": "value3"}]}));
+
+        let records_query = &explain["records_query"];
+        assert!(records_query["sql"].as_str().unwrap().starts_with("SELECT"));
+        assert_eq!(records_query["arguments"].as_array().unwrap().len(), 5);
+        assert_eq!(records_query["arguments"][2], json!("value3"));
+        assert!(records_query.get("explain").is_none());
+
+        let count_query = &explain["count_query"];
+        assert!(count_query["sql"].as_str().unwrap().contains("count(*)"));
+    }
+
+    #[test]
+    fn test_explain_search_run_explain() {
+        let wallet = TestWallet::new_default(false);
+
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let explain = explain_search(&wallet, &record.type_, r#"{"tag1": "value1"}"#, r#"{"retrieveRecords": false, "retrieveTotalCount": true, "runExplain": true}"#);
+
+        assert!(explain.get("records_query").is_none());
+        assert!(explain["count_query"]["explain"].is_object());
+    }
+
+    #[test]
+    fn test_explain_search_invalid_query_format() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("...").unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut explain_json_p: *const c_char = ptr::null_mut();
+        let mut explain_handle: i32 = -1;
+
+        let err = api::mysql_storage_explain_search(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut explain_json_p, &mut explain_handle);
+        assert_eq!(err, ErrorCode::InvalidStructure);
+    }
+
+    #[test]
+    fn test_explain_search_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("{}").unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut explain_json_p: *const c_char = ptr::null_mut();
+        let mut explain_handle: i32 = -1;
+
+        let err = api::mysql_storage_explain_search(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut explain_json_p, &mut explain_handle);
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_free_json_invalid_handle() {
+        let err = api::mysql_storage_free_json(-1);
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    /** Fetch Records Tests */
+
+    fn get_search_missing_keys(wallet: &TestWallet, search_handle: i32) -> serde_json::Value {
+        let mut missing_keys_p: *const c_char = ptr::null_mut();
+
+        let err = api::mysql_storage_get_search_missing_keys(wallet.handle, search_handle, &mut missing_keys_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        serde_json::from_str(unsafe { CStr::from_ptr(missing_keys_p) }.to_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_fetch_records() {
+        let wallet = TestWallet::new_default(false);
+
+        let record1 = TestRecord::new_default(true);
+        let mut record2 = TestRecord::new_default(true);
+        record2.type_ = record1.type_.clone();
+        wallet.add_record(&record1);
+        wallet.add_record(&record2);
+
+        let missing_id = random_string(10);
+        let ids_json = CString::new(json!([record1.id.to_str().unwrap(), missing_id, record2.id.to_str().unwrap(), record1.id.to_str().unwrap()]).to_string()).unwrap();
+        let options_json = fetch_options(true, true, true);
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_fetch_records(wallet.handle, record1.type_.as_ptr(), ids_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut total_count: usize = 0;
+        let err = api::get_search_total_count(wallet.handle, search_handle, &mut total_count);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(total_count, 2);
+
+        let missing_keys = get_search_missing_keys(&wallet, search_handle);
+        assert_eq!(missing_keys, json!([{"type": record1.type_.to_str().unwrap(), "id": missing_id}]));
+
+        let mut found = fetch_search_types_and_ids(&wallet, search_handle);
+        found.sort();
+
+        let mut expected = vec![(record1.type_.clone(), record1.id.clone()), (record2.type_.clone(), record2.id.clone())];
+        expected.sort();
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_fetch_records_value_and_tags() {
+        let wallet = TestWallet::new_default(false);
+
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
+
+        let ids_json = CString::new(json!([record.id.to_str().unwrap()]).to_string()).unwrap();
+        let options_json = fetch_options(false, true, false);
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_fetch_records(wallet.handle, record.type_.as_ptr(), ids_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut record_handle: i32 = -1;
+        let err = api::fetch_search_next_record(wallet.handle, search_handle, &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut value_p: *const u8 = ptr::null();
+        let mut value_len: usize = 0;
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(unsafe { slice::from_raw_parts(value_p, value_len) }, &record.value[..]);
+
+        let mut type_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let mut tags_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_tags(wallet.handle, record_handle, &mut tags_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::fetch_search_next_record(wallet.handle, search_handle, &mut record_handle);
+        assert_eq!(err, ErrorCode::ItemNotFound);
+
+        let err = api::free_search(wallet.handle, search_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_fetch_records_by_keys() {
+        let wallet = TestWallet::new_default(false);
+
+        let record1 = TestRecord::new_default(true);
+        let record2 = TestRecord::new_default(true);
+        wallet.add_record(&record1);
+        wallet.add_record(&record2);
+
+        let missing_type = random_string(10);
+        let keys_json = CString::new(json!([
+            {"type": record1.type_.to_str().unwrap(), "id": record1.id.to_str().unwrap()},
+            {"type": record2.type_.to_str().unwrap(), "id": record2.id.to_str().unwrap()},
+            {"type": missing_type, "id": record1.id.to_str().unwrap()},
+        ]).to_string()).unwrap();
+        let options_json = fetch_options(false, false, false);
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_fetch_records_by_keys(wallet.handle, keys_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let missing_keys = get_search_missing_keys(&wallet, search_handle);
+        assert_eq!(missing_keys, json!([{"type": missing_type, "id": record1.id.to_str().unwrap()}]));
+
+        let mut found = fetch_search_types_and_ids(&wallet, search_handle);
+        found.sort();
+
+        let mut expected = vec![(record1.type_.clone(), record1.id.clone()), (record2.type_.clone(), record2.id.clone())];
+        expected.sort();
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_fetch_records_empty_ids() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let ids_json = CString::new("[]").unwrap();
+        let options_json = fetch_options(true, true, true);
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_fetch_records(wallet.handle, type_.as_ptr(), ids_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        assert_eq!(get_search_missing_keys(&wallet, search_handle), json!([]));
+        assert!(fetch_search_types_and_ids(&wallet, search_handle).is_empty());
+    }
+
+    #[test]
+    fn test_fetch_records_invalid_ids() {
+        let wallet = TestWallet::new_default(false);
+
+        let type_ = CString::new(random_string(10)).unwrap();
+        let ids_json = CString::new(r#"{"id": "value"}"#).unwrap();
+        let options_json = fetch_options(true, true, true);
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_fetch_records(wallet.handle, type_.as_ptr(), ids_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
         assert_eq!(err, ErrorCode::InvalidStructure);
     }
 
-    #[test]
-    fn test_search_records_invalid_query_lte_with_non_string_arg() {
-        let wallet = TestWallet::new_default(false);
+    #[test]
+    fn test_get_search_missing_keys_for_regular_search() {
+        let wallet = TestWallet::new_default(false);
+
+        let mut search_handle: i32 = -1;
+        let err = api::search_all_records(wallet.handle, &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut missing_keys_p: *const c_char = ptr::null_mut();
+        let err = api::mysql_storage_get_search_missing_keys(wallet.handle, search_handle, &mut missing_keys_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let err = api::free_search(wallet.handle, search_handle);
+        assert_eq!(err, ErrorCode::Success);
+    }
+
+    #[test]
+    fn test_fetch_records_invalid_storage_handle() {
+        let type_ = CString::new(random_string(10)).unwrap();
+        let ids_json = CString::new("[]").unwrap();
+        let options_json = fetch_options(true, true, true);
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_fetch_records(-1, type_.as_ptr(), ids_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
+    /** Search Records By Types Tests */
+
+    fn search_records_by_types(wallet: &TestWallet, types_json: &str, query_json: &str, options_json: &str) -> (usize, Vec<(CString, CString)>) {
+        let types_json = CString::new(types_json).unwrap();
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new(options_json).unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_search_records_by_types(wallet.handle, types_json.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut total_count: usize = 0;
+        let err = api::get_search_total_count(wallet.handle, search_handle, &mut total_count);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut records = fetch_search_types_and_ids(wallet, search_handle);
+        records.sort();
+
+        (total_count, records)
+    }
+
+    ///
+    /// Fetches the type and id of all remaining records of a search and frees the search.
+    ///
+    fn fetch_search_types_and_ids(wallet: &TestWallet, search_handle: i32) -> Vec<(CString, CString)> {
+        let mut records: Vec<(CString, CString)> = Vec::new();
+        let mut record_handle: i32 = -1;
+
+        while api::fetch_search_next_record(wallet.handle, search_handle, &mut record_handle) == ErrorCode::Success {
+            let mut type_p: *const c_char = ptr::null_mut();
+            let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+            assert_eq!(err, ErrorCode::Success);
+
+            let mut id_p: *const c_char = ptr::null_mut();
+            let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+            assert_eq!(err, ErrorCode::Success);
 
-        let type_ = CString::new(random_string(10)).unwrap();
+            records.push((unsafe { CStr::from_ptr(type_p) }.to_owned(), unsafe { CStr::from_ptr(id_p) }.to_owned()));
+        }
 
-        let query_json = json!({
+        let err = api::free_search(wallet.handle, search_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-            "k3": {"$lte":  1}
+        records
+    }
 
-        });
+    fn add_records_with_type_prefix(wallet: &TestWallet, type_prefix: &str, num_of_types: usize) -> Vec<TestRecord> {
+        let mut records: Vec<TestRecord> = Vec::new();
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+        for i in 0..num_of_types {
+            let mut record = TestRecord::new_default(true);
+            record.type_ = CString::new(format!("{}{}", type_prefix, i)).unwrap();
+            wallet.add_record(&record);
+            records.push(record);
+        }
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        records
     }
 
     #[test]
-    fn test_search_records_invalid_query_like_with_non_string_arg() {
+    fn test_search_records_by_type_list() {
         let wallet = TestWallet::new_default(false);
 
-        let type_ = CString::new(random_string(10)).unwrap();
-
-        let query_json = json!({
+        let records = add_records_with_type_prefix(&wallet, &random_string(10), 3);
 
-            "k2": {"$like": 1},
+        let types_json = json!([records[0].type_.to_str().unwrap(), records[1].type_.to_str().unwrap()]).to_string();
 
-        });
+        let (total_count, found) = search_records_by_types(&wallet, &types_json, r#"{"tag1": "value1"}"#, r#"{"retrieveTotalCount": true}"#);
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+        let mut expected: Vec<(CString, CString)> = records[..2].iter().map(|record| (record.type_.clone(), record.id.clone())).collect();
+        expected.sort();
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        assert_eq!(total_count, 2);
+        assert_eq!(found, expected);
     }
 
     #[test]
-    fn test_search_records_invalid_query_not_with_non_string_arg() {
+    fn test_search_records_by_type_pattern() {
         let wallet = TestWallet::new_default(false);
 
-        let type_ = CString::new(random_string(10)).unwrap();
-
-        let query_json = json!({
+        let type_prefix = random_string(10);
+        let records = add_records_with_type_prefix(&wallet, &type_prefix, 3);
+        wallet.add_record(&TestRecord::new_default(true));
 
-            "$in": [1, "value_nem33"]
+        let types_json = json!({"$like": format!("{}%", type_prefix)}).to_string();
 
-        });
+        let (total_count, found) = search_records_by_types(&wallet, &types_json, "{}", r#"{"retrieveTotalCount": true}"#);
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+        let mut expected: Vec<(CString, CString)> = records.iter().map(|record| (record.type_.clone(), record.id.clone())).collect();
+        expected.sort();
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        assert_eq!(total_count, 3);
+        assert_eq!(found, expected);
     }
 
     #[test]
-    fn test_search_records_invalid_query_or_with_non_string_arg() {
+    fn test_search_records_by_types_with_type_field() {
         let wallet = TestWallet::new_default(false);
 
-        let type_ = CString::new(random_string(10)).unwrap();
-
-        let query_json = json!({
+        let type_prefix = random_string(10);
+        let records = add_records_with_type_prefix(&wallet, &type_prefix, 3);
 
-            "$or": [
-                {
-                    "k2": 1,
-                },
-            ],
+        let types_json = json!({"$like": format!("{}%", type_prefix)}).to_string();
+        let query_json = json!({"$type": {"$neq": records[1].type_.to_str().unwrap()}, "~tag3": "value3"}).to_string();
 
-        });
+        let (total_count, found) = search_records_by_types(&wallet, &types_json, &query_json, r#"{"retrieveTotalCount": true}"#);
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+        let mut expected: Vec<(CString, CString)> = vec![
+            (records[0].type_.clone(), records[0].id.clone()),
+            (records[2].type_.clone(), records[2].id.clone()),
+        ];
+        expected.sort();
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        assert_eq!(total_count, 2);
+        assert_eq!(found, expected);
     }
 
     #[test]
-    fn test_search_records_invalid_query_in_with_non_string_arg() {
+    fn test_search_records_with_type_field() {
         let wallet = TestWallet::new_default(false);
 
-        let type_ = CString::new(random_string(10)).unwrap();
-
-        let query_json = json!({
+        let record = TestRecord::new_default(true);
+        wallet.add_record(&record);
 
-            "$in": [1, "value_nem33"]
+        let query_json = CString::new(json!({"$type": {"$in": [record.type_.to_str().unwrap(), random_string(10)]}}).to_string()).unwrap();
+        let options_json = CString::new(r#"{"retrieveTotalCount": true}"#).unwrap();
+        let mut search_handle: i32 = -1;
 
-        });
+        let err = api::search_records(wallet.handle, record.type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+        let mut total_count: usize = 0;
+        let err = api::get_search_total_count(wallet.handle, search_handle, &mut total_count);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(total_count, 1);
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        let err = api::free_search(wallet.handle, search_handle);
+        assert_eq!(err, ErrorCode::Success);
     }
 
     #[test]
-    fn test_search_records_invalid_query_gt_with_encrypted_tag_name() {
+    fn test_search_records_by_types_invalid_types_format() {
         let wallet = TestWallet::new_default(false);
 
-        let type_ = CString::new(random_string(10)).unwrap();
+        let query_json = CString::new("{}").unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
 
-        let query_json = json!({
+        for types_json in &["[]", "[1]", r#"{"$in": ["type"]}"#, "..."] {
+            let types_json = CString::new(*types_json).unwrap();
 
-            "k3": {"$gt": "a"}
+            let err = api::mysql_storage_search_records_by_types(wallet.handle, types_json.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+            assert_eq!(err, ErrorCode::InvalidStructure);
+        }
+    }
 
-        });
+    #[test]
+    fn test_search_records_by_types_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
+        let types_json = CString::new(json!([random_string(10)]).to_string()).unwrap();
+        let query_json = CString::new("{}").unwrap();
         let options_json = CString::new("{}").unwrap();
         let mut search_handle: i32 = -1;
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        let err = api::mysql_storage_search_records_by_types(wallet.handle, types_json.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidState);
     }
 
-    #[test]
-    fn test_search_records_invalid_query_gte_with_encrypted_tag_name() {
-        let wallet = TestWallet::new_default(false);
-
-        let type_ = CString::new(random_string(10)).unwrap();
+    /** Aggregate Records Tests */
 
-        let query_json = json!({
+    fn aggregate_records(wallet: &TestWallet, query_json: &str, options_json: &str) -> Result<serde_json::Value, ErrorCode> {
+        let query_json = CString::new(query_json).unwrap();
+        let options_json = CString::new(options_json).unwrap();
+        let mut aggregate_json_p: *const c_char = ptr::null_mut();
+        let mut aggregate_handle: i32 = -1;
 
-            "k3": {"$gte": "a"}
+        let err = api::mysql_storage_aggregate_records(wallet.handle, query_json.as_ptr(), options_json.as_ptr(), &mut aggregate_json_p, &mut aggregate_handle);
+        if err != ErrorCode::Success {
+            return Err(err);
+        }
 
-        });
+        let aggregate_json = unsafe { CStr::from_ptr(aggregate_json_p).to_str().unwrap().to_string() };
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+        let err = api::mysql_storage_free_json(aggregate_handle);
+        assert_eq!(err, ErrorCode::Success);
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        Ok(serde_json::from_str(&aggregate_json).unwrap())
     }
 
     #[test]
-    fn test_search_records_invalid_query_lt_with_encrypted_tag_name() {
+    fn test_aggregate_records_by_type() {
         let wallet = TestWallet::new_default(false);
 
-        let type_ = CString::new(random_string(10)).unwrap();
-
-        let query_json = json!({
-
-            "k3": {"$lt": "a"}
+        let type_prefix = random_string(10);
+        let records = add_records_with_type_prefix(&wallet, &type_prefix, 2);
 
-        });
+        let mut record = TestRecord::new_default(true);
+        record.type_ = records[0].type_.clone();
+        wallet.add_record(&record);
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+        let options_json = json!({"groupBy": "$type", "types": {"$like": format!("{}%", type_prefix)}}).to_string();
+        let aggregate = aggregate_records(&wallet, "{}", &options_json).unwrap();
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        assert_eq!(aggregate, json!({
+            "groupBy": "$type",
+            "buckets": [
+                {"value": records[0].type_.to_str().unwrap(), "count": 2},
+                {"value": records[1].type_.to_str().unwrap(), "count": 1},
+            ]
+        }));
     }
 
     #[test]
-    fn test_search_records_invalid_query_lte_with_encrypted_tag_name() {
+    fn test_aggregate_records_by_tag() {
         let wallet = TestWallet::new_default(false);
 
         let type_ = CString::new(random_string(10)).unwrap();
 
-        let query_json = json!({
+        for tags_json in &[r#"{"~schema": "schema1"}"#, r#"{"~schema": "schema1"}"#, r#"{"~schema": "schema2"}"#, r#"{"tag1": "value1"}"#] {
+            let mut record = TestRecord::new_default(false);
+            record.type_ = type_.clone();
+            record.tags_json = CString::new(*tags_json).unwrap();
+            wallet.add_record(&record);
+        }
 
-            "k3": {"$lte": "a"}
+        let options_json = json!({"groupBy": "~schema", "types": [type_.to_str().unwrap()]}).to_string();
+        let aggregate = aggregate_records(&wallet, "{}", &options_json).unwrap();
 
-        });
+        assert_eq!(aggregate["buckets"], json!([{"value": "schema1", "count": 2}, {"value": "schema2", "count": 1}]));
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+        let options_json = json!({"groupBy": "~schema", "distinct": true, "limit": 1}).to_string();
+        let aggregate = aggregate_records(&wallet, r#"{"~schema": {"$neq": "schema1"}}"#, &options_json).unwrap();
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        assert_eq!(aggregate, json!({"groupBy": "~schema", "values": ["schema2"]}));
     }
 
     #[test]
-    fn test_search_records_invalid_query_like_with_encrypted_tag_name() {
+    fn test_aggregate_records_by_encrypted_tag() {
         let wallet = TestWallet::new_default(false);
 
-        let type_ = CString::new(random_string(10)).unwrap();
-
-        let query_json = json!({
-
-            "k2": {"$like": "like_target"},
-
-        });
+        assert_eq!(aggregate_records(&wallet, "{}", r#"{"groupBy": "tag1"}"#).unwrap_err(), ErrorCode::InvalidStructure);
+    }
 
-        let query_json = serde_json::to_string(&query_json).unwrap();
-        let query_json = CString::new(query_json).unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
+    #[test]
+    fn test_aggregate_records_invalid_options_format() {
+        let wallet = TestWallet::new_default(false);
 
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidStructure);
+        assert_eq!(aggregate_records(&wallet, "{}", "{}").unwrap_err(), ErrorCode::InvalidStructure);
+        assert_eq!(aggregate_records(&wallet, "{}", r#"{"groupBy": "$type", "types": []}"#).unwrap_err(), ErrorCode::InvalidStructure);
+        assert_eq!(aggregate_records(&wallet, "...", r#"{"groupBy": "$type"}"#).unwrap_err(), ErrorCode::InvalidStructure);
     }
 
     #[test]
-    fn test_search_records_invalid_storage_handle() {
+    fn test_aggregate_records_invalid_storage_handle() {
         let wallet = TestWallet::new_default(true);
 
-        let type_ = CString::new(random_string(10)).unwrap();
-        let query_json = CString::new("{}").unwrap();
-        let options_json = CString::new("{}").unwrap();
-        let mut search_handle: i32 = -1;
-
-        let err = api::search_records(wallet.handle, type_.as_ptr(), query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
-        assert_eq!(err, ErrorCode::InvalidState);
+        assert_eq!(aggregate_records(&wallet, "{}", r#"{"groupBy": "$type"}"#).unwrap_err(), ErrorCode::InvalidState);
     }
 
     /** Search All Records Tests */
@@ -2906,6 +5829,142 @@ mod high_casees {
         assert_eq!(err, ErrorCode::InvalidState);
     }
 
+    #[test]
+    fn test_search_all_records_with_options() {
+        let wallet = TestWallet::new_default(false);
+
+        let mut expected: Vec<(CString, CString)> = Vec::new();
+
+        // -- Add records --
+        for _i in 0..3 {
+            let record = TestRecord::new_default(true);
+            wallet.add_record(&record);
+            expected.push((record.type_, record.id));
+        }
+
+        wallet.add_record(&TestRecord::new_default(false));
+
+        expected.sort();
+
+        // -- Search Records --
+        let query_json = CString::new(r#"{"~tag3": "value3"}"#).unwrap();
+        let options_json = CString::new(r#"{"retrieveTotalCount": true, "retrieveValue": false}"#).unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_search_all_records_with_options(wallet.handle, query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut total_count: usize = 0;
+        let err = api::get_search_total_count(wallet.handle, search_handle, &mut total_count);
+        assert_eq!(err, ErrorCode::Success);
+        assert_eq!(total_count, 3);
+
+        let mut record_handle = -1;
+        let err = api::fetch_search_next_record(wallet.handle, search_handle, &mut record_handle);
+        assert_eq!(err, ErrorCode::Success);
+
+        // Check type, it is always fetched
+        let mut type_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_type(wallet.handle, record_handle, &mut type_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        // Check value
+        let mut value_p: *const u8 = ptr::null_mut();
+        let mut value_len_p = 0;
+        let err = api::get_record_value(wallet.handle, record_handle, &mut value_p, &mut value_len_p);
+        assert_eq!(err, ErrorCode::InvalidState);
+
+        let mut id_p: *const c_char = ptr::null_mut();
+        let err = api::get_record_id(wallet.handle, record_handle, &mut id_p);
+        assert_eq!(err, ErrorCode::Success);
+
+        let mut found = fetch_search_types_and_ids(&wallet, search_handle);
+        found.push((unsafe { CStr::from_ptr(type_p) }.to_owned(), unsafe { CStr::from_ptr(id_p) }.to_owned()));
+        found.sort();
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_search_all_records_with_options_in_chunks() {
+        let wallet = TestWallet::new_default(false);
+
+        let num_of_records: usize = 5;
+        let chunk_size: usize = 2;
+        let mut expected: Vec<(CString, CString)> = Vec::new();
+
+        // -- Add records --
+        for _i in 0..num_of_records {
+            let record = TestRecord::new_default(true);
+            wallet.add_record(&record);
+            expected.push((record.type_, record.id));
+        }
+
+        expected.sort();
+
+        // -- Walk the wallet chunk by chunk --
+        let query_json = CString::new("{}").unwrap();
+        let mut found: Vec<(CString, CString)> = Vec::new();
+        let mut after: Option<serde_json::Value> = None;
+
+        loop {
+            let mut options = json!({"chunkSize": chunk_size, "retrieveValue": false});
+            if let Some(after) = after {
+                options["after"] = after;
+            }
+
+            let options_json = CString::new(options.to_string()).unwrap();
+            let mut search_handle: i32 = -1;
+
+            let err = api::mysql_storage_search_all_records_with_options(wallet.handle, query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+            assert_eq!(err, ErrorCode::Success);
+
+            let chunk = fetch_search_types_and_ids(&wallet, search_handle);
+            assert!(chunk.len() <= chunk_size);
+
+            if chunk.is_empty() {
+                break;
+            }
+
+            let &(ref last_type, ref last_id) = chunk.last().unwrap();
+            after = Some(json!({"type": last_type.to_str().unwrap(), "id": last_id.to_str().unwrap()}));
+
+            found.extend(chunk);
+        }
+
+        // chunks follow the collation of the `type` and `name` columns
+        found.sort();
+
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_search_all_records_with_options_invalid_options_format() {
+        let wallet = TestWallet::new_default(false);
+
+        let query_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        for options_json in &[r#"{"chunkSize": 0}"#, r#"{"chunkSize": -1}"#, r#"{"after": {"type": "type"}}"#, "..."] {
+            let options_json = CString::new(*options_json).unwrap();
+
+            let err = api::mysql_storage_search_all_records_with_options(wallet.handle, query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+            assert_eq!(err, ErrorCode::InvalidStructure);
+        }
+    }
+
+    #[test]
+    fn test_search_all_records_with_options_invalid_storage_handle() {
+        let wallet = TestWallet::new_default(true);
+
+        let query_json = CString::new("{}").unwrap();
+        let options_json = CString::new("{}").unwrap();
+        let mut search_handle: i32 = -1;
+
+        let err = api::mysql_storage_search_all_records_with_options(wallet.handle, query_json.as_ptr(), options_json.as_ptr(), &mut search_handle);
+        assert_eq!(err, ErrorCode::InvalidState);
+    }
+
     #[test]
     fn fetch_search_next_record_invalid_search_handle() {
         let wallet = TestWallet::new_default(false);
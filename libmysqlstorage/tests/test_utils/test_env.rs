@@ -1,8 +1,10 @@
 use std::env;
+use serde_json;
 
 pub struct TestEnv {
     config: String,
     credentials: String,
+    shard_db_names: Vec<String>,
 }
 
 impl TestEnv {
@@ -14,7 +16,8 @@ impl TestEnv {
                     "read_host": env::var("DB_READ_HOST").unwrap_or("wallet".to_string()),
                     "write_host": env::var("DB_WRITE_HOST").unwrap_or("wallet".to_string()),
                     "port": env::var("DB_PORT").unwrap_or(3306.to_string()).parse::<u32>().unwrap(),
-                    "db_name": env::var("DB_NAME").unwrap_or("wallet".to_string())
+                    "db_name": env::var("DB_NAME").unwrap_or("wallet".to_string()),
+                    "table_prefix": env::var("DB_TABLE_PREFIX").unwrap_or("".to_string())
                 }
             ).to_string(),
             credentials: json!(
@@ -22,7 +25,11 @@ impl TestEnv {
                     "user": env::var("DB_USER").unwrap_or("wallet".to_string()),
                     "pass": env::var("DB_PASS").unwrap_or("wallet".to_string())
                 }
-            ).to_string()
+            ).to_string(),
+            shard_db_names: vec![
+                env::var("DB_NAME").unwrap_or("wallet".to_string()),
+                env::var("DB_SHARD_NAME").unwrap_or("wallet_shard".to_string()),
+            ]
         }
     }
 
@@ -34,4 +41,42 @@ impl TestEnv {
         self.credentials.clone()
     }
 
+    ///
+    /// Default config with the option `key` set to `value`.
+    ///
+    pub fn get_config_with(&self, key: &str, value: serde_json::Value) -> String {
+        let mut config: serde_json::Value = serde_json::from_str(&self.config).unwrap();
+        config[key] = value;
+
+        config.to_string()
+    }
+
+    ///
+    /// Config of a storage sharded across the default DB and the `DB_SHARD_NAME` DB on the same host,
+    /// the shards are named `shard-0` and `shard-1`.
+    ///
+    pub fn get_sharded_config(&self, shard_placement: &str) -> String {
+        let mut config: serde_json::Value = serde_json::from_str(&self.config).unwrap();
+
+        config["shards"] = self.shard_db_names.iter().enumerate().map(|(index, db_name)| json!({
+            "name": format!("shard-{}", index),
+            "read_host": config["read_host"],
+            "write_host": config["write_host"],
+            "db_name": db_name
+        })).collect();
+        config["shard_placement"] = json!(shard_placement);
+
+        config.to_string()
+    }
+
+    ///
+    /// Config pointing directly to a single shard of the sharded config.
+    ///
+    pub fn get_shard_config(&self, index: usize) -> String {
+        let mut config: serde_json::Value = serde_json::from_str(&self.config).unwrap();
+        config["db_name"] = json!(self.shard_db_names[index]);
+
+        config.to_string()
+    }
+
 }
@@ -41,6 +41,7 @@ async def import_to_mysql(config, name):
             "port": config["mysql"]["port"],
             "write_host": config["mysql"]["host"],
             "read_host": config["mysql"]["host"],
+            "table_prefix": table_prefix(config),
         }
     }
     wallet_creds = {
@@ -65,32 +66,67 @@ def create_database(config):
                             user=config["mysql"]["user"],
                             password=config["mysql"]["password"])
     cursor = cnx.cursor()
+    prefix = table_prefix(config)
     cursor.execute('''
-    CREATE TABLE IF NOT EXISTS `wallets` (
+    CREATE TABLE IF NOT EXISTS `{prefix}wallets` (
         `id` BIGINT(20) NOT NULL AUTO_INCREMENT,
         `name` VARCHAR(1024) NOT NULL,
         `metadata` VARCHAR(10240) NOT NULL,
+        `metadata_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0,
         PRIMARY KEY (`id`),
         UNIQUE KEY `wallet_name` (`name`)
     ) ENGINE=InnoDB DEFAULT CHARSET=ascii;
-    ''')
+    '''.format(prefix=prefix))
     cursor.execute('''
-    CREATE TABLE IF NOT EXISTS `items` (
+    CREATE TABLE IF NOT EXISTS `{prefix}items` (
         `id` BIGINT(20) NOT NULL AUTO_INCREMENT,
         `wallet_id` BIGINT(20) NOT NULL,
         `type` VARCHAR(128) NOT NULL,
         `name` VARCHAR(1024) NOT NULL,
         `value` LONGBLOB NOT NULL,
+        `value_chunks` INT(10) UNSIGNED NOT NULL DEFAULT 0,
+        `value_encoding` TINYINT(3) UNSIGNED NOT NULL DEFAULT 0,
         `tags` JSON NOT NULL,
+        `version` BIGINT(20) UNSIGNED NOT NULL DEFAULT 1,
         PRIMARY KEY (`id`),
         UNIQUE KEY `ux_items_wallet_id_type_name` (`wallet_id`, `type`, `name`),
-        CONSTRAINT `fk_items_wallet_id` FOREIGN KEY (`wallet_id`)
-            REFERENCES `wallets` (`id`)
+        CONSTRAINT `fk_{prefix}items_wallet_id` FOREIGN KEY (`wallet_id`)
+            REFERENCES `{prefix}wallets` (`id`)
             ON DELETE CASCADE
             ON UPDATE CASCADE
     ) ENGINE=InnoDB DEFAULT CHARSET=ascii
-    ''')
+    '''.format(prefix=prefix))
+    cursor.execute('''
+    CREATE TABLE IF NOT EXISTS `{prefix}item_value_chunks` (
+        `item_id` BIGINT(20) NOT NULL,
+        `seq` INT(10) UNSIGNED NOT NULL,
+        `data` LONGBLOB NOT NULL,
+        PRIMARY KEY (`item_id`, `seq`),
+        CONSTRAINT `fk_{prefix}item_value_chunks_item_id` FOREIGN KEY (`item_id`)
+            REFERENCES `{prefix}items` (`id`)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+    ) ENGINE=InnoDB DEFAULT CHARSET=ascii
+    '''.format(prefix=prefix))
+    cursor.execute('''
+    CREATE TABLE IF NOT EXISTS `{prefix}wallet_data_keys` (
+        `wallet_id` BIGINT(20) NOT NULL,
+        `version` INT(10) UNSIGNED NOT NULL,
+        `kek_id` VARCHAR(255) NOT NULL,
+        `wrapped_key` VARBINARY(255) NOT NULL,
+        `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (`wallet_id`, `version`),
+        CONSTRAINT `fk_{prefix}wallet_data_keys_wallet_id` FOREIGN KEY (`wallet_id`)
+            REFERENCES `{prefix}wallets` (`id`)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+    ) ENGINE=InnoDB DEFAULT CHARSET=ascii
+    '''.format(prefix=prefix))
+
+
 
+def table_prefix(config) -> str:
+    return config["mysql"].get("table_prefix") or ""
 
 
 def parse_config(config_path):
@@ -107,6 +143,7 @@ def report_config_changes(config):
         "port": config["mysql"]["port"],
         "write_host": config["mysql"]["host"],
         "read_host": config["mysql"]["host"],
+        "table_prefix": table_prefix(config),
     })
     print("\"storage_config\": {},".format(json.dumps(cfg)))
     creds = json.dumps({